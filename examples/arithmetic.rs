@@ -2,8 +2,8 @@ extern crate atln_processor;
 
 use atln_processor::emulator::memory::Memory;
 use atln_processor::emulator::processor::processor::{Core, Ports};
-use atln_processor::emulator::processor::processor::instruction::{Data, Instruction};
-use atln_processor::emulator::processor::processor::instruction::operand::{AllPresent, Destination, Dynamic, Operands};
+use atln_processor::emulator::processor::processor::instruction::builder::InstructionBuilder;
+use atln_processor::emulator::processor::processor::instruction::operand::Dynamic;
 use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
 use atln_processor::emulator::processor::processor::instruction::operation::Extension;
 use atln_processor::number;
@@ -17,15 +17,14 @@ fn main() {
     cpu0.context.registers[2] = 1;
 
     loop {
-        let instruction = Instruction::new(Extension::Arithmetic(Arithmetic::Add), Some(Data {
-            destination: Destination::Dynamic,
-            width: Size::Word,
-            synchronous: false,
-            operands: Operands::AllPresent(AllPresent {
-                x_static: 2,
-                x_dynamic: Dynamic::Memory(number::Data::Word(10))
-            })
-        })).unwrap();
+        let instruction = InstructionBuilder::new()
+            .extension(Extension::Arithmetic(Arithmetic::Add))
+            .width(Size::Word)
+            .static_register(2)
+            .dynamic(Dynamic::Memory(number::Data::Word(10)))
+            .destination_dynamic()
+            .build()
+            .unwrap();
 
         cpu0.execute(&instruction, &mut memory, &mut ports);
         
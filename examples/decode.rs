@@ -1,3 +1,5 @@
+#![allow(clippy::unusual_byte_groupings)]
+
 extern crate atln_processor;
 
 use std::io::Cursor;
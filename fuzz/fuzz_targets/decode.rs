@@ -0,0 +1,18 @@
+//! Decoding arbitrary bytes must produce an instruction or an error without panicking. Run with
+//! `cargo fuzz run decode` from the repository root.
+
+#![no_main]
+
+use std::io::Cursor;
+use atln_processor::emulator::processor::processor::instruction::Instruction;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let mut stream = Cursor::new(bytes);
+
+    // Decode instructions back to back like a processor fetching from memory until the stream runs out or an error is
+    // returned.
+    while (stream.position() as usize) < bytes.len() {
+        if Instruction::decode(&mut stream).is_err() { break }
+    }
+});
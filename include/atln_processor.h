@@ -0,0 +1,129 @@
+#ifndef ATLN_PROCESSOR_H
+#define ATLN_PROCESSOR_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of executing instructions.
+ */
+typedef enum AtlnStatus {
+  ATLN_STATUS_RUNNING,
+  ATLN_STATUS_HALTED,
+  ATLN_STATUS_FAULTED,
+  ATLN_STATUS_BUDGET_EXHAUSTED,
+  /**
+   * The machine pointer was null.
+   */
+  ATLN_STATUS_INVALID_MACHINE,
+} AtlnStatus;
+
+/**
+ * A core with its own memory and ports. Only ever accessed through a pointer from C.
+ */
+typedef struct AtlnMachine AtlnMachine;
+
+/**
+ * Create a machine with zeroed memory of the given size. The machine must be released with [atln_machine_free].
+ */
+struct AtlnMachine *atln_machine_new(size_t memory_size);
+
+/**
+ * Release a machine.
+ *
+ * # Safety
+ * The machine must have come from [atln_machine_new] and must not be used after this.
+ */
+void atln_machine_free(struct AtlnMachine *machine);
+
+/**
+ * Copy `length` bytes into physical memory starting at `address` and start executing from there. Returns the number of
+ * bytes that fit in memory.
+ *
+ * # Safety
+ * The machine must be valid or null, and `bytes` must point to `length` readable bytes.
+ */
+size_t atln_machine_load(struct AtlnMachine *machine,
+                         uint64_t address,
+                         const uint8_t *bytes,
+                         size_t length);
+
+/**
+ * Execute a single instruction.
+ *
+ * # Safety
+ * The machine must be valid or null.
+ */
+enum AtlnStatus atln_machine_step(struct AtlnMachine *machine);
+
+/**
+ * Execute until the core stops or `instructions` have been executed.
+ *
+ * # Safety
+ * The machine must be valid or null.
+ */
+enum AtlnStatus atln_machine_run(struct AtlnMachine *machine, uint64_t instructions);
+
+/**
+ * Store the value of a register in `value`. Returns false if the machine is null or the register does not exist.
+ *
+ * # Safety
+ * The machine must be valid or null, and `value` must be writable.
+ */
+bool atln_machine_register(const struct AtlnMachine *machine,
+                           size_t index,
+                           uint64_t *value);
+
+/**
+ * Set the value of a register. Returns false if the machine is null or the register does not exist.
+ *
+ * # Safety
+ * The machine must be valid or null.
+ */
+bool atln_machine_set_register(struct AtlnMachine *machine, size_t index, uint64_t value);
+
+/**
+ * Address of the next instruction, or 0 if the machine is null.
+ *
+ * # Safety
+ * The machine must be valid or null.
+ */
+uint64_t atln_machine_program_counter(const struct AtlnMachine *machine);
+
+/**
+ * Number of cycles executed, or 0 if the machine is null.
+ *
+ * # Safety
+ * The machine must be valid or null.
+ */
+uint64_t atln_machine_cycles(const struct AtlnMachine *machine);
+
+/**
+ * Copy up to `length` bytes of physical memory starting at `address` into `buffer`. Returns the number of bytes
+ * copied, which is less than `length` if the range leaves memory.
+ *
+ * # Safety
+ * The machine must be valid or null, and `buffer` must point to `length` writable bytes.
+ */
+size_t atln_machine_read_memory(const struct AtlnMachine *machine,
+                                uint64_t address,
+                                uint8_t *buffer,
+                                size_t length);
+
+/**
+ * Copy `length` bytes from `bytes` into physical memory starting at `address`. Returns the number of bytes written.
+ *
+ * # Safety
+ * The machine must be valid or null, and `bytes` must point to `length` readable bytes.
+ */
+size_t atln_machine_write_memory(struct AtlnMachine *machine,
+                                 uint64_t address,
+                                 const uint8_t *bytes,
+                                 size_t length);
+
+#endif  /* ATLN_PROCESSOR_H */
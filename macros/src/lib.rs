@@ -0,0 +1,118 @@
+//! Procedural macros for [atln_processor].
+//!
+//! [assemble!] runs the assembler of `atln_processor` while compiling, so fixtures and examples hold the source of a
+//! program instead of hand written bytes.
+
+extern crate atln_processor;
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Literal, Punct, Spacing, TokenStream, TokenTree};
+use atln_processor::programming::assembler;
+
+/// Assemble a string literal of assembly into a byte array expression at compile time, in the same way as
+/// `atln_processor::programming::assembler::assemble`. A program which fails to assemble is a compile error pointing at
+/// the line and the token at fault.
+/// ```
+/// # extern crate atln_processor;
+/// # extern crate atln_processor_macros;
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor_macros::assemble;
+///
+/// const PROGRAM: &[u8] = &assemble!("add.q r1, 3
+///                                    halt");
+///
+/// let mut core = Core::default();
+/// core.run(&mut Memory::from(PROGRAM.to_vec()), &mut Default::default(), None);
+/// assert_eq!(core.context.registers[1], 3);
+/// ```
+///
+/// ```compile_fail
+/// # extern crate atln_processor_macros;
+/// use atln_processor_macros::assemble;
+///
+/// let program = assemble!("add.q r1, missing");
+/// ```
+#[proc_macro]
+pub fn assemble(input: TokenStream) -> TokenStream {
+    let source = match literal(input) {
+        Some(source) => source,
+        None => return error("expected a single string literal")
+    };
+
+    match assembler::assemble(&source) {
+        Ok(bytes) => {
+            let mut array = TokenStream::new();
+            for byte in bytes {
+                array.extend([TokenTree::Literal(Literal::u8_suffixed(byte)), TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+            }
+
+            TokenTree::Group(Group::new(Delimiter::Bracket, array)).into()
+        },
+        Err(failure) => error(failure.report("").trim_end())
+    }
+}
+
+/// Contents of the only token, if it is a string literal. Raw strings are taken as they are and escapes of normal
+/// strings are resolved.
+fn literal(input: TokenStream) -> Option<String> {
+    let mut tokens = input.into_iter().flat_map(|token| match token {
+        // Literals passed through another macro arrive wrapped in a group without delimiters.
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => group.stream().into_iter().collect(),
+        token => vec![token]
+    });
+
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        _ => return None
+    };
+
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw.get(hashes + 1..raw.len() - hashes - 1).map(String::from)
+    }
+
+    unescape(literal.strip_prefix('"')?.strip_suffix('"')?)
+}
+
+/// Resolve the escapes of a string literal.
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '\\' { result.push(character); continue }
+
+        match characters.next()? {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            '0' => result.push('\0'),
+            '\\' => result.push('\\'),
+            '\'' => result.push('\''),
+            '"' => result.push('"'),
+            // A line continuation skips the line break and the indentation after it.
+            '\n' => {
+                let rest = characters.as_str().trim_start();
+                characters = rest.chars();
+            },
+            'x' => {
+                let digits: String = characters.by_ref().take(2).collect();
+                result.push(u8::from_str_radix(&digits, 16).ok().filter(u8::is_ascii)? as char);
+            },
+            'u' => {
+                let escape: String = characters.by_ref().take_while(|&character| character != '}').collect();
+                let code = u32::from_str_radix(escape.strip_prefix('{')?, 16).ok()?;
+                result.push(char::from_u32(code)?);
+            },
+            _ => return None
+        }
+    }
+
+    Some(result)
+}
+
+/// Expand to a compile error.
+fn error(message: &str) -> TokenStream {
+    format!("compile_error!({message:?})").parse().unwrap()
+}
@@ -0,0 +1,404 @@
+//! Command line driver for assembling, disassembling and running guest programs.
+//!
+//! ```text
+//! atln assemble <source> <output> [--include <directory>]... [--listing <file>]
+//! atln compile <source> <object> [--include <directory>]... [--listing <file>]
+//! atln link <output> <object>...
+//! atln disassemble <binary> [--strict] [--recover <alignment>]
+//! atln run <binary> [--memory <bytes>] [--budget <instructions>] [--trace] [--export-trace <file>] [--semihosting]
+//! atln debug <binary> [--memory <bytes>]
+//! atln serve <address> [--memory <bytes>]
+//! atln test <directory>
+//! atln vectors <directory> [--sample <count>] [--seed <seed>]
+//! ```
+//!
+//! `assemble` writes an executable image. Programs split over multiple files are compiled into objects one file at a
+//! time and then linked into an image at address 0. Included files are looked for next to the file including them and
+//! then in each `--include` directory. `--listing` also writes a [Listing] of the address and bytes of every line,
+//! where `compile` lists values left for the linker as zeros. Images are loaded with [Image::load_into] and start at
+//! their entry point, any other file is treated as a flat program which is loaded and executed at address 0.
+//! `disassemble --strict` fails on the first instruction which is not [encoded
+//! canonically](Instruction::decode_slice_strict). With `--recover`, bytes which are not a valid instruction are listed
+//! as `.byte` data up to the next multiple of the alignment, which is 1 to skip a single byte, and disassembly
+//! continues after them. `run` prints the registers once the core stops, and with `--trace` it also prints every
+//! instruction before it executes. `--export-trace` writes every executed instruction to a file as JSON lines, in the
+//! format of [Record::to_json]. With `--semihosting`, the program can use the standard streams and files of the host
+//! through `hcall`, and exits with the code it passes to the exit call. `debug` opens the [Monitor] on standard input.
+//! With the `server` feature, `serve` listens for JSON-RPC requests on a TCP address such as `127.0.0.1:4000`,
+//! controlling a machine with empty memory which the client loads a program into. `test` runs the [conformance] tests
+//! of a directory and prints how each failing test differs from its expectation. `vectors` writes golden [vectors] for
+//! every operation into a directory as conformance tests, or with `--sample`, that many random instructions drawn from
+//! `--seed`.
+
+extern crate atln_processor;
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use atln_processor::emulator::{conformance, vectors};
+use atln_processor::emulator::loader::Image;
+use atln_processor::emulator::memory::Memory;
+use atln_processor::emulator::monitor::Monitor;
+use atln_processor::emulator::processor::processor::{Budget, Core, Ports, Status};
+use atln_processor::emulator::processor::processor::instruction::DecodeError;
+use atln_processor::emulator::processor::processor::instruction::iterator::{Disassembled, InstructionIterator, Recovery};
+use atln_processor::emulator::processor::processor::semihosting::Semihosting;
+use atln_processor::emulator::processor::processor::trace::Tracer;
+#[cfg(doc)]
+use atln_processor::emulator::processor::processor::trace::Record;
+#[cfg(doc)]
+use atln_processor::emulator::processor::processor::instruction::Instruction;
+use atln_processor::programming::{assembler, linker};
+use atln_processor::programming::assembler::include::Directories;
+use atln_processor::programming::assembler::listing::Listing;
+use atln_processor::programming::debug::DebugInfo;
+use atln_processor::programming::object::Object;
+#[cfg(feature = "server")]
+use atln_processor::server;
+#[cfg(feature = "server")]
+use atln_processor::server::Server;
+use atln_processor::utility::Encodable;
+
+/// Memory given to the guest when `--memory` is not passed.
+const DEFAULT_MEMORY_BYTES: usize = 64 * 1024;
+
+const USAGE: &str = "usage:
+    atln assemble <source> <output> [--include <directory>]... [--listing <file>]
+    atln compile <source> <object> [--include <directory>]... [--listing <file>]
+    atln link <output> <object>...
+    atln disassemble <binary> [--strict] [--recover <alignment>]
+    atln run <binary> [--memory <bytes>] [--budget <instructions>] [--trace] [--export-trace <file>] [--semihosting]
+    atln debug <binary> [--memory <bytes>]
+    atln serve <address> [--memory <bytes>]
+    atln test <directory>
+    atln vectors <directory> [--sample <count>] [--seed <seed>]";
+
+/// Options shared by the commands that execute a program.
+struct Machine {
+    memory_bytes: usize,
+    budget: Option<u64>,
+    trace: bool,
+    /// File executed instructions are exported to.
+    export_trace: Option<String>,
+    semihosting: bool
+}
+
+impl Machine {
+    fn parse(options: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut machine = Self { memory_bytes: DEFAULT_MEMORY_BYTES, budget: None, trace: false, export_trace: None, semihosting: false };
+        let mut options = options.iter();
+
+        while let Some(option) = options.next() {
+            if option == "--trace" {
+                machine.trace = true;
+                continue;
+            }
+
+            if option == "--semihosting" {
+                machine.semihosting = true;
+                continue;
+            }
+
+            let value = options.next().ok_or_else(|| format!("{option} expects a value"))?;
+            match option.as_str() {
+                "--memory" => machine.memory_bytes = value.parse()?,
+                "--budget" => machine.budget = Some(value.parse()?),
+                "--export-trace" => machine.export_trace = Some(value.clone()),
+                _ => return Err(format!("unknown option {option}").into())
+            }
+        }
+
+        Ok(machine)
+    }
+}
+
+/// An error along with every error that caused it.
+fn chain(error: &dyn Error) -> String {
+    let mut text = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        text += &format!(": {error}");
+        source = error.source();
+    }
+    text
+}
+
+/// Print an error along with every error that caused it.
+fn report(error: &dyn Error) {
+    eprintln!("error: {}", chain(error));
+}
+
+/// Options shared by the commands that assemble a file.
+struct Assembly {
+    resolver: Directories,
+    /// File the listing is written to.
+    listing: Option<String>
+}
+
+impl Assembly {
+    fn parse(options: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut assembly = Self { resolver: Directories::default(), listing: None };
+        let mut options = options.iter();
+
+        while let Some(option) = options.next() {
+            let value = options.next().ok_or_else(|| format!("{option} expects a value"))?;
+            match option.as_str() {
+                "--include" => assembly.resolver.directories.push(value.into()),
+                "--listing" => assembly.listing = Some(value.clone()),
+                _ => return Err(format!("unknown option {option}").into())
+            }
+        }
+
+        Ok(assembly)
+    }
+
+    /// Assemble a file into an object named after it along with its listing.
+    fn object(&mut self, source: &str) -> Result<(Object, Listing), Box<dyn Error>> {
+        let (mut object, listing) = assembler::assemble_object_listing(&fs::read_to_string(source)?, source, &mut self.resolver)
+            .map_err(|error| error.report(source).trim_end().to_owned())?;
+        object.file = source.to_owned();
+        Ok((object, listing))
+    }
+
+    fn write_listing(&self, listing: &Listing) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.listing { fs::write(path, listing.to_string())?; }
+        Ok(())
+    }
+}
+
+fn assemble(source: &str, output: &str, options: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut assembly = Assembly::parse(options)?;
+    let (object, mut listing) = assembly.object(source)?;
+    let image = linker::link(&[object], 0)?;
+    fs::write(output, image.encode())?;
+
+    listing.fill(&image.sections[0].data);
+    assembly.write_listing(&listing)
+}
+
+fn compile(source: &str, output: &str, options: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut assembly = Assembly::parse(options)?;
+    let (object, listing) = assembly.object(source)?;
+    fs::write(output, object.encode())?;
+    assembly.write_listing(&listing)
+}
+
+/// Label and source line of an address formatted as a trailing comment, or nothing without debug information.
+fn annotation(debug: Option<&DebugInfo>, address: u64) -> String {
+    debug.and_then(|debug| debug.annotate(address)).map(|annotation| format!(" ; {annotation}")).unwrap_or_default()
+}
+
+fn link(output: &str, objects: &[&str]) -> Result<(), Box<dyn Error>> {
+    let objects = objects.iter().map(|path| Ok(Object::parse(&fs::read(path)?)?)).collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    fs::write(output, linker::link(&objects, 0)?.encode())?;
+    Ok(())
+}
+
+fn disassemble(binary: &str, options: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut strict = false;
+    let mut recovery = None;
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--strict" => strict = true,
+            "--recover" => {
+                let alignment: u64 = options.next().ok_or("--recover expects a value")?.parse()?;
+                recovery = Some(if alignment <= 1 { Recovery::SkipByte } else { Recovery::Align(alignment) });
+            },
+            _ => return Err(format!("unknown option {option}").into())
+        }
+    }
+
+    let bytes = fs::read(binary)?;
+    let image = if Image::is_image(&bytes) { Image::parse(&bytes)? } else { Image::flat(0, bytes) };
+
+    let debug = image.debug.as_ref();
+
+    for section in image.sections.iter().filter(|section| section.permissions.execute) {
+        let mut instructions = InstructionIterator::new(section.data.as_slice());
+        if strict { instructions = instructions.strict(); }
+
+        let listing: Box<dyn Iterator<Item = Result<(u64, Disassembled), DecodeError>>> = match recovery {
+            Some(recovery) => Box::new(instructions.recover(recovery).map(Ok)),
+            None => Box::new(instructions.map(|instruction| instruction.map(|(offset, instruction)| (offset, Disassembled::Instruction(instruction)))))
+        };
+
+        for item in listing {
+            let (offset, item) = item?;
+            let address = section.address + offset;
+
+            for symbol in debug.into_iter().flat_map(|debug| &debug.symbols).filter(|symbol| symbol.address == address) {
+                println!("{}:", symbol.name);
+            }
+
+            println!("{address:08x}: {item}{}", annotation(debug, address));
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a core and memory holding the program, with the program counter at its entry point. Debug information is
+/// returned if the program is an image that has it.
+fn load(binary: &str, machine: &Machine) -> Result<(Core, Memory, Option<DebugInfo>), Box<dyn Error>> {
+    let program = fs::read(binary)?;
+    let mut memory = Memory::from(vec![0u8; machine.memory_bytes]);
+    let mut core = Core::default();
+
+    if !Image::is_image(&program) {
+        if memory.write_bytes(0, false, &program) != program.len() { return Err("program does not fit in memory".into()) }
+        return Ok((core, memory, None));
+    }
+
+    let image = Image::parse(&program)?;
+    image.load_into(&mut memory)?;
+    core.context.program_counter = image.entry;
+    Ok((core, memory, image.debug))
+}
+
+fn run(binary: &str, machine: Machine) -> Result<ExitCode, Box<dyn Error>> {
+    let (mut core, mut memory, debug) = load(binary, &machine)?;
+    let semihosting = machine.semihosting.then(|| Arc::new(Mutex::new(Semihosting::default())));
+    core.semihosting = semihosting.clone();
+    let tracer = match &machine.export_trace {
+        Some(path) => Some(Arc::new(Mutex::new(Tracer::new(Box::new(BufWriter::new(File::create(path)?)))))),
+        None => None
+    };
+    core.tracer = tracer.clone();
+    let trace = machine.trace;
+    let mut ports = Ports::default();
+    let mut executed = 0;
+
+    let status = loop {
+        if machine.budget.is_some_and(|budget| executed >= budget) { break Status::BudgetExhausted }
+
+        if trace {
+            let address = core.context.program_counter;
+            if let Ok((instruction, _)) = core.decode(&memory, address) { println!("{address:08x}: {instruction}{}", annotation(debug.as_ref(), address)); }
+        }
+
+        let status = if trace { core.step(&mut memory, &mut ports) } else {
+            core.run(&mut memory, &mut ports, machine.budget.map(|budget| Budget::Instructions(budget - executed)))
+        };
+
+        if !status.is_running() { break status }
+        if trace { println!("          {:?}", core.context.registers); }
+        executed += 1;
+    };
+
+    println!("registers: {:?}", core.context.registers);
+    println!("program counter: {:#x}, cycles: {}", core.context.program_counter, core.cycles);
+
+    if let Some(tracer) = tracer { tracer.lock().unwrap().finish()?; }
+    let exit_code = semihosting.and_then(|semihosting| semihosting.lock().unwrap().exit_code);
+
+    Ok(match status {
+        Status::Halted => exit_code.map_or(ExitCode::SUCCESS, |code| ExitCode::from(u8::try_from(code).unwrap_or(u8::MAX))),
+        Status::Faulted(exception) => {
+            report(&exception);
+            eprintln!("at {:08x}{}", core.context.program_counter, annotation(debug.as_ref(), core.context.program_counter));
+            ExitCode::FAILURE
+        },
+        _ => {
+            eprintln!("budget exhausted");
+            ExitCode::from(2)
+        }
+    })
+}
+
+fn debug(binary: &str, machine: Machine) -> Result<ExitCode, Box<dyn Error>> {
+    let (core, memory, debug) = load(binary, &machine)?;
+    let mut monitor = Monitor::new(core, memory, Ports::default());
+    monitor.debug = debug;
+    monitor.repl(io::stdin().lock(), io::stdout())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "server")]
+fn serve(address: &str, machine: Machine) -> Result<ExitCode, Box<dyn Error>> {
+    let memory = Memory::from(vec![0; machine.memory_bytes]);
+    let listener = std::net::TcpListener::bind(address)?;
+    eprintln!("listening on {}", listener.local_addr()?);
+
+    Server::new(server::Machine::new(Core::default(), memory)).listen(listener)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn test(directory: &str) -> Result<ExitCode, Box<dyn Error>> {
+    let reports = conformance::run_directory(directory.as_ref())?;
+    let mut failed = 0;
+
+    for report in &reports {
+        if report.passed() {
+            println!("pass {}", report.name);
+            continue;
+        }
+
+        failed += 1;
+        println!("FAIL {}", report.name);
+        match &report.result {
+            Ok(mismatches) => for mismatch in mismatches { println!("    {mismatch}"); },
+            Err(error) => println!("    {}", chain(error))
+        }
+    }
+
+    println!("{} passed, {failed} failed", reports.len() - failed);
+    Ok(if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn generate(directory: &str, options: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let mut sample = None;
+    let mut seed = 0;
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| format!("{option} expects a value"))?;
+        match option.as_str() {
+            "--sample" => sample = Some(value.parse()?),
+            "--seed" => seed = value.parse()?,
+            _ => return Err(format!("unknown option {option}").into())
+        }
+    }
+
+    let instructions = match sample {
+        Some(count) => vectors::sample(seed, count),
+        None => vectors::enumerate()
+    };
+
+    let generated = vectors::vectors(instructions);
+    vectors::write_directory(directory.as_ref(), &generated)?;
+    println!("wrote {} vectors", generated.len());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match arguments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["assemble", source, output, ..] => assemble(source, output, &arguments[3..]).map(|_| ExitCode::SUCCESS),
+        ["compile", source, output, ..] => compile(source, output, &arguments[3..]).map(|_| ExitCode::SUCCESS),
+        ["link", output, objects @ ..] if !objects.is_empty() => link(output, objects).map(|_| ExitCode::SUCCESS),
+        ["disassemble", binary, ..] => disassemble(binary, &arguments[2..]).map(|_| ExitCode::SUCCESS),
+        ["run", binary, ..] => Machine::parse(&arguments[2..]).and_then(|machine| run(binary, machine)),
+        ["debug", binary, ..] => Machine::parse(&arguments[2..]).and_then(|machine| debug(binary, machine)),
+        #[cfg(feature = "server")]
+        ["serve", address, ..] => Machine::parse(&arguments[2..]).and_then(|machine| serve(address, machine)),
+        ["test", directory] => test(directory),
+        ["vectors", directory, ..] => generate(directory, &arguments[2..]),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(64)
+        }
+    };
+
+    result.unwrap_or_else(|error| {
+        report(error.as_ref());
+        ExitCode::FAILURE
+    })
+}
@@ -1,2 +1,16 @@
+#[cfg(feature = "std")]
+pub mod conformance;
+pub mod device;
+pub mod diff;
+pub mod differential;
+pub mod event;
+pub mod fault;
+pub mod instrument;
+pub mod loader;
 pub mod memory;
-pub mod processor;
\ No newline at end of file
+pub mod monitor;
+pub mod port;
+pub mod processor;
+pub mod system;
+#[cfg(feature = "std")]
+pub mod vectors;
\ No newline at end of file
@@ -0,0 +1,425 @@
+//! Conformance tests which run guest programs and compare the state they finish in against an expectation.
+//!
+//! A test directory holds pairs of files named after each test. `<name>.bin` is the program, either an executable
+//! [image](super::loader) or a flat program loaded and executed at address 0. `<name>.expect` is a manifest with one
+//! entry per line, where blank lines and lines starting with `#` are ignored.
+//!
+//! | Entry                    | Expectation                                                                      |
+//! |--------------------------|----------------------------------------------------------------------------------|
+//! | `memory <bytes>`         | Size of the memory, [DEFAULT_MEMORY_BYTES] by default.                           |
+//! | `budget <instructions>`  | Instructions executed before giving up, [DEFAULT_BUDGET] by default.             |
+//! | `status <status>`        | `halted` by default, or `faulted` or `exhausted`.                                |
+//! | `r<index> <value>`       | Value of a register.                                                             |
+//! | `pc <address>`           | Program counter once the core stopped.                                           |
+//! | `flags <flags>`          | Condition flags in the same form as they are displayed, such as `z---`.          |
+//! | `mem <address> <bytes>`  | Bytes of physical memory at an address, as hexadecimal digits.                   |
+//!
+//! Numbers are decimal or hexadecimal with a `0x` prefix. Anything without an entry is not checked, so a test only
+//! states what it is about.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use emulator::diff;
+use emulator::loader::{Image, LoadError};
+use emulator::memory::Memory;
+use emulator::processor::processor::{Budget, Core, Registers, Status};
+use emulator::processor::processor::instruction::operation::condition::Flags;
+use programming::assembler::parse_number;
+
+/// Memory given to a test which does not set its size.
+pub const DEFAULT_MEMORY_BYTES: usize = 64 * 1024;
+/// Instructions a test may execute when it does not set a budget, so a program which never halts still finishes.
+pub const DEFAULT_BUDGET: u64 = 1_000_000;
+pub const PROGRAM_EXTENSION: &str = "bin";
+pub const MANIFEST_EXTENSION: &str = "expect";
+
+/// How the core stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Outcome {
+    #[default]
+    Halted,
+    Faulted,
+    BudgetExhausted
+}
+
+impl Outcome {
+    pub(crate) fn of(status: &Status) -> Self {
+        match status {
+            Status::Faulted(_) => Self::Faulted,
+            Status::BudgetExhausted | Status::Running => Self::BudgetExhausted,
+            Status::Halted => Self::Halted
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Halted => "halted",
+            Self::Faulted => "faulted",
+            Self::BudgetExhausted => "exhausted"
+        })
+    }
+}
+
+/// The problem with an entry of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryError {
+    /// The entry does not exist.
+    Key,
+    /// A value is missing or invalid.
+    Value
+}
+
+impl Display for EntryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Key => "entry does not exist",
+            Self::Value => "value is missing or invalid"
+        })
+    }
+}
+
+impl Error for EntryError {}
+
+/// A line of a manifest which could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    /// Line number starting from 1.
+    pub line: usize,
+    pub error: EntryError
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid manifest line {}", self.line)
+    }
+}
+
+impl Error for ManifestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// State a test program is expected to finish in, parsed from a manifest.
+/// ```
+/// use atln_processor::emulator::conformance::{EntryError, Expectation, ManifestError, Outcome};
+///
+/// let expectation = Expectation::parse("# Adds two numbers.\nr1 5\npc 0x10\nmem 32 05ff\n").unwrap();
+/// assert_eq!(expectation.registers, [(1, 5)]);
+/// assert_eq!(expectation.program_counter, Some(16));
+/// assert_eq!(expectation.memory, [(32, vec![5, 0xFF])]);
+/// assert_eq!(expectation.outcome, Outcome::Halted);
+///
+/// assert_eq!(Expectation::parse("status halted\nr9 1"), Err(ManifestError { line: 2, error: EntryError::Value }));
+/// assert_eq!(Expectation::parse("sp 1"), Err(ManifestError { line: 1, error: EntryError::Key }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expectation {
+    pub memory_bytes: usize,
+    pub budget: u64,
+    pub outcome: Outcome,
+    /// Index and value of registers.
+    pub registers: Vec<(u8, u64)>,
+    pub program_counter: Option<u64>,
+    pub flags: Option<Flags>,
+    /// Address and bytes of memory.
+    pub memory: Vec<(u64, Vec<u8>)>
+}
+
+impl Default for Expectation {
+    fn default() -> Self {
+        Self {
+            memory_bytes: DEFAULT_MEMORY_BYTES,
+            budget: DEFAULT_BUDGET,
+            outcome: Outcome::default(),
+            registers: Vec::new(),
+            program_counter: None,
+            flags: None,
+            memory: Vec::new()
+        }
+    }
+}
+
+impl Expectation {
+    pub fn parse(manifest: &str) -> Result<Self, ManifestError> {
+        let mut expectation = Self::default();
+
+        for (index, line) in manifest.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            expectation.entry(line).map_err(|error| ManifestError { line: index + 1, error })?;
+        }
+
+        Ok(expectation)
+    }
+
+    fn entry(&mut self, line: &str) -> Result<(), EntryError> {
+        let mut words = line.split_whitespace();
+        let key = words.next().ok_or(EntryError::Key)?;
+        let mut value = || words.next().ok_or(EntryError::Value);
+        let number = |text: &str| parse_number(text).ok_or(EntryError::Value);
+
+        match key {
+            "memory" => self.memory_bytes = usize::try_from(number(value()?)?).map_err(|_| EntryError::Value)?,
+            "budget" => self.budget = number(value()?)?,
+            "status" => self.outcome = match value()? {
+                "halted" => Outcome::Halted,
+                "faulted" => Outcome::Faulted,
+                "exhausted" => Outcome::BudgetExhausted,
+                _ => return Err(EntryError::Value)
+            },
+            "pc" => self.program_counter = Some(number(value()?)?),
+            "flags" => self.flags = Some(parse_flags(value()?).ok_or(EntryError::Value)?),
+            "mem" => {
+                let address = number(value()?)?;
+                let bytes = parse_bytes(value()?).ok_or(EntryError::Value)?;
+                self.memory.push((address, bytes));
+            },
+            _ => {
+                let register = key.strip_prefix('r').and_then(|index| index.parse::<u8>().ok()).ok_or(EntryError::Key)?;
+                if register as usize >= Registers::default().len() { return Err(EntryError::Value) }
+                self.registers.push((register, number(value()?)?));
+            }
+        }
+
+        if words.next().is_some() { return Err(EntryError::Value) }
+        Ok(())
+    }
+}
+
+impl Display for Expectation {
+    /// Manifest with an entry for everything expected, which [Expectation::parse] reads back into the same expectation.
+    /// ```
+    /// use atln_processor::emulator::conformance::Expectation;
+    ///
+    /// let expectation = Expectation::parse("r1 5\nflags z---\nmem 0x20 05ff").unwrap();
+    /// assert_eq!(expectation.to_string(), "memory 65536\nbudget 1000000\nstatus halted\nr1 0x5\nflags z---\nmem 0x20 05ff\n");
+    /// assert_eq!(Expectation::parse(&expectation.to_string()), Ok(expectation));
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "memory {}", self.memory_bytes)?;
+        writeln!(f, "budget {}", self.budget)?;
+        writeln!(f, "status {}", self.outcome)?;
+        for (register, value) in &self.registers { writeln!(f, "r{register} {value:#x}")?; }
+        if let Some(program_counter) = self.program_counter { writeln!(f, "pc {program_counter:#x}")?; }
+        if let Some(flags) = self.flags { writeln!(f, "flags {flags}")?; }
+
+        for (address, bytes) in &self.memory {
+            write!(f, "mem {address:#x} ")?;
+            for byte in bytes { write!(f, "{byte:02x}")?; }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse flags in the form they are displayed, with a letter for each set flag and `-` for each clear one.
+fn parse_flags(text: &str) -> Option<Flags> {
+    let set = |character: Option<char>, letter: char| match character {
+        Some('-') => Some(false),
+        Some(character) if character == letter => Some(true),
+        _ => None
+    };
+
+    let mut characters = text.chars();
+    let flags = Flags {
+        zero: set(characters.next(), 'z')?,
+        carry: set(characters.next(), 'c')?,
+        sign: set(characters.next(), 's')?,
+        overflow: set(characters.next(), 'o')?
+    };
+
+    characters.next().is_none().then_some(flags)
+}
+
+/// Parse an even number of hexadecimal digits into bytes.
+fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() { return None }
+    (0..text.len()).step_by(2).map(|index| u8::from_str_radix(&text[index..index + 2], 16).ok()).collect()
+}
+
+/// A way the final state differs from the expectation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    Outcome { expected: Outcome, actual: Outcome },
+    Register { register: u8, expected: u64, actual: u64 },
+    ProgramCounter { expected: u64, actual: u64 },
+    Flags { expected: Flags, actual: Flags },
+    /// Contiguous bytes which differ from those expected, which are shorter than expected if memory ended first.
+    Memory { address: u64, expected: Vec<u8>, actual: Vec<u8> }
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Outcome { expected, actual } => write!(f, "expected the status to be {expected} but it is {actual}"),
+            Self::Register { register, expected, actual } => write!(f, "expected r{register} to be {expected:#x} but it is {actual:#x}"),
+            Self::ProgramCounter { expected, actual } => write!(f, "expected the program counter to be {expected:#x} but it is {actual:#x}"),
+            Self::Flags { expected, actual } => write!(f, "expected the flags to be {expected} but they are {actual}"),
+            Self::Memory { address, expected, actual } => write!(f, "expected memory at {address:#x} to be {expected:02x?} but it is {actual:02x?}")
+        }
+    }
+}
+
+/// Run a program and compare the state it finishes in against an expectation. An empty list means the test passed.
+/// ```
+/// use atln_processor::emulator::conformance::{check, Expectation, Mismatch};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let program = assemble("add.q r1, 5\nhalt").unwrap();
+///
+/// assert_eq!(check(&program, &Expectation::parse("r1 5\npc 6").unwrap()), Ok(vec![]));
+/// assert_eq!(check(&program, &Expectation::parse("r1 6").unwrap()), Ok(vec![Mismatch::Register { register: 1, expected: 6, actual: 5 }]));
+///
+/// // Only the bytes that differ are reported.
+/// let mismatches = check(&program, &Expectation::parse("mem 0 0008ff05").unwrap()).unwrap();
+/// assert_eq!(mismatches, [Mismatch::Memory { address: 2, expected: vec![0xFF], actual: vec![0xC8] }]);
+/// ```
+pub fn check(program: &[u8], expectation: &Expectation) -> Result<Vec<Mismatch>, LoadError> {
+    let (core, memory, status) = execute(program, expectation.memory_bytes, expectation.budget)?;
+    let context = &core.context;
+    let mut mismatches = Vec::new();
+
+    let outcome = Outcome::of(&status);
+    if outcome != expectation.outcome { mismatches.push(Mismatch::Outcome { expected: expectation.outcome, actual: outcome }); }
+
+    for &(register, expected) in &expectation.registers {
+        let actual = context.registers[register as usize];
+        if actual != expected { mismatches.push(Mismatch::Register { register, expected, actual }); }
+    }
+
+    if let Some(expected) = expectation.program_counter {
+        if context.program_counter != expected { mismatches.push(Mismatch::ProgramCounter { expected, actual: context.program_counter }); }
+    }
+
+    if let Some(expected) = expectation.flags {
+        if context.flags != expected { mismatches.push(Mismatch::Flags { expected, actual: context.flags }); }
+    }
+
+    for (address, expected) in &expectation.memory {
+        let mut actual = vec![0; expected.len()];
+        let read = memory.read_bytes(*address, false, &mut actual);
+        actual.truncate(read);
+
+        for range in diff::memory(*address, expected, &actual) {
+            mismatches.push(Mismatch::Memory { address: range.address, expected: range.before, actual: range.after });
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Load a program into zeroed memory in the same way as a test and run it on a new core until it stops or exhausts the
+/// budget.
+pub(crate) fn execute(program: &[u8], memory_bytes: usize, budget: u64) -> Result<(Core, Memory, Status), LoadError> {
+    let mut memory = Memory::from(vec![0; memory_bytes]);
+    let mut core = Core::default();
+
+    if Image::is_image(program) {
+        let image = Image::parse(program)?;
+        image.load_into(&mut memory)?;
+        core.context.program_counter = image.entry;
+    } else if memory.write_bytes(0, false, program) != program.len() {
+        return Err(LoadError::Memory);
+    }
+
+    let status = core.run(&mut memory, &mut Default::default(), Some(Budget::Instructions(budget)));
+    Ok((core, memory, status))
+}
+
+/// Why a test could not be run.
+#[derive(Debug)]
+pub enum TestError {
+    /// The program or manifest could not be read.
+    Io(io::Error),
+    Manifest(ManifestError),
+    Load(LoadError)
+}
+
+impl Display for TestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Io(_) => "failed to read the test",
+            Self::Manifest(_) => "failed to parse the manifest",
+            Self::Load(_) => "failed to load the program"
+        })
+    }
+}
+
+impl Error for TestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Manifest(error) => Some(error),
+            Self::Load(error) => Some(error)
+        }
+    }
+}
+
+/// Result of a test in a directory.
+#[derive(Debug)]
+pub struct Report {
+    pub name: String,
+    /// Mismatches found, or why the test could not be run.
+    pub result: Result<Vec<Mismatch>, TestError>
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.result.as_ref().is_ok_and(Vec::is_empty)
+    }
+}
+
+/// Run every test of a directory in order of their names. Only an error listing the directory is returned, failures of
+/// individual tests are part of their report.
+/// ```
+/// use std::fs;
+/// use atln_processor::emulator::conformance::run_directory;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let directory = std::env::temp_dir().join("atln-conformance-example");
+/// fs::create_dir_all(&directory).unwrap();
+/// fs::write(directory.join("add.bin"), assemble("add.q r1, 5\nhalt").unwrap()).unwrap();
+/// fs::write(directory.join("add.expect"), "r1 5\n").unwrap();
+/// fs::write(directory.join("spin.bin"), assemble("divert r0").unwrap()).unwrap();
+/// fs::write(directory.join("spin.expect"), "budget 10\nstatus halted\n").unwrap();
+///
+/// let reports = run_directory(&directory).unwrap();
+/// fs::remove_dir_all(&directory).unwrap();
+///
+/// assert_eq!(reports.iter().map(|report| (report.name.as_str(), report.passed())).collect::<Vec<_>>(), [("add", true), ("spin", false)]);
+/// ```
+pub fn run_directory(directory: &Path) -> io::Result<Vec<Report>> {
+    let mut manifests = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == MANIFEST_EXTENSION) { manifests.push(path); }
+    }
+
+    manifests.sort();
+
+    Ok(manifests.into_iter().map(|manifest| {
+        let name = manifest.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let result = fs::read_to_string(&manifest)
+            .map_err(TestError::Io)
+            .and_then(|text| Expectation::parse(&text).map_err(TestError::Manifest))
+            .and_then(|expectation| {
+                let program = fs::read(manifest.with_extension(PROGRAM_EXTENSION)).map_err(TestError::Io)?;
+                check(&program, &expectation).map_err(TestError::Load)
+            });
+
+        Report { name, result }
+    }).collect())
+}
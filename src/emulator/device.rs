@@ -0,0 +1,10 @@
+//! Devices which operate alongside the processor.
+
+pub mod audio;
+pub mod dma;
+pub mod gpio;
+#[cfg(feature = "std")]
+pub mod ipc;
+pub mod scheduler;
+pub mod timer;
+pub mod watchdog;
@@ -0,0 +1,145 @@
+//! Audio output, where the guest queues samples which are played at a fixed rate of emulated time.
+//!
+//! Samples are 8 bit unsigned PCM of a single channel, with [SILENCE] in the middle. The guest pushes them into a FIFO
+//! of [Audio::capacity] samples through the port of the device once it is plugged into a
+//! [port space](crate::emulator::port), and reads the port to find how many samples fit into the FIFO, up to 255. As
+//! the device is [ticked](Clocked::tick), usually by a [Scheduler](super::scheduler), a sample is played every
+//! [Audio::period] cycles. Played samples go to the [sink](Audio::sink) if there is one, which frontends hand to the
+//! sound system of the host, and into [Audio::output] otherwise. The sample rate is the number of cycles the frontend
+//! emulates per second divided by the period.
+//!
+//! The guest has to keep the FIFO filled, and [SILENCE] is played whenever it is empty. Samples pushed while the FIFO
+//! is full are dropped. Both are counted in the [Statistics].
+//! ```
+//! use atln_processor::emulator::device::audio::{Audio, SILENCE};
+//! use atln_processor::emulator::device::scheduler::Clocked;
+//! use atln_processor::emulator::port::Device;
+//!
+//! // 4 samples per 100 cycles with room for 3 of them.
+//! let mut audio = Audio::new(25, 3);
+//! assert_eq!(audio.read(0), 3);
+//!
+//! for sample in [0x90, 0xA0, 0xB0, 0xC0] { audio.write(0, sample) }
+//! assert_eq!((audio.read(0), audio.statistics.overruns), (0, 1));
+//!
+//! audio.tick(100);
+//! assert_eq!(audio.output, [0x90, 0xA0, 0xB0, SILENCE]);
+//! assert_eq!((audio.statistics.played, audio.statistics.underruns), (4, 1));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use emulator::port::Device;
+use super::scheduler::Clocked;
+
+/// Sample played while the FIFO is empty, which is the middle of the range of samples.
+pub const SILENCE: u8 = 0x80;
+
+/// Function receiving played samples.
+pub type Sink = Box<dyn FnMut(&[u8]) + Send>;
+
+/// Counts of what happened to samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Samples played, including silence played for underruns.
+    pub played: u64,
+    /// Samples due to be played while the FIFO was empty.
+    pub underruns: u64,
+    /// Samples dropped because they were pushed while the FIFO was full.
+    pub overruns: u64
+}
+
+/// Plays the samples queued by the guest at a fixed rate.
+pub struct Audio {
+    /// Number of cycles between samples. A period of 0 stops playing.
+    pub period: u64,
+    /// Most samples the FIFO holds.
+    pub capacity: usize,
+    pub statistics: Statistics,
+    /// Samples played while there is no sink, which the host takes from here.
+    pub output: Vec<u8>,
+    /// Cycles left until the next sample is played.
+    remaining: u64,
+    fifo: VecDeque<u8>,
+    sink: Option<Sink>
+}
+
+impl Debug for Audio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Audio")
+            .field("period", &self.period)
+            .field("capacity", &self.capacity)
+            .field("statistics", &self.statistics)
+            .field("queued", &self.fifo.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Audio {
+    /// Create a device playing a sample every `period` cycles from a FIFO of `capacity` samples.
+    pub fn new(period: u64, capacity: usize) -> Self {
+        Self { period, capacity, statistics: Statistics::default(), output: Vec::new(), remaining: period, fifo: VecDeque::new(), sink: None }
+    }
+
+    /// Send played samples to a function instead of [Audio::output], replacing the previous one. The function is called
+    /// with the samples played by each tick, in between instructions, so waiting there for the sound system of the host
+    /// to play them stalls the guest. It should rather queue them for the callback of the sound system to take.
+    pub fn sink(&mut self, sink: impl FnMut(&[u8]) + Send + 'static) {
+        self.sink = Some(Box::new(sink));
+    }
+
+    /// Number of samples waiting in the FIFO.
+    pub fn queued(&self) -> usize {
+        self.fifo.len()
+    }
+
+    /// Queue a sample, dropping it if the FIFO is full. Returns whether it was queued.
+    pub fn push(&mut self, sample: u8) -> bool {
+        if self.fifo.len() >= self.capacity {
+            self.statistics.overruns += 1;
+            return false
+        }
+
+        self.fifo.push_back(sample);
+        true
+    }
+}
+
+impl Clocked for Audio {
+    fn tick(&mut self, cycles: u64) {
+        if self.period == 0 { return }
+
+        if cycles < self.remaining {
+            self.remaining -= cycles;
+            return;
+        }
+
+        let overflow = cycles - self.remaining;
+        let due = 1 + overflow / self.period;
+        self.remaining = self.period - overflow % self.period;
+
+        let played: Vec<u8> = (0..due).map(|_| self.fifo.pop_front().unwrap_or_else(|| {
+            self.statistics.underruns += 1;
+            SILENCE
+        })).collect();
+
+        self.statistics.played += due;
+        match &mut self.sink {
+            Some(sink) => sink(&played),
+            None => self.output.extend(played)
+        }
+    }
+}
+
+impl Device for Audio {
+    fn read(&mut self, _offset: u16) -> u8 {
+        self.capacity.saturating_sub(self.fifo.len()).min(u8::MAX as usize) as u8
+    }
+
+    fn write(&mut self, _offset: u16, value: u8) {
+        self.push(value);
+    }
+}
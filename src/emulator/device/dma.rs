@@ -0,0 +1,201 @@
+//! Direct memory access, which devices use to move data to and from guest memory without the core. [Dma] is the part
+//! every such device shares, so devices only decide what to transfer and never touch memory themselves.
+//!
+//! Devices address memory with bus addresses. Without an IOMMU, bus addresses are physical addresses. Giving the device
+//! [windows](Dma::windows) models an IOMMU, where only bus addresses within a window are mapped and each window places
+//! its addresses onto physical memory starting at its target. Windows may be read only, so a device can be given a
+//! buffer to send from without being able to corrupt it. Whichever way the addresses are translated, the physical
+//! addresses must be within [Dma::allowed], and every byte is accessed through [MemoryAccess] as a physical access, so
+//! mirrors apply, guard pages fault and writes are counted for the caches of cores.
+//!
+//! A transfer has to lie within a single window and within the allowed addresses, and is refused as a whole otherwise.
+//! A transfer failing part way through memory stops at the byte which failed, with the bytes before it transferred. With
+//! an [Injector], transfers also fail or are delayed as it decides.
+//! ```
+//! use atln_processor::emulator::device::dma::{Dma, DmaError, Window};
+//! use atln_processor::emulator::memory::Memory;
+//!
+//! let mut memory = Memory::from(vec![0; 0x400]);
+//!
+//! // The device sees a read only buffer at 0x200 through bus addresses starting at 0x1000, and a writable one at 0x300.
+//! let mut dma = Dma::default();
+//! dma.windows = Some(vec![
+//!     Window { range: 0x1000..0x1100, target: 0x200, writable: false },
+//!     Window { range: 0x2000..0x2100, target: 0x300, writable: true }
+//! ]);
+//!
+//! memory.bytes[0x200..0x204].copy_from_slice(b"ping");
+//! let mut buffer = [0; 4];
+//! dma.read(&memory, 0x1000, &mut buffer).unwrap();
+//! dma.write(&mut memory, 0x2000, &buffer).unwrap();
+//! assert_eq!(&memory.bytes[0x300..0x304], b"ping");
+//!
+//! assert_eq!(dma.write(&mut memory, 0x1000, b"pong"), Err(DmaError::ReadOnly(0x1000)));
+//! assert_eq!(dma.read(&memory, 0x10FE, &mut buffer), Err(DmaError::Unmapped(0x1100)));
+//! assert_eq!(dma.read(&memory, 0x3000, &mut buffer), Err(DmaError::Unmapped(0x3000)));
+//! assert_eq!((dma.statistics.bytes, dma.statistics.refused), (8, 3));
+//! ```
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::ops::Range;
+use emulator::fault::Injector;
+use emulator::memory::{Frame, GetError, MemoryAccess};
+use number;
+use number::Size;
+
+/// Bus addresses an IOMMU maps onto physical memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// Bus addresses covered by the window.
+    pub range: Range<u64>,
+    /// Physical address which the start of the range maps to.
+    pub target: u64,
+    /// Whether the device may write through the window.
+    pub writable: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DmaError {
+    /// The bus address is not in the window holding the start of the transfer, or in any window.
+    Unmapped(u64),
+    /// The transfer writes through a read only window at the bus address.
+    ReadOnly(u64),
+    /// The transfer reaches the physical address, which is not allowed for the device.
+    Denied(u64),
+    /// Memory could not be accessed at the physical address.
+    Memory { address: u64, error: GetError },
+    /// The [Injector] failed the transfer.
+    Injected
+}
+
+impl Display for DmaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unmapped(address) => write!(f, "bus address {address:#x} is not mapped"),
+            Self::ReadOnly(address) => write!(f, "bus address {address:#x} is mapped read only"),
+            Self::Denied(address) => write!(f, "physical address {address:#x} is not allowed for the device"),
+            Self::Memory { address, .. } => write!(f, "failed to access memory at {address:#x}"),
+            Self::Injected => f.write_str("transfer failed by fault injection")
+        }
+    }
+}
+
+impl Error for DmaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Memory { error, .. } => Some(error),
+            _ => None
+        }
+    }
+}
+
+/// Counts of the transfers a device made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    pub reads: u64,
+    pub writes: u64,
+    /// Bytes moved by transfers, including the ones moved before a transfer failed.
+    pub bytes: u64,
+    /// Transfers refused before moving any bytes.
+    pub refused: u64
+}
+
+/// Checked access to guest memory on behalf of a device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dma {
+    /// Windows of the IOMMU, or [None] if bus addresses are physical addresses.
+    pub windows: Option<Vec<Window>>,
+    /// Physical addresses the device may access.
+    pub allowed: Range<u64>,
+    /// Source of the transfer failures and delays injected, if any.
+    pub injector: Option<Injector>,
+    pub statistics: Statistics
+}
+
+impl Default for Dma {
+    /// Access to all of physical memory without an IOMMU.
+    fn default() -> Self {
+        Self { windows: None, allowed: 0..u64::MAX, injector: None, statistics: Statistics::default() }
+    }
+}
+
+impl Dma {
+    /// Physical address of a transfer of a number of bytes starting at a bus address, after checking that the device
+    /// may make it.
+    pub fn translate(&self, address: u64, length: u64, write: bool) -> Result<u64, DmaError> {
+        let last = address.checked_add(length.saturating_sub(1)).ok_or(DmaError::Unmapped(address))?;
+
+        let physical = match &self.windows {
+            None => address,
+            Some(windows) => {
+                let window = windows.iter().find(|window| window.range.contains(&address)).ok_or(DmaError::Unmapped(address))?;
+                if !window.range.contains(&last) { return Err(DmaError::Unmapped(window.range.end)) }
+                if write && !window.writable { return Err(DmaError::ReadOnly(address)) }
+
+                window.target.checked_add(address - window.range.start).ok_or(DmaError::Denied(u64::MAX))?
+            }
+        };
+
+        let end = physical.checked_add(length).ok_or(DmaError::Denied(u64::MAX))?;
+        if !self.allowed.contains(&physical) { return Err(DmaError::Denied(physical)) }
+        if length != 0 && end > self.allowed.end { return Err(DmaError::Denied(self.allowed.end)) }
+        Ok(physical)
+    }
+
+    /// Read bytes from guest memory into a buffer. Returns the cycles the transfer is delayed by, which the device
+    /// should wait before completing it.
+    pub fn read(&mut self, memory: &dyn MemoryAccess, address: u64, buffer: &mut [u8]) -> Result<u64, DmaError> {
+        let (physical, delay) = self.begin(address, buffer.len() as u64, false)?;
+        self.statistics.reads += 1;
+
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            let address = physical + index as u64;
+            *byte = memory.get(Frame { address, size: Size::Byte }, false).map_err(|error| DmaError::Memory { address, error })?.quad() as u8;
+            self.statistics.bytes += 1;
+        }
+
+        Ok(delay)
+    }
+
+    /// Write bytes to guest memory. Returns the cycles the transfer is delayed by like [Dma::read].
+    /// ```
+    /// use atln_processor::emulator::device::dma::{Dma, DmaError};
+    /// use atln_processor::emulator::memory::{GetError, Memory};
+    ///
+    /// let mut memory = Memory::from(vec![0; 32]);
+    /// memory.guards.push(16..32);
+    ///
+    /// let mut dma = Dma::default();
+    /// dma.allowed = 8..32;
+    ///
+    /// assert_eq!(dma.write(&mut memory, 4, &[1; 8]), Err(DmaError::Denied(4)));
+    /// assert_eq!(dma.write(&mut memory, 12, &[1; 8]), Err(DmaError::Memory { address: 16, error: GetError::Guard(16) }));
+    /// assert_eq!(memory.bytes[8..20], [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0]);
+    /// ```
+    pub fn write(&mut self, memory: &mut dyn MemoryAccess, address: u64, data: &[u8]) -> Result<u64, DmaError> {
+        let (physical, delay) = self.begin(address, data.len() as u64, true)?;
+        self.statistics.writes += 1;
+
+        for (index, byte) in data.iter().enumerate() {
+            let address = physical + index as u64;
+            memory.set(Frame { address, size: Size::Byte }, false, number::Data::Byte(*byte)).map_err(|error| DmaError::Memory { address, error })?;
+            self.statistics.bytes += 1;
+        }
+
+        Ok(delay)
+    }
+
+    /// Check a transfer and ask the injector about it, counting it as refused if it fails.
+    fn begin(&mut self, address: u64, length: u64, write: bool) -> Result<(u64, u64), DmaError> {
+        let result = self.translate(address, length, write).and_then(|physical| match &mut self.injector {
+            Some(injector) => if injector.device_error() { Err(DmaError::Injected) } else { Ok((physical, injector.delay())) },
+            None => Ok((physical, 0))
+        });
+
+        if result.is_err() { self.statistics.refused += 1 }
+        result
+    }
+}
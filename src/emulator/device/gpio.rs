@@ -0,0 +1,167 @@
+//! General purpose pins, which guest firmware reads and drives like the pins of a microcontroller while the host
+//! scripts the outside world connected to them.
+//!
+//! A [Gpio] has 64 pins, each either an input driven by the host or an output driven by the guest. Once the device is
+//! plugged into a [port space](crate::emulator::port), the guest accesses the pins in banks of 8 through [PORTS] ports:
+//!
+//! | Offset | Read                                | Write                                          |
+//! | ------ | ----------------------------------- | ---------------------------------------------- |
+//! | 0 - 7  | Levels of the pins of the bank.     | Levels the output pins of the bank are set to. |
+//! | 8 - 15 | Which pins of the bank are outputs. | Which pins of the bank are outputs.            |
+//!
+//! The host drives the inputs directly with [Gpio::drive], or through a [driver](Gpio::script) which is called as the
+//! device is [ticked](Clocked::tick) to drive the inputs according to the emulated time. An
+//! [observer](Gpio::observe) is told about every pin whose level changes.
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use atln_processor::emulator::device::gpio::Gpio;
+//! use atln_processor::emulator::device::scheduler::Clocked;
+//! use atln_processor::emulator::port::Device;
+//!
+//! let changes = Arc::new(Mutex::new(Vec::new()));
+//! let observed = changes.clone();
+//!
+//! let mut gpio = Gpio::default();
+//! gpio.observe(move |pin, level| observed.lock().unwrap().push((pin, level)));
+//!
+//! // A button on pin 0 is pressed after 100 cycles.
+//! gpio.script(|now, inputs| if now >= 100 { inputs | 1 } else { inputs });
+//!
+//! // The firmware makes pin 9 an output and lights an LED on it.
+//! gpio.write(9, 0b10);
+//! gpio.write(1, 0b10);
+//! assert!(gpio.level(9));
+//!
+//! gpio.tick(60);
+//! assert_eq!(gpio.read(0), 0);
+//! gpio.tick(60);
+//! assert_eq!(gpio.read(0), 1);
+//!
+//! assert_eq!(*changes.lock().unwrap(), [(9, true), (0, true)]);
+//! ```
+
+use alloc::boxed::Box;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use emulator::port::Device;
+use super::scheduler::Clocked;
+
+/// Number of pins.
+pub const PINS: u8 = 64;
+/// Offset of the first port giving which pins are outputs.
+pub const DIRECTION_OFFSET: u16 = 8;
+/// Number of ports the device answers.
+pub const PORTS: u16 = 16;
+
+/// Function driving the inputs, called with the cycles elapsed so far and the current input levels, and returning the
+/// new input levels.
+pub type Driver = Box<dyn FnMut(u64, u64) -> u64 + Send>;
+/// Function told about a pin whose level changed, along with its new level.
+pub type Observer = Box<dyn FnMut(u8, bool) + Send>;
+
+/// Pins which are read and driven by both the guest and the host.
+#[derive(Default)]
+pub struct Gpio {
+    /// One bit for each pin which is an output driven by the guest.
+    pub outputs: u64,
+    /// Levels the guest drives the output pins to.
+    pub latch: u64,
+    /// Levels the host drives the input pins to.
+    pub inputs: u64,
+    /// Cycles elapsed since the device was created.
+    pub now: u64,
+    driver: Option<Driver>,
+    observer: Option<Observer>
+}
+
+impl Debug for Gpio {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gpio")
+            .field("outputs", &self.outputs)
+            .field("latch", &self.latch)
+            .field("inputs", &self.inputs)
+            .field("now", &self.now)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Gpio {
+    /// Drive the inputs with a function as the device is ticked, replacing the previous one.
+    pub fn script(&mut self, driver: impl FnMut(u64, u64) -> u64 + Send + 'static) {
+        self.driver = Some(Box::new(driver));
+    }
+
+    /// Tell a function about every pin whose level changes, replacing the previous one. The function is called in the
+    /// middle of a guest write, a tick or [Gpio::drive], while the device is borrowed, so it can't drive pins itself and
+    /// must not lock the port space holding the device.
+    pub fn observe(&mut self, observer: impl FnMut(u8, bool) + Send + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Level of every pin, one bit each.
+    pub fn levels(&self) -> u64 {
+        (self.latch & self.outputs) | (self.inputs & !self.outputs)
+    }
+
+    /// Level of a pin. Pins past [PINS] are low.
+    pub fn level(&self, pin: u8) -> bool {
+        pin < PINS && self.levels() & (1 << pin) != 0
+    }
+
+    /// Drive an input pin to a level. The level is kept for the pin but does not show while it is an output.
+    /// ```
+    /// use atln_processor::emulator::device::gpio::Gpio;
+    ///
+    /// let mut gpio = Gpio::default();
+    /// gpio.drive(3, true);
+    /// assert!(gpio.level(3));
+    ///
+    /// gpio.outputs = 1 << 3;
+    /// assert!(!gpio.level(3));
+    /// ```
+    pub fn drive(&mut self, pin: u8, level: bool) {
+        if pin >= PINS { return }
+        self.change(|gpio| if level { gpio.inputs |= 1 << pin } else { gpio.inputs &= !(1 << pin) });
+    }
+
+    /// Make a change to the pins, telling the observer about every pin whose level it changed.
+    fn change(&mut self, change: impl FnOnce(&mut Self)) {
+        let before = self.levels();
+        change(self);
+        let changed = before ^ self.levels();
+
+        let Some(observer) = &mut self.observer else { return };
+        for pin in (0..PINS).filter(|pin| changed & (1 << pin) != 0) { observer(pin, before & (1 << pin) == 0) }
+    }
+}
+
+impl Clocked for Gpio {
+    fn tick(&mut self, cycles: u64) {
+        self.now = self.now.wrapping_add(cycles);
+
+        let Some(mut driver) = self.driver.take() else { return };
+        let inputs = driver(self.now, self.inputs);
+        self.driver = Some(driver);
+        self.change(|gpio| gpio.inputs = inputs);
+    }
+}
+
+impl Device for Gpio {
+    fn read(&mut self, offset: u16) -> u8 {
+        match offset {
+            0..DIRECTION_OFFSET => (self.levels() >> (offset * 8)) as u8,
+            DIRECTION_OFFSET..PORTS => (self.outputs >> ((offset - DIRECTION_OFFSET) * 8)) as u8,
+            _ => 0
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        let set = |bits: &mut u64, bank: u16| *bits = *bits & !(0xFF << (bank * 8)) | (value as u64) << (bank * 8);
+
+        match offset {
+            0..DIRECTION_OFFSET => self.change(|gpio| set(&mut gpio.latch, offset)),
+            DIRECTION_OFFSET..PORTS => self.change(|gpio| set(&mut gpio.outputs, offset - DIRECTION_OFFSET)),
+            _ => {}
+        }
+    }
+}
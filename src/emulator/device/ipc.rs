@@ -0,0 +1,260 @@
+//! Shared memory between the guest and host services, for exchanging bulk data without copying it through ports. Only
+//! available with the `std` feature.
+//!
+//! A [Segment] is a block of bytes owned by the host, which services on any host thread access while the guest runs.
+//! An [Ipc] device maps it into guest memory at [Ipc::base] through [Hooks], so guest reads of the mapped range see the
+//! bytes of the segment and guest writes are stored to it. Guest memory must exist under the mapped range, and it keeps
+//! a copy of what the guest wrote which guests must not rely on.
+//!
+//! Either side tells the other that data is ready by ringing its doorbell with a value, which queues up until the
+//! other side takes it. Each doorbell holds up to [DOORBELL_CAPACITY] values, and values rung while it is full are
+//! dropped and counted in the [Overruns] of the segment. The guest rings the host by writing a port of the device, and
+//! takes the values the host rang it with by reading it:
+//!
+//! | Offset | Read                                                            | Write                                                              |
+//! | ------ | --------------------------------------------------------------- | ------------------------------------------------------------------ |
+//! | 0      | Next value the host rang the guest with, or 0 if there is none. | Ring the host with the value, dropping it if the doorbell is full. |
+//! | 1      | Number of values waiting for the guest.                         |                                                                    |
+//!
+//! Host services [wait](Segment::wait) for the guest to ring, and [ring](Segment::ring) the guest, which raises
+//! [Ipc::vector] on the core while values wait for it once the owner of the core [applies](Ipc::apply) it.
+//! ```
+//! use std::sync::Arc;
+//! use atln_processor::emulator::device::ipc::{Ipc, Segment};
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::memory::hook::{Hooked, Hooks};
+//! use atln_processor::emulator::port::Device;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The host service fills the segment, which the guest sees at 64, and the guest writes a result after it.
+//! let segment = Arc::new(Segment::new(16));
+//! segment.write(0, &40u64.to_le_bytes());
+//!
+//! let mut ipc = Ipc::new(segment.clone(), 64);
+//! let mut hooks = Hooks::default();
+//! ipc.map(&mut hooks);
+//!
+//! let mut program = assemble("add.q r1, [64]\nadd.q r1, 2\nadd.q [72], r1\nhalt").unwrap();
+//! program.resize(80, 0);
+//! let mut memory = Memory::from(program);
+//! Core::default().run(&mut Hooked { memory: &mut memory, hooks: &hooks }, &mut Default::default(), None);
+//!
+//! // The guest rings the host once the result is ready.
+//! ipc.write(0, 1);
+//! assert_eq!(segment.receive(), Some(1));
+//!
+//! let mut result = [0; 8];
+//! segment.read(8, &mut result);
+//! assert_eq!(u64::from_le_bytes(result), 42);
+//! ```
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use emulator::memory::hook::Hooks;
+use emulator::port::Device;
+use emulator::processor::processor::Context;
+use number;
+
+/// Offset of the port giving the number of values waiting for the guest.
+pub const WAITING_OFFSET: u16 = 1;
+/// Most values either doorbell holds, which is also the most the port giving the number of waiting values can report.
+pub const DOORBELL_CAPACITY: usize = u8::MAX as usize;
+
+/// Counts of values dropped because they were rung while the doorbell was full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Overruns {
+    /// Values the guest rang the host with.
+    pub host: u64,
+    /// Values the host rang the guest with.
+    pub guest: u64
+}
+
+/// Values rung by either side which the other side did not take yet.
+#[derive(Debug, Default)]
+struct Doorbells {
+    host: VecDeque<u8>,
+    guest: VecDeque<u8>,
+    overruns: Overruns
+}
+
+/// Queue a value on a doorbell unless it is full, counting it as an overrun otherwise. Returns whether it was queued.
+fn queue(doorbell: &mut VecDeque<u8>, overruns: &mut u64, value: u8) -> bool {
+    if doorbell.len() >= DOORBELL_CAPACITY {
+        *overruns += 1;
+        return false
+    }
+
+    doorbell.push_back(value);
+    true
+}
+
+/// Bytes shared by the guest and host services along with the doorbells of both sides.
+#[derive(Debug)]
+pub struct Segment {
+    bytes: Mutex<Vec<u8>>,
+    doorbells: Mutex<Doorbells>,
+    /// Notified whenever the guest rings the host.
+    rung: Condvar
+}
+
+impl Segment {
+    /// Create a segment of zeroed bytes.
+    pub fn new(length: usize) -> Self {
+        Self { bytes: Mutex::new(vec![0; length]), doorbells: Mutex::default(), rung: Condvar::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy bytes starting at an offset into a buffer. Returns whether the bytes are within the segment, and nothing is
+    /// copied otherwise.
+    pub fn read(&self, offset: usize, buffer: &mut [u8]) -> bool {
+        let bytes = self.bytes();
+        let Some(source) = offset.checked_add(buffer.len()).and_then(|end| bytes.get(offset..end)) else { return false };
+        buffer.copy_from_slice(source);
+        true
+    }
+
+    /// Copy bytes into the segment starting at an offset. Returns whether the bytes are within the segment, and nothing
+    /// is copied otherwise.
+    pub fn write(&self, offset: usize, data: &[u8]) -> bool {
+        let mut bytes = self.bytes();
+        let Some(target) = offset.checked_add(data.len()).and_then(|end| bytes.get_mut(offset..end)) else { return false };
+        target.copy_from_slice(data);
+        true
+    }
+
+    /// Ring the doorbell of the guest with a value, dropping it if the doorbell is full. Returns whether it was queued.
+    /// ```
+    /// use atln_processor::emulator::device::ipc::{Segment, DOORBELL_CAPACITY};
+    ///
+    /// let segment = Segment::new(0);
+    /// for value in 0..DOORBELL_CAPACITY { assert!(segment.ring(value as u8)) }
+    ///
+    /// assert!(!segment.ring(0));
+    /// assert_eq!(segment.overruns().guest, 1);
+    /// ```
+    pub fn ring(&self, value: u8) -> bool {
+        let doorbells = &mut *self.doorbells();
+        queue(&mut doorbells.guest, &mut doorbells.overruns.guest, value)
+    }
+
+    /// Values dropped by either doorbell so far.
+    pub fn overruns(&self) -> Overruns {
+        self.doorbells().overruns
+    }
+
+    /// Take the next value the guest rang the host with, if there is one.
+    pub fn receive(&self) -> Option<u8> {
+        self.doorbells().host.pop_front()
+    }
+
+    /// Take the next value the guest rang the host with, waiting up to a timeout for the guest to ring if there is
+    /// none.
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use atln_processor::emulator::device::ipc::{Ipc, Segment};
+    /// use atln_processor::emulator::port::Device;
+    ///
+    /// let segment = Arc::new(Segment::new(0));
+    /// let mut ipc = Ipc::new(segment.clone(), 0);
+    ///
+    /// let service = thread::spawn(move || segment.wait(Duration::from_secs(60)));
+    /// ipc.write(0, 7);
+    /// assert_eq!(service.join().unwrap(), Some(7));
+    /// ```
+    pub fn wait(&self, timeout: Duration) -> Option<u8> {
+        let doorbells = self.doorbells();
+        let (mut doorbells, _) = self.rung
+            .wait_timeout_while(doorbells, timeout, |doorbells| doorbells.host.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+
+        doorbells.host.pop_front()
+    }
+
+    fn bytes(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.bytes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn doorbells(&self) -> MutexGuard<'_, Doorbells> {
+        self.doorbells.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Guest side of a [Segment].
+#[derive(Debug, Clone)]
+pub struct Ipc {
+    pub segment: Arc<Segment>,
+    /// Physical address the segment is mapped at.
+    pub base: u64,
+    /// Vector raised while values wait for the guest, or [None] to leave the guest polling for them.
+    pub vector: Option<u8>
+}
+
+impl Ipc {
+    pub fn new(segment: Arc<Segment>, base: u64) -> Self {
+        Self { segment, base, vector: None }
+    }
+
+    /// Map the segment into guest memory accessed through the hooks, returning the identifier of the hook for
+    /// [Hooks::remove]. Accesses partly outside of the mapped range only reach the segment for the bytes inside it.
+    pub fn map(&self, hooks: &mut Hooks) -> u64 {
+        let (segment, base) = (self.segment.clone(), self.base);
+        let range = base..base.saturating_add(segment.len() as u64);
+
+        hooks.on_access(range, move |event| {
+            let mut value = event.new.quad().to_le_bytes();
+            let size = (event.size.size() as usize).min(value.len());
+
+            for (index, byte) in value.iter_mut().enumerate().take(size) {
+                let Some(offset) = event.address.wrapping_add(index as u64).checked_sub(base) else { continue };
+                let Ok(offset) = usize::try_from(offset) else { continue };
+                let slice = core::slice::from_mut(byte);
+
+                if event.write { segment.write(offset, slice); } else { segment.read(offset, slice); }
+            }
+
+            (!event.write).then(|| number::Data::from_size_selecting(&event.size, u64::from_le_bytes(value)))
+        })
+    }
+
+    /// Raise [Ipc::vector] on a core if values wait for the guest. Returns whether it was raised.
+    pub fn apply(&self, context: &mut Context) -> bool {
+        let Some(vector) = self.vector else { return false };
+        if self.segment.doorbells().guest.is_empty() { return false }
+
+        context.interrupts.raise(vector);
+        true
+    }
+}
+
+impl Device for Ipc {
+    fn read(&mut self, offset: u16) -> u8 {
+        let mut doorbells = self.segment.doorbells();
+        match offset {
+            0 => doorbells.guest.pop_front().unwrap_or(0),
+            WAITING_OFFSET => doorbells.guest.len() as u8,
+            _ => 0
+        }
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        if offset != 0 { return }
+
+        let doorbells = &mut *self.segment.doorbells();
+        if queue(&mut doorbells.host, &mut doorbells.overruns.host, value) { self.segment.rung.notify_all() }
+    }
+}
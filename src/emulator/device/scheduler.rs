@@ -0,0 +1,170 @@
+//! Advancing devices by the emulated time a core spends executing, so timers, serial baud rates and DMA transfers
+//! progress at the same rate relative to the guest however fast the host runs it.
+//!
+//! The [Scheduler] follows the cycle counter of a core. Each time it is [synchronised](Scheduler::sync), every
+//! registered device is advanced by the cycles elapsed since the last time. A long gap, such as after the host stopped
+//! running the core for a while, is caught up in slices of at most [Scheduler::slice] cycles with every device advanced
+//! by each slice in turn, so devices which affect each other observe the same interleaving as when they are synchronised
+//! often.
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use atln_processor::emulator::device::scheduler::Scheduler;
+//! use atln_processor::emulator::device::timer::Timer;
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::{Core, Status};
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let program = assemble("loop: add.q r1, 1\ncmp.q r1, 50\nmovz.q r3, exit\nmovnz.q r3, loop\ndivert r3\nexit: halt").unwrap();
+//!
+//! let timer = Arc::new(Mutex::new(Timer::new(10)));
+//! let mut scheduler = Scheduler::default();
+//! scheduler.add(timer.clone());
+//!
+//! let mut core = Core::default();
+//! let status = scheduler.run(&mut core, &mut Memory::from(program), &mut Default::default(), 16);
+//! assert!(matches!(status, Status::Halted));
+//! assert_eq!(scheduler.now, core.cycles);
+//! assert_eq!(timer.lock().unwrap().pending, core.cycles / 10);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, PoisonError};
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Budget, Core, Ports, Status};
+use super::timer::Timer;
+
+/// A device whose state changes as emulated time passes.
+pub trait Clocked {
+    /// Let a number of cycles elapse.
+    fn tick(&mut self, cycles: u64);
+}
+
+impl Clocked for Timer {
+    fn tick(&mut self, cycles: u64) {
+        self.advance(cycles)
+    }
+}
+
+/// Devices shared with the host, which inspects them while they are scheduled.
+#[cfg(feature = "std")]
+impl<T: Clocked> Clocked for Arc<Mutex<T>> {
+    fn tick(&mut self, cycles: u64) {
+        self.lock().unwrap_or_else(PoisonError::into_inner).tick(cycles)
+    }
+}
+
+/// A device along with its identifier.
+struct Entry {
+    identifier: u64,
+    device: Box<dyn Clocked + Send>
+}
+
+/// Devices advanced in step with the cycle counter of a core.
+pub struct Scheduler {
+    /// Cycle counter value the devices were last advanced to.
+    pub now: u64,
+    /// Most cycles devices are advanced by at once while catching up. A slice of 0 advances them by all the elapsed
+    /// cycles at once.
+    pub slice: u64,
+    devices: Vec<Entry>,
+    next: u64
+}
+
+impl Default for Scheduler {
+    /// Catch up in slices of 1024 cycles.
+    fn default() -> Self {
+        Self { now: 0, slice: 1024, devices: Vec::new(), next: 0 }
+    }
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("now", &self.now)
+            .field("slice", &self.slice)
+            .field("devices", &self.devices.iter().map(|entry| entry.identifier).collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduler {
+    /// Register a device, returning an identifier for [Scheduler::remove]. The device starts being advanced from the
+    /// current time.
+    pub fn add(&mut self, device: impl Clocked + Send + 'static) -> u64 {
+        let identifier = self.next;
+        self.next += 1;
+        self.devices.push(Entry { identifier, device: Box::new(device) });
+        identifier
+    }
+
+    /// Stop advancing a device, returning it.
+    pub fn remove(&mut self, identifier: u64) -> Option<Box<dyn Clocked + Send>> {
+        let index = self.devices.iter().position(|entry| entry.identifier == identifier)?;
+        Some(self.devices.remove(index).device)
+    }
+
+    /// Number of registered devices.
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Advance every device to a cycle counter value. Counter values before the current time are ignored, and so the
+    /// scheduler should be [reset](Scheduler::reset) when the counter is.
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use atln_processor::emulator::device::scheduler::{Clocked, Scheduler};
+    ///
+    /// /// Records the cycles it was advanced by.
+    /// #[derive(Default)]
+    /// struct Log(Vec<u64>);
+    ///
+    /// impl Clocked for Log {
+    ///     fn tick(&mut self, cycles: u64) { self.0.push(cycles) }
+    /// }
+    ///
+    /// let log = Arc::new(Mutex::new(Log::default()));
+    /// let mut scheduler = Scheduler::default();
+    /// scheduler.slice = 4;
+    /// scheduler.add(log.clone());
+    ///
+    /// scheduler.sync(10);
+    /// scheduler.sync(7);
+    /// assert_eq!(scheduler.now, 10);
+    /// assert_eq!(log.lock().unwrap().0, [4, 4, 2]);
+    /// ```
+    pub fn sync(&mut self, cycles: u64) {
+        let mut elapsed = cycles.saturating_sub(self.now);
+
+        while elapsed != 0 {
+            let slice = if self.slice == 0 { elapsed } else { elapsed.min(self.slice) };
+            for entry in &mut self.devices { entry.device.tick(slice) }
+            elapsed -= slice;
+        }
+
+        self.now = self.now.max(cycles);
+    }
+
+    /// Set the current time without advancing the devices.
+    pub fn reset(&mut self, cycles: u64) {
+        self.now = cycles;
+    }
+
+    /// Run a core until it stops, advancing the devices after every `quantum` cycles it executes. The devices lag
+    /// behind the core by at most a quantum and the cycles of the instruction crossing it.
+    pub fn run(&mut self, core: &mut Core, memory: &mut dyn MemoryAccess, ports: &mut Ports, quantum: u64) -> Status {
+        loop {
+            let status = core.run(memory, ports, Some(Budget::Cycles(quantum.max(1))));
+            self.sync(core.cycles);
+
+            if !matches!(status, Status::BudgetExhausted) { return status }
+        }
+    }
+}
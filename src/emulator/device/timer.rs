@@ -0,0 +1,65 @@
+//! Periodic timer driven by the cycle counter of a core.
+
+/// Counts cycles and expires every period. The timer is driven by feeding it either a number of elapsed cycles through
+/// [Timer::advance] or the cycle counter of a core through [Timer::sync].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Timer {
+    /// Number of cycles between expirations. A period of 0 disables the timer.
+    pub period: u64,
+    /// Cycles left until the next expiration.
+    pub remaining: u64,
+    /// Number of expirations that were not acknowledged yet.
+    pub pending: u64,
+    /// The cycle counter value the timer was last synchronized to.
+    pub synchronized: u64
+}
+
+impl Timer {
+    pub fn new(period: u64) -> Self {
+        Self { period, remaining: period, pending: 0, synchronized: 0 }
+    }
+
+    /// Let a number of cycles elapse.
+    /// ```
+    /// use atln_processor::emulator::device::timer::Timer;
+    ///
+    /// let mut timer = Timer::new(10);
+    /// timer.advance(25);
+    ///
+    /// assert_eq!(timer.pending, 2);
+    /// assert_eq!(timer.remaining, 5);
+    /// ```
+    pub fn advance(&mut self, cycles: u64) {
+        if self.period == 0 { return }
+
+        if cycles < self.remaining {
+            self.remaining -= cycles;
+            return;
+        }
+
+        let overflow = cycles - self.remaining;
+        self.pending += 1 + overflow / self.period;
+        self.remaining = self.period - overflow % self.period;
+    }
+
+    /// Let the cycles elapse which were counted since the last synchronization.
+    /// ```
+    /// use atln_processor::emulator::device::timer::Timer;
+    ///
+    /// let mut timer = Timer::new(4);
+    /// timer.sync(3);
+    /// assert_eq!(timer.pending, 0);
+    ///
+    /// timer.sync(8);
+    /// assert_eq!(timer.pending, 2);
+    /// ```
+    pub fn sync(&mut self, cycles: u64) {
+        self.advance(cycles.saturating_sub(self.synchronized));
+        self.synchronized = cycles;
+    }
+
+    /// Clear the pending expirations and return how many there were.
+    pub fn acknowledge(&mut self) -> u64 {
+        core::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,176 @@
+//! Watchdog which acts on the guest if it stops petting it, for testing that guest software stays live and recovers
+//! when it does not.
+//!
+//! The [Watchdog] counts down cycles as it is [ticked](Clocked::tick), usually by a [Scheduler](super::scheduler).
+//! Petting it starts the count down again. Once it runs out, the watchdog expires, notifies the host if it is
+//! [watched](Watchdog::watch) and starts counting down again. Expirations are held until the owner of the core
+//! [applies](Watchdog::apply) them, which either raises an interrupt or resets the core.
+//!
+//! Guest software reaches the watchdog once it is plugged into a [port space](crate::emulator::port). Writing anything
+//! to its port pets it, and reading the port gives the number of times it expired so far, wrapping around.
+//! ```
+//! use std::sync::{Arc, Mutex};
+//! use atln_processor::emulator::device::scheduler::Scheduler;
+//! use atln_processor::emulator::device::watchdog::{Action, Watchdog};
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::{Budget, Core, Status};
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The guest hangs without petting the watchdog, whose interrupt handler of vector 5 at 16 halts it. The table is at
+//! // 32.
+//! let mut program = assemble("divert r3").unwrap();
+//! program.resize(16, 0);
+//! program.extend(assemble("halt").unwrap());
+//! program.resize(72, 0);
+//! program.extend(16u64.to_le_bytes());
+//!
+//! let bitten = Arc::new(Mutex::new(0));
+//! let notified = bitten.clone();
+//!
+//! let mut watchdog = Watchdog::new(100, Action::Interrupt(5));
+//! watchdog.watch(move |expirations| *notified.lock().unwrap() = expirations);
+//!
+//! let watchdog = Arc::new(Mutex::new(watchdog));
+//! let mut scheduler = Scheduler::default();
+//! scheduler.add(watchdog.clone());
+//!
+//! let mut memory = Memory::from(program);
+//! let mut core = Core::default();
+//! core.context.interrupts.table = 32;
+//! core.context.interrupts.enabled = true;
+//!
+//! let status = loop {
+//!     let status = core.run(&mut memory, &mut Default::default(), Some(Budget::Cycles(16)));
+//!     scheduler.sync(core.cycles);
+//!     watchdog.lock().unwrap().apply(&mut core.context);
+//!
+//!     if !matches!(status, Status::BudgetExhausted) { break status }
+//! };
+//!
+//! assert!(matches!(status, Status::Halted));
+//! assert!(core.cycles >= 100);
+//! assert_eq!(*bitten.lock().unwrap(), 1);
+//! ```
+
+use alloc::boxed::Box;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use emulator::port::Device;
+use emulator::processor::processor::Context;
+use super::scheduler::Clocked;
+
+/// What an expired watchdog does to the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Raise the interrupt vector.
+    Interrupt(u8),
+    /// Reset the execution context of the core, which then starts executing from the address. Memory is kept.
+    Reset(u64)
+}
+
+/// Counts down the cycles until the guest is acted on unless it pets the watchdog first.
+pub struct Watchdog {
+    /// Number of cycles the guest has to pet the watchdog in. An interval of 0 disables the watchdog.
+    pub interval: u64,
+    /// Cycles left until the watchdog expires.
+    pub remaining: u64,
+    pub action: Action,
+    /// Expirations which were not applied to the guest yet.
+    pub pending: u64,
+    /// Expirations since the watchdog was created.
+    pub expirations: u64,
+    /// Called with the number of expirations whenever the watchdog expires.
+    notify: Option<Box<dyn FnMut(u64) + Send>>
+}
+
+impl Debug for Watchdog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("interval", &self.interval)
+            .field("remaining", &self.remaining)
+            .field("action", &self.action)
+            .field("pending", &self.pending)
+            .field("expirations", &self.expirations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Watchdog {
+    pub fn new(interval: u64, action: Action) -> Self {
+        Self { interval, remaining: interval, action, pending: 0, expirations: 0, notify: None }
+    }
+
+    /// Call a function with the number of expirations whenever the watchdog expires, replacing the previous one. The
+    /// function is called from the tick that expired it, before the expiration reaches the core, so it can report or
+    /// count expirations but acting on the core is left to [Watchdog::apply].
+    pub fn watch(&mut self, notify: impl FnMut(u64) + Send + 'static) {
+        self.notify = Some(Box::new(notify));
+    }
+
+    /// Start counting down the interval again.
+    pub fn pet(&mut self) {
+        self.remaining = self.interval;
+    }
+
+    /// Act on a core for the pending expirations, which are cleared. Several expirations only act once. Returns the
+    /// action taken, if any.
+    /// ```
+    /// use atln_processor::emulator::device::scheduler::Clocked;
+    /// use atln_processor::emulator::device::watchdog::{Action, Watchdog};
+    /// use atln_processor::emulator::processor::processor::Context;
+    ///
+    /// let mut context = Context { identifier: 3, program_counter: 0x40, ..Default::default() };
+    /// context.registers[1] = 7;
+    ///
+    /// let mut watchdog = Watchdog::new(10, Action::Reset(0x1000));
+    /// watchdog.tick(9);
+    /// watchdog.pet();
+    /// watchdog.tick(9);
+    /// assert_eq!(watchdog.apply(&mut context), None);
+    ///
+    /// watchdog.tick(25);
+    /// assert_eq!(watchdog.apply(&mut context), Some(Action::Reset(0x1000)));
+    /// assert_eq!((context.program_counter, context.registers[1], context.identifier), (0x1000, 0, 3));
+    /// assert_eq!(watchdog.expirations, 3);
+    /// ```
+    pub fn apply(&mut self, context: &mut Context) -> Option<Action> {
+        if core::mem::take(&mut self.pending) == 0 { return None }
+
+        match self.action {
+            Action::Interrupt(vector) => context.interrupts.raise(vector),
+            Action::Reset(address) => *context = Context { identifier: context.identifier, program_counter: address, ..Default::default() }
+        }
+
+        Some(self.action)
+    }
+}
+
+impl Clocked for Watchdog {
+    fn tick(&mut self, cycles: u64) {
+        if self.interval == 0 { return }
+
+        if cycles < self.remaining {
+            self.remaining -= cycles;
+            return;
+        }
+
+        let overflow = cycles - self.remaining;
+        let expired = 1 + overflow / self.interval;
+        self.pending += expired;
+        self.expirations += expired;
+        self.remaining = self.interval - overflow % self.interval;
+
+        let expirations = self.expirations;
+        if let Some(notify) = &mut self.notify { notify(expirations) }
+    }
+}
+
+impl Device for Watchdog {
+    fn read(&mut self, _offset: u16) -> u8 {
+        self.expirations as u8
+    }
+
+    fn write(&mut self, _offset: u16, _value: u8) {
+        self.pet()
+    }
+}
@@ -0,0 +1,135 @@
+//! Differences between two snapshots of the state of a machine, for checking that two executions agree and for showing
+//! what an instruction did.
+//!
+//! [context] compares the registers, program counter, flags, modes and model specific registers of a core. Interrupts,
+//! the reservation, the identifier, performance counters and pending TLB flushes are not compared. [memory] compares
+//! bytes and groups the ones that changed into contiguous ranges.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::processor::processor::Context;
+use emulator::processor::processor::instruction::operation::condition::Flags;
+
+/// Part of the context of a core which differs between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Register { index: u8, before: u64, after: u64 },
+    ProgramCounter { before: u64, after: u64 },
+    Flags { before: Flags, after: Flags },
+    VirtualMode { before: bool, after: bool },
+    UserMode { before: bool, after: bool },
+    ModelSpecific { index: u8, before: u64, after: u64 }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register { index, before, after } => write!(f, "r{index}: {before:#x} -> {after:#x}"),
+            Self::ProgramCounter { before, after } => write!(f, "pc: {before:#x} -> {after:#x}"),
+            Self::Flags { before, after } => write!(f, "flags: {before} -> {after}"),
+            Self::VirtualMode { before, after } => write!(f, "virtual mode: {before} -> {after}"),
+            Self::UserMode { before, after } => write!(f, "user mode: {before} -> {after}"),
+            Self::ModelSpecific { index, before, after } => write!(f, "msr{index}: {before:#x} -> {after:#x}")
+        }
+    }
+}
+
+/// Contiguous bytes of memory which differ between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub address: u64,
+    /// Bytes in the first snapshot. Shorter than the bytes after if the first snapshot ended first.
+    pub before: Vec<u8>,
+    /// Bytes in the second snapshot. Shorter than the bytes before if the second snapshot ended first.
+    pub after: Vec<u8>
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}: {:02x?} -> {:02x?}", self.address, self.before, self.after)
+    }
+}
+
+/// Registers, program counter, modes, flags and model specific registers that differ between two contexts, in the
+/// order of the fields of [Context]. Interrupts, the reservation, the identifier, performance counters and pending TLB flushes are
+/// skipped, so that comparing the context before and after an instruction shows what it computed rather than counters
+/// which change with every instruction.
+/// ```
+/// use atln_processor::emulator::diff::{context, Change};
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let mut core = Core::default();
+/// let before = core.context.clone();
+/// core.run(&mut Memory::from(assemble("add.q r2, 7\ncmp.q r2, 7\nhalt").unwrap()), &mut Default::default(), None);
+///
+/// let changes = context(&before, &core.context);
+/// assert_eq!(changes[0], Change::Register { index: 2, before: 0, after: 7 });
+/// assert_eq!(changes[1], Change::ProgramCounter { before: 0, after: 10 });
+/// assert_eq!(changes[2].to_string(), "flags: ---- -> z---");
+/// ```
+pub fn context(before: &Context, after: &Context) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for (index, (&before, &after)) in before.registers.iter().zip(&after.registers).enumerate() {
+        if before != after { changes.push(Change::Register { index: index as u8, before, after }); }
+    }
+
+    if before.program_counter != after.program_counter { changes.push(Change::ProgramCounter { before: before.program_counter, after: after.program_counter }); }
+    if before.virtual_mode != after.virtual_mode { changes.push(Change::VirtualMode { before: before.virtual_mode, after: after.virtual_mode }); }
+    if before.user_mode != after.user_mode { changes.push(Change::UserMode { before: before.user_mode, after: after.user_mode }); }
+    if before.flags != after.flags { changes.push(Change::Flags { before: before.flags, after: after.flags }); }
+
+    for (index, (&before, &after)) in before.model_specific.iter().zip(&after.model_specific).enumerate() {
+        if before != after { changes.push(Change::ModelSpecific { index: index as u8, before, after }); }
+    }
+
+    changes
+}
+
+/// Ranges of bytes that differ between two snapshots of memory starting at the same address. If one snapshot is
+/// longer, the bytes past the end of the other form a last range.
+/// ```
+/// use atln_processor::emulator::diff::{memory, Range};
+///
+/// let ranges = memory(0x100, &[1, 2, 3, 4, 5], &[1, 9, 9, 4, 6, 7]);
+/// assert_eq!(ranges, [
+///     Range { address: 0x101, before: vec![2, 3], after: vec![9, 9] },
+///     Range { address: 0x104, before: vec![5], after: vec![6, 7] }
+/// ]);
+/// ```
+pub fn memory(address: u64, before: &[u8], after: &[u8]) -> Vec<Range> {
+    let common = before.len().min(after.len());
+    let mut ranges: Vec<Range> = Vec::new();
+    let mut start = None;
+
+    for index in 0..=common {
+        let differs = index < common && before[index] != after[index];
+
+        match (start, differs) {
+            (None, true) => start = Some(index),
+            (Some(first), false) => {
+                ranges.push(Range { address: address.wrapping_add(first as u64), before: before[first..index].to_vec(), after: after[first..index].to_vec() });
+                start = None;
+            },
+            _ => ()
+        }
+    }
+
+    if before.len() != after.len() {
+        // Extend a range which reaches the end of the shorter snapshot rather than starting another right after it.
+        let first = match ranges.last() {
+            Some(last) if last.address.wrapping_sub(address) as usize + last.before.len() == common => {
+                let last = ranges.pop().unwrap();
+                last.address.wrapping_sub(address) as usize
+            },
+            _ => common
+        };
+
+        ranges.push(Range { address: address.wrapping_add(first as u64), before: before[first..].to_vec(), after: after[first..].to_vec() });
+    }
+
+    ranges
+}
@@ -0,0 +1,150 @@
+//! Differential execution of the same program by two backends in lockstep, for validating a rewrite of the execution
+//! path against the one it replaces.
+//!
+//! Each side has its own copy of the core, memory and ports. After the candidate [Backend] advances, the reference
+//! advances until it has completed as many instructions, as counted by the
+//! [performance counters](crate::emulator::processor::processor::instruction::operation::machine::Counters) of each
+//! core. The contexts, memory and statuses of both sides are then compared with [diff], and the first difference is
+//! reported as a [Divergence]. A backend which executes whole blocks is only compared at the end of each block, so the
+//! divergence points to the block rather than the instruction within it.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+use emulator::diff;
+use emulator::diff::{Change, Range};
+use emulator::memory::Memory;
+use emulator::processor::processor::{Core, Ports, Status};
+#[cfg(feature = "jit")]
+use emulator::processor::processor::jit::Jit;
+
+/// A way of executing instructions on a core.
+pub trait Backend {
+    /// Execute at least one instruction starting at the program counter, unless the core stops first.
+    fn advance(&mut self, core: &mut Core, memory: &mut Memory, ports: &mut Ports) -> Status;
+}
+
+impl<F: FnMut(&mut Core, &mut Memory, &mut Ports) -> Status> Backend for F {
+    fn advance(&mut self, core: &mut Core, memory: &mut Memory, ports: &mut Ports) -> Status {
+        self(core, memory, ports)
+    }
+}
+
+/// Executes one instruction at a time with [Core::step].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Interpreter;
+
+impl Backend for Interpreter {
+    fn advance(&mut self, core: &mut Core, memory: &mut Memory, ports: &mut Ports) -> Status {
+        core.step(memory, ports)
+    }
+}
+
+/// Executes whole blocks decoded by [Core::decode_block]. An instruction which fails to decode is left to
+/// [Core::step] so it faults in the same way.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blocks;
+
+impl Backend for Blocks {
+    fn advance(&mut self, core: &mut Core, memory: &mut Memory, ports: &mut Ports) -> Status {
+        match core.decode_block(memory, core.context.program_counter) {
+            Ok(block) => core.execute_block(&block, memory, ports),
+            Err(_) => core.step(memory, ports)
+        }
+    }
+}
+
+#[cfg(feature = "jit")]
+impl Backend for Jit {
+    /// Execute a block through the compiler. A block which fails to compile is executed by the interpreter instead.
+    fn advance(&mut self, core: &mut Core, memory: &mut Memory, ports: &mut Ports) -> Status {
+        let block = match core.decode_block(memory, core.context.program_counter) {
+            Ok(block) => block,
+            Err(_) => return core.step(memory, ports)
+        };
+
+        self.execute_block(core, &block, memory, ports).unwrap_or_else(|_| core.execute_block(&block, memory, ports))
+    }
+}
+
+/// First point at which the two sides disagreed. Differences are from the reference to the candidate.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Instructions completed by the reference when the difference was found.
+    pub instructions: u64,
+    /// Program counter of the candidate before it executed the instructions that diverged.
+    pub address: u64,
+    pub changes: Vec<Change>,
+    pub memory: Vec<Range>,
+    /// Statuses of the reference and the candidate, if they stopped in different ways.
+    pub statuses: Option<Box<(Status, Status)>>
+}
+
+/// State of one side of a lockstep execution.
+struct Side {
+    core: Core,
+    memory: Memory,
+    ports: Ports,
+    status: Status
+}
+
+impl Side {
+    fn advance(&mut self, backend: &mut dyn Backend) {
+        self.status = backend.advance(&mut self.core, &mut self.memory, &mut self.ports);
+    }
+
+    fn instructions(&self) -> u64 {
+        self.core.context.counters.instructions
+    }
+}
+
+/// Execute a program on both backends from the same core and memory until both stop or `limit` instructions were
+/// completed. Returns the number of instructions completed, or the first divergence.
+/// ```
+/// use atln_processor::emulator::differential::{lockstep, Blocks, Interpreter};
+/// use atln_processor::emulator::diff::Change;
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::{Core, Ports, Status};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let memory = Memory::from(assemble("loop: add.q r1, 1\ncmp.q r1, 3\nmovz.q r3, exit\nmovnz.q r3, loop\ndivert r3\nexit: halt").unwrap());
+/// assert_eq!(lockstep(&Core::default(), &memory, &mut Interpreter, &mut Blocks, 1000).unwrap(), 16);
+///
+/// // A backend which forgets to write back the second register diverges on the first instruction.
+/// let mut broken = |core: &mut Core, memory: &mut Memory, ports: &mut Ports| {
+///     let status = core.step(memory, ports);
+///     core.context.registers[1] = 0;
+///     status
+/// };
+///
+/// let divergence = lockstep(&Core::default(), &memory, &mut Interpreter, &mut broken, 1000).unwrap_err();
+/// assert_eq!((divergence.instructions, divergence.address), (1, 0));
+/// assert_eq!(divergence.changes, [Change::Register { index: 1, before: 1, after: 0 }]);
+/// ```
+pub fn lockstep(core: &Core, memory: &Memory, reference: &mut dyn Backend, candidate: &mut dyn Backend, limit: u64) -> Result<u64, Divergence> {
+    let side = || Side { core: core.clone(), memory: memory.clone(), ports: Ports::default(), status: Status::Running };
+    let mut expected = side();
+    let mut actual = side();
+    let start = core.context.counters.instructions;
+
+    while actual.status.is_running() && expected.status.is_running() && actual.instructions().wrapping_sub(start) < limit {
+        let address = actual.core.context.program_counter;
+        actual.advance(candidate);
+
+        // Catch up whichever side is behind, in case the reference also executes more than one instruction at a time.
+        while expected.status.is_running() && expected.instructions() < actual.instructions() { expected.advance(reference); }
+        while actual.status.is_running() && actual.instructions() < expected.instructions() { actual.advance(candidate); }
+
+        let changes = diff::context(&expected.core.context, &actual.core.context);
+        let ranges = diff::memory(0, &expected.memory.bytes, &actual.memory.bytes);
+        let stopped = mem::discriminant(&expected.status) != mem::discriminant(&actual.status);
+
+        if !changes.is_empty() || !ranges.is_empty() || stopped || expected.instructions() != actual.instructions() {
+            let instructions = expected.instructions().wrapping_sub(start);
+            let statuses = stopped.then(|| Box::new((mem::replace(&mut expected.status, Status::Running), mem::replace(&mut actual.status, Status::Running))));
+            return Err(Divergence { instructions, address, changes, memory: ranges, statuses })
+        }
+    }
+
+    Ok(actual.instructions().wrapping_sub(start))
+}
@@ -0,0 +1,91 @@
+//! Notifying observers of what happens in the machine as it happens.
+//!
+//! Every [Core] has [Events] which subscribers are added to, either directly or for all cores of a
+//! [System](super::system::System) at once. Subscribers are called on the thread executing the core, in the order they
+//! subscribed, before the core continues. Events name the core they happened on by its
+//! [identifier](super::processor::processor::Context::identifier).
+//!
+//! Port reads are not reported, since operations access the ports directly. Writes are detected by comparing the ports
+//! before and after each instruction, so writing the value a port already holds is not reported either.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use emulator::memory::GetError;
+#[cfg(doc)]
+use emulator::processor::processor::Core;
+
+/// Something that happened on a core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The core halted, either by executing `halt` or by exiting through
+    /// [semihosting](super::processor::processor::semihosting).
+    Halt { core: u64 },
+    /// The core entered the handler of an interrupt.
+    Trap { core: u64, vector: u8 },
+    /// An instruction changed the value of a port.
+    PortWrite { core: u64, port: u8, value: u8 },
+    /// The instruction at an address faulted because memory could not be accessed.
+    MemoryFault { core: u64, address: u64, error: GetError },
+    /// Debug [breakpoints](super::processor::processor::debug) matched, one bit for each.
+    Breakpoint { core: u64, hits: u64 }
+}
+
+/// Function called with every event.
+pub type Subscriber = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Subscribers of a core. Clones of a core share its subscribers.
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use atln_processor::emulator::event::Event;
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let events = Arc::new(Mutex::new(Vec::new()));
+/// let received = events.clone();
+///
+/// let mut core = Core::default();
+/// core.events.subscribe(move |event| received.lock().unwrap().push(event.clone()));
+/// core.run(&mut Memory::from(assemble("add.b r1, [64]\nhalt").unwrap()), &mut Default::default(), None);
+///
+/// assert!(matches!(events.lock().unwrap()[..], [Event::MemoryFault { core: 0, address: 0, .. }]));
+/// ```
+#[derive(Clone, Default)]
+pub struct Events {
+    subscribers: Vec<Subscriber>
+}
+
+impl Events {
+    /// Call a function with every event from now on.
+    pub fn subscribe(&mut self, subscriber: impl Fn(&Event) + Send + Sync + 'static) {
+        self.add(Arc::new(subscriber));
+    }
+
+    /// Add a subscriber which may already be shared with other cores.
+    pub fn add(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    /// Remove every subscriber.
+    pub fn clear(&mut self) {
+        self.subscribers.clear();
+    }
+
+    /// Whether there are no subscribers, which lets the core skip gathering events.
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Call every subscriber with an event.
+    pub fn emit(&self, event: Event) {
+        for subscriber in &self.subscribers { subscriber(&event); }
+    }
+}
+
+impl Debug for Events {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events").field("subscribers", &self.subscribers.len()).finish()
+    }
+}
@@ -0,0 +1,165 @@
+//! Fault injection, for testing how guest software copes with failing hardware. An [Injector] decides pseudo randomly
+//! when a fault happens, and the same seed always makes the same decisions so a failure can be reproduced.
+//!
+//! Faults are given as rates of one in a number of chances, where a rate of 0 never injects the fault:
+//! - Bit flips corrupt a single bit of values read from memory through [Injected], like a soft error in RAM.
+//! - Device errors make a device operation fail transiently. Devices ask [Injector::device_error] before completing
+//!   an operation and report the error to the guest the way they would report a real one.
+//! - Delays hold back a DMA transfer by a number of cycles. Devices ask [Injector::delay] when a transfer starts.
+//! ```
+//! use std::cell::RefCell;
+//! use atln_processor::emulator::fault::{Injected, Injector};
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let mut program = assemble("add.q r1, [64]\nhalt").unwrap();
+//! program.resize(72, 0);
+//! let mut memory = Memory::from(program);
+//!
+//! // Every read of the data at 64 flips a bit.
+//! let mut injector = Injector::new(7);
+//! injector.bit_flips = 1;
+//! injector.range = 64..72;
+//!
+//! let injector = RefCell::new(injector);
+//! let mut core = Core::default();
+//! core.run(&mut Injected { memory: &mut memory, injector: &injector }, &mut Default::default(), None);
+//!
+//! assert_eq!(core.context.registers[1].count_ones(), 1);
+//! assert_eq!(injector.borrow().statistics.flips, 1);
+//! assert_eq!(memory.bytes[64], 0);
+//! ```
+
+use core::cell::RefCell;
+use core::ops::Range;
+use emulator::memory::{Frame, GetError, MemoryAccess};
+use number;
+use number::Size;
+use utility::splitmix64;
+
+/// Counts of the faults injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    pub flips: u64,
+    pub device_errors: u64,
+    pub delays: u64
+}
+
+/// Deterministic source of faults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injector {
+    /// Rate of reads which have a bit flipped, as one in this many reads.
+    pub bit_flips: u64,
+    /// Addresses whose reads can have a bit flipped, which are virtual while the core is in virtual mode. A read is
+    /// eligible if its first byte is in the range.
+    pub range: Range<u64>,
+    /// Rate of device operations which fail, as one in this many operations.
+    pub device_errors: u64,
+    /// Rate of DMA transfers which are delayed, as one in this many transfers.
+    pub delays: u64,
+    /// Most cycles a transfer is delayed by. Delays are between 1 and this many cycles.
+    pub max_delay: u64,
+    pub statistics: Statistics,
+    state: u64
+}
+
+impl Injector {
+    /// Create an injector which injects no faults until its rates are set.
+    pub fn new(seed: u64) -> Self {
+        Self { bit_flips: 0, range: 0..u64::MAX, device_errors: 0, delays: 0, max_delay: 1, statistics: Statistics::default(), state: seed }
+    }
+
+    /// Flip a pseudo randomly picked bit of a value read from an address if a bit flip is due.
+    /// ```
+    /// use atln_processor::emulator::fault::Injector;
+    /// use atln_processor::number::Data;
+    ///
+    /// let mut injector = Injector::new(3);
+    /// injector.bit_flips = 1;
+    /// injector.range = 0..8;
+    ///
+    /// assert_eq!(injector.flip(8, Data::Word(0)), Data::Word(0));
+    /// assert_eq!((injector.flip(0, Data::Byte(0xFF)).quad() as u8).count_ones(), 7);
+    /// ```
+    pub fn flip(&mut self, address: u64, value: number::Data) -> number::Data {
+        if !self.range.contains(&address) || !self.chance(self.bit_flips) { return value }
+
+        self.statistics.flips += 1;
+        let bit = self.next() % (value.size() as u64 * 8);
+        number::Data::from_size_selecting(&Size::from(value.clone()), value.quad() ^ (1 << bit))
+    }
+
+    /// Whether a device operation should fail. The same seed gives the same sequence of failures.
+    /// ```
+    /// use atln_processor::emulator::fault::Injector;
+    ///
+    /// let decisions = |seed| {
+    ///     let mut injector = Injector::new(seed);
+    ///     injector.device_errors = 4;
+    ///     (0..64).map(|_| injector.device_error()).collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(decisions(1), decisions(1));
+    /// assert!(decisions(1).contains(&true) && decisions(1).contains(&false));
+    /// ```
+    pub fn device_error(&mut self) -> bool {
+        let error = self.chance(self.device_errors);
+        if error { self.statistics.device_errors += 1 }
+        error
+    }
+
+    /// Cycles to hold back a DMA transfer by, which is 0 unless a delay is due.
+    pub fn delay(&mut self) -> u64 {
+        if !self.chance(self.delays) { return 0 }
+
+        self.statistics.delays += 1;
+        1 + self.next() % self.max_delay.max(1)
+    }
+
+    /// Whether a fault with a rate of one in a number of chances happens.
+    fn chance(&mut self, rate: u64) -> bool {
+        rate != 0 && self.next().is_multiple_of(rate)
+    }
+
+    /// Advance the generator with [splitmix64].
+    fn next(&mut self) -> u64 {
+        splitmix64(&mut self.state)
+    }
+}
+
+/// Memory whose reads may have a bit flipped by an [Injector]. Only the value returned is corrupted, so memory keeps
+/// holding what was written. Instructions are fetched by reading memory too, so code in the range can be corrupted.
+pub struct Injected<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub injector: &'a RefCell<Injector>
+}
+
+impl<'a> MemoryAccess for Injected<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let address = frame.address;
+        let value = self.memory.get(frame, r#virtual)?;
+        Ok(self.injector.borrow_mut().flip(address, value))
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.memory.set(frame, r#virtual, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let injector = self.injector;
+        self.memory.synchronise(&mut |memory| operation(&mut Injected { memory, injector }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
@@ -0,0 +1,40 @@
+//! Diagnostics from the emulator itself through the [tracing](https://docs.rs/tracing) ecosystem.
+//!
+//! With the `tracing` feature, cores and memories emit spans and events which any `tracing` subscriber can collect.
+//! Each subsystem emits to its own target, so the level of each is chosen separately by a filter such as
+//! `atln_processor::execute=trace,atln_processor::memory=debug`. Routine work is emitted at the trace level and
+//! failures at the debug level, which keeps debug output short enough to leave enabled.
+//!
+//! | Target           | Trace                                         | Debug                                |
+//! | ---------------- | --------------------------------------------- | ------------------------------------ |
+//! | [DECODE_TARGET]  | Instructions decoded rather than cached.      | Instructions which failed to decode. |
+//! | [EXECUTE_TARGET] | Spans of each step and instructions executed. | Faults and interrupts delivered.     |
+//! | [MEMORY_TARGET]  | Reads and writes of memory.                   | Reads and writes which failed.       |
+//! | [PORT_TARGET]    |                                               | Ports changed by an instruction.     |
+//!
+//! Without the feature nothing is emitted and none of the instrumentation is compiled. Accesses are only seen when they
+//! reach a [Memory] or [SharedMemory](super::memory::shared::SharedMemory), so stores held in a store buffer are traced
+//! once they drain.
+
+#[cfg(feature = "tracing")]
+use emulator::memory::{Frame, GetError};
+#[cfg(doc)]
+use emulator::memory::Memory;
+#[cfg(feature = "tracing")]
+use number;
+
+pub const DECODE_TARGET : &str = "atln_processor::decode";
+pub const EXECUTE_TARGET: &str = "atln_processor::execute";
+pub const MEMORY_TARGET : &str = "atln_processor::memory";
+pub const PORT_TARGET   : &str = "atln_processor::port";
+
+/// Emit the result of reading or writing a frame of memory.
+#[cfg(feature = "tracing")]
+pub(crate) fn access(write: bool, frame: &Frame, r#virtual: bool, result: Result<&number::Data, &GetError>) {
+    let (address, size) = (frame.address, frame.size.size());
+
+    match result {
+        Ok(value) => tracing::trace!(target: MEMORY_TARGET, write, address, size, translated = r#virtual, value = value.quad(), "access"),
+        Err(error) => tracing::debug!(target: MEMORY_TARGET, write, address, size, translated = r#virtual, %error, "access failed")
+    }
+}
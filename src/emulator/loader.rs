@@ -0,0 +1,266 @@
+//! Executable images and loading them into memory.
+//!
+//! # Format
+//! All numbers are little endian.
+//!
+//! | Field         | Size | Description                                              |
+//! |---------------|------|----------------------------------------------------------|
+//! | Magic         | 4    | [MAGIC].                                                 |
+//! | Version       | 2    | [VERSION].                                               |
+//! | Section count | 2    | Number of sections following the header.                 |
+//! | Entry         | 8    | Address of the first instruction to execute.             |
+//!
+//! Every section is a header followed by its data.
+//!
+//! | Field         | Size | Description                                              |
+//! |---------------|------|----------------------------------------------------------|
+//! | Permissions   | 1    | [Permissions] flags.                                     |
+//! | Address       | 8    | Physical address the section is loaded at.               |
+//! | Size          | 8    | Bytes the section occupies in memory.                    |
+//! | Data length   | 8    | Bytes of data stored in the image, at most the size.     |
+//!
+//! Memory past the data of a section is zeroed up to its size, so uninitialized data takes up no room in the image.
+//!
+//! Anything after the last section is [DebugInfo]. It is never loaded into memory.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::Memory;
+use programming::debug::{DebugError, DebugInfo};
+use utility::{Encodable, SliceReader};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub const MAGIC: [u8; 4] = *b"ATLN";
+pub const VERSION: u16 = 1;
+pub const HEADER_BYTES: usize = 16;
+pub const SECTION_HEADER_BYTES: usize = 25;
+
+/// Source of the zeroes written past the data of sections.
+const ZEROES: [u8; 256] = [0; 256];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    /// The image does not start with [MAGIC].
+    Magic,
+    /// The image was made for a version of the format that is not supported.
+    Version(u16),
+    /// The image ended in the middle of a header or section.
+    Truncated,
+    /// A section has more data than its size.
+    SectionData,
+    /// A section does not fit in memory.
+    Memory,
+    /// The debug information after the sections is invalid.
+    Debug(DebugError)
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Magic => f.write_str("image does not start with the magic bytes"),
+            Self::Version(version) => write!(f, "image version {version} is not supported"),
+            Self::Truncated => f.write_str("image ended unexpectedly"),
+            Self::SectionData => f.write_str("section data is larger than the section"),
+            Self::Memory => f.write_str("section does not fit in memory"),
+            Self::Debug(_) => f.write_str("debug information is invalid")
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Debug(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<DebugError> for LoadError {
+    fn from(value: DebugError) -> Self {
+        Self::Debug(value)
+    }
+}
+
+/// Access allowed to the memory of a section. These are recorded for tools and operating systems, loading does not
+/// enforce them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool
+}
+
+impl Permissions {
+    pub const READ: u8 = 1 << 0;
+    pub const WRITE: u8 = 1 << 1;
+    pub const EXECUTE: u8 = 1 << 2;
+
+    pub fn from_flags(flags: u8) -> Self {
+        Self { read: flags & Self::READ != 0, write: flags & Self::WRITE != 0, execute: flags & Self::EXECUTE != 0 }
+    }
+
+    pub fn flags(&self) -> u8 {
+        (self.read as u8 * Self::READ) | (self.write as u8 * Self::WRITE) | (self.execute as u8 * Self::EXECUTE)
+    }
+}
+
+/// A region of memory described by an image.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Section {
+    pub permissions: Permissions,
+    pub address: u64,
+    /// Bytes occupied in memory. Anything past [Section::data] is zeroed.
+    pub size: u64,
+    pub data: Vec<u8>
+}
+
+/// A program along with where to load it and where to start executing it.
+/// ```
+/// use atln_processor::emulator::loader::{Image, Permissions, Section};
+/// use atln_processor::utility::Encodable;
+///
+/// let mut image = Image {
+///     entry: 0x100,
+///     sections: vec![
+///         Section { permissions: Permissions { read: true, execute: true, ..Default::default() }, address: 0x100, size: 2, data: vec![0b000010_0_0, 0b0000_00_00] },
+///         Section { permissions: Permissions { read: true, write: true, ..Default::default() }, address: 0x200, size: 64, data: vec![] }
+///     ],
+///     debug: None
+/// };
+///
+/// assert_eq!(Image::parse(&image.encode()), Ok(image.clone()));
+///
+/// image.debug = Some(Default::default());
+/// assert_eq!(Image::parse(&image.encode()), Ok(image));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Image {
+    pub entry: u64,
+    pub sections: Vec<Section>,
+    /// Symbols and source lines for tools, if the image was linked with them.
+    pub debug: Option<DebugInfo>
+}
+
+impl Image {
+    /// Create an image with a single section holding a flat program which starts executing at its first byte.
+    pub fn flat(address: u64, program: Vec<u8>) -> Self {
+        Self {
+            entry: address,
+            sections: vec![Section {
+                permissions: Permissions { read: true, write: false, execute: true },
+                address,
+                size: program.len() as u64,
+                data: program
+            }],
+            debug: None
+        }
+    }
+
+    /// Whether some bytes start like an image, as opposed to being a flat program.
+    pub fn is_image(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, LoadError> {
+        let mut reader = SliceReader::new(bytes);
+
+        if reader.take(MAGIC.len()) != Some(&MAGIC[..]) { return Err(LoadError::Magic) }
+
+        let version = reader.u16().ok_or(LoadError::Truncated)?;
+        if version != VERSION { return Err(LoadError::Version(version)) }
+
+        let count = reader.u16().ok_or(LoadError::Truncated)?;
+        let entry = reader.u64().ok_or(LoadError::Truncated)?;
+        let mut sections = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let permissions = Permissions::from_flags(reader.u8().ok_or(LoadError::Truncated)?);
+            let address = reader.u64().ok_or(LoadError::Truncated)?;
+            let size = reader.u64().ok_or(LoadError::Truncated)?;
+            let length = reader.u64().ok_or(LoadError::Truncated)?;
+
+            if length > size { return Err(LoadError::SectionData) }
+            let data = usize::try_from(length).ok().and_then(|length| reader.take(length)).ok_or(LoadError::Truncated)?.to_vec();
+
+            sections.push(Section { permissions, address, size, data });
+        }
+
+        let debug = if reader.remaining().is_empty() { None } else { Some(DebugInfo::parse(reader.remaining())?) };
+        Ok(Self { entry, sections, debug })
+    }
+
+    /// Write every section into physical memory, zeroing the part of each section past its data.
+    pub fn load_into(&self, memory: &mut Memory) -> Result<(), LoadError> {
+        for section in &self.sections {
+            if memory.write_bytes(section.address, false, &section.data) != section.data.len() { return Err(LoadError::Memory) }
+
+            // Zeroed in chunks so a large size does not need an equally large buffer before memory rejects it.
+            let mut address = section.address.wrapping_add(section.data.len() as u64);
+            let mut remaining = section.size.saturating_sub(section.data.len() as u64);
+            while remaining > 0 {
+                let chunk = &ZEROES[..remaining.min(ZEROES.len() as u64) as usize];
+                if memory.write_bytes(address, false, chunk) != chunk.len() { return Err(LoadError::Memory) }
+
+                address = address.wrapping_add(chunk.len() as u64);
+                remaining -= chunk.len() as u64;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Encodable<Vec<u8>> for Image {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.sections.iter().map(|section| SECTION_HEADER_BYTES + section.data.len()).sum::<usize>());
+        bytes.extend(MAGIC);
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((self.sections.len() as u16).to_le_bytes());
+        bytes.extend(self.entry.to_le_bytes());
+
+        for section in &self.sections {
+            bytes.push(section.permissions.flags());
+            bytes.extend(section.address.to_le_bytes());
+            bytes.extend(section.size.to_le_bytes());
+            bytes.extend((section.data.len() as u64).to_le_bytes());
+            bytes.extend(&section.data);
+        }
+
+        if let Some(debug) = &self.debug { bytes.extend(debug.encode()); }
+
+        bytes
+    }
+}
+
+/// Parse an image and load its sections into memory. The entry point is returned so the program counter can be set to
+/// it.
+/// ```
+/// use atln_processor::emulator::loader::{load, Image, LoadError};
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::utility::Encodable;
+///
+/// let mut image = Image::flat(4, vec![1, 2, 3]);
+/// image.sections[0].size = 5;
+///
+/// let mut memory = Memory::from(vec![0xFF; 10]);
+/// assert_eq!(load(&mut memory, &image.encode()), Ok(4));
+/// assert_eq!(memory.bytes, [0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3, 0, 0, 0xFF]);
+///
+/// // Sections must fit in memory.
+/// image.sections[0].address = 8;
+/// assert_eq!(load(&mut memory, &image.encode()), Err(LoadError::Memory));
+/// assert_eq!(load(&mut memory, b"ELF"), Err(LoadError::Magic));
+/// ```
+pub fn load(memory: &mut Memory, bytes: &[u8]) -> Result<u64, LoadError> {
+    let image = Image::parse(bytes)?;
+    image.load_into(memory)?;
+    Ok(image.entry)
+}
@@ -29,14 +29,43 @@
 //! # Translation
 //! Virtual addresses are meant to be translated before they can be used by the processor. Translation involves 
 //! injecting a different page into the address and then using that new address. The item remains the same.
-
-use std::collections::HashMap;
+//!
+//! # Mirrors
+//! Hardware often decodes only some of the bits of an address, so the same memory appears at several physical
+//! addresses. A [Mirror] makes a range of physical addresses alias other memory, either repeating a smaller block
+//! through the whole range or placing a window onto memory elsewhere. Physical addresses, including translated ones,
+//! are [resolved](Memory::resolve) through the mirrors before memory is accessed.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::Range;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
 use std::io;
-use std::io::{ErrorKind, Read, Seek, SeekFrom};
-use utility::{LastError, ReadAll, write_buffer_into_vec};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom};
+use utility::Map;
+#[cfg(feature = "std")]
+use utility::{LastError, ReadAll};
+#[cfg(feature = "tracing")]
+use emulator::instrument;
 use crate::number;
-use crate::number::{BYTE_SIZE, DUAL_SIZE, QUAD_SIZE, Size, WORD_SIZE};
-use crate::utility::read_vec_into_buffer;
+use crate::number::{QUAD_SIZE, Size};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod bank;
+pub mod dump;
+pub mod guard;
+pub mod hook;
+pub mod search;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod swap;
+pub mod tlb;
 
 // region: Constants
 pub const WORD_ALIGNED_MASK   : u64 = 0b1;
@@ -46,14 +75,16 @@ pub const QUAD_ALIGNED_MASK   : u64 = 0b111;
 pub const PAGE_ITEM_BITS      : u64 = 13;
 pub const PAGE_IDENTIFIER_MASK: u64 = u64::MAX << PAGE_ITEM_BITS;
 pub const PAGE_ITEM_MASK      : u64 = u64::MAX >> (64 - PAGE_ITEM_BITS);
-pub const MAX_PAGES_COUNT     : u64 = u64::MAX & PAGE_IDENTIFIER_MASK;
-pub const PAGE_BYTES_COUNT    : u64 = (u64::MAX & PAGE_ITEM_MASK) + 1;
+pub const MAX_PAGES_COUNT     : u64 = PAGE_IDENTIFIER_MASK;
+pub const PAGE_BYTES_COUNT    : u64 = PAGE_ITEM_MASK + 1;
+pub const WRITE_LINE_BITS     : u64 = 6;
 // pub const PAGE_BYTES_COUNT    : u64 = 2u64.pow(PAGE_ITEM_BITS as u32); TODO: Whats the issue? This generates the 
 //                                                                        TODO: maximum index, not the count. 
 // endregion
 
 /// An address frame which includes a memory address and the frame size.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Frame {
     pub address: u64,
     pub size: number::Size
@@ -63,7 +94,7 @@ impl Frame {
     /// Check to see if the current address frame is aligned to memory. Only aligned frames can be used to interact
     /// with memory.
     /// ```
-    /// use atln_processor::memory::Frame;
+    /// use atln_processor::emulator::memory::Frame;
     /// use atln_processor::number::Size;
     ///
     /// // Aligned
@@ -89,9 +120,15 @@ impl Frame {
         masked == 0
     }
 
-    /// Gets the largest targeted address.
+    /// Gets the largest targeted address. This saturates at [u64::MAX] rather than overflowing, so frames at the very
+    /// end of the address space are still out of bounds.
     pub fn max_address(&self) -> u64 {
-        self.address + self.size.size() as u64
+        self.address.saturating_add(self.size.size() as u64)
+    }
+
+    /// Error from the frame reaching past the end of memory.
+    pub(crate) fn out_of_bounds(&self) -> GetError {
+        GetError::OutOfBounds(MemoryError::OutOfBounds { address: self.address, length: self.size.size() as u64 })
     }
 }
 
@@ -128,19 +165,22 @@ pub trait Address {
 
 impl Address for u64 {
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use atln_processor::emulator::memory::{Address, PAGE_ITEM_BITS};
+    ///
+    /// assert_eq!(0b1010u64.extract_item(), 0b1010);
+    /// assert_eq!(((5u64 << PAGE_ITEM_BITS) | 0b1010).extract_item(), 0b1010);
     /// ```
     fn extract_item(&self) -> u64 {
         PAGE_ITEM_MASK & self
     }
 
     /// ```
-    /// use atln_processor::memory::Address;
+    /// use atln_processor::emulator::memory::Address;
     ///
     /// // TODO: Exhaustive testing potentially required.
-    /// assert_eq!(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_11111111), 0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_11111111);
-    /// assert_eq!(0b00000000_00000000_00000000_00000000_00000000_00000000_00100000_00000000.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000011), 0b00000000_00000000_00000000_00000000_00000000_00000000_00100000_00000011);
-    /// assert_eq!(0b11111111_00000000_00000000_00000000_00000000_00000000_00000000_00000000.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00001010), 0b11111111_00000000_00000000_00000000_00000000_00000000_00000000_00001010);
+    /// assert_eq!(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000_u64.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_11111111), 0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_11111111);
+    /// assert_eq!(0b00000000_00000000_00000000_00000000_00000000_00000000_00100000_00000000_u64.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000011), 0b00000000_00000000_00000000_00000000_00000000_00000000_00100000_00000011);
+    /// assert_eq!(0b11111111_00000000_00000000_00000000_00000000_00000000_00000000_00000000_u64.set_item(0b00000000_00000000_00000000_00000000_00000000_00000000_00000000_00001010), 0b11111111_00000000_00000000_00000000_00000000_00000000_00000000_00001010);
     /// ```
     fn set_item(&self, r#virtual: u64) -> u64 {
         let page_item = r#virtual & PAGE_ITEM_MASK;
@@ -148,17 +188,23 @@ impl Address for u64 {
     }
 
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use atln_processor::emulator::memory::{Address, PAGE_ITEM_BITS};
+    ///
+    /// assert_eq!(0b1010u64.extract_page(), 0);
+    /// assert_eq!(((5u64 << PAGE_ITEM_BITS) | 0b1010).extract_page(), 5);
     /// ```
     fn extract_page(&self) -> u64 {
         (PAGE_IDENTIFIER_MASK & self) >> PAGE_ITEM_BITS
     }
 
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use atln_processor::emulator::memory::{Address, PAGE_ITEM_BITS};
+    ///
+    /// assert_eq!(0b1010u64.set_page(5), (5 << PAGE_ITEM_BITS) | 0b1010);
+    /// assert_eq!(((3u64 << PAGE_ITEM_BITS) | 0b1010).set_page(0), 0b1010);
     /// ```
-    fn set_page(&self, _page: u64) -> u64 {
-        todo!()
+    fn set_page(&self, page: u64) -> u64 {
+        (self & PAGE_ITEM_MASK) | page.offset_page()
     }
 
     /// ```
@@ -174,17 +220,109 @@ impl Address for u64 {
 /// - If the memory is size constrained, then ensure the frame is not reaching past the memory size limit.
 /// - Frames must be aligned to simulate hardware limitations of an implemented memory module.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Memory {
     pub bytes: Vec<u8>,
     pub max_address: Option<u64>,
     /// Number of bytes in each page.
     pub page_size: u64,
     /// Mappings of virtual page addresses to physical page addresses.
-    pub pages: HashMap<u64, u64>
+    pub pages: Map<u64, u64>,
+    /// Number of writes made through [Memory::set] to each line of physical memory, keyed by the line index. A line is
+    /// 2 to the power of [WRITE_LINE_BITS] bytes. Caches of data derived from memory compare these counters to detect
+    /// that their source was modified. Writing to [Memory::bytes] directly is not tracked.
+    pub line_writes: Map<u64, u64>,
+    /// Ranges of physical addresses which alias other memory. When mirrors overlap, the first one containing an address
+    /// is used.
+    pub mirrors: Vec<Mirror>,
+    /// Ranges of physical addresses which fault on any access, usually whole pages. See [guard].
+    pub guards: Vec<Range<u64>>
+}
+
+/// A range of physical addresses which aliases other memory. Address `range.start + n` aliases `target + n % length`,
+/// so a length shorter than the range repeats the same block of memory through it, while a length of the whole range
+/// places a window onto memory starting at the target. A length of 0 is treated as the length of the range.
+///
+/// A frame is placed by the address of its first byte, so frames of aligned accesses stay within a block as long as
+/// the range and length are multiples of 8. Targets are not resolved again, so a mirror can't point into another one.
+/// ```
+/// use atln_processor::emulator::memory::{Frame, Memory, Mirror};
+/// use atln_processor::number::{Data, Size};
+///
+/// // 16 bytes of memory, where 16 to 32 repeats the 4 bytes from 0 and 32 to 48 is a window onto 8 to 24.
+/// let mut memory = Memory::from(vec![0u8; 16]);
+/// memory.max_address = None;
+/// memory.mirrors.push(Mirror { range: 16..32, target: 0, length: 4 });
+/// memory.mirrors.push(Mirror { range: 32..48, target: 8, length: 16 });
+///
+/// memory.set(Frame { address: 20, size: Size::Word }, false, Data::Word(0x0201)).unwrap();
+/// assert_eq!(memory.bytes[..4], [1, 2, 0, 0]);
+/// assert_eq!(memory.get(Frame { address: 28, size: Size::Byte }, false), Ok(Data::Byte(1)));
+///
+/// assert_eq!(memory.resolve(32), 8);
+/// assert_eq!(memory.read_number(36, Size::Dual), memory.read_number(12, Size::Dual));
+/// assert!(memory.get(Frame { address: 40, size: Size::Quad }, false).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mirror {
+    pub range: Range<u64>,
+    /// Physical address aliased by the start of the range.
+    pub target: u64,
+    /// Number of bytes which repeat through the range.
+    pub length: u64
+}
+
+impl Mirror {
+    /// Address aliased by an address in the range, or [None] if the address is outside of it.
+    pub fn resolve(&self, address: u64) -> Option<u64> {
+        if !self.range.contains(&address) { return None }
+
+        let offset = address - self.range.start;
+        Some(self.target.wrapping_add(offset.checked_rem(self.length).unwrap_or(offset)))
+    }
+}
+
+/// Operations a core needs from the memory it executes against. This is implemented by [Memory] and by
+/// [SharedMemory](shared::SharedMemory), which lets cores on different host threads use the same memory at once.
+pub trait MemoryAccess {
+    /// Read the data targeted by a frame. See [Memory::get].
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError>;
+
+    /// Write data to the target of a frame. See [Memory::set].
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError>;
+
+    /// Translate a virtual address into a physical address. See [Memory::translate_virtual].
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64>;
+
+    /// Get the number of writes made to the line containing a physical address. See [Memory::write_count].
+    fn write_count(&self, address: u64) -> u64;
+
+    /// Perform an operation that must not have its accesses interleaved with those of any other synchronised operation
+    /// on the same memory. Instructions with the synchronise bit set are executed through this.
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess));
+
+    /// Make every earlier access visible to other users of the memory before any later one. Memory that only has a
+    /// single user does nothing.
+    fn fence(&mut self) {}
+
+    /// Read bytes one at a time into the buffer starting from an address. See [Memory::read_bytes].
+    fn read_bytes(&self, address: u64, translate: bool, buffer: &mut [u8]) -> usize {
+        for (index, byte) in buffer.iter_mut().enumerate() {
+            let frame = Frame { address: address.wrapping_add(index as u64), size: Size::Byte };
+            match self.get(frame, translate) {
+                Ok(value) => *byte = u8::from(value),
+                Err(_) => return index
+            }
+        }
+
+        buffer.len()
+    }
 }
 
 // region: Memory cursor
-/// A tool used for interacting with memory through a [Read] and [Write] stream.
+/// A tool used for interacting with memory through a [Read] and [Write] stream. Only available with the `std` feature.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct MemoryCursor<'a> {
     /// The location to start reading from. This does not apply when doing direct reads.
@@ -197,9 +335,16 @@ pub struct MemoryCursor<'a> {
     pub get_error: Option<GetError>
 }
 
+#[cfg(feature = "std")]
 impl<'a> From<&'a mut Memory> for MemoryCursor<'a> {
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use atln_processor::emulator::memory::{Memory, MemoryCursor};
+    ///
+    /// let mut memory = Memory::from(vec![0u8; 4]);
+    /// let cursor = MemoryCursor::from(&mut memory);
+    ///
+    /// assert_eq!(cursor.read_head, 0);
+    /// assert!(!cursor.translate);
     /// ```
     fn from(value: &'a mut Memory) -> Self {
         Self {
@@ -211,6 +356,7 @@ impl<'a> From<&'a mut Memory> for MemoryCursor<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> LastError<GetError> for MemoryCursor<'a> {
     fn last_error(&self) -> &Option<GetError> {
         &self.get_error
@@ -219,32 +365,42 @@ impl<'a> LastError<GetError> for MemoryCursor<'a> {
 
 // TODO: Implement write
 
+#[cfg(feature = "std")]
 impl<'a> Read for MemoryCursor<'a> {
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use std::io::Read;
+    /// use atln_processor::emulator::memory::{Memory, MemoryCursor};
+    ///
+    /// let mut memory = Memory::from(vec![1, 2, 3, 4]);
+    /// let mut cursor = MemoryCursor::from(&mut memory);
+    /// let mut buffer = [0u8; 2];
+    ///
+    /// assert_eq!(cursor.read(&mut buffer).unwrap(), 2);
+    /// assert_eq!(buffer, [1, 2]);
     /// ```
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let size = match Size::from_size(buf.len()) {
             Some(value) => value,
-            None => return Err(io::Error::new(ErrorKind::Other, "Invalid buffer length"))
+            None => return Err(io::Error::other("Invalid buffer length"))
         };
 
         let data = match self.memory.get(Frame { address: self.read_head, size }, self.translate) {
             Ok(result) => result,
             // Memory errors can be accessed after this function by executing
             // LastError<GetError>::last_error(&mut Memory).
-            Err(_) => return Err(io::Error::new(ErrorKind::Other, "Failed to read from memory"))
+            Err(_) => return Err(io::Error::other("Failed to read from memory"))
         };
 
         Ok(data.read_all(buf))
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> Seek for MemoryCursor<'a> {
     /// ```
     /// // TODO; Test
     /// ```
-    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         match pos {
             SeekFrom::Start(start) => self.read_head = start,
             SeekFrom::End(end) => self.read_head = (self.memory.bytes.len() as i64 - end) as u64,
@@ -257,22 +413,91 @@ impl<'a> Seek for MemoryCursor<'a> {
 // endregion
 
 /// Error caused from setting data in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SetError {
     /// Error from using an unaligned address frame.
     UnalignedFrame
 }
 
+impl Display for SetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnalignedFrame => "frame is not aligned to its size"
+        })
+    }
+}
+
+impl Error for SetError {}
+
 /// Caused by invalid parameters to initialize an address frame.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GetError {
     /// The memory address requested data that is sized outside the memory aligned divisions.
     UnalignedFrame,
     /// The address frame crosses the positive memory boundaries.
-    OutOfBounds,
+    OutOfBounds(MemoryError),
     /// Virtual memory context was in use but the remapping did not exist in the page list.
-    PageFault
+    PageFault,
+    /// The frame touches a [guard](Memory::guards) at a physical address.
+    Guard(u64)
+}
+
+impl Display for GetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnalignedFrame => "frame is not aligned to its size",
+            Self::OutOfBounds(_) => "frame is outside of memory",
+            Self::PageFault => "virtual address has no page mapping",
+            Self::Guard(_) => "frame touches a guard page"
+        })
+    }
+}
+
+impl Error for GetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::OutOfBounds(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<MemoryError> for GetError {
+    fn from(value: MemoryError) -> Self {
+        Self::OutOfBounds(value)
+    }
 }
 
+/// Error from accessing a range of physical memory. Every access a guest makes is range checked, so reaching outside of
+/// memory is reported as a fault of the instruction rather than stopping the host.
+/// ```
+/// use atln_processor::emulator::memory::{GetError, Memory, MemoryError};
+/// use atln_processor::emulator::processor::processor::{Core, Status};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let program = assemble("add.q r1, [0xFFFFFFFFFFFFFFF8]").unwrap();
+/// let mut memory = Memory { max_address: None, ..Memory::from(program) };
+///
+/// let Status::Faulted(exception) = Core::default().step(&mut memory, &mut Default::default()) else { panic!() };
+/// let error = MemoryError::OutOfBounds { address: 0xFFFFFFFFFFFFFFF8, length: 8 };
+/// assert_eq!(exception.memory(), Some(&GetError::OutOfBounds(error)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryError {
+    /// Some of the bytes in the range starting from the address are outside of memory.
+    OutOfBounds { address: u64, length: u64 }
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { address, length } => write!(f, "{length} bytes at {address:#x} are outside of memory")
+        }
+    }
+}
+
+impl Error for MemoryError {}
+
 impl Memory {
     /// Translate the virtual address into a physical address based on the current situation. This returns a unit if the
     /// page mapping does not exist. This is a page fault.
@@ -280,7 +505,7 @@ impl Memory {
     /// fault.
     /// ```
     /// use std::collections::HashMap;
-    /// use atln_processor::memory::{Memory};
+    /// use atln_processor::emulator::memory::{Memory};
     ///
     /// let mut memory = Memory::from(Vec::new());
     /// memory.pages = HashMap::from([
@@ -307,18 +532,33 @@ impl Memory {
         Some(physical_page.set_item(virtual_item))
     }
 
+    /// Physical address that memory is accessed at for a physical address, which is the address aliased by the first
+    /// [Mirror] containing it. Addresses outside of every mirror are returned as they are.
+    pub fn resolve(&self, address: u64) -> u64 {
+        self.mirrors.iter().find_map(|mirror| mirror.resolve(address)).unwrap_or(address)
+    }
+
     /// Utility function to check for errors in an address frame when performing operations on memory and to handle
     /// translating frame addresses.
     ///
     /// If the frame is marked as virtual through the [r#virtual] parameter, then the frame will have its address
-    /// translated. This also tests for the following errors:
+    /// translated. The physical address is then [resolved](Memory::resolve) through the mirrors. This also tests for the
+    /// following errors:
     /// - If the address is unaligned, then [Err(GetError::UnalignedFrame)] is returned.
     /// - Otherwise, if a page fault occurred, then [Err(GetError::PageFault)] is returned.
-    /// - Finally, if the address is out of bounds, then [Err(GetError::OutOfBounds)] is returned.
+    /// - Otherwise, if the frame touches a guard, then [Err(GetError::Guard)] is returned.
+    /// - Finally, if the address is out of bounds, then [Err(GetError::OutOfBounds)] is returned with the range.
     /// ```
-    /// assert!(false); // TODO: Test
+    /// use atln_processor::emulator::memory::{Frame, GetError, Memory, MemoryError};
+    /// use atln_processor::number::Size;
+    ///
+    /// let memory = Memory::from(vec![0u8; 4]);
+    ///
+    /// assert_eq!(memory.get(Frame { address: 1, size: Size::Word }, false), Err(GetError::UnalignedFrame));
+    /// assert_eq!(memory.get(Frame { address: 0, size: Size::Word }, true), Err(GetError::PageFault));
+    /// assert_eq!(memory.get(Frame { address: 0, size: Size::Quad }, false), Err(GetError::OutOfBounds(MemoryError::OutOfBounds { address: 0, length: 8 })));
     /// ```
-    fn process_test_frame(&self, frame: &mut Frame, translate: bool) -> Result<(), GetError> {
+    pub(crate) fn process_test_frame(&self, frame: &mut Frame, translate: bool) -> Result<(), GetError> {
         // Ensure the frame is aligned to emulate hardware limitations.
         if !frame.is_aligned() { return Err(GetError::UnalignedFrame) }
 
@@ -329,8 +569,15 @@ impl Memory {
             };
         }
 
+        frame.address = self.resolve(frame.address);
+
+        let end = frame.address.saturating_add(frame.size.size() as u64);
+        if let Some(guard) = self.guards.iter().find(|guard| guard.start < end && frame.address < guard.end) {
+            return Err(GetError::Guard(frame.address.max(guard.start)))
+        }
+
         // Make sure the frame bounds lies in the memory size range.
-        if let Some(max_address) = self.max_address { if frame.max_address() > max_address { return Err(GetError::OutOfBounds) }}
+        if let Some(max_address) = self.max_address { if frame.max_address() > max_address { return Err(frame.out_of_bounds()) }}
 
         Ok(())
     }
@@ -339,7 +586,7 @@ impl Memory {
     /// is not cached in this list, then a [GetError::PageFault] is caused.
     /// ```
     /// use std::collections::HashMap;
-    /// use atln_processor::memory::{Frame, Memory, PAGE_BYTES_COUNT, PAGE_ITEM_BITS};
+    /// use atln_processor::emulator::memory::{Frame, Memory, PAGE_BYTES_COUNT, PAGE_ITEM_BITS};
     /// use atln_processor::number::{Data, Size};
     ///
     /// // region: Basic non virtual addressing.
@@ -383,44 +630,307 @@ impl Memory {
     /// ```
     pub fn get(&self, mut frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
         self.process_test_frame(&mut frame, r#virtual)?;
-        let mut max_buffer = [0u8; QUAD_SIZE];
-
-        Ok(match frame.size {
-            Size::Byte => {
-                let buffer = &mut max_buffer[0..BYTE_SIZE];
-                if read_vec_into_buffer(&self.bytes, frame.address as usize, buffer) != buffer.len() { return Err(GetError::OutOfBounds) }
-                number::Data::Byte(buffer[0])
-            },
-            Size::Word => {
-                let buffer = &mut max_buffer[0..WORD_SIZE];
-                if read_vec_into_buffer(&self.bytes, frame.address as usize, buffer) != buffer.len() { return Err(GetError::OutOfBounds) }
-                number::Data::Word(u16::from_le_bytes([ buffer[0], buffer[1] ]))
-            },
-            Size::Dual => {
-                let buffer = &mut max_buffer[0..DUAL_SIZE];
-                if read_vec_into_buffer(&self.bytes, frame.address as usize, buffer) != buffer.len() { return Err(GetError::OutOfBounds) }
-                number::Data::Dual(u32::from_le_bytes([ buffer[0], buffer[1], buffer[2], buffer[3] ]))
-            },
-            Size::Quad => {
-                let buffer = &mut max_buffer[0..QUAD_SIZE];
-                if read_vec_into_buffer(&self.bytes, frame.address as usize, buffer) != buffer.len() { return Err(GetError::OutOfBounds) }
-                number::Data::Quad(u64::from_le_bytes([ buffer[0], buffer[1], buffer[2], buffer[3], buffer[4], buffer[5], buffer[6], buffer[7] ]))
-            }
-        })
+        Ok(self.number(frame.address, frame.size)?)
     }
     
     pub fn set(&mut self, mut frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
         self.process_test_frame(&mut frame, r#virtual)?;
-        let max_buffer = value.quad_buffer();
-        let buffer = frame.size.buffer(&max_buffer);
-        
-        match frame.size {
-            Size::Byte => *self.bytes.get_mut(frame.address as usize).ok_or(GetError::OutOfBounds)? = u8::from(value),
-            _ => if write_buffer_into_vec(&mut self.bytes, frame.address as usize, buffer) != buffer.len() { return Err(GetError::OutOfBounds) }
+        Ok(self.store(frame.address, &number::Data::from_size_selecting(&frame.size, value.quad()).to_le_bytes())?)
+    }
+
+    /// Read a little endian number from physical memory. Unlike [Memory::get], the address doesn't have to be aligned, so
+    /// this is meant for host tooling that inspects memory the way a program laid it out. Reading any byte outside of
+    /// memory is an error rather than a panic. The address is [resolved](Memory::resolve) through the mirrors first.
+    /// ```
+    /// use atln_processor::emulator::memory::{Memory, MemoryError};
+    /// use atln_processor::number::{Data, Size};
+    ///
+    /// let memory = Memory::from(vec![0, 1, 2, 3, 4]);
+    ///
+    /// assert_eq!(memory.read_number(1, Size::Dual), Ok(Data::Dual(0x04030201)));
+    /// assert_eq!(memory.read_number(4, Size::Word), Err(MemoryError::OutOfBounds { address: 4, length: 2 }));
+    /// assert_eq!(memory.read_number(u64::MAX, Size::Quad), Err(MemoryError::OutOfBounds { address: u64::MAX, length: 8 }));
+    /// ```
+    pub fn read_number(&self, address: u64, size: Size) -> Result<number::Data, MemoryError> {
+        self.number(self.resolve(address), size)
+    }
+
+    /// Read a little endian number from a resolved physical address.
+    fn number(&self, address: u64, size: Size) -> Result<number::Data, MemoryError> {
+        let mut buffer = [0u8; QUAD_SIZE];
+        let bytes = self.range(address, size.size() as usize)?;
+        buffer[..bytes.len()].copy_from_slice(bytes);
+
+        Ok(number::Data::from_size_selecting(&size, u64::from_le_bytes(buffer)))
+    }
+
+    /// Write a number to physical memory in little endian at any address. Memory without a [Memory::max_address] grows
+    /// to fit the number, and otherwise writing any byte past the maximum address is an error. The write is counted in
+    /// [Memory::line_writes] like one made through [Memory::set].
+    /// ```
+    /// use atln_processor::emulator::memory::{Memory, MemoryError};
+    /// use atln_processor::number::Data;
+    ///
+    /// let mut memory = Memory::from(vec![0u8; 4]);
+    ///
+    /// memory.write_number(1, Data::Word(0x0201)).unwrap();
+    /// assert_eq!(memory.bytes, [0, 1, 2, 0]);
+    /// assert_eq!(memory.write_number(3, Data::Word(0)), Err(MemoryError::OutOfBounds { address: 3, length: 2 }));
+    ///
+    /// let mut memory = Memory::default();
+    /// memory.write_number(2, Data::Byte(7)).unwrap();
+    /// assert_eq!(memory.bytes, [0, 0, 7]);
+    /// ```
+    pub fn write_number(&mut self, address: u64, value: number::Data) -> Result<(), MemoryError> {
+        self.put(address, &value.to_le_bytes())
+    }
+
+    /// Read a little endian [u16] from physical memory. See [Memory::read_number].
+    pub fn read_u16_le(&self, address: u64) -> Result<u16, MemoryError> {
+        self.load(address).map(u16::from_le_bytes)
+    }
+
+    /// Read a big endian [u16] from physical memory. See [Memory::read_number].
+    pub fn read_u16_be(&self, address: u64) -> Result<u16, MemoryError> {
+        self.load(address).map(u16::from_be_bytes)
+    }
+
+    /// Read a little endian [u32] from physical memory. See [Memory::read_number].
+    pub fn read_u32_le(&self, address: u64) -> Result<u32, MemoryError> {
+        self.load(address).map(u32::from_le_bytes)
+    }
+
+    /// Read a big endian [u32] from physical memory. See [Memory::read_number].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    ///
+    /// let memory = Memory::from(vec![0x12, 0x34, 0x56, 0x78]);
+    ///
+    /// assert_eq!(memory.read_u32_be(0), Ok(0x12345678));
+    /// assert_eq!(memory.read_u32_le(0), Ok(0x78563412));
+    /// assert!(memory.read_u32_be(1).is_err());
+    /// ```
+    pub fn read_u32_be(&self, address: u64) -> Result<u32, MemoryError> {
+        self.load(address).map(u32::from_be_bytes)
+    }
+
+    /// Read a little endian [u64] from physical memory. See [Memory::read_number].
+    pub fn read_u64_le(&self, address: u64) -> Result<u64, MemoryError> {
+        self.load(address).map(u64::from_le_bytes)
+    }
+
+    /// Read a big endian [u64] from physical memory. See [Memory::read_number].
+    pub fn read_u64_be(&self, address: u64) -> Result<u64, MemoryError> {
+        self.load(address).map(u64::from_be_bytes)
+    }
+
+    /// Write a little endian [u16] to physical memory. See [Memory::write_number].
+    pub fn write_u16_le(&mut self, address: u64, value: u16) -> Result<(), MemoryError> {
+        self.put(address, &value.to_le_bytes())
+    }
+
+    /// Write a big endian [u16] to physical memory. See [Memory::write_number].
+    pub fn write_u16_be(&mut self, address: u64, value: u16) -> Result<(), MemoryError> {
+        self.put(address, &value.to_be_bytes())
+    }
+
+    /// Write a little endian [u32] to physical memory. See [Memory::write_number].
+    pub fn write_u32_le(&mut self, address: u64, value: u32) -> Result<(), MemoryError> {
+        self.put(address, &value.to_le_bytes())
+    }
+
+    /// Write a big endian [u32] to physical memory. See [Memory::write_number].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    ///
+    /// let mut memory = Memory::from(vec![0u8; 6]);
+    ///
+    /// memory.write_u32_be(1, 0x12345678).unwrap();
+    /// assert_eq!(memory.bytes, [0, 0x12, 0x34, 0x56, 0x78, 0]);
+    /// assert_eq!(memory.read_u16_le(2), Ok(0x5634));
+    /// assert!(memory.write_u32_le(3, 0).is_err());
+    /// ```
+    pub fn write_u32_be(&mut self, address: u64, value: u32) -> Result<(), MemoryError> {
+        self.put(address, &value.to_be_bytes())
+    }
+
+    /// Write a little endian [u64] to physical memory. See [Memory::write_number].
+    pub fn write_u64_le(&mut self, address: u64, value: u64) -> Result<(), MemoryError> {
+        self.put(address, &value.to_le_bytes())
+    }
+
+    /// Write a big endian [u64] to physical memory. See [Memory::write_number].
+    pub fn write_u64_be(&mut self, address: u64, value: u64) -> Result<(), MemoryError> {
+        self.put(address, &value.to_be_bytes())
+    }
+
+    /// Bytes of physical memory in a range, as long as every one of them is in memory.
+    fn range(&self, address: u64, length: usize) -> Result<&[u8], MemoryError> {
+        let error = MemoryError::OutOfBounds { address, length: length as u64 };
+        let end = address.checked_add(length as u64).filter(|&end| self.max_address.is_none_or(|max| end <= max)).ok_or(error.clone())?;
+        let end = usize::try_from(end).map_err(|_| error.clone())?;
+
+        self.bytes.get(end - length..end).ok_or(error)
+    }
+
+    /// Copy a fixed number of bytes out of physical memory, resolving the address through the mirrors.
+    fn load<const LENGTH: usize>(&self, address: u64) -> Result<[u8; LENGTH], MemoryError> {
+        let mut bytes = [0u8; LENGTH];
+        bytes.copy_from_slice(self.range(self.resolve(address), LENGTH)?);
+        Ok(bytes)
+    }
+
+    /// Copy bytes into physical memory, resolving the address through the mirrors.
+    fn put(&mut self, address: u64, bytes: &[u8]) -> Result<(), MemoryError> {
+        self.store(self.resolve(address), bytes)
+    }
+
+    /// Copy bytes into physical memory, growing it to fit them if it has no maximum address. Growing fails rather than
+    /// aborting when the bytes can't be allocated.
+    fn store(&mut self, address: u64, bytes: &[u8]) -> Result<(), MemoryError> {
+        let error = MemoryError::OutOfBounds { address, length: bytes.len() as u64 };
+        let end = address.checked_add(bytes.len() as u64).filter(|&end| self.max_address.is_none_or(|max| end <= max)).ok_or(error.clone())?;
+        let end = usize::try_from(end).map_err(|_| error.clone())?;
+
+        if self.bytes.len() < end {
+            self.bytes.try_reserve(end - self.bytes.len()).map_err(|_| error)?;
+            self.bytes.resize(end, 0);
         }
-        
+        self.bytes[end - bytes.len()..end].copy_from_slice(bytes);
+        self.record_write(address, bytes.len() as u64);
         Ok(())
     }
+
+    /// Read bytes one at a time into the buffer starting from an address. Reading stops at the first byte that cannot be
+    /// read, so the number of bytes read is returned. This is used for fetching byte streams that have no alignment
+    /// such as encoded instructions.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    ///
+    /// let memory = Memory::from(vec![1, 2, 3]);
+    /// let mut buffer = [0u8; 4];
+    ///
+    /// assert_eq!(memory.read_bytes(1, false, &mut buffer), 2);
+    /// assert_eq!(buffer[0..2], [2, 3]);
+    /// ```
+    pub fn read_bytes(&self, address: u64, translate: bool, buffer: &mut [u8]) -> usize {
+        MemoryAccess::read_bytes(self, address, translate, buffer)
+    }
+
+    /// Write bytes one at a time from the buffer starting at an address. Writing stops at the first byte that cannot be
+    /// written, so the number of bytes written is returned. Unlike writing to [Memory::bytes] directly, every write is
+    /// counted so cached instructions in the range are decoded again.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    ///
+    /// let mut memory = Memory::from(vec![0u8; 3]);
+    ///
+    /// assert_eq!(memory.write_bytes(1, false, &[5, 6, 7]), 2);
+    /// assert_eq!(memory.bytes, [0, 5, 6]);
+    /// assert_eq!(memory.write_count(0), 2);
+    /// ```
+    pub fn write_bytes(&mut self, address: u64, translate: bool, buffer: &[u8]) -> usize {
+        for (index, byte) in buffer.iter().enumerate() {
+            let frame = Frame { address: address.wrapping_add(index as u64), size: Size::Byte };
+            if self.set(frame, translate, number::Data::Byte(*byte)).is_err() { return index }
+        }
+
+        buffer.len()
+    }
+
+    /// Get the number of writes made to the line containing a physical address. See [Memory::line_writes]. For an address
+    /// in a [Mirror], this also counts the writes to the line of the address it [resolves](Memory::resolve) to, and the
+    /// times the mirror was [retargeted](Memory::retarget).
+    /// ```
+    /// use atln_processor::emulator::memory::{Frame, Memory};
+    /// use atln_processor::number::{Data, Size};
+    ///
+    /// let mut memory = Memory::from(vec![0u8; 128]);
+    /// assert_eq!(memory.write_count(0), 0);
+    ///
+    /// memory.set(Frame { address: 2, size: Size::Word }, false, Data::Word(1)).unwrap();
+    /// assert_eq!(memory.write_count(0), 1);
+    /// assert_eq!(memory.write_count(100), 0);
+    /// ```
+    pub fn write_count(&self, address: u64) -> u64 {
+        let line = |address: u64| self.line_writes.get(&(address >> WRITE_LINE_BITS)).copied().unwrap_or(0);
+        let resolved = self.resolve(address);
+
+        if resolved == address { line(address) } else { line(address).wrapping_add(line(resolved)) }
+    }
+
+    /// Point a mirror at a different target, such as to switch the bank shown in a window. Memory seen through the range
+    /// changes without being written, so the write count of every line in the range is raised past what it was before,
+    /// which makes caches of anything decoded from the range decode it again. This takes time proportional to the
+    /// length of the range.
+    /// ```
+    /// use atln_processor::emulator::memory::{Memory, Mirror};
+    ///
+    /// let mut memory = Memory::from((0..16).collect::<Vec<u8>>());
+    /// memory.mirrors.push(Mirror { range: 64..68, target: 0, length: 4 });
+    /// let before = memory.write_count(64);
+    ///
+    /// memory.retarget(0, 8);
+    /// assert_eq!(memory.read_u32_le(64), Ok(u32::from_le_bytes([8, 9, 10, 11])));
+    /// assert!(memory.write_count(64) > before);
+    /// ```
+    pub fn retarget(&mut self, mirror: usize, target: u64) {
+        let Some(range) = self.mirrors.get(mirror).map(|mirror| mirror.range.clone()) else { return };
+        if range.is_empty() { return }
+
+        let lines = (range.start >> WRITE_LINE_BITS)..=((range.end - 1) >> WRITE_LINE_BITS);
+        let before: Vec<u64> = lines.clone().map(|line| self.write_count((line << WRITE_LINE_BITS).max(range.start))).collect();
+        self.mirrors[mirror].target = target;
+
+        for (line, before) in lines.zip(before) {
+            let own = self.line_writes.get(&line).copied().unwrap_or(0);
+            let resolved = self.write_count((line << WRITE_LINE_BITS).max(range.start)).wrapping_sub(own);
+            self.line_writes.insert(line, own.wrapping_add(1).max(before.wrapping_add(1).saturating_sub(resolved)));
+        }
+    }
+
+    /// Count a write to every line touched by a range of physical memory.
+    fn record_write(&mut self, address: u64, length: u64) {
+        if length == 0 { return }
+
+        let first = address >> WRITE_LINE_BITS;
+        let last = address.saturating_add(length - 1) >> WRITE_LINE_BITS;
+        for line in first..=last { *self.line_writes.entry(line).or_insert(0) += 1; }
+    }
+}
+
+impl MemoryAccess for Memory {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        #[cfg(feature = "tracing")]
+        let traced = frame.clone();
+        let result = Memory::get(self, frame, r#virtual);
+        #[cfg(feature = "tracing")]
+        instrument::access(false, &traced, r#virtual, result.as_ref());
+        result
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        #[cfg(feature = "tracing")]
+        let (traced, written) = (frame.clone(), value.clone());
+        let result = Memory::set(self, frame, r#virtual, value);
+        #[cfg(feature = "tracing")]
+        instrument::access(true, &traced, r#virtual, result.as_ref().map(|_| &written));
+        result
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        Memory::translate_virtual(self, r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        Memory::write_count(self, address)
+    }
+
+    /// Memory owned by a single core is only reachable through this mutable borrow, so no other access can interleave
+    /// with the operation and it is performed directly. [SharedMemory](shared::SharedMemory) takes a lock instead.
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        operation(self)
+    }
 }
 
 impl From<Vec<u8>> for Memory {
@@ -430,7 +940,10 @@ impl From<Vec<u8>> for Memory {
             max_address: Some(value.len() as u64),
             page_size: 0,
             bytes: value,
-            pages: HashMap::new()
+            pages: Map::new(),
+            line_writes: Map::new(),
+            mirrors: Vec::new(),
+            guards: Vec::new()
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+//! Bank switching, where a window of the address space shows one of several equally sized banks of memory at a time.
+//! Systems with narrow addresses use this to give programs more memory than they can address at once.
+//!
+//! The banks lie one after another in physical memory, usually past the addresses a program can reach, and the window
+//! is a [Mirror] of the selected one. The guest selects a bank by writing its number to either a port or a register in
+//! memory. Switching through a register happens as soon as it is written while memory is accessed through [Switched].
+//! Ports are read by [Banks::update], which the host calls between instructions.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::memory::bank::{Banks, Select, Switched};
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The window at 64 shows one of 4 banks of 8 bytes starting at 128, selected by the byte at 96.
+//! let mut program = assemble("add.q r1, [64]\nadd.b r3, 2\nadd.b [96], r3\nadd.q r2, [64]\nhalt").unwrap();
+//! program.resize(160, 0);
+//! program[128] = 5;
+//! program[144] = 7;
+//!
+//! let mut memory = Memory::from(program);
+//! let mut banks = Banks { window: 64..72, base: 128, count: 4, selected: 0, select: Select::Register(96) };
+//! banks.switch(0, &mut memory);
+//!
+//! let mut core = Core::default();
+//! core.run(&mut Switched { memory: &mut memory, banks: &mut banks }, &mut Default::default(), None);
+//!
+//! assert_eq!((core.context.registers[1], core.context.registers[2]), (5, 7));
+//! assert_eq!(banks.selected, 2);
+//! ```
+
+use core::ops::Range;
+use emulator::memory::{Frame, GetError, Memory, MemoryAccess, Mirror};
+use emulator::processor::processor::Ports;
+use number;
+
+/// Where the guest writes the number of the bank to select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Select {
+    /// Index of a port.
+    Port(u8),
+    /// Physical address of a byte in memory.
+    Register(u64)
+}
+
+/// A window onto one of several banks of memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Banks {
+    /// Range of physical addresses showing the selected bank. Every bank is as long as the window.
+    pub window: Range<u64>,
+    /// Physical address of the first bank.
+    pub base: u64,
+    /// Number of banks. Selecting a bank past the last one wraps around to the first.
+    pub count: u64,
+    pub selected: u64,
+    pub select: Select
+}
+
+impl Banks {
+    /// Physical address of the start of a bank.
+    pub fn address(&self, bank: u64) -> u64 {
+        self.base.wrapping_add(bank.wrapping_mul(self.window.end.saturating_sub(self.window.start)))
+    }
+
+    /// Select a bank and show it in the window of memory. The window is added to the mirrors of memory if it isn't there
+    /// already, ahead of any others.
+    pub fn switch(&mut self, bank: u64, memory: &mut Memory) {
+        self.selected = bank.checked_rem(self.count).unwrap_or(0);
+        let target = self.address(self.selected);
+
+        match memory.mirrors.iter().position(|mirror| mirror.range == self.window) {
+            Some(index) => if memory.mirrors[index].target != target { memory.retarget(index, target) },
+            None => memory.mirrors.insert(0, Mirror { range: self.window.clone(), target, length: 0 })
+        }
+    }
+
+    /// Select the bank held by the select port, if banks are selected through a port.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::memory::bank::{Banks, Select};
+    ///
+    /// let mut memory = Memory::from((0..32).collect::<Vec<u8>>());
+    /// let mut banks = Banks { window: 0..4, base: 16, count: 4, selected: 0, select: Select::Port(3) };
+    ///
+    /// let mut ports = [0; 8];
+    /// ports[3] = 2;
+    /// banks.update(&ports, &mut memory);
+    ///
+    /// assert_eq!(memory.read_u16_le(0), Ok(u16::from_le_bytes([24, 25])));
+    /// ```
+    pub fn update(&mut self, ports: &Ports, memory: &mut Memory) {
+        if let Select::Port(port) = self.select {
+            if let Some(&bank) = ports.get(port as usize) { self.switch(bank as u64, memory) }
+        }
+    }
+}
+
+/// Memory whose bank select register switches banks as soon as it is written.
+#[derive(Debug)]
+pub struct Switched<'a> {
+    pub memory: &'a mut Memory,
+    pub banks: &'a mut Banks
+}
+
+impl<'a> MemoryAccess for Switched<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        MemoryAccess::get(self.memory, frame, r#virtual)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        let physical = if r#virtual { self.memory.translate_virtual(frame.address) } else { Some(frame.address) };
+        let offset = match (physical, self.banks.select) {
+            (Some(address), Select::Register(register)) => register.checked_sub(address).filter(|&offset| offset < frame.size.size() as u64),
+            _ => None
+        };
+
+        let bank = offset.map(|offset| value.quad() >> (offset * 8) & 0xFF);
+        MemoryAccess::set(self.memory, frame, r#virtual, value)?;
+
+        if let Some(bank) = bank { self.banks.switch(bank, self.memory) }
+        Ok(())
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    /// Performed on the switched memory rather than the inner [Memory], so a synchronised write to the select register
+    /// still switches banks. Both are mutably borrowed, so as with [Memory::synchronise] nothing can interleave.
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        operation(self)
+    }
+}
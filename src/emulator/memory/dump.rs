@@ -0,0 +1,136 @@
+//! Dumping ranges of memory, either as the raw bytes for writing out images or as a [Hexdump] for reading.
+//!
+//! A hexdump lists a range row by row, each starting with the address of its first byte. Every row may end with the
+//! printable ASCII characters of its bytes, where every other byte is shown as `.`. With disassembly, each instruction
+//! starts a new row followed by its mnemonic and operands, and bytes which are not a valid instruction are shown a byte
+//! at a time as data.
+//! ```
+//! use atln_processor::emulator::memory::dump::Hexdump;
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let memory = Memory::from(assemble("add.b r1, r2\nhalt\n.ascii \"Hi\"").unwrap());
+//!
+//! let dump = Hexdump { row_bytes: 4, ..Default::default() }.dump(&memory, 0, 7);
+//! assert_eq!(dump, "\
+//! 00000000  00 00 0a 08  |....|
+//! 00000004  00 48 69     |.Hi|
+//! ");
+//!
+//! let dump = Hexdump { row_bytes: 4, ascii: false, disassembly: true, ..Default::default() }.dump(&memory, 0, 7);
+//! assert_eq!(dump, "\
+//! 00000000  00 00 0a     add.b r1, r2
+//! 00000003  08 00        halt
+//! 00000005  48           .byte 0x48
+//! 00000006  69           .byte 0x69
+//! ");
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use emulator::processor::processor::instruction::Instruction;
+use super::MemoryAccess;
+
+/// Most bytes read from memory at a time by [raw].
+const CHUNK_BYTES: u64 = 4096;
+
+/// Read a range of memory as it is, stopping at the first byte which can't be read. The range is read a chunk at a
+/// time, so a length far past the end of memory only allocates what was actually read.
+/// ```
+/// use atln_processor::emulator::memory::dump::raw;
+/// use atln_processor::emulator::memory::Memory;
+///
+/// let memory = Memory::from(vec![1, 2, 3, 4]);
+/// assert_eq!(raw(&memory, 1, 2, false), [2, 3]);
+/// assert_eq!(raw(&memory, 2, 8, false), [3, 4]);
+/// assert_eq!(raw(&memory, 0, u64::MAX, false), [1, 2, 3, 4]);
+/// ```
+pub fn raw(memory: &dyn MemoryAccess, address: u64, length: u64, translate: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut chunk = [0; CHUNK_BYTES as usize];
+
+    while (bytes.len() as u64) < length {
+        let wanted = (length - bytes.len() as u64).min(CHUNK_BYTES) as usize;
+        let read = memory.read_bytes(address.wrapping_add(bytes.len() as u64), translate, &mut chunk[..wanted]);
+
+        bytes.extend_from_slice(&chunk[..read]);
+        if read < wanted { break }
+    }
+
+    bytes
+}
+
+/// Layout of a hexdump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hexdump {
+    /// Most bytes shown on a single row.
+    pub row_bytes: usize,
+    /// Whether each row ends with the ASCII characters of its bytes.
+    pub ascii: bool,
+    /// Whether every instruction starts a row and is disassembled after its bytes.
+    pub disassembly: bool,
+    /// Whether addresses are translated from virtual to physical addresses.
+    pub translate: bool
+}
+
+impl Default for Hexdump {
+    fn default() -> Self {
+        Self { row_bytes: 16, ascii: true, disassembly: false, translate: false }
+    }
+}
+
+impl Hexdump {
+    /// Dump a range of memory, stopping at the first byte which can't be read.
+    pub fn dump(&self, memory: &dyn MemoryAccess, address: u64, length: u64) -> String {
+        self.format(address, &raw(memory, address, length, self.translate))
+    }
+
+    /// Dump bytes as if they were in memory starting at an address.
+    pub fn format(&self, address: u64, bytes: &[u8]) -> String {
+        let mut output = String::new();
+        let row_bytes = self.row_bytes.max(1);
+
+        if !self.disassembly {
+            for (index, row) in bytes.chunks(row_bytes).enumerate() {
+                self.row(&mut output, address.wrapping_add((index * row_bytes) as u64), row, None);
+            }
+
+            return output
+        }
+
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let (text, length) = match Instruction::decode_slice(&bytes[offset..]) {
+                Ok((instruction, length)) => (instruction.to_string(), length),
+                Err(_) => (format!(".byte {:#04x}", bytes[offset]), 1)
+            };
+
+            for (index, row) in bytes[offset..offset + length].chunks(row_bytes).enumerate() {
+                let row_address = address.wrapping_add((offset + index * row_bytes) as u64);
+                self.row(&mut output, row_address, row, (index == 0).then_some(text.as_str()));
+            }
+
+            offset += length;
+        }
+
+        output
+    }
+
+    /// Write a single row of bytes along with the text that follows them.
+    fn row(&self, output: &mut String, address: u64, bytes: &[u8], disassembly: Option<&str>) {
+        let hex = bytes.iter().map(|byte| format!("{byte:02x}")).collect::<Vec<_>>().join(" ");
+        write!(output, "{address:08x}  {hex:<width$}", width = self.row_bytes.max(1) * 3 - 1).unwrap();
+
+        if self.ascii {
+            let ascii: String = bytes.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect();
+            write!(output, "  |{ascii}|").unwrap();
+            if disassembly.is_some() { output.extend(core::iter::repeat_n(' ', self.row_bytes.max(1) - bytes.len())); }
+        }
+
+        if let Some(disassembly) = disassembly { write!(output, "  {disassembly}").unwrap(); }
+        let trimmed = output.trim_end_matches(' ').len();
+        output.truncate(trimmed);
+        output.push('\n');
+    }
+}
@@ -0,0 +1,90 @@
+//! Guard pages, which fault on any access so a guest running off the end of a region is stopped at the first access
+//! outside of it rather than silently corrupting whatever lies next to it.
+//!
+//! Guards are ranges of physical addresses in [Memory::guards], checked after addresses are translated and resolved
+//! through the mirrors. An access touching one fails with [GetError::Guard], which faults the instruction like any
+//! other memory error. Guards only apply to accesses made through [MemoryAccess](super::MemoryAccess), so the host can
+//! still read and write the guarded memory with [Memory::read_number] and the like.
+//!
+//! A [Stack] is the common case of a region between 2 guard pages. The guard below catches the stack overflowing as it
+//! grows downwards, and the guard above catches it being popped more than it was pushed.
+//! ```
+//! use atln_processor::emulator::memory::{Memory, PAGE_BYTES_COUNT};
+//! use atln_processor::emulator::memory::guard::Stack;
+//! use atln_processor::emulator::processor::processor::{Core, Status};
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // Push forever, like runaway recursion, with a 2 page stack above the program.
+//! let mut program = assemble("recurse: add.q -[r7], r1\ndivert recurse").unwrap();
+//! program.resize(PAGE_BYTES_COUNT as usize * 5, 0);
+//!
+//! let mut memory = Memory::from(program);
+//! let stack = Stack::new(PAGE_BYTES_COUNT, 2);
+//! stack.guard(&mut memory);
+//!
+//! let mut core = Core::default();
+//! core.context.registers[7] = stack.top();
+//!
+//! let Status::Faulted(exception) = core.run(&mut memory, &mut Default::default(), None) else { panic!() };
+//! assert!(exception.memory().is_some_and(|error| stack.overflowed(error)));
+//! assert_eq!(core.context.registers[7], stack.range.start);
+//! ```
+
+use core::ops::Range;
+use super::{GetError, Memory, PAGE_BYTES_COUNT, PAGE_IDENTIFIER_MASK};
+
+/// A downwards growing stack with a guard page on either side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stack {
+    /// Physical addresses usable by the stack.
+    pub range: Range<u64>,
+    /// Guard page below the stack, which catches overflows.
+    pub overflow: Range<u64>,
+    /// Guard page above the stack, which catches underflows.
+    pub underflow: Range<u64>
+}
+
+impl Stack {
+    /// Lay out a stack of a number of pages, with the guard page catching overflows starting at the page holding the
+    /// base address.
+    /// ```
+    /// use atln_processor::emulator::memory::PAGE_BYTES_COUNT;
+    /// use atln_processor::emulator::memory::guard::Stack;
+    ///
+    /// let stack = Stack::new(PAGE_BYTES_COUNT + 5, 1);
+    /// assert_eq!(stack.overflow, PAGE_BYTES_COUNT..PAGE_BYTES_COUNT * 2);
+    /// assert_eq!(stack.range, PAGE_BYTES_COUNT * 2..PAGE_BYTES_COUNT * 3);
+    /// assert_eq!(stack.underflow, PAGE_BYTES_COUNT * 3..PAGE_BYTES_COUNT * 4);
+    /// ```
+    pub fn new(base: u64, pages: u64) -> Self {
+        let start = (base & PAGE_IDENTIFIER_MASK).saturating_add(PAGE_BYTES_COUNT);
+        let end = start.saturating_add(pages.saturating_mul(PAGE_BYTES_COUNT));
+
+        Self {
+            range: start..end,
+            overflow: start - PAGE_BYTES_COUNT..start,
+            underflow: end..end.saturating_add(PAGE_BYTES_COUNT)
+        }
+    }
+
+    /// Address to start the stack pointer at, which is the end of the stack since it grows downwards.
+    pub fn top(&self) -> u64 {
+        self.range.end
+    }
+
+    /// Add the guard pages of the stack to memory.
+    pub fn guard(&self, memory: &mut Memory) {
+        memory.guards.push(self.overflow.clone());
+        memory.guards.push(self.underflow.clone());
+    }
+
+    /// Whether an error was caused by an access to the guard page below the stack.
+    pub fn overflowed(&self, error: &GetError) -> bool {
+        matches!(error, GetError::Guard(address) if self.overflow.contains(address))
+    }
+
+    /// Whether an error was caused by an access to the guard page above the stack.
+    pub fn underflowed(&self, error: &GetError) -> bool {
+        matches!(error, GetError::Guard(address) if self.underflow.contains(address))
+    }
+}
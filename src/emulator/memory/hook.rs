@@ -0,0 +1,203 @@
+//! Host callbacks which run when a guest accesses a range of physical memory, for tracing particular addresses, filling
+//! in memory the first time it is touched or emulating simple devices at a high level. Unlike the breakpoints of
+//! [debug](crate::emulator::processor::processor::debug), hooks are invisible to the guest.
+//!
+//! Callbacks are registered in [Hooks], and take effect on the memory given to a core through [Hooked]. Each callback
+//! is told about every access which overlaps its range with an [Event]. It may return a value to use in place of the
+//! one the access would have had, which a read returns to the guest instead of what memory holds and a write stores
+//! instead of what the guest wrote. Callbacks run in the order they were registered, and each sees the value as the
+//! callbacks before it left it. Reads are hooked once they succeed, and writes are hooked before they are made.
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::memory::hook::{Hooked, Hooks};
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::number::Data;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let mut program = assemble("add.q r1, [64]\nadd.q [72], r1\nhalt").unwrap();
+//! program.resize(80, 0);
+//! let mut memory = Memory::from(program);
+//!
+//! // Reads of 64 see a device register holding 5, and writes of 72 are logged.
+//! let writes = Rc::new(RefCell::new(Vec::new()));
+//! let log = writes.clone();
+//! let mut hooks = Hooks::default();
+//! hooks.on_read(64..72, |_| Some(Data::Quad(5)));
+//! hooks.on_write(72..80, move |event| {
+//!     log.borrow_mut().push((event.address, event.old.quad(), event.new.quad()));
+//!     None
+//! });
+//!
+//! Core::default().run(&mut Hooked { memory: &mut memory, hooks: &hooks }, &mut Default::default(), None);
+//! assert_eq!(*writes.borrow(), [(72, 0, 5)]);
+//! assert_eq!(memory.bytes[64], 0);
+//! assert_eq!(memory.bytes[72], 5);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::ops::Range;
+use number;
+use number::Size;
+use super::{Frame, GetError, MemoryAccess};
+
+/// An access to memory which a hook watches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub write: bool,
+    /// Physical address of the first byte accessed.
+    pub address: u64,
+    pub size: Size,
+    /// Value held by memory before the access.
+    pub old: number::Data,
+    /// Value held by memory after the access, which is the same as the old value for reads.
+    pub new: number::Data
+}
+
+/// Function called on the accesses a hook watches, which returns the value to use in place of [Event::new] if any.
+pub type Callback = Box<dyn FnMut(&Event) -> Option<number::Data>>;
+
+/// A callback along with the accesses it watches.
+struct Hook {
+    identifier: u64,
+    range: Range<u64>,
+    reads: bool,
+    writes: bool,
+    callback: Callback
+}
+
+/// Callbacks on ranges of physical memory.
+#[derive(Default)]
+pub struct Hooks {
+    hooks: RefCell<Vec<Hook>>,
+    next: u64
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("ranges", &self.hooks.borrow().iter().map(|hook| hook.range.clone()).collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Hooks {
+    /// Call a function on reads which overlap a range, returning an identifier for [Hooks::remove].
+    pub fn on_read(&mut self, range: Range<u64>, callback: impl FnMut(&Event) -> Option<number::Data> + 'static) -> u64 {
+        self.add(range, true, false, Box::new(callback))
+    }
+
+    /// Call a function on writes which overlap a range, returning an identifier for [Hooks::remove].
+    pub fn on_write(&mut self, range: Range<u64>, callback: impl FnMut(&Event) -> Option<number::Data> + 'static) -> u64 {
+        self.add(range, false, true, Box::new(callback))
+    }
+
+    /// Call a function on both reads and writes which overlap a range, returning an identifier for [Hooks::remove].
+    pub fn on_access(&mut self, range: Range<u64>, callback: impl FnMut(&Event) -> Option<number::Data> + 'static) -> u64 {
+        self.add(range, true, true, Box::new(callback))
+    }
+
+    /// Stop calling a hook. Returns whether the hook existed.
+    /// ```
+    /// use atln_processor::emulator::memory::hook::Hooks;
+    ///
+    /// let mut hooks = Hooks::default();
+    /// let hook = hooks.on_read(0..8, |_| None);
+    ///
+    /// assert!(hooks.remove(hook));
+    /// assert!(!hooks.remove(hook));
+    /// assert!(hooks.is_empty());
+    /// ```
+    pub fn remove(&mut self, identifier: u64) -> bool {
+        let hooks = self.hooks.get_mut();
+        let length = hooks.len();
+        hooks.retain(|hook| hook.identifier != identifier);
+        hooks.len() != length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.borrow().is_empty()
+    }
+
+    fn add(&mut self, range: Range<u64>, reads: bool, writes: bool, callback: Callback) -> u64 {
+        let identifier = self.next;
+        self.next += 1;
+        self.hooks.get_mut().push(Hook { identifier, range, reads, writes, callback });
+        identifier
+    }
+
+    /// Physical address of a frame, if any hook watches an access to it.
+    fn watching(&self, memory: &dyn MemoryAccess, frame: &Frame, r#virtual: bool, write: bool) -> Option<u64> {
+        let hooks = self.hooks.borrow();
+        if hooks.is_empty() { return None }
+
+        let address = if r#virtual { memory.translate_virtual(frame.address)? } else { frame.address };
+        let end = address.saturating_add(frame.size.size() as u64);
+        hooks.iter()
+            .any(|hook| (if write { hook.writes } else { hook.reads }) && hook.range.start < end && address < hook.range.end)
+            .then_some(address)
+    }
+
+    /// Call every hook watching an access, returning the value the access ends up with.
+    fn fire(&self, write: bool, address: u64, size: Size, old: number::Data, new: number::Data) -> number::Data {
+        let mut event = Event { write, address, size, old, new };
+        let end = address.saturating_add(event.size.size() as u64);
+
+        for hook in self.hooks.borrow_mut().iter_mut() {
+            if !(if write { hook.writes } else { hook.reads }) || hook.range.start >= end || address >= hook.range.end { continue }
+            if let Some(value) = (hook.callback)(&event) { event.new = number::Data::from_size_selecting(&event.size, value.quad()); }
+        }
+
+        event.new
+    }
+}
+
+/// Memory which calls [Hooks] on the accesses made through it.
+pub struct Hooked<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub hooks: &'a Hooks
+}
+
+impl<'a> MemoryAccess for Hooked<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let value = self.memory.get(frame.clone(), r#virtual)?;
+        Ok(match self.hooks.watching(&*self.memory, &frame, r#virtual, false) {
+            Some(address) => self.hooks.fire(false, address, frame.size, value.clone(), value),
+            None => value
+        })
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        let value = match self.hooks.watching(&*self.memory, &frame, r#virtual, true) {
+            Some(address) => {
+                let old = self.memory.get(frame.clone(), r#virtual)?;
+                let new = number::Data::from_size_selecting(&frame.size, value.quad());
+                self.hooks.fire(true, address, frame.size.clone(), old, new)
+            },
+            None => value
+        };
+
+        self.memory.set(frame, r#virtual, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let hooks = self.hooks;
+        self.memory.synchronise(&mut |memory| operation(&mut Hooked { memory, hooks }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
@@ -0,0 +1,129 @@
+//! Searching physical memory so debuggers and cheat tables can find where a program keeps its data. Memory can be
+//! searched for an exact sequence of bytes, for a [Pattern] where some bits may be anything, or for a number of a given
+//! size. Every search lazily yields the address of each match in ascending order, and matches may overlap.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::memory::search::Pattern;
+//! use atln_processor::number::Size;
+//!
+//! let memory = Memory::from(vec![0, 100, 0, 0, 100, 0, 0xA5, 0xB7]);
+//!
+//! assert_eq!(memory.find(&[100, 0]).collect::<Vec<_>>(), [1, 4]);
+//! assert_eq!(memory.find_value(100, Size::Word, false).collect::<Vec<_>>(), [1, 4]);
+//! assert_eq!(memory.find_value(100, Size::Word, true).collect::<Vec<_>>(), [4]);
+//!
+//! let pattern: Pattern = "00 a? ?7".parse().unwrap();
+//! assert_eq!(memory.find_pattern(&pattern).next(), Some(5));
+//! ```
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use number;
+use number::Size;
+use super::Memory;
+
+/// Bytes to search for where only the bits set in the mask have to match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub bytes: Vec<u8>,
+    /// Bits of each byte which have to match. Bytes without a mask have to match entirely.
+    pub mask: Vec<u8>
+}
+
+impl Pattern {
+    /// Whether the bytes match the pattern. The bytes must be as long as the pattern.
+    /// ```
+    /// use atln_processor::emulator::memory::search::Pattern;
+    ///
+    /// let pattern = Pattern { bytes: vec![0x12, 0x30], mask: vec![0xFF, 0xF0] };
+    ///
+    /// assert!(pattern.matches(&[0x12, 0x3F]));
+    /// assert!(!pattern.matches(&[0x12, 0x4F]));
+    /// assert!(!pattern.matches(&[0x12]));
+    /// ```
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() == self.bytes.len() && self.bytes.iter().zip(bytes).enumerate().all(|(index, (expected, byte))| {
+            let mask = self.mask.get(index).copied().unwrap_or(u8::MAX);
+            expected & mask == byte & mask
+        })
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    /// Parse bytes separated by whitespace. Each byte is 2 hexadecimal digits, where a digit may be `?` to match any 4
+    /// bits.
+    /// ```
+    /// use atln_processor::emulator::memory::search::{Pattern, PatternError};
+    ///
+    /// assert_eq!("de ?? 0?".parse(), Ok(Pattern { bytes: vec![0xDE, 0, 0], mask: vec![0xFF, 0, 0xF0] }));
+    /// assert_eq!("de adbe".parse::<Pattern>(), Err(PatternError::Byte(1)));
+    /// assert_eq!("".parse::<Pattern>(), Err(PatternError::Empty));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pattern = Self::default();
+        for (index, byte) in s.split_whitespace().enumerate() {
+            let digits = byte.chars().map(|digit| match digit {
+                '?' => Some((0, 0)),
+                _ => digit.to_digit(16).map(|value| (value as u8, 0xF))
+            }).collect::<Option<Vec<_>>>().filter(|digits| digits.len() == 2).ok_or(PatternError::Byte(index))?;
+
+            pattern.bytes.push(digits[0].0 << 4 | digits[1].0);
+            pattern.mask.push(digits[0].1 << 4 | digits[1].1);
+        }
+
+        if pattern.bytes.is_empty() { return Err(PatternError::Empty) }
+        Ok(pattern)
+    }
+}
+
+/// Error from parsing a [Pattern].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// There are no bytes in the pattern.
+    Empty,
+    /// The byte at an index is not 2 hexadecimal digits or `?`.
+    Byte(usize)
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("pattern has no bytes"),
+            Self::Byte(index) => write!(f, "byte {index} of the pattern is not 2 hexadecimal digits or `?`")
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+impl Memory {
+    /// Addresses in physical memory where the bytes appear. Searching for no bytes finds nothing.
+    pub fn find<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = u64> + 'a {
+        self.scan(bytes.len(), 1, move |window| window == bytes)
+    }
+
+    /// Addresses in physical memory where the bytes match a pattern.
+    pub fn find_pattern<'a>(&'a self, pattern: &'a Pattern) -> impl Iterator<Item = u64> + 'a {
+        self.scan(pattern.bytes.len(), 1, move |window| pattern.matches(window))
+    }
+
+    /// Addresses in physical memory holding a little endian number of a size, where the number is truncated to the
+    /// size. Aligned searches only look at addresses a [Frame](super::Frame) of the size could read, which is how a
+    /// program normally stores numbers.
+    pub fn find_value(&self, value: u64, size: Size, aligned: bool) -> impl Iterator<Item = u64> + '_ {
+        let bytes = number::Data::from_size_selecting(&size, value).to_le_bytes();
+        let step = if aligned { bytes.len() } else { 1 };
+        self.scan(bytes.len(), step, move |window| window == bytes.as_slice())
+    }
+
+    /// Addresses every step bytes where a window of a length of memory matches.
+    fn scan<'a>(&'a self, length: usize, step: usize, matches: impl Fn(&[u8]) -> bool + 'a) -> impl Iterator<Item = u64> + 'a {
+        let windows = if length == 0 { [].windows(1) } else { self.bytes.windows(length) };
+        windows.enumerate().step_by(step).filter(move |(_, window)| matches(window)).map(|(address, _)| address as u64)
+    }
+}
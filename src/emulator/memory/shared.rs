@@ -0,0 +1,170 @@
+//! Memory shared by cores running on separate host threads. Only available with the `std` feature.
+//!
+//! Physical memory is stored as 64 bit atomic words. Frames are always aligned to their size, so every frame lies within
+//! a single word and is read or written with one atomic operation. Ordinary accesses are therefore lock free and never
+//! observe a partially written value.
+//!
+//! Synchronised operations additionally hold a lock which every other synchronised operation on the same memory takes,
+//! making each of them atomic with respect to the others. Ordinary accesses from other threads may still land in
+//! between the accesses of a synchronised operation. Loads acquire and stores release, and fences are sequentially
+//! consistent, which satisfies the [memory ordering model](crate::emulator::processor::processor::ordering).
+//!
+//! The page mappings and the size of the memory are fixed when the shared memory is created. Unlike [Memory], writing
+//! past the end of the memory does not grow it.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic;
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+#[cfg(feature = "tracing")]
+use emulator::instrument;
+use number;
+use number::{Size, QUAD_SIZE};
+use super::{Frame, GetError, Memory, MemoryAccess, WRITE_LINE_BITS};
+use utility::Map;
+
+#[derive(Debug)]
+struct Shared {
+    /// Physical memory as little endian words.
+    words: Vec<AtomicU64>,
+    /// Number of bytes in the memory, which may not be a multiple of the word size.
+    length: u64,
+    /// Write count of every line. See [Memory::line_writes].
+    lines: Vec<AtomicU64>,
+    /// Bounds and page mappings used to check and translate frames. Its bytes are always empty.
+    layout: Memory,
+    /// Held for the whole of a synchronised operation.
+    lock: Mutex<()>
+}
+
+/// A handle to memory which can be cloned and sent to other threads. Every clone accesses the same memory.
+/// ```
+/// use std::thread;
+/// use atln_processor::emulator::memory::{Frame, Memory, MemoryAccess};
+/// use atln_processor::emulator::memory::shared::SharedMemory;
+/// use atln_processor::number::{Data, Size};
+///
+/// let memory = SharedMemory::from(Memory::from(vec![0u8; 16]));
+/// let counter = Frame { address: 8, size: Size::Quad };
+///
+/// thread::scope(|scope| for _ in 0..4 {
+///     let mut memory = memory.clone();
+///     let counter = &counter;
+///
+///     scope.spawn(move || for _ in 0..1000 {
+///         memory.synchronise(&mut |memory| {
+///             let value = memory.get(counter.clone(), false).unwrap().quad();
+///             memory.set(counter.clone(), false, Data::Quad(value + 1)).unwrap();
+///         });
+///     });
+/// });
+///
+/// assert_eq!(memory.get(counter, false), Ok(Data::Quad(4000)));
+/// assert_eq!(memory.to_memory().bytes[8..10], [0xA0, 0x0F]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedMemory {
+    shared: Arc<Shared>
+}
+
+impl SharedMemory {
+    /// Copy the current contents into a [Memory] with the same bounds and page mappings.
+    pub fn to_memory(&self) -> Memory {
+        let shared = &self.shared;
+        let mut bytes: Vec<u8> = shared.words.iter().flat_map(|word| word.load(Ordering::Acquire).to_le_bytes()).collect();
+        bytes.truncate(shared.length as usize);
+
+        let line_writes = shared.lines.iter()
+            .enumerate()
+            .map(|(line, writes)| (line as u64, writes.load(Ordering::Acquire)))
+            .filter(|(_, writes)| *writes != 0)
+            .collect();
+
+        Memory { bytes, line_writes, ..shared.layout.clone() }
+    }
+
+    /// Check a frame and translate it to the word holding it, along with the offset of the frame in bits.
+    fn locate(&self, mut frame: Frame, r#virtual: bool) -> Result<(&AtomicU64, u32, Frame), GetError> {
+        self.shared.layout.process_test_frame(&mut frame, r#virtual)?;
+        if frame.max_address() > self.shared.length { return Err(frame.out_of_bounds()) }
+
+        let word = self.shared.words.get(frame.address as usize / QUAD_SIZE).ok_or_else(|| frame.out_of_bounds())?;
+        let shift = (frame.address % QUAD_SIZE as u64) as u32 * 8;
+        Ok((word, shift, frame))
+    }
+}
+
+/// Mask of the bits a size covers.
+fn mask(size: &Size) -> u64 {
+    u64::MAX >> (64 - size.size() as u32 * 8)
+}
+
+impl MemoryAccess for SharedMemory {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        #[cfg(feature = "tracing")]
+        let traced = frame.clone();
+        let result = self.locate(frame, r#virtual).map(|(word, shift, frame)| {
+            let value = word.load(Ordering::Acquire) >> shift & mask(&frame.size);
+            number::Data::from_size_selecting(&frame.size, value)
+        });
+
+        #[cfg(feature = "tracing")]
+        instrument::access(false, &traced, r#virtual, result.as_ref());
+        result
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        #[cfg(feature = "tracing")]
+        let traced = frame.clone();
+        let result = self.locate(frame, r#virtual).map(|(word, shift, frame)| {
+            let mask = mask(&frame.size) << shift;
+            let bits = value.quad() << shift & mask;
+
+            word.fetch_update(Ordering::AcqRel, Ordering::Acquire, |word| Some(word & !mask | bits)).unwrap();
+            self.shared.lines[(frame.address >> WRITE_LINE_BITS) as usize].fetch_add(1, Ordering::AcqRel);
+        });
+
+        #[cfg(feature = "tracing")]
+        instrument::access(true, &traced, r#virtual, result.as_ref().map(|_| &value));
+        result
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.shared.layout.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.shared.lines.get((self.shared.layout.resolve(address) >> WRITE_LINE_BITS) as usize).map_or(0, |writes| writes.load(Ordering::Acquire))
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let shared = self.shared.clone();
+        let _guard = shared.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        operation(self)
+    }
+
+    fn fence(&mut self) {
+        atomic::fence(Ordering::SeqCst);
+    }
+}
+
+impl From<Memory> for SharedMemory {
+    /// Move the contents of memory into shared memory. The size of the shared memory is the number of bytes the memory
+    /// holds.
+    fn from(memory: Memory) -> Self {
+        let length = memory.bytes.len() as u64;
+        let words = memory.bytes.chunks(QUAD_SIZE).map(|chunk| {
+            let mut word = [0u8; QUAD_SIZE];
+            word[..chunk.len()].copy_from_slice(chunk);
+            AtomicU64::new(u64::from_le_bytes(word))
+        }).collect();
+
+        let lines = (0..length.div_ceil(1 << WRITE_LINE_BITS))
+            .map(|line| AtomicU64::new(memory.line_writes.get(&line).copied().unwrap_or(0)))
+            .collect();
+
+        let layout = Memory { bytes: Vec::new(), line_writes: Map::new(), ..memory };
+        Self { shared: Arc::new(Shared { words, length, lines, layout, lock: Mutex::new(()) }) }
+    }
+}
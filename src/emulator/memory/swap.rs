@@ -0,0 +1,252 @@
+//! Demand paging, where virtual pages are only loaded into physical memory once they are accessed, and are evicted to
+//! a host file once too many are resident. Guests can then use far more memory than the host gives them. Only
+//! available with the `std` feature.
+//!
+//! A [Pager] backs a range of virtual pages with a swap file, which holds page `pages.start + n` at byte
+//! `n * PAGE_BYTES_COUNT`. Pages past the end of the file read as zeros, so an empty file backs pages with zeros and a
+//! file holding an image maps it into memory lazily. Resident pages are loaded into a range of physical pages, and once
+//! all of them are used, the least recently used page is evicted to make room. Only pages written since they were
+//! loaded are written back to the swap file.
+//!
+//! Memory accessed through [Paged] loads pages as they are accessed, so guests never see the page faults of pages in
+//! the swap file. Evicting a page changes the mappings in [Memory::pages], so cores simulating a
+//! [TLB](super::tlb) should flush it when the eviction count changes.
+//! ```
+//! use std::io::Cursor;
+//! use atln_processor::emulator::memory::{Memory, PAGE_BYTES_COUNT};
+//! use atln_processor::emulator::memory::swap::{Paged, Pager};
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The program is on page 0 and reads the numbers on pages 1 and 2, then writes their sum to page 1.
+//! let mut image = assemble("add.q r1, [0x2000]\nadd.q r1, [0x4000]\nadd.q [0x2008], r1\nhalt").unwrap();
+//! image.resize(PAGE_BYTES_COUNT as usize * 3, 0);
+//! image[0x2000] = 5;
+//! image[0x4000] = 7;
+//!
+//! // Only 2 of the 3 pages fit in memory at once.
+//! let mut memory = Memory::default();
+//! let mut pager = Pager::new(Cursor::new(image), 0..3, 0..2);
+//!
+//! let mut core = Core::default();
+//! core.context.virtual_mode = true;
+//! core.run(&mut Paged::new(&mut memory, &mut pager), &mut Default::default(), None);
+//!
+//! assert_eq!(core.context.registers[1], 12);
+//! assert_eq!((pager.statistics.faults, pager.statistics.evictions), (4, 2));
+//! assert_eq!(memory.bytes.len(), PAGE_BYTES_COUNT as usize * 2);
+//!
+//! pager.write_back(&memory).unwrap();
+//! assert_eq!(pager.swap.get_ref()[0x2008], 12);
+//! ```
+
+use core::cell::RefCell;
+use core::ops::Range;
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use number;
+use utility::{LastError, Map};
+use super::{Address, Frame, GetError, Memory, MemoryAccess, PAGE_BYTES_COUNT, PAGE_ITEM_BITS, WRITE_LINE_BITS};
+
+/// Counts of what happened to the pages of a [Pager].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Accesses to pages which were not resident.
+    pub faults: u64,
+    /// Pages evicted to make room for another one.
+    pub evictions: u64,
+    /// Pages read from the swap file.
+    pub reads: u64,
+    /// Pages written to the swap file.
+    pub writes: u64
+}
+
+/// A page loaded into physical memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Resident {
+    /// Physical page holding the page.
+    frame: u64,
+    /// When the page was last accessed, which orders pages for eviction.
+    used: u64,
+    /// Writes made to the physical page when it was loaded or last written back.
+    writes: u64
+}
+
+/// Virtual pages loaded on demand from a swap file.
+#[derive(Debug)]
+pub struct Pager<S> {
+    /// Host file holding the contents of pages which are not resident.
+    pub swap: S,
+    /// Virtual pages backed by the swap file. Accesses to other pages are translated as usual.
+    pub pages: Range<u64>,
+    /// Physical pages which resident pages are loaded into. Its length is the most pages resident at once.
+    pub frames: Range<u64>,
+    pub statistics: Statistics,
+    /// Error from the last access of the swap file which failed. The access is a page fault to the guest.
+    pub io_error: Option<io::Error>,
+    /// Resident pages, keyed by their virtual page.
+    resident: Map<u64, Resident>,
+    clock: u64
+}
+
+impl<S: Read + Write + Seek> Pager<S> {
+    /// Back virtual pages with a swap file, loading them into the frames when they are accessed.
+    pub fn new(swap: S, pages: Range<u64>, frames: Range<u64>) -> Self {
+        Self { swap, pages, frames, statistics: Statistics::default(), io_error: None, resident: Map::default(), clock: 0 }
+    }
+
+    /// Number of pages currently in physical memory.
+    pub fn resident(&self) -> usize {
+        self.resident.len()
+    }
+
+    /// Translate a virtual address, loading its page into memory first if it is backed by the swap file but not
+    /// resident. Returns [None] for a page fault, which includes failing to access the swap file.
+    /// ```
+    /// use std::io::Cursor;
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::memory::swap::Pager;
+    ///
+    /// let mut memory = Memory::default();
+    /// let mut pager = Pager::new(Cursor::new(Vec::new()), 4..8, 10..11);
+    ///
+    /// assert_eq!(pager.translate(&mut memory, 0x8004), Some(0x14004));
+    /// assert_eq!(pager.translate(&mut memory, 0xA004), Some(0x14004));
+    /// assert_eq!(pager.translate(&mut memory, 0x0004), None);
+    /// assert_eq!(memory.pages.len(), 1);
+    /// assert_eq!(pager.statistics.writes, 0);
+    /// ```
+    pub fn translate(&mut self, memory: &mut Memory, address: u64) -> Option<u64> {
+        let page = address.extract_page();
+        self.clock += 1;
+
+        if let Some(resident) = self.resident.get_mut(&page) {
+            resident.used = self.clock;
+        } else if self.pages.contains(&page) {
+            self.statistics.faults += 1;
+            if let Err(error) = self.load(memory, page) {
+                self.io_error = Some(error);
+                return None
+            }
+        }
+
+        memory.translate_virtual(address)
+    }
+
+    /// Write every resident page written since it was loaded to the swap file, so the file holds the current contents of
+    /// all the pages it backs. Pages stay resident.
+    pub fn write_back(&mut self, memory: &Memory) -> io::Result<()> {
+        let pages: Vec<u64> = self.resident.keys().copied().collect();
+        for page in pages { self.store(memory, page)? }
+        self.swap.flush()
+    }
+
+    /// Load a page into a free frame, evicting the least recently used page if there are none.
+    fn load(&mut self, memory: &mut Memory, page: u64) -> io::Result<()> {
+        let frame = if (self.resident.len() as u64) < self.frames.end.saturating_sub(self.frames.start) {
+            self.frames.start + self.resident.len() as u64
+        } else {
+            let victim = self.resident.iter()
+                .min_by_key(|(_, resident)| resident.used)
+                .map(|(&page, _)| page)
+                .ok_or_else(|| io::Error::other("no frames to load pages into"))?;
+
+            self.store(memory, victim)?;
+            memory.pages.remove(&victim);
+            self.statistics.evictions += 1;
+            self.resident.remove(&victim).unwrap().frame
+        };
+
+        let mut bytes = vec![0; PAGE_BYTES_COUNT as usize];
+        self.swap.seek(SeekFrom::Start((page - self.pages.start) << PAGE_ITEM_BITS))?;
+        let mut filled = 0;
+        while filled < bytes.len() {
+            match self.swap.read(&mut bytes[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error)
+            }
+        }
+
+        memory.store(frame.offset_page(), &bytes).map_err(io::Error::other)?;
+        self.statistics.reads += 1;
+
+        memory.pages.insert(page, frame);
+        self.resident.insert(page, Resident { frame, used: self.clock, writes: frame_writes(memory, frame) });
+        Ok(())
+    }
+
+    /// Write a resident page to the swap file if it was written since it was loaded or last stored.
+    fn store(&mut self, memory: &Memory, page: u64) -> io::Result<()> {
+        let resident = self.resident[&page];
+        let writes = frame_writes(memory, resident.frame);
+        if writes == resident.writes { return Ok(()) }
+
+        let bytes = memory.range(resident.frame.offset_page(), PAGE_BYTES_COUNT as usize).map_err(io::Error::other)?;
+        self.swap.seek(SeekFrom::Start((page - self.pages.start) << PAGE_ITEM_BITS))?;
+        self.swap.write_all(bytes)?;
+
+        self.statistics.writes += 1;
+        if let Some(resident) = self.resident.get_mut(&page) { resident.writes = writes }
+        Ok(())
+    }
+}
+
+impl<S> LastError<io::Error> for Pager<S> {
+    fn last_error(&self) -> &Option<io::Error> {
+        &self.io_error
+    }
+}
+
+/// Total writes recorded for the lines of a physical page.
+fn frame_writes(memory: &Memory, frame: u64) -> u64 {
+    let first = frame.offset_page() >> WRITE_LINE_BITS;
+    (first..first + (PAGE_BYTES_COUNT >> WRITE_LINE_BITS)).filter_map(|line| memory.line_writes.get(&line)).sum()
+}
+
+/// Memory whose virtual pages are loaded by a [Pager] as they are accessed.
+#[derive(Debug)]
+pub struct Paged<'a, S> {
+    memory: RefCell<&'a mut Memory>,
+    pager: RefCell<&'a mut Pager<S>>
+}
+
+impl<'a, S: Read + Write + Seek> Paged<'a, S> {
+    pub fn new(memory: &'a mut Memory, pager: &'a mut Pager<S>) -> Self {
+        Self { memory: RefCell::new(memory), pager: RefCell::new(pager) }
+    }
+
+    /// Make sure the page of a virtual frame is resident before it is accessed. Unaligned frames are left for memory
+    /// to reject.
+    fn fault(&self, frame: &Frame, r#virtual: bool) {
+        if r#virtual && frame.is_aligned() { self.translate_virtual(frame.address); }
+    }
+}
+
+impl<'a, S: Read + Write + Seek> MemoryAccess for Paged<'a, S> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        self.fault(&frame, r#virtual);
+        self.memory.borrow().get(frame, r#virtual)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.fault(&frame, r#virtual);
+        self.memory.get_mut().set(frame, r#virtual, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.pager.borrow_mut().translate(&mut self.memory.borrow_mut(), r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.borrow().write_count(address)
+    }
+
+    /// Performed on the paged memory rather than the inner [Memory], so pages the operation touches are still loaded
+    /// from the swap file first. The memory and pager are exclusively borrowed, so as with [Memory::synchronise] nothing
+    /// can interleave.
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        operation(self)
+    }
+}
@@ -0,0 +1,262 @@
+//! Translation lookaside buffer, which caches the translations of virtual pages so the cost of paging and the
+//! correctness of guest TLB management can be studied.
+//!
+//! A [Tlb] holds a number of entries split into sets of [Tlb::ways] entries each. A virtual page can only be cached in
+//! the set selected by its page number, and the least recently used entry of a full set is evicted to make room. Setting
+//! [Core::tlb](crate::emulator::processor::processor::Core::tlb) makes every translation of a stepped instruction go
+//! through the TLB, including fetching the instruction itself.
+//!
+//! Like hardware, the TLB is not told when page mappings change. Entries for pages which were remapped keep translating
+//! to the old physical page until the guest flushes them by writing to
+//! [TLB_FLUSH_PAGE_REGISTER](crate::emulator::processor::processor::instruction::operation::control::TLB_FLUSH_PAGE_REGISTER)
+//! or
+//! [TLB_FLUSH_ALL_REGISTER](crate::emulator::processor::processor::instruction::operation::control::TLB_FLUSH_ALL_REGISTER).
+//! ```
+//! use atln_processor::emulator::memory::{Memory, PAGE_BYTES_COUNT};
+//! use atln_processor::emulator::memory::tlb::Tlb;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // Virtual page 0 maps to physical page 0, which holds the program, and the program reads virtual page 1 twice.
+//! let mut bytes = assemble("add.b r1, [0x2000]\nadd.b r1, [0x2000]\nhalt").unwrap();
+//! bytes.resize(PAGE_BYTES_COUNT as usize * 3, 0);
+//! bytes[PAGE_BYTES_COUNT as usize * 2] = 3;
+//!
+//! let mut memory = Memory::from(bytes);
+//! memory.pages.insert(0, 0);
+//! memory.pages.insert(1, 2);
+//!
+//! let mut core = Core::default();
+//! core.context.virtual_mode = true;
+//! core.tlb = Some(Tlb::new(4, 2));
+//! core.run(&mut memory, &mut Default::default(), None);
+//!
+//! let statistics = core.tlb.unwrap().statistics;
+//! assert_eq!(core.context.registers[1], 6);
+//! assert_eq!((statistics.hits, statistics.misses), (3, 2));
+//! ```
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use number;
+use super::{Address, Frame, GetError, MemoryAccess};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Entries of a TLB to throw away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Flush {
+    /// The entry of the page holding a virtual address.
+    Page(u64),
+    /// Every entry.
+    All
+}
+
+/// Counts of what happened to lookups in a TLB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Lookups which found their page cached.
+    pub hits: u64,
+    /// Lookups which had to translate their page through memory.
+    pub misses: u64,
+    /// Entries thrown away to make room for another page.
+    pub evictions: u64,
+    /// Flushes requested, whether or not they threw away any entries.
+    pub flushes: u64
+}
+
+impl Statistics {
+    /// Fraction of lookups which were hits, or 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 { 0.0 } else { self.hits as f64 / lookups as f64 }
+    }
+}
+
+/// A cached translation of a virtual page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    r#virtual: u64,
+    physical: u64
+}
+
+/// Set associative cache of page translations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tlb {
+    /// Number of entries in each set.
+    pub ways: usize,
+    pub statistics: Statistics,
+    /// Entries of each set, from the most to the least recently used.
+    sets: Vec<Vec<Entry>>
+}
+
+impl Default for Tlb {
+    /// 64 entries in sets of 4.
+    fn default() -> Self {
+        Self::new(64, 4)
+    }
+}
+
+impl Tlb {
+    /// Create an empty TLB with a number of entries in sets of `ways` entries. Ways are limited to the number of
+    /// entries, so a TLB with as many ways as entries is fully associative and one with a single way is direct mapped.
+    /// At least 1 entry is always kept, and a number of entries which isn't a multiple of the ways is rounded up to the
+    /// next one.
+    /// ```
+    /// use atln_processor::emulator::memory::tlb::Tlb;
+    ///
+    /// assert_eq!(Tlb::new(8, 4).entries(), 8);
+    /// assert_eq!(Tlb::new(10, 4).entries(), 12);
+    /// assert_eq!(Tlb::new(0, 4).entries(), 1);
+    /// ```
+    pub fn new(entries: usize, ways: usize) -> Self {
+        let entries = entries.max(1);
+        let ways = ways.clamp(1, entries);
+        Self { ways, statistics: Statistics::default(), sets: vec![Vec::with_capacity(ways); entries.div_ceil(ways)] }
+    }
+
+    /// Total number of entries.
+    pub fn entries(&self) -> usize {
+        self.sets.len() * self.ways
+    }
+
+    /// Translate a virtual address, looking it up in the TLB first and translating its page through memory on a miss.
+    /// Pages which aren't mapped are not cached, so [None] is returned for a page fault.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::memory::tlb::{Flush, Tlb};
+    ///
+    /// let mut memory = Memory::default();
+    /// memory.pages.insert(0, 5);
+    /// memory.pages.insert(1, 6);
+    /// memory.pages.insert(2, 7);
+    ///
+    /// // A direct mapped TLB with 2 sets, where pages 0 and 2 share a set.
+    /// let mut tlb = Tlb::new(2, 1);
+    /// tlb.translate(&memory, 0x0010);
+    /// tlb.translate(&memory, 0x4010);
+    /// tlb.translate(&memory, 0x0020);
+    /// assert_eq!((tlb.statistics.misses, tlb.statistics.evictions), (3, 2));
+    ///
+    /// // Stale entries keep translating remapped pages until they are flushed.
+    /// tlb.translate(&memory, 0x2000);
+    /// memory.pages.insert(1, 9);
+    /// assert_eq!(tlb.translate(&memory, 0x2004), Some(0xC004));
+    ///
+    /// tlb.flush(Flush::Page(0x2000));
+    /// assert_eq!(tlb.translate(&memory, 0x2004), Some(0x12004));
+    /// assert_eq!(tlb.translate(&memory, 0x8000), None);
+    /// ```
+    pub fn translate(&mut self, memory: &dyn MemoryAccess, address: u64) -> Option<u64> {
+        let page = address.extract_page();
+        let set = (page % self.sets.len() as u64) as usize;
+        let entries = &mut self.sets[set];
+
+        if let Some(index) = entries.iter().position(|entry| entry.r#virtual == page) {
+            self.statistics.hits += 1;
+            let entry = entries.remove(index);
+            entries.insert(0, entry);
+            return Some(entry.physical.offset_page().set_item(address))
+        }
+
+        self.statistics.misses += 1;
+        let physical = memory.translate_virtual(address)?;
+        if entries.len() == self.ways {
+            entries.pop();
+            self.statistics.evictions += 1;
+        }
+
+        entries.insert(0, Entry { r#virtual: page, physical: physical.extract_page() });
+        Some(physical)
+    }
+
+    /// Throw away entries so their pages are translated through memory again. Guests flush entries through model
+    /// specific registers, which take effect once the instruction writing them finishes.
+    /// ```
+    /// use atln_processor::emulator::memory::{Memory, PAGE_BYTES_COUNT};
+    /// use atln_processor::emulator::memory::tlb::Tlb;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let program = "add.b r1, [0x2000]\nadd.b r1, [0x2000]\nwrmsr.q r2, 14\nadd.b r1, [0x2000]\nhalt";
+    /// let mut bytes = assemble(program).unwrap();
+    /// bytes.resize(PAGE_BYTES_COUNT as usize * 4, 0);
+    /// bytes[PAGE_BYTES_COUNT as usize * 2] = 3;
+    /// bytes[PAGE_BYTES_COUNT as usize * 3] = 10;
+    ///
+    /// let mut memory = Memory::from(bytes);
+    /// memory.pages.insert(0, 0);
+    /// memory.pages.insert(1, 2);
+    ///
+    /// let mut core = Core::default();
+    /// core.context.virtual_mode = true;
+    /// core.context.registers[2] = 0x2000;
+    /// core.tlb = Some(Tlb::default());
+    /// core.step(&mut memory, &mut Default::default());
+    ///
+    /// // The second read still sees the old page, and the third sees the new one after the guest flushes it.
+    /// memory.pages.insert(1, 3);
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// assert_eq!(core.context.registers[1], 16);
+    /// assert_eq!(core.tlb.unwrap().statistics.flushes, 1);
+    /// ```
+    pub fn flush(&mut self, flush: Flush) {
+        self.statistics.flushes += 1;
+        match flush {
+            Flush::Page(address) => {
+                let page = address.extract_page();
+                let set = (page % self.sets.len() as u64) as usize;
+                self.sets[set].retain(|entry| entry.r#virtual != page);
+            },
+            Flush::All => self.sets.iter_mut().for_each(Vec::clear)
+        }
+    }
+}
+
+/// Memory seen by a core with a TLB, which translates virtual addresses through the TLB.
+pub(crate) struct Translated<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub tlb: &'a RefCell<Tlb>
+}
+
+impl<'a> Translated<'a> {
+    /// Translate the address of a virtual frame, checking alignment first like memory does.
+    fn physical(&self, frame: Frame, r#virtual: bool) -> Result<Frame, GetError> {
+        if !r#virtual { return Ok(frame) }
+        if !frame.is_aligned() { return Err(GetError::UnalignedFrame) }
+
+        let address = self.translate_virtual(frame.address).ok_or(GetError::PageFault)?;
+        Ok(Frame { address, ..frame })
+    }
+}
+
+impl<'a> MemoryAccess for Translated<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let frame = self.physical(frame, r#virtual)?;
+        self.memory.get(frame, false)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        let frame = self.physical(frame, r#virtual)?;
+        self.memory.set(frame, false, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.tlb.borrow_mut().translate(&*self.memory, r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let tlb = self.tlb;
+        self.memory.synchronise(&mut |memory| operation(&mut Translated { memory, tlb }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
@@ -0,0 +1,316 @@
+//! Interactive machine monitor for inspecting and stepping through a program by hand.
+//!
+//! Commands are entered one per line:
+//!
+//! | Command                      | Effect                                                                  |
+//! |------------------------------|-------------------------------------------------------------------------|
+//! | `step [count]`               | Execute instructions, 1 by default.                                     |
+//! | `continue`                   | Execute until the core stops or reaches a breakpoint.                   |
+//! | `regs`                       | Show the registers, program counter, flags, mode and cycle count.       |
+//! | `mem <addr> <len>`           | Dump memory in rows of 16 bytes.                                        |
+//! | `peek <addr> <size>`         | Show the number in 1, 2, 4 or 8 bytes at a physical address.            |
+//! | `poke <addr> <size> <value>` | Write a number to 1, 2, 4 or 8 bytes at a physical address.             |
+//! | `find <byte>...`             | List the physical addresses where a sequence of bytes appears.          |
+//! | `disas [addr] [count]`       | Disassemble instructions, starting from the program counter by default. |
+//! | `break <addr>`               | Stop `continue` before the instruction at an address executes.          |
+//! | `delete <addr>`              | Remove a breakpoint.                                                    |
+//! | `breaks`                     | List the breakpoints.                                                   |
+//! | `changes`                    | Show what the last `step` or `continue` changed in the core and memory. |
+//!
+//! Numbers are decimal or hexadecimal with a `0x` prefix, and numbers in memory are little endian. Addresses are
+//! translated when the core is in virtual mode, except by `peek`, `poke` and `find`. With [Monitor::debug] set, symbol
+//! names can be used in place of numbers and disassembly is annotated with labels and source lines.
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter, Write};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+use emulator::diff;
+use emulator::memory::{Memory, MemoryError};
+use emulator::processor::processor::{Context, Core, Ports, Status};
+use programming::assembler::parse_number;
+use programming::debug::DebugInfo;
+use number;
+
+/// Number of bytes shown on each row of a memory dump.
+const DUMP_ROW_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorError {
+    /// The command does not exist.
+    Command,
+    /// An argument is missing or is not a number.
+    Argument,
+    /// The core stopped running, so it can't be stepped.
+    Stopped,
+    /// A number could not be read from or written to memory.
+    Memory(MemoryError)
+}
+
+impl Display for MonitorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Command => "command does not exist",
+            Self::Argument => "argument is missing or is not a number",
+            Self::Stopped => "core is no longer running",
+            Self::Memory(_) => "memory could not be accessed"
+        })
+    }
+}
+
+impl Error for MonitorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Memory(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+/// Machine being debugged along with the breakpoints set on it.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::monitor::Monitor;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let program = assemble("add.b r1, 5\nadd.b r1, r1\nhalt").unwrap();
+/// let mut monitor = Monitor::new(Core::default(), Memory::from(program), Default::default());
+///
+/// assert_eq!(monitor.execute("disas 0 2").unwrap(), "> 00000000: add.b r1, 5\n  00000004: add.b r1, r1\n");
+/// monitor.execute("break 0x4").unwrap();
+///
+/// assert_eq!(monitor.execute("continue").unwrap(), "breakpoint\n* 00000004: add.b r1, r1\n");
+/// assert_eq!(monitor.core.context.registers[1], 5);
+/// assert_eq!(monitor.execute("changes").unwrap(), "r1: 0x0 -> 0x5\npc: 0x0 -> 0x4\n");
+///
+/// assert_eq!(monitor.execute("continue").unwrap(), "halted\n");
+/// assert_eq!(monitor.core.context.registers[1], 10);
+/// assert!(monitor.execute("step").is_err());
+///
+/// assert_eq!(monitor.execute("mem 0 4").unwrap(), "00000000: 00 08 08 05\n");
+/// assert!(monitor.execute("mem 0 0xffffffffffffffff").is_ok());
+///
+/// monitor.execute("poke 2 2 0x1234").unwrap();
+/// assert_eq!(monitor.execute("peek 1 4").unwrap(), "00000001: 0x123408\n");
+/// assert!(monitor.execute("peek 8 8").is_err());
+/// assert_eq!(monitor.execute("find 0x34 0x12").unwrap(), "00000002\n");
+/// ```
+///
+/// Debug information from a linked image allows referring to labels.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::monitor::Monitor;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble_object;
+/// use atln_processor::programming::linker::link;
+///
+/// let mut object = assemble_object("add.b r1, 5\nend: halt").unwrap();
+/// object.file = "main.s".into();
+/// let mut image = link(&[object], 0).unwrap();
+///
+/// let mut monitor = Monitor::new(Core::default(), Memory::from(image.sections.remove(0).data), Default::default());
+/// monitor.debug = image.debug;
+///
+/// monitor.execute("break end").unwrap();
+/// assert_eq!(monitor.execute("continue").unwrap(), "breakpoint\nend:\n* 00000004: halt ; main.s:2\n");
+/// ```
+#[derive(Debug)]
+pub struct Monitor {
+    pub core: Core,
+    pub memory: Memory,
+    pub ports: Ports,
+    pub breakpoints: BTreeSet<u64>,
+    /// Symbols and source lines of the program being debugged.
+    pub debug: Option<DebugInfo>,
+    /// Status from the last instruction executed. Stepping is refused once the core is no longer running.
+    status: Status,
+    /// Context and physical memory before the last `step` or `continue`, which `changes` compares against.
+    previous: Option<(Context, Vec<u8>)>
+}
+
+impl Monitor {
+    pub fn new(core: Core, memory: Memory, ports: Ports) -> Self {
+        Self { core, memory, ports, breakpoints: BTreeSet::new(), debug: None, status: Status::Running, previous: None }
+    }
+
+    /// Status from the last instruction executed.
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    /// Run a command and return what it prints. Empty lines do nothing.
+    pub fn execute(&mut self, command: &str) -> Result<String, MonitorError> {
+        let mut words = command.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(String::new())
+        };
+
+        let arguments = words.map(|word| parse_number(word)
+            .or_else(|| self.debug.as_ref()?.symbol(word))
+            .ok_or(MonitorError::Argument)).collect::<Result<Vec<_>, _>>()?;
+        let argument = |index: usize| arguments.get(index).copied();
+        let mut output = String::new();
+
+        match name {
+            "step" => {
+                self.previous = Some((self.core.context.clone(), self.memory.bytes.clone()));
+                for _ in 0..argument(0).unwrap_or(1) {
+                    if !self.step()? { break }
+                }
+
+                self.describe(&mut output);
+            },
+            "continue" => {
+                self.previous = Some((self.core.context.clone(), self.memory.bytes.clone()));
+                self.step()?;
+                while self.status.is_running() && !self.breakpoints.contains(&self.core.context.program_counter) {
+                    self.step()?;
+                }
+
+                if self.status.is_running() { output.push_str("breakpoint\n"); }
+                self.describe(&mut output);
+            },
+            "regs" => {
+                for (index, register) in self.core.context.registers.iter().enumerate() {
+                    writeln!(output, "r{index} = {register:#018x}").unwrap();
+                }
+
+                writeln!(output, "pc = {:#018x}", self.core.context.program_counter).unwrap();
+                writeln!(output, "flags = {}", self.core.context.flags).unwrap();
+                writeln!(output, "mode = {}", if self.core.context.user_mode { "user" } else { "supervisor" }).unwrap();
+                writeln!(output, "cycles = {}", self.core.cycles).unwrap();
+            },
+            "mem" => {
+                let (address, length) = argument(0).zip(argument(1)).ok_or(MonitorError::Argument)?;
+                let mut bytes = vec![0u8; length.min(self.memory.bytes.len() as u64) as usize];
+                let read = self.memory.read_bytes(address, self.core.context.virtual_mode, &mut bytes);
+
+                for (row, chunk) in bytes[..read].chunks(DUMP_ROW_BYTES).enumerate() {
+                    write!(output, "{:08x}:", address.wrapping_add((row * DUMP_ROW_BYTES) as u64)).unwrap();
+                    for byte in chunk { write!(output, " {byte:02x}").unwrap(); }
+                    output.push('\n');
+                }
+            },
+            "peek" => {
+                let (address, size) = argument(0).zip(argument(1).and_then(|size| number::Size::from_size(size as usize))).ok_or(MonitorError::Argument)?;
+                let value = self.memory.read_number(address, size).map_err(MonitorError::Memory)?;
+                writeln!(output, "{address:08x}: {:#x}", value.quad()).unwrap();
+            },
+            "poke" => {
+                let (address, size) = argument(0).zip(argument(1).and_then(|size| number::Size::from_size(size as usize))).ok_or(MonitorError::Argument)?;
+                let value = number::Data::from_size_selecting(&size, argument(2).ok_or(MonitorError::Argument)?);
+                self.memory.write_number(address, value).map_err(MonitorError::Memory)?;
+            },
+            "find" => {
+                let bytes = arguments.iter().map(|&byte| u8::try_from(byte).map_err(|_| MonitorError::Argument)).collect::<Result<Vec<_>, _>>()?;
+                if bytes.is_empty() { return Err(MonitorError::Argument) }
+                for address in self.memory.find(&bytes) { writeln!(output, "{address:08x}").unwrap(); }
+            },
+            "disas" => self.disassemble(&mut output, argument(0).unwrap_or(self.core.context.program_counter), argument(1).unwrap_or(1)),
+            "break" => { self.breakpoints.insert(argument(0).ok_or(MonitorError::Argument)?); },
+            "delete" => { self.breakpoints.remove(&argument(0).ok_or(MonitorError::Argument)?); },
+            "breaks" => for breakpoint in &self.breakpoints { writeln!(output, "{breakpoint:08x}").unwrap(); },
+            "changes" => if let Some((context, bytes)) = &self.previous {
+                for change in diff::context(context, &self.core.context) { writeln!(output, "{change}").unwrap(); }
+                for range in diff::memory(0, bytes, &self.memory.bytes) { writeln!(output, "{range}").unwrap(); }
+            },
+            _ => return Err(MonitorError::Command)
+        }
+
+        Ok(output)
+    }
+
+    /// Read commands until the input ends or `quit` is entered, writing the output of each command. Errors are written
+    /// to the output instead of stopping the monitor.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::monitor::Monitor;
+    /// use atln_processor::emulator::processor::processor::Core;
+    ///
+    /// let mut monitor = Monitor::new(Core::default(), Memory::from(vec![0b000010_0_0, 0b0000_00_00]), Default::default());
+    /// let mut output = Vec::new();
+    /// monitor.repl("jump\nstep\nquit\nstep\n".as_bytes(), &mut output).unwrap();
+    ///
+    /// assert_eq!(String::from_utf8(output).unwrap(), "> error: command does not exist\n> halted\n> ");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn repl(&mut self, input: impl BufRead, mut output: impl io::Write) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        for line in input.lines() {
+            let line = line?;
+            if line.trim() == "quit" { break }
+
+            match self.execute(&line) {
+                Ok(text) => write!(output, "{text}")?,
+                Err(error) => writeln!(output, "error: {error}")?
+            }
+
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Execute a single instruction. Returns whether the core can keep running.
+    fn step(&mut self) -> Result<bool, MonitorError> {
+        if !self.status.is_running() { return Err(MonitorError::Stopped) }
+        self.status = self.core.step(&mut self.memory, &mut self.ports);
+        Ok(self.status.is_running())
+    }
+
+    /// Write why the core stopped, or the next instruction if it is still running.
+    fn describe(&mut self, output: &mut String) {
+        match &self.status {
+            Status::Running => self.disassemble(output, self.core.context.program_counter, 1),
+            Status::Halted => output.push_str("halted\n"),
+            Status::Faulted(exception) => {
+                write!(output, "faulted: {exception}").unwrap();
+                let mut source = exception.source();
+                while let Some(error) = source {
+                    write!(output, ": {error}").unwrap();
+                    source = error.source();
+                }
+                output.push('\n');
+            },
+            Status::BudgetExhausted => output.push_str("budget exhausted\n")
+        }
+    }
+
+    /// Write instructions starting from an address. The program counter is marked with `>` and breakpoints with `*`.
+    /// Instructions are preceded by the labels placed at them and followed by their source line when known.
+    fn disassemble(&mut self, output: &mut String, mut address: u64, count: u64) {
+        for _ in 0..count {
+            let marker = if self.breakpoints.contains(&address) { '*' } else if address == self.core.context.program_counter { '>' } else { ' ' };
+            match self.core.decode(&self.memory, address) {
+                Ok((instruction, length)) => {
+                    let debug = self.debug.as_ref();
+                    for symbol in debug.into_iter().flat_map(|debug| &debug.symbols).filter(|symbol| symbol.address == address) {
+                        writeln!(output, "{}:", symbol.name).unwrap();
+                    }
+
+                    write!(output, "{marker} {address:08x}: {instruction}").unwrap();
+                    if let Some((file, line)) = debug.and_then(|debug| debug.line(address)) { write!(output, " ; {file}:{line}").unwrap(); }
+                    output.push('\n');
+
+                    address = address.wrapping_add(length);
+                },
+                Err(error) => {
+                    writeln!(output, "{marker} {address:08x}: invalid ({error})").unwrap();
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Port space, where devices claim ranges of port addresses and are plugged in or removed while the machine runs.
+//!
+//! A [PortSpace] maps each 16 bit port address to the [Device] which claimed it. Claims can't overlap, and accessing an
+//! address nobody claimed either faults or reads a default value depending on [PortSpace::unclaimed].
+//!
+//! Operations see the 8 [Ports] of a core, which are a window onto the port space starting at [PortSpace::window]. With
+//! [Core::port_space](super::processor::processor::Core::port_space) set, the core
+//! [refreshes](PortSpace::refresh) the window from the devices before each instruction and
+//! [commits](PortSpace::commit) the ports the instruction changed to them afterwards. Reads are not seen since
+//! operations access the ports directly, so only writes to unclaimed ports fault.
+//! ```
+//! use atln_processor::emulator::port::{Device, PortError, PortSpace, Unclaimed};
+//!
+//! /// Counts the values written to it.
+//! #[derive(Default)]
+//! struct Counter(u8);
+//!
+//! impl Device for Counter {
+//!     fn read(&mut self, _offset: u16) -> u8 { self.0 }
+//!     fn write(&mut self, _offset: u16, value: u8) { self.0 = self.0.wrapping_add(value) }
+//! }
+//!
+//! let mut space = PortSpace::default();
+//! space.unclaimed = Unclaimed::Fault;
+//! let counter = space.claim(2..4, Counter::default()).unwrap();
+//!
+//! let mut ports = [0; 8];
+//! ports[2] = 5;
+//! space.commit(&[0; 8], &mut ports).unwrap();
+//! space.write(3, 1).unwrap();
+//!
+//! space.refresh(&mut ports);
+//! assert_eq!(ports[2..4], [6, 6]);
+//!
+//! // Once the device is unplugged, writing its ports faults and undoes the write.
+//! space.release(counter).unwrap();
+//! let before = ports;
+//! ports[2] = 9;
+//! assert_eq!(space.commit(&before, &mut ports), Err(PortError::Unclaimed(2)));
+//! assert_eq!(ports[2], 6);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, PoisonError};
+use emulator::processor::processor::Ports;
+
+/// Something which answers accesses to the ports it claims.
+pub trait Device: Send {
+    /// Value of the port at an offset from the start of the claimed range.
+    fn read(&mut self, offset: u16) -> u8;
+
+    /// Write the port at an offset from the start of the claimed range.
+    fn write(&mut self, offset: u16, value: u8);
+}
+
+/// Devices shared with the host, which inspects them while they are plugged in.
+#[cfg(feature = "std")]
+impl<T: Device> Device for Arc<Mutex<T>> {
+    fn read(&mut self, offset: u16) -> u8 {
+        self.lock().unwrap_or_else(PoisonError::into_inner).read(offset)
+    }
+
+    fn write(&mut self, offset: u16, value: u8) {
+        self.lock().unwrap_or_else(PoisonError::into_inner).write(offset, value)
+    }
+}
+
+/// What accessing a port nobody claimed does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unclaimed {
+    /// Fail with [PortError::Unclaimed].
+    Fault,
+    /// Read the value and ignore writes.
+    Value(u8)
+}
+
+impl Default for Unclaimed {
+    /// Unclaimed ports read 0, like ports did before devices could claim them.
+    fn default() -> Self {
+        Self::Value(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimError {
+    /// The range has no ports.
+    Empty,
+    /// Part of the range is claimed by the device with the identifier.
+    Overlaps(u64)
+}
+
+impl Display for ClaimError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("port range is empty"),
+            Self::Overlaps(identifier) => write!(f, "port range overlaps the ports of device {identifier}")
+        }
+    }
+}
+
+impl Error for ClaimError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortError {
+    /// No device claimed the port at the address.
+    Unclaimed(u16)
+}
+
+impl Display for PortError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unclaimed(port) => write!(f, "port {port:#06x} is not claimed by a device")
+        }
+    }
+}
+
+impl Error for PortError {}
+
+/// A device along with the ports it claimed.
+struct Claim {
+    identifier: u64,
+    range: Range<u16>,
+    device: Box<dyn Device>
+}
+
+/// Devices keyed by the ranges of port addresses they claimed.
+#[derive(Default)]
+pub struct PortSpace {
+    /// Address of the port operations see as port 0.
+    pub window: u16,
+    pub unclaimed: Unclaimed,
+    claims: Vec<Claim>,
+    next: u64
+}
+
+impl Debug for PortSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PortSpace")
+            .field("window", &self.window)
+            .field("unclaimed", &self.unclaimed)
+            .field("claims", &self.claims.iter().map(|claim| (claim.identifier, claim.range.clone())).collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PortSpace {
+    /// Plug in a device which answers a range of ports, returning an identifier for [PortSpace::release].
+    /// ```
+    /// use atln_processor::emulator::port::{ClaimError, Device, PortSpace};
+    ///
+    /// struct Null;
+    ///
+    /// impl Device for Null {
+    ///     fn read(&mut self, _offset: u16) -> u8 { 0 }
+    ///     fn write(&mut self, _offset: u16, _value: u8) {}
+    /// }
+    ///
+    /// let mut space = PortSpace::default();
+    /// let first = space.claim(0x10..0x20, Null).unwrap();
+    ///
+    /// assert_eq!(space.claim(0x18..0x28, Null), Err(ClaimError::Overlaps(first)));
+    /// assert_eq!(space.claim(0x20..0x20, Null), Err(ClaimError::Empty));
+    /// assert!(space.claim(0x20..0x28, Null).is_ok());
+    /// assert_eq!(space.claimant(0x1F), Some(first));
+    /// ```
+    pub fn claim(&mut self, range: Range<u16>, device: impl Device + 'static) -> Result<u64, ClaimError> {
+        if range.is_empty() { return Err(ClaimError::Empty) }
+        if let Some(claim) = self.claims.iter().find(|claim| claim.range.start < range.end && range.start < claim.range.end) {
+            return Err(ClaimError::Overlaps(claim.identifier))
+        }
+
+        let identifier = self.next;
+        self.next += 1;
+        self.claims.push(Claim { identifier, range, device: Box::new(device) });
+        Ok(identifier)
+    }
+
+    /// Unplug a device, returning it so it can be plugged in again later.
+    pub fn release(&mut self, identifier: u64) -> Option<Box<dyn Device>> {
+        let index = self.claims.iter().position(|claim| claim.identifier == identifier)?;
+        Some(self.claims.remove(index).device)
+    }
+
+    /// Identifier of the device which claimed a port.
+    pub fn claimant(&self, port: u16) -> Option<u64> {
+        self.claims.iter().find(|claim| claim.range.contains(&port)).map(|claim| claim.identifier)
+    }
+
+    pub fn read(&mut self, port: u16) -> Result<u8, PortError> {
+        match self.find(port) {
+            Some((claim, offset)) => Ok(claim.device.read(offset)),
+            None => self.fallback(port)
+        }
+    }
+
+    pub fn write(&mut self, port: u16, value: u8) -> Result<(), PortError> {
+        let Some((claim, offset)) = self.find(port) else { return self.fallback(port).map(|_| ()) };
+        claim.device.write(offset, value);
+        Ok(())
+    }
+
+    /// Read every port of the window into the ports. Ports which would fault keep their value.
+    pub fn refresh(&mut self, ports: &mut Ports) {
+        for (index, port) in ports.iter_mut().enumerate() {
+            if let Ok(value) = self.read(self.window.wrapping_add(index as u16)) { *port = value }
+        }
+    }
+
+    /// Write the ports which changed since they held the values before to the window. Writing stops at the first port
+    /// which faults, and the ports from there on are set back to their values before.
+    pub fn commit(&mut self, before: &Ports, ports: &mut Ports) -> Result<(), PortError> {
+        for index in 0..ports.len() {
+            if before[index] == ports[index] { continue }
+
+            if let Err(error) = self.write(self.window.wrapping_add(index as u16), ports[index]) {
+                ports[index..].copy_from_slice(&before[index..]);
+                return Err(error)
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Claim holding a port along with the offset of the port in it.
+    fn find(&mut self, port: u16) -> Option<(&mut Claim, u16)> {
+        self.claims.iter_mut().find(|claim| claim.range.contains(&port)).map(|claim| {
+            let offset = port - claim.range.start;
+            (claim, offset)
+        })
+    }
+
+    /// Value of a port nobody claimed.
+    fn fallback(&self, port: u16) -> Result<u8, PortError> {
+        match self.unclaimed {
+            Unclaimed::Fault => Err(PortError::Unclaimed(port)),
+            Unclaimed::Value(value) => Ok(value)
+        }
+    }
+}
@@ -1,8 +1,69 @@
-use emulator::memory::Memory;
-use super::processor::instruction::{Instruction, operation::Operation};
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cell::RefCell;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::event::{Event, Events};
+#[cfg(feature = "tracing")]
+use emulator::instrument::{DECODE_TARGET, EXECUTE_TARGET, PORT_TARGET};
+use emulator::memory::{Frame, GetError, MemoryAccess, PAGE_BYTES_COUNT, PAGE_ITEM_MASK};
+use emulator::memory::tlb::{Flush, Tlb, Translated};
+use emulator::port::PortError;
+#[cfg(feature = "std")]
+use emulator::port::PortSpace;
+use number::Size;
+use super::processor::block::{Block, MAX_BLOCK_INSTRUCTIONS};
+use super::processor::cache::{BlockCache, DecodeCache};
+use super::processor::instruction::{DecodeError, Instruction, MAX_INSTRUCTION_BYTES};
+use super::processor::instruction::prefix::Prefixes;
+use super::processor::instruction::operand::DynamicReadError;
+use super::processor::instruction::operation::{Extension, ExtensionError, OperationExecuteError};
+use super::processor::instruction::operation::condition::Flags;
+use super::processor::instruction::operation::data::Reservation;
+use super::processor::instruction::operation::control::MODEL_SPECIFIC_REGISTERS;
+use super::processor::instruction::operation::machine::Counters;
+use super::processor::instruction::operation::executor::Executor;
+use super::processor::coverage::Coverage;
+use super::processor::debug::Watched;
+#[cfg(feature = "heatmap")]
+use super::processor::heatmap::{Heated, Heatmap};
+use super::processor::interrupt::Interrupts;
+use super::processor::ordering::{Buffered, StoreBuffer};
+use super::processor::pipeline::Pipeline;
+use super::processor::profiler::Profiler;
+#[cfg(feature = "std")]
+use super::processor::semihosting::Semihosting;
+use super::processor::semihosting::CallError;
+use super::processor::timing::{Timed, Timing};
+#[cfg(feature = "std")]
+use super::processor::trace::{Record, Recorded, Tracer};
+use super::processor::undefined::Hook;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 pub mod array;
+pub mod block;
+pub mod cache;
+pub mod coverage;
+pub mod debug;
+#[cfg(feature = "heatmap")]
+pub mod heatmap;
 pub mod instruction;
+pub mod interrupt;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod ordering;
+pub mod pipeline;
+pub mod predictor;
+pub mod profiler;
+pub mod semihosting;
+pub mod timing;
+pub mod trace;
+pub mod undefined;
 
 /// Ports list for input and output.
 pub type Ports = [u8; 8];
@@ -10,23 +71,723 @@ pub type Ports = [u8; 8];
 /// Registers array.
 pub type Registers = [u64; 8];
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Core {
     pub context: Context,
+    /// Cycle costs of instructions.
+    pub timing: Timing,
+    /// Number of cycles spent executing instructions, according to [Core::timing].
+    pub cycles: u64,
+    /// Execution counts collected while profiling is enabled by setting this to [Some].
+    pub profiler: Option<Profiler>,
+    /// Executed addresses collected while coverage is enabled by setting this to [Some].
+    pub coverage: Option<Coverage>,
+    /// Accesses to each region of memory counted while the heatmap is enabled by setting this to [Some]. See
+    /// [heatmap].
+    #[cfg(feature = "heatmap")]
+    pub heatmap: Option<Heatmap>,
+    /// Stall statistics collected while the pipeline is simulated by setting this to [Some]. See [pipeline].
+    pub pipeline: Option<Pipeline>,
+    /// Instructions decoded by [Core::decode]. This does not contribute to the state of the core.
+    pub cache: DecodeCache,
+    /// Blocks decoded by [Core::decode_block]. This does not contribute to the state of the core.
+    pub blocks: BlockCache,
+    /// Stores held back while weak memory ordering is simulated by setting this to [Some]. See [ordering].
+    pub store_buffer: Option<StoreBuffer>,
+    /// Cached page translations and their statistics while a TLB is simulated by setting this to [Some]. See
+    /// [tlb](crate::emulator::memory::tlb).
+    pub tlb: Option<Tlb>,
+    /// Devices behind the ports while they are mapped by setting this to [Some]. See [port](crate::emulator::port).
+    /// Clones of the core share the port space.
+    #[cfg(feature = "std")]
+    pub port_space: Option<Arc<Mutex<PortSpace>>>,
+    /// Handler of the `hcall` operation. Without one, `hcall` faults. Clones of the core share the handler.
+    #[cfg(feature = "std")]
+    pub semihosting: Option<Arc<Mutex<Semihosting>>>,
+    /// Where executed instructions are exported while tracing is enabled by setting this to [Some]. See [trace]. Clones
+    /// of the core share the tracer.
+    #[cfg(feature = "std")]
+    pub tracer: Option<Arc<Mutex<Tracer>>>,
+    /// Handler emulating instructions with an [invalid code](DecodeError::InvalidCode). See [undefined].
+    pub undefined: Option<Hook>,
+    /// Subscribers notified of what the core does. See [event](crate::emulator::event).
+    pub events: Events
 }
 
 /// The execution context of an individual core.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Context {
     pub registers: Registers,
+    /// Address of the next instruction to fetch.
+    pub program_counter: u64,
     /// Whether virtual memory address translation is enabled.
-    pub virtual_mode: bool
+    pub virtual_mode: bool,
+    /// Whether the core is unprivileged, which makes privileged operations fault. Delivering an interrupt leaves user
+    /// mode.
+    pub user_mode: bool,
+    /// Pending interrupts and the handler table they are delivered through.
+    pub interrupts: Interrupts,
+    /// Address reserved by the last load linked, if it was not used or broken since.
+    pub reservation: Option<Reservation>,
+    /// Flags written by the last `cmp`, which conditional moves test.
+    pub flags: Flags,
+    /// Index of the core within its system, which `cpuid` reports.
+    pub identifier: u64,
+    /// Performance counters, which `rdctr` reads.
+    pub counters: Counters,
+    /// Model specific registers which are not backed by other state of the core, which `rdmsr` and `wrmsr` access.
+    pub model_specific: [u64; MODEL_SPECIFIC_REGISTERS],
+    /// TLB entries the guest asked to flush during the current step, which are flushed once it ends.
+    pub flush: Option<Flush>
+}
+
+/// Reason for a core being unable to continue executing.
+#[derive(Debug)]
+pub enum Exception {
+    /// The instruction at the program counter could not be decoded.
+    Decode(DecodeError),
+    /// The instruction failed to execute.
+    Execute(OperationExecuteError<ExtensionError>),
+    /// The handler address of a pending interrupt could not be read from the handler table.
+    Interrupt(GetError),
+    /// The host could not make the call requested by `hcall`.
+    HostCall(CallError),
+    /// The instruction wrote a port of the [port space](crate::emulator::port) which no device answers.
+    Port(PortError)
+}
+
+impl Display for Exception {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Decode(_) => "failed to decode instruction",
+            Self::Execute(_) => "failed to execute instruction",
+            Self::Interrupt(_) => "failed to read the interrupt handler address",
+            Self::HostCall(_) => "failed to make the host call",
+            Self::Port(_) => "failed to access a port"
+        })
+    }
+}
+
+impl Error for Exception {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Decode(error) => Some(error),
+            Self::Execute(error) => Some(error),
+            Self::Interrupt(error) => Some(error),
+            Self::HostCall(error) => Some(error),
+            Self::Port(error) => Some(error)
+        }
+    }
+}
+
+impl Exception {
+    /// The memory access error which caused the exception, if it was caused by one.
+    pub fn memory(&self) -> Option<&GetError> {
+        match self {
+            Self::Execute(OperationExecuteError::DynamicRead(DynamicReadError::Memory(error))) | Self::Interrupt(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<DecodeError> for Exception {
+    fn from(value: DecodeError) -> Self {
+        Self::Decode(value)
+    }
 }
 
+impl From<OperationExecuteError<ExtensionError>> for Exception {
+    fn from(value: OperationExecuteError<ExtensionError>) -> Self {
+        Self::Execute(value)
+    }
+}
+
+/// The state a core is left in after executing.
+#[derive(Debug)]
+pub enum Status {
+    /// The core can continue executing.
+    Running,
+    /// A halt instruction was executed.
+    Halted,
+    /// An exception prevented the core from continuing. The program counter is left at the faulting instruction.
+    Faulted(Exception),
+    /// The budget given to [Core::run] was used up before the core halted.
+    BudgetExhausted
+}
+
+/// Limit on how much [Core::run] may execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Budget {
+    /// Number of instructions.
+    Instructions(u64),
+    /// Number of cycles. The instruction which crosses the limit still executes fully.
+    Cycles(u64)
+}
+
+impl Status {
+    /// Whether the core can continue executing.
+    pub fn is_running(&self) -> bool {
+        matches!(self, Self::Running)
+    }
+}
+
+/// Cores are equal when their [contexts](Context) are. Only the architectural state is compared, so the cycle count,
+/// timing, caches, store buffer and attached tools of two cores may differ even when they are equal.
+impl PartialEq for Core {
+    fn eq(&self, other: &Self) -> bool {
+        self.context == other.context
+    }
+}
+
+impl Eq for Core {}
+
 impl Core {
-    /// Execute an instruction and see if the processor must halt. Doing this could modify the execution context.
-    pub fn execute(&mut self, instruction: &Instruction, memory: &mut Memory, ports: &mut Ports) -> bool {
-        instruction.extension().operation().execute(instruction.data().as_ref(), memory, &mut self.context, ports).expect("TODO: panic message");
-        false
+    /// Execute an instruction and count its cycles. Doing this could modify the execution context. The returned status
+    /// is never [Status::BudgetExhausted]. Synchronised instructions are executed through [MemoryAccess::synchronise].
+    /// With a [StoreBuffer], other instructions execute through the buffer and the buffer is emptied once the core stops.
+    /// Once the instruction executed without faulting, the dynamic operand
+    /// [updates](instruction::operand::Dynamic::update) its base register and matching data [breakpoints](debug) raise a
+    /// debug exception. With a [port space](Core::port_space), the ports are read from its devices before the instruction
+    /// and the ports it wrote are written to them after.
+    pub fn execute(&mut self, instruction: &Instruction, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(target: EXECUTE_TARGET, %instruction, "execute");
+
+        let data = instruction.data().as_ref();
+        #[cfg(feature = "std")]
+        if let Some(space) = &self.port_space { space.lock().unwrap_or_else(PoisonError::into_inner).refresh(ports) }
+
+        let before = *ports;
+        let breakpoints = debug::data_breakpoints(&self.context);
+        let hits = Cell::new(0);
+        let mut operation = |memory: &mut dyn MemoryAccess, context: &mut Context| match breakpoints {
+            Some(breakpoints) => instruction.extension().execute(data, &mut Watched { memory, breakpoints, hits: &hits }, context, ports),
+            None => instruction.extension().execute(data, memory, context, ports)
+        };
+
+        let result = if instruction.synchronised() {
+            self.drain_stores(memory);
+            let mut result = Ok(());
+            memory.synchronise(&mut |memory| result = operation(memory, &mut self.context));
+            result
+        } else if let Some(buffer) = &mut self.store_buffer {
+            operation(&mut Buffered { memory, buffer }, &mut self.context)
+        } else {
+            operation(memory, &mut self.context)
+        };
+
+        if let Err(error) = result {
+            self.drain_stores(memory);
+            return Status::Faulted(Exception::Execute(error));
+        }
+
+        if let Err(error) = self.commit_ports(&before, ports) {
+            self.drain_stores(memory);
+            return Status::Faulted(Exception::Port(error));
+        }
+
+        let exited = match self.host_call(instruction, memory) {
+            Ok(exited) => exited,
+            Err(error) => return Status::Faulted(Exception::HostCall(error))
+        };
+
+        if let Some(data) = data { if let Some(dynamic) = data.operands.x_dynamic() { dynamic.update(&data.width, &mut self.context) } }
+
+        if let Some(vector) = self.context.interrupts.trap.take() {
+            self.drain_stores(memory);
+            if let Err(error) = self.interrupt(vector, memory) { return Status::Faulted(Exception::Interrupt(error)) }
+        }
+
+        self.retire(instruction);
+        self.stall(self.timing.port_writes(&before, ports));
+        self.breakpoint(hits.get());
+
+        if !self.events.is_empty() || cfg!(feature = "tracing") {
+            for (port, (before, after)) in before.iter().zip(ports.iter()).enumerate() {
+                if before == after { continue }
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(target: PORT_TARGET, port, value = *after, "write");
+                self.events.emit(Event::PortWrite { core: self.context.identifier, port: port as u8, value: *after });
+            }
+        }
+
+        if exited || matches!(instruction.extension(), Extension::Executor(Executor::Halt)) {
+            self.drain_stores(memory);
+            self.events.emit(Event::Halt { core: self.context.identifier });
+            Status::Halted
+        } else {
+            Status::Running
+        }
+    }
+
+    /// Fetch the instruction at the program counter, advance the program counter past it and execute it. Instructions
+    /// which divert set the program counter themselves. If the instruction faults, then the program counter is moved
+    /// back to it. A pending interrupt is delivered first, so the instruction executed is the first of its handler.
+    /// Instructions with an invalid code are passed to the [undefined] handler before faulting.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // The handler table holds a single handler at address 16.
+    /// let mut program = assemble("add.b r1, 1\nhalt").unwrap();
+    /// program.resize(16, 0);
+    /// program.extend(assemble("add.b r2, 1\nresume").unwrap());
+    /// program.resize(32, 0);
+    /// program.extend(16u64.to_le_bytes());
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.interrupts.table = 32;
+    /// core.context.interrupts.enabled = true;
+    /// core.context.interrupts.raise(0);
+    ///
+    /// core.step(&mut memory, &mut Default::default());
+    /// assert_eq!((core.context.registers[2], core.context.program_counter), (1, 20));
+    /// assert!(!core.context.interrupts.enabled);
+    ///
+    /// core.step(&mut memory, &mut Default::default());
+    /// core.step(&mut memory, &mut Default::default());
+    /// assert_eq!(core.context.registers[1], 1);
+    /// assert!(core.context.interrupts.enabled);
+    /// ```
+    pub fn step(&mut self, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!(target: EXECUTE_TARGET, "step", core = self.context.identifier, program_counter = self.context.program_counter).entered();
+
+        let status = match self.tlb.take() {
+            Some(tlb) => {
+                let tlb = RefCell::new(tlb);
+                let status = self.step_instruction(&mut Translated { memory, tlb: &tlb }, ports);
+                self.tlb = Some(tlb.into_inner());
+                status
+            },
+            None => self.step_instruction(memory, ports)
+        };
+
+        self.flush_tlb();
+        status
+    }
+
+    /// Deliver a pending interrupt and execute the next instruction, which is everything [Core::step] does.
+    fn step_instruction(&mut self, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        if let Some(vector) = self.context.interrupts.next() {
+            self.drain_stores(memory);
+            if let Err(error) = self.interrupt(vector, memory) { return self.report(Status::Faulted(Exception::Interrupt(error))) }
+        }
+
+        let address = self.context.program_counter;
+        let (instruction, length) = match self.decode(memory, address) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                self.drain_stores(memory);
+                if matches!(error, DecodeError::InvalidCode(_)) && self.emulate(address, memory, ports) { return Status::Running }
+                return Status::Faulted(Exception::Decode(error))
+            }
+        };
+
+        self.observe(address, &instruction);
+        self.context.program_counter = address.wrapping_add(length);
+        let status = self.dispatch(address, &instruction, memory, ports);
+        if let Status::Faulted(_) = status {
+            self.context.program_counter = address;
+            return self.report(status)
+        }
+
+        let hits = debug::execute_hits(&self.context, address);
+        self.breakpoint(hits);
+        status
+    }
+
+    /// Step through instructions starting from the program counter until the processor stops running. If a budget is
+    /// given, then [Status::BudgetExhausted] is returned once it is used up. This allows hosts to time slice guests by
+    /// calling this repeatedly.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::{Budget, Core, Status};
+    ///
+    /// let mut memory = Memory::from(vec![
+    ///     // add.b r1, r2
+    ///     0b000000_0_0, 0b0000_00_00, 0b00_001_010,
+    ///     // divert 8
+    ///     0b000010_0_0, 0b0001_10_00, 0b00_000_000, 8,
+    ///     // Skipped.
+    ///     0,
+    ///     // halt
+    ///     0b000010_0_0, 0b0000_00_00
+    /// ]);
+    ///
+    /// let mut core = Core::default();
+    /// core.context.registers[2] = 5;
+    ///
+    /// assert!(matches!(core.run(&mut memory, &mut Default::default(), Some(Budget::Instructions(1))), Status::BudgetExhausted));
+    /// assert_eq!(core.context.program_counter, 3);
+    ///
+    /// assert!(matches!(core.run(&mut memory, &mut Default::default(), None), Status::Halted));
+    /// assert_eq!(core.context.registers[1], 5);
+    /// assert_eq!(core.context.program_counter, 10);
+    /// assert_eq!(core.cycles, 3);
+    /// ```
+    pub fn run(&mut self, memory: &mut dyn MemoryAccess, ports: &mut Ports, budget: Option<Budget>) -> Status {
+        let start = self.cycles;
+        let mut executed = 0;
+        loop {
+            let exhausted = match budget {
+                Some(Budget::Instructions(budget)) => executed >= budget,
+                Some(Budget::Cycles(budget)) => self.cycles.wrapping_sub(start) >= budget,
+                None => false
+            };
+
+            if exhausted { return Status::BudgetExhausted }
+
+            let status = self.step(memory, ports);
+            if !status.is_running() { return status }
+            executed += 1;
+        }
+    }
+
+    /// Fetch and decode the instruction at an address. The address is translated if the core is in virtual mode.
+    /// Decoded instructions are kept in the [DecodeCache] so decoding the same address again is skipped unless the
+    /// memory the instruction was decoded from has been written to since. The instruction is returned along with its
+    /// encoded length.
+    /// ```
+    /// use atln_processor::emulator::memory::{Frame, Memory};
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::number::{Data, Size};
+    ///
+    /// // add.b r1, r2
+    /// let mut memory = Memory::from(vec![0b000000_0_0, 0b0000_00_00, 0b00_001_010, 0]);
+    /// let mut core = Core::default();
+    ///
+    /// let (first, length) = core.decode(&memory, 0).unwrap();
+    /// let (second, _) = core.decode(&memory, 0).unwrap();
+    ///
+    /// assert_eq!(length, 3);
+    /// assert!(std::sync::Arc::ptr_eq(&first, &second));
+    ///
+    /// // Writing over the instruction makes it decode again.
+    /// memory.set(Frame { address: 2, size: Size::Byte }, false, Data::Byte(0b00_011_010)).unwrap();
+    /// let (third, _) = core.decode(&memory, 0).unwrap();
+    ///
+    /// assert!(!std::sync::Arc::ptr_eq(&first, &third));
+    /// ```
+    pub fn decode(&mut self, memory: &dyn MemoryAccess, address: u64) -> Result<(Arc<Instruction>, u64), DecodeError> {
+        let physical = if self.context.virtual_mode { memory.translate_virtual(address) } else { Some(address) };
+        if let Some(entry) = physical.and_then(|physical| self.cache.get(memory, physical)) {
+            return Ok((entry.value.clone(), entry.length));
+        }
+
+        // An instruction which can't reach the next page is read from the physical address already translated, rather
+        // than translating each of its bytes again.
+        let mut encoded = [0u8; MAX_INSTRUCTION_BYTES];
+        let within_page = (address & PAGE_ITEM_MASK) + MAX_INSTRUCTION_BYTES as u64 <= PAGE_BYTES_COUNT;
+        let available = match physical {
+            Some(physical) if within_page => memory.read_bytes(physical, false, &mut encoded),
+            _ => memory.read_bytes(address, self.context.virtual_mode, &mut encoded)
+        };
+
+        let (instruction, length) = match Instruction::decode_slice(&encoded[..available]) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(target: DECODE_TARGET, address, %error, "decode failed");
+                return Err(error)
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(target: DECODE_TARGET, address, length, %instruction, "decoded");
+
+        let instruction = Arc::new(instruction);
+        let length = length as u64;
+
+        // Only cache instructions which occupy contiguous physical memory. In virtual mode an instruction crossing a
+        // page boundary can be split between unrelated physical pages.
+        let contiguous = !self.context.virtual_mode || (address & PAGE_ITEM_MASK) + length <= PAGE_BYTES_COUNT;
+        if let (Some(physical), true) = (physical, contiguous) { self.cache.insert(memory, physical, instruction.clone(), length); }
+
+        Ok((instruction, length))
+    }
+
+    /// Decode the block of instructions starting at an address. The block ends after the first instruction which
+    /// diverts, after [MAX_BLOCK_INSTRUCTIONS] instructions, or before an instruction that fails to decode. In virtual
+    /// mode the block also ends at the page boundary. Only a failure to decode the first instruction is an error.
+    /// Blocks are cached in the same way as instructions in [Core::decode].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    ///
+    /// // add.b r1, r2 twice followed by bytes which are not an instruction.
+    /// let mut memory = Memory::from(vec![0, 0, 0b00_001_010, 0, 0, 0b00_001_010, 0b111111_0_0, 0]);
+    /// let mut core = Core::default();
+    /// core.context.registers[2] = 3;
+    ///
+    /// let block = core.decode_block(&memory, 0).unwrap();
+    /// assert_eq!(block.instructions.len(), 2);
+    /// assert_eq!(block.end(), 6);
+    ///
+    /// assert!(core.execute_block(&block, &mut memory, &mut Default::default()).is_running());
+    /// assert_eq!(core.context.registers[1], 6);
+    /// ```
+    pub fn decode_block(&mut self, memory: &dyn MemoryAccess, address: u64) -> Result<Arc<Block>, DecodeError> {
+        let physical = if self.context.virtual_mode { memory.translate_virtual(address) } else { Some(address) };
+        if let Some(entry) = physical.and_then(|physical| self.blocks.get(memory, physical)) {
+            return Ok(entry.value.clone());
+        }
+
+        let mut block = Block { start: address, length: 0, instructions: Vec::new() };
+        let page_end = (address & !PAGE_ITEM_MASK).saturating_add(PAGE_BYTES_COUNT);
+
+        while block.instructions.len() < MAX_BLOCK_INSTRUCTIONS {
+            let (instruction, length) = match self.decode(memory, block.end()) {
+                Ok(decoded) => decoded,
+                Err(error) => if block.instructions.is_empty() { return Err(error) } else { break }
+            };
+
+            if self.context.virtual_mode && block.end() + length > page_end && !block.instructions.is_empty() { break }
+
+            let diverts = instruction.diverts();
+            block.length += length;
+            block.instructions.push((instruction, length));
+            if diverts { break }
+        }
+
+        let block = Arc::new(block);
+        let contiguous = !self.context.virtual_mode || block.end() <= page_end;
+        if let (Some(physical), true) = (physical, contiguous) { self.blocks.insert(memory, physical, block.clone(), block.length); }
+
+        Ok(block)
+    }
+
+    /// Execute every instruction of a block in order. The program counter is advanced past each instruction before it
+    /// executes, in the same way as [Core::step]. Execution stops at the first instruction that does not leave the core
+    /// running or that matches an execute breakpoint. Writes made by the block to its own instructions take effect the
+    /// next time the block is decoded, while TLB flushes take effect after the instruction asking for them.
+    /// ```
+    /// use atln_processor::emulator::memory::{Memory, PAGE_BYTES_COUNT};
+    /// use atln_processor::emulator::memory::tlb::Tlb;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut bytes = assemble("add.b r1, [0x2000]\nwrmsr.q r2, 14\nhalt").unwrap();
+    /// bytes.resize(PAGE_BYTES_COUNT as usize * 4, 0);
+    ///
+    /// let mut memory = Memory::from(bytes);
+    /// memory.pages.insert(0, 0);
+    /// memory.pages.insert(1, 2);
+    ///
+    /// let mut core = Core::default();
+    /// core.context.virtual_mode = true;
+    /// core.context.registers[2] = 0x2000;
+    /// core.tlb = Some(Tlb::default());
+    /// core.step(&mut memory, &mut Default::default());
+    ///
+    /// // The block flushes the remapped page, so it is translated through memory again.
+    /// memory.pages.insert(1, 3);
+    /// let block = core.decode_block(&memory, core.context.program_counter).unwrap();
+    /// core.execute_block(&block, &mut memory, &mut Default::default());
+    ///
+    /// let tlb = core.tlb.as_mut().unwrap();
+    /// assert_eq!(tlb.statistics.flushes, 1);
+    /// assert_eq!(tlb.translate(&memory, 0x2000), Some(PAGE_BYTES_COUNT * 3));
+    /// ```
+    pub fn execute_block(&mut self, block: &Block, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        self.execute_instructions(block.start, &block.instructions, memory, ports)
+    }
+
+    /// Execute a run of consecutive instructions starting at an address. See [Core::execute_block].
+    pub fn execute_instructions(&mut self, mut address: u64, instructions: &[(Arc<Instruction>, u64)], memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        for (instruction, length) in instructions {
+            self.observe(address, instruction);
+            self.context.program_counter = address.wrapping_add(*length);
+            let status = self.dispatch(address, instruction, memory, ports);
+            self.flush_tlb();
+            if let Status::Faulted(_) = status {
+                self.context.program_counter = address;
+                return self.report(status)
+            }
+
+            let hits = debug::execute_hits(&self.context, address);
+            self.breakpoint(hits);
+            if hits != 0 || !status.is_running() { return status }
+            address = address.wrapping_add(*length);
+        }
+
+        Status::Running
+    }
+
+    /// Enter the handler of an interrupt. The interrupt stays pending if its handler address can't be read.
+    fn interrupt(&mut self, vector: u8, memory: &dyn MemoryAccess) -> Result<(), GetError> {
+        let frame = Frame { address: self.context.interrupts.handler(vector), size: Size::Quad };
+        let handler = memory.get(frame, self.context.virtual_mode)?.quad();
+
+        self.context.interrupts.enter(vector, self.context.program_counter, self.context.flags, self.context.user_mode);
+        self.context.user_mode = false;
+        self.context.program_counter = handler;
+        self.context.reservation = None;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(target: EXECUTE_TARGET, core = self.context.identifier, vector, handler, "interrupt");
+        self.events.emit(Event::Trap { core: self.context.identifier, vector });
+        Ok(())
+    }
+
+    /// Flush the entries of the TLB the guest asked to flush, if any.
+    fn flush_tlb(&mut self) {
+        if let (Some(flush), Some(tlb)) = (self.context.flush.take(), &mut self.tlb) { tlb.flush(flush) }
+    }
+
+    /// Write every store held back by the store buffer to memory.
+    fn drain_stores(&mut self, memory: &mut dyn MemoryAccess) {
+        if let Some(buffer) = &mut self.store_buffer { buffer.drain(memory); }
+    }
+
+    /// Raise a debug exception for breakpoints that matched and notify subscribers. Nothing happens if none matched.
+    fn breakpoint(&mut self, hits: u64) {
+        if hits == 0 { return }
+
+        debug::trigger(&mut self.context, hits);
+        self.events.emit(Event::Breakpoint { core: self.context.identifier, hits });
+    }
+
+    /// Notify subscribers of a memory fault held by a status, trace the fault and pass the status on. The program counter
+    /// must be at the faulting instruction.
+    fn report(&self, status: Status) -> Status {
+        #[cfg(feature = "tracing")]
+        if let Status::Faulted(exception) = &status {
+            tracing::debug!(target: EXECUTE_TARGET, core = self.context.identifier, address = self.context.program_counter, %exception, "fault");
+        }
+
+        if let Some(error) = match &status { Status::Faulted(exception) => exception.memory(), _ => None } {
+            self.events.emit(Event::MemoryFault { core: self.context.identifier, address: self.context.program_counter, error: error.clone() });
+        }
+
+        status
+    }
+
+    /// Pass the instruction at an address to the [undefined] handler. Returns whether the handler emulated it.
+    fn emulate(&mut self, address: u64, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> bool {
+        let hook = match &self.undefined {
+            Some(hook) => hook.clone(),
+            None => return false
+        };
+
+        let mut encoded = [0u8; MAX_INSTRUCTION_BYTES];
+        let available = memory.read_bytes(address, self.context.virtual_mode, &mut encoded);
+        let stream = &encoded[..available];
+
+        let driver = match Prefixes::decode_slice(stream).map(|(_, start)| stream.get(start..start + 2)) {
+            Ok(Some(&[driver0, driver1])) => [driver0, driver1],
+            _ => return false
+        };
+
+        match hook.0.handle(driver, stream, &mut self.context, memory, ports) {
+            Some(length) => self.context.program_counter = self.context.program_counter.wrapping_add(length),
+            None => {
+                self.context.program_counter = address;
+                return false
+            }
+        }
+
+        true
+    }
+
+    /// Pass the request of an `hcall` to the [Semihosting] handler. Returns whether the guest exited, and does nothing
+    /// for other instructions.
+    fn host_call(&mut self, instruction: &Instruction, memory: &mut dyn MemoryAccess) -> Result<bool, CallError> {
+        if !matches!(instruction.extension(), Extension::Executor(Executor::HostCall)) { return Ok(false) }
+        self.drain_stores(memory);
+
+        #[cfg(feature = "std")]
+        return self.semihosting.as_ref()
+            .ok_or(CallError::Unavailable)?
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .call(&mut self.context, memory);
+
+        #[cfg(not(feature = "std"))]
+        Err(CallError::Unavailable)
+    }
+
+    /// Write the ports an instruction changed to the devices of the [port space](Core::port_space) if there is one.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn commit_ports(&self, before: &Ports, ports: &mut Ports) -> Result<(), PortError> {
+        #[cfg(feature = "std")]
+        if let Some(space) = &self.port_space { return space.lock().unwrap_or_else(PoisonError::into_inner).commit(before, ports) }
+
+        Ok(())
+    }
+
+    /// Record an execution of an instruction with the profiler and coverage if they are enabled.
+    fn observe(&mut self, address: u64, instruction: &Arc<Instruction>) {
+        if let Some(profiler) = &mut self.profiler { profiler.record(address, instruction); }
+        if let Some(coverage) = &mut self.coverage { coverage.record(address); }
+    }
+
+    /// Execute an instruction fetched from an address, exporting it to the [tracer](Core::tracer) if there is one and
+    /// feeding it to the [pipeline](Core::pipeline) once it completes. The program counter must already be past it. With
+    /// the `heatmap` feature, the instruction and its accesses are counted in the heatmap if there is one. Accesses to
+    /// memory with a [latency](timing::Latency) add their cycles.
+    fn dispatch(&mut self, address: u64, instruction: &Instruction, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Status {
+        #[cfg(feature = "heatmap")]
+        if let Some(mut heatmap) = self.heatmap.take() {
+            heatmap.execute(self.context.identifier, address);
+            let heatmap = RefCell::new(heatmap);
+            let status = self.dispatch(address, instruction, &mut Heated { memory, heatmap: &heatmap, core: self.context.identifier }, ports);
+            self.heatmap = Some(heatmap.into_inner());
+            return status
+        }
+
+        if !self.timing.memory.is_empty() {
+            let regions = core::mem::take(&mut self.timing.memory);
+            let cycles = Cell::new(0);
+            let status = self.dispatch(address, instruction, &mut Timed { memory, regions: &regions, cycles: &cycles }, ports);
+            self.timing.memory = regions;
+            self.stall(cycles.get());
+            return status
+        }
+
+        let next = self.context.program_counter;
+
+        #[cfg(feature = "std")]
+        let status = match self.tracer.clone() {
+            Some(tracer) => {
+                let accesses = RefCell::new(Vec::new());
+                let status = self.execute(instruction, &mut Recorded { memory, accesses: &accesses }, ports);
+                let record = Record::new(address, instruction, &self.context, self.cycles, accesses.into_inner(), &status);
+
+                tracer.lock().unwrap_or_else(PoisonError::into_inner).record(&record);
+                status
+            },
+            None => self.execute(instruction, memory, ports)
+        };
+
+        #[cfg(not(feature = "std"))]
+        let status = self.execute(instruction, memory, ports);
+
+        if let Some(pipeline) = &mut self.pipeline {
+            let target = Some(self.context.program_counter).filter(|&target| target != next);
+            if !matches!(status, Status::Faulted(_)) { pipeline.record(address, instruction, target); }
+        }
+
+        status
+    }
+
+    /// Account for cycles spent waiting on memory or devices in the cycles and the cycle counter.
+    fn stall(&mut self, cycles: u64) {
+        self.cycles = self.cycles.wrapping_add(cycles);
+        self.context.counters.cycles = self.context.counters.cycles.wrapping_add(cycles);
+    }
+
+    /// Account for an instruction that completed in the cycles and the performance counters.
+    fn retire(&mut self, instruction: &Instruction) {
+        let cost = self.timing.cost(instruction);
+
+        self.cycles = self.cycles.wrapping_add(cost);
+        self.context.counters.cycles = self.context.counters.cycles.wrapping_add(cost);
+        self.context.counters.instructions = self.context.counters.instructions.wrapping_add(1);
     }
 }
@@ -0,0 +1,34 @@
+//! Straight line runs of pre-decoded instructions.
+//!
+//! A block starts at an address and covers every following instruction up to and including the first one that diverts
+//! control flow. Executing a block runs its instructions back to back without fetching or decoding each of them, which
+//! amortizes the dispatch cost over the whole run.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use super::instruction::Instruction;
+
+/// Largest number of instructions decoded into a single block.
+pub const MAX_BLOCK_INSTRUCTIONS: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct Block {
+    /// Address of the first instruction.
+    pub start: u64,
+    /// Number of bytes the encoded instructions occupy.
+    pub length: u64,
+    /// Decoded instructions along with their encoded lengths in execution order.
+    pub instructions: Vec<(Arc<Instruction>, u64)>
+}
+
+impl Block {
+    /// Address directly after the last instruction of the block.
+    pub fn end(&self) -> u64 {
+        self.start.wrapping_add(self.length)
+    }
+
+    /// Whether the block ends because of an instruction that diverts control flow rather than a limit.
+    pub fn terminated(&self) -> bool {
+        self.instructions.last().is_some_and(|(instruction, _)| instruction.diverts())
+    }
+}
@@ -0,0 +1,91 @@
+//! Caches of decoded instructions and blocks keyed by their physical address.
+//!
+//! Decoding an instruction involves reading the driver bytes, the registers byte and the immediate before conditioning
+//! them into an [Instruction]. Hot loops execute the same addresses many times, so the decoded form is kept and reused
+//! for as long as the memory it was decoded from stays unchanged. Changes are detected through
+//! [MemoryAccess::write_count] on every line the encoded form occupies, meaning self modifying code is always decoded
+//! again.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+use emulator::memory::{MemoryAccess, WRITE_LINE_BITS};
+use utility::Map;
+use super::block::Block;
+use super::instruction::Instruction;
+
+/// A decoded value along with what is needed to check if it is still valid.
+#[derive(Debug, Clone)]
+pub struct Entry<T> {
+    pub value: T,
+    /// Number of bytes the encoded form occupies.
+    pub length: u64,
+    /// Write count of each line the encoded form occupied at the time it was decoded.
+    writes: Vec<u64>
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache<T> {
+    entries: Map<u64, Entry<T>>
+}
+
+/// Cache of individual instructions.
+pub type DecodeCache = Cache<Arc<Instruction>>;
+
+/// Cache of straight line runs of instructions.
+pub type BlockCache = Cache<Arc<Block>>;
+
+impl<T> Default for Cache<T> {
+    fn default() -> Self {
+        Self { entries: Map::new() }
+    }
+}
+
+/// Lines occupied by a range of physical memory.
+fn lines(address: u64, length: u64) -> RangeInclusive<u64> {
+    let last = address.saturating_add(length.max(1) - 1);
+    (address >> WRITE_LINE_BITS)..=(last >> WRITE_LINE_BITS)
+}
+
+impl<T> Cache<T> {
+    /// Get the cached value at a physical address. [None] is returned if there is no entry or if the memory the
+    /// entry was decoded from has been written to since.
+    pub fn get(&self, memory: &dyn MemoryAccess, address: u64) -> Option<&Entry<T>> {
+        let entry = self.entries.get(&address)?;
+        let unchanged = lines(address, entry.length)
+            .zip(entry.writes.iter())
+            .all(|(line, writes)| memory.write_count(line << WRITE_LINE_BITS) == *writes);
+
+        if unchanged { Some(entry) } else { None }
+    }
+
+    /// Store a value that was decoded from a physical address. The encoded form must occupy `length` contiguous bytes
+    /// of physical memory.
+    pub fn insert(&mut self, memory: &dyn MemoryAccess, address: u64, value: T, length: u64) {
+        let writes = lines(address, length)
+            .map(|line| memory.write_count(line << WRITE_LINE_BITS))
+            .collect();
+
+        self.entries.insert(address, Entry { value, length, writes });
+    }
+
+    /// Remove every entry that overlaps a range of physical memory. This is only needed when memory is modified without
+    /// going through [MemoryAccess::set], such as writing to [Memory::bytes](crate::emulator::memory::Memory::bytes) directly.
+    pub fn invalidate(&mut self, address: u64, length: u64) {
+        let end = address.saturating_add(length);
+        self.entries.retain(|start, entry| *start >= end || start.saturating_add(entry.length) <= address);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Number of cached values, including those that may have gone stale.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
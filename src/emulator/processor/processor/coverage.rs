@@ -0,0 +1,94 @@
+//! Record of which guest addresses were executed, for measuring how much of a guest program its tests reach.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Number of address bits covered by a single word of the bitmap.
+const WORD_BITS: u32 = 6;
+
+/// Executed instruction addresses stored as a sparse bitmap with one bit per address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    /// Words of 64 addresses keyed by the address shifted right by [WORD_BITS].
+    words: BTreeMap<u64, u64>
+}
+
+impl Coverage {
+    /// Mark an address as executed.
+    pub fn record(&mut self, address: u64) {
+        *self.words.entry(address >> WORD_BITS).or_insert(0) |= 1 << (address & 63);
+    }
+
+    pub fn covered(&self, address: u64) -> bool {
+        self.words.get(&(address >> WORD_BITS)).is_some_and(|word| word & (1 << (address & 63)) != 0)
+    }
+
+    /// Every executed address in ascending order.
+    pub fn addresses(&self) -> impl Iterator<Item = u64> + '_ {
+        self.words.iter().flat_map(|(index, word)| (0..64)
+            .filter(move |bit| word & (1 << bit) != 0)
+            .map(move |bit| (index << WORD_BITS) | bit))
+    }
+
+    /// Number of executed addresses.
+    pub fn len(&self) -> usize {
+        self.words.values().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Bitmap of a range of addresses, where bit `n % 8` of byte `n / 8` is set if `start + n` was executed.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::coverage::Coverage;
+    ///
+    /// let mut coverage = Coverage::default();
+    /// coverage.record(3);
+    /// coverage.record(9);
+    ///
+    /// assert_eq!(coverage.bitmap(0, 16), vec![0b0000_1000, 0b0000_0010]);
+    /// assert_eq!(coverage.bitmap(3, 1), vec![1]);
+    /// ```
+    pub fn bitmap(&self, start: u64, length: u64) -> Vec<u8> {
+        let mut bitmap = vec![0u8; length.div_ceil(8) as usize];
+        for offset in 0..length {
+            if self.covered(start.wrapping_add(offset)) { bitmap[(offset / 8) as usize] |= 1 << (offset % 8); }
+        }
+
+        bitmap
+    }
+
+    /// Executed addresses as hexadecimal text, one per line.
+    pub fn to_text(&self) -> String {
+        self.addresses().fold(String::new(), |mut text, address| {
+            let _ = writeln!(text, "{address:#x}");
+            text
+        })
+    }
+
+    /// Export in the LCOV tracefile format, treating each executed address as a line of `source`. Only executed
+    /// addresses are known, so every listed line has a hit.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::coverage::Coverage;
+    ///
+    /// let mut coverage = Coverage::default();
+    /// coverage.record(0);
+    /// coverage.record(3);
+    ///
+    /// assert_eq!(coverage.to_lcov("program.bin"), "TN:\nSF:program.bin\nDA:0,1\nDA:3,1\nLH:2\nLF:2\nend_of_record\n");
+    /// ```
+    pub fn to_lcov(&self, source: &str) -> String {
+        let mut lcov = format!("TN:\nSF:{source}\n");
+        for address in self.addresses() { let _ = writeln!(lcov, "DA:{address},1"); }
+
+        let _ = write!(lcov, "LH:{0}\nLF:{0}\nend_of_record\n", self.len());
+        lcov
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+}
@@ -0,0 +1,207 @@
+//! Breakpoints which guest software or a debugger sets through model specific registers.
+//!
+//! Each of the [BREAKPOINTS] breakpoints has an address register starting at [DEBUG_ADDRESS_REGISTER] and two bits of
+//! [DEBUG_CONTROL_REGISTER], starting from bit `2 * n` for breakpoint `n`, which select what it [watches](Watch). Once
+//! an instruction which matched a breakpoint completes, bit `n` of [DEBUG_STATUS_REGISTER] is set and [DEBUG_VECTOR]
+//! is raised, so the debug exception is delivered like any other interrupt and its handler returns to the instruction
+//! after the matching one. The handler clears the status register itself.
+//!
+//! Data breakpoints match any memory access made while executing an instruction which overlaps their address. Execute
+//! breakpoints match the address of an instruction once it completes, whether it was fetched by
+//! [Core::step](super::Core::step) or run in a block. A block stops after the matching instruction so the debug
+//! exception is delivered before the next one.
+
+use core::cell::Cell;
+use emulator::memory::{Frame, GetError, MemoryAccess};
+use number;
+use super::Context;
+use super::instruction::operation::control::{DEBUG_ADDRESS_REGISTER, DEBUG_CONTROL_REGISTER, DEBUG_STATUS_REGISTER};
+
+/// Number of breakpoints.
+pub const BREAKPOINTS: usize = 4;
+/// Vector raised when a breakpoint matches.
+pub const DEBUG_VECTOR: u8 = 1;
+
+/// What a breakpoint matches, encoded in its two bits of the control register.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::debug::Watch;
+/// use atln_processor::emulator::processor::processor::instruction::operation::control::{DEBUG_ADDRESS_REGISTER, DEBUG_CONTROL_REGISTER, DEBUG_STATUS_REGISTER};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // The handler at 16 counts debug exceptions in r3, and the handler table at 32 holds it for vector 1.
+/// let second = assemble("add.b r1, 1").unwrap().len() as u64;
+/// let mut program = assemble("add.b r1, 1\nadd.q [48], r1\nhalt").unwrap();
+/// program.resize(16, 0);
+/// program.extend(assemble("add.b r3, 1\nresume").unwrap());
+/// program.resize(40, 0);
+/// program.extend(16u64.to_le_bytes());
+/// program.resize(56, 0);
+///
+/// let mut core = Core::default();
+/// core.context.interrupts.table = 32;
+/// core.context.interrupts.enabled = true;
+/// core.context.model_specific[DEBUG_ADDRESS_REGISTER as usize] = second;
+/// core.context.model_specific[DEBUG_ADDRESS_REGISTER as usize + 1] = 48;
+/// core.context.model_specific[DEBUG_CONTROL_REGISTER as usize] = Watch::Execute.control(0) | Watch::Write.control(1);
+/// core.run(&mut Memory::from(program), &mut Default::default(), None);
+///
+/// // The instruction at the breakpoint address both executed and wrote to the watched address.
+/// assert_eq!(core.context.registers[3], 1);
+/// assert_eq!(core.context.model_specific[DEBUG_STATUS_REGISTER as usize], 0b11);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Watch {
+    #[default]
+    Disabled,
+    /// Fetching the instruction at the address.
+    Execute,
+    /// Writing to the address.
+    Write,
+    /// Reading from or writing to the address.
+    Access
+}
+
+impl Watch {
+    /// What a breakpoint matches according to the debug control register.
+    pub fn of(control: u64, breakpoint: usize) -> Self {
+        match control >> (2 * breakpoint) & 0b11 {
+            0 => Self::Disabled,
+            1 => Self::Execute,
+            2 => Self::Write,
+            _ => Self::Access
+        }
+    }
+
+    /// Bits of the debug control register which make a breakpoint match this.
+    pub fn control(self, breakpoint: usize) -> u64 {
+        let bits = match self {
+            Self::Disabled => 0,
+            Self::Execute => 1,
+            Self::Write => 2,
+            Self::Access => 3
+        };
+
+        bits << (2 * breakpoint)
+    }
+}
+
+/// The enabled breakpoints of a core with their addresses.
+pub fn breakpoints(context: &Context) -> impl Iterator<Item = (usize, u64, Watch)> + '_ {
+    let control = context.model_specific[DEBUG_CONTROL_REGISTER as usize];
+
+    (0..BREAKPOINTS)
+        .map(move |breakpoint| (breakpoint, context.model_specific[DEBUG_ADDRESS_REGISTER as usize + breakpoint], Watch::of(control, breakpoint)))
+        .filter(|(_, _, watch)| *watch != Watch::Disabled)
+}
+
+/// Breakpoints watching the instruction at an address, one bit for each.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::debug::{Watch, DEBUG_VECTOR};
+/// use atln_processor::emulator::processor::processor::instruction::operation::control::{DEBUG_ADDRESS_REGISTER, DEBUG_CONTROL_REGISTER, DEBUG_STATUS_REGISTER};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // The breakpoint watches the second of three instructions in a block.
+/// let second = assemble("add.b r1, 1").unwrap().len() as u64;
+/// let mut memory = Memory::from(assemble("add.b r1, 1\nadd.b r2, 1\nadd.b r3, 1").unwrap());
+///
+/// let mut core = Core::default();
+/// core.context.interrupts.enabled = true;
+/// core.context.model_specific[DEBUG_ADDRESS_REGISTER as usize] = second;
+/// core.context.model_specific[DEBUG_CONTROL_REGISTER as usize] = Watch::Execute.control(0);
+///
+/// let block = core.decode_block(&memory, 0).unwrap();
+/// core.execute_block(&block, &mut memory, &mut Default::default());
+///
+/// // The block stopped after the watched instruction with the debug exception pending.
+/// assert_eq!(core.context.registers[1..4], [1, 1, 0]);
+/// assert_eq!(core.context.program_counter, 2 * second);
+/// assert_eq!(core.context.model_specific[DEBUG_STATUS_REGISTER as usize], 1);
+/// assert_eq!(core.context.interrupts.next(), Some(DEBUG_VECTOR));
+/// ```
+pub fn execute_hits(context: &Context, address: u64) -> u64 {
+    breakpoints(context)
+        .filter(|&(_, breakpoint, watch)| watch == Watch::Execute && breakpoint == address)
+        .fold(0, |hits, (index, ..)| hits | 1 << index)
+}
+
+/// Whether an execute breakpoint watches any of a number of bytes starting at an address.
+pub fn executes_within(context: &Context, address: u64, length: u64) -> bool {
+    breakpoints(context).any(|(_, breakpoint, watch)| watch == Watch::Execute && breakpoint.wrapping_sub(address) < length)
+}
+
+/// Signal that breakpoints matched by setting their status bits and raising [DEBUG_VECTOR]. Nothing happens if none
+/// matched.
+pub fn trigger(context: &mut Context, hits: u64) {
+    if hits == 0 { return }
+
+    context.model_specific[DEBUG_STATUS_REGISTER as usize] |= hits;
+    context.interrupts.raise(DEBUG_VECTOR);
+}
+
+/// Addresses of the enabled data breakpoints of a core, or [None] if there are none.
+pub fn data_breakpoints(context: &Context) -> Option<[(u64, Watch); BREAKPOINTS]> {
+    let mut breakpoints = [(0, Watch::Disabled); BREAKPOINTS];
+    let mut any = false;
+
+    for (index, address, watch) in self::breakpoints(context).filter(|(_, _, watch)| *watch != Watch::Execute) {
+        breakpoints[index] = (address, watch);
+        any = true;
+    }
+
+    if any { Some(breakpoints) } else { None }
+}
+
+/// Memory seen by an operation while data breakpoints are enabled, which records the breakpoints its accesses match.
+pub(crate) struct Watched<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub breakpoints: [(u64, Watch); BREAKPOINTS],
+    /// One bit for each breakpoint matched so far.
+    pub hits: &'a Cell<u64>
+}
+
+impl<'a> Watched<'a> {
+    fn record(&self, frame: &Frame, write: bool) {
+        for (index, (address, watch)) in self.breakpoints.iter().enumerate() {
+            let watched = match watch {
+                Watch::Write => write,
+                Watch::Access => true,
+                _ => false
+            };
+
+            if watched && *address >= frame.address && *address < frame.max_address() { self.hits.set(self.hits.get() | 1 << index); }
+        }
+    }
+}
+
+impl<'a> MemoryAccess for Watched<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        self.record(&frame, false);
+        self.memory.get(frame, r#virtual)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.record(&frame, true);
+        self.memory.set(frame, r#virtual, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let (breakpoints, hits) = (self.breakpoints, self.hits);
+        self.memory.synchronise(&mut |memory| operation(&mut Watched { memory, breakpoints, hits }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
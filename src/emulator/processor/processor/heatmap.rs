@@ -0,0 +1,191 @@
+//! Counts of the reads, writes and executions of each region of guest memory, for finding hot data and false sharing
+//! between cores. Only available with the `heatmap` feature.
+//!
+//! Memory is split into regions of 2 to the power of [Heatmap::bits] bytes, which are pages by default. Smaller regions
+//! such as cache lines show which data within a page is hot. Regions are of the addresses the guest uses, so they are
+//! virtual while the core is in virtual mode. Every core keeps its own heatmap, and heatmaps of the cores of a system
+//! are [merged](Heatmap::merge) to find [contended](Heatmap::contended) regions.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::emulator::processor::processor::heatmap::Heatmap;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let mut program = assemble("add.q r1, [64]\nadd.q [72], r1\nadd.q [72], r1\nhalt").unwrap();
+//! program.resize(80, 0);
+//!
+//! let mut core = Core::default();
+//! core.heatmap = Some(Heatmap::new(6));
+//! core.run(&mut Memory::from(program), &mut Default::default(), None);
+//!
+//! let heatmap = core.heatmap.unwrap();
+//! assert_eq!((heatmap.get(0).executes, heatmap.get(0).reads), (4, 0));
+//! assert_eq!((heatmap.get(64).reads, heatmap.get(64).writes), (3, 2));
+//! assert_eq!(heatmap.to_csv(), "address,reads,writes,executes,cores\n0x0,0,0,4,1\n0x40,3,2,0,1\n");
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::cell::RefCell;
+use core::fmt::Write;
+use emulator::memory::{Frame, GetError, MemoryAccess, PAGE_ITEM_BITS};
+use number;
+
+/// Accesses made to a region of memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub reads: u64,
+    pub writes: u64,
+    /// Instructions executed from the region.
+    pub executes: u64,
+    /// One bit for each core that accessed the region, selected by its identifier modulo 64.
+    pub cores: u64,
+    /// One bit for each core that wrote to the region, selected like [Counts::cores].
+    pub writers: u64
+}
+
+impl Counts {
+    fn add(&mut self, other: &Self) {
+        self.reads = self.reads.wrapping_add(other.reads);
+        self.writes = self.writes.wrapping_add(other.writes);
+        self.executes = self.executes.wrapping_add(other.executes);
+        self.cores |= other.cores;
+        self.writers |= other.writers;
+    }
+}
+
+/// Access counts of each region of memory which was accessed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    /// Number of address bits covered by a single region.
+    pub bits: u64,
+    /// Counts keyed by the address shifted right by [Heatmap::bits].
+    regions: BTreeMap<u64, Counts>
+}
+
+impl Default for Heatmap {
+    /// Regions of a page each.
+    fn default() -> Self {
+        Self::new(PAGE_ITEM_BITS)
+    }
+}
+
+impl Heatmap {
+    /// Create an empty heatmap with regions of 2 to the power of `bits` bytes.
+    pub fn new(bits: u64) -> Self {
+        Self { bits: bits.min(63), regions: BTreeMap::new() }
+    }
+
+    /// Record a read made by a core.
+    pub fn read(&mut self, core: u64, address: u64) {
+        let counts = self.region(core, address);
+        counts.reads = counts.reads.wrapping_add(1);
+    }
+
+    /// Record a write made by a core.
+    pub fn write(&mut self, core: u64, address: u64) {
+        let counts = self.region(core, address);
+        counts.writes = counts.writes.wrapping_add(1);
+        counts.writers |= 1 << (core % 64);
+    }
+
+    /// Record an instruction executed by a core.
+    pub fn execute(&mut self, core: u64, address: u64) {
+        let counts = self.region(core, address);
+        counts.executes = counts.executes.wrapping_add(1);
+    }
+
+    /// Counts of the region holding an address.
+    pub fn get(&self, address: u64) -> Counts {
+        self.regions.get(&(address >> self.bits)).copied().unwrap_or_default()
+    }
+
+    /// Start address and counts of every accessed region in ascending order.
+    pub fn regions(&self) -> impl Iterator<Item = (u64, &Counts)> + '_ {
+        let bits = self.bits;
+        self.regions.iter().map(move |(&region, counts)| (region << bits, counts))
+    }
+
+    /// Regions accessed by more than one core where at least one of them wrote, which is where cores contend for
+    /// memory. With regions the size of a cache line, data used by different cores in the same line is false sharing.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::heatmap::Heatmap;
+    ///
+    /// let mut first = Heatmap::new(6);
+    /// first.write(0, 0x100);
+    /// first.read(0, 0x200);
+    ///
+    /// let mut second = Heatmap::new(6);
+    /// second.read(1, 0x108);
+    /// second.read(1, 0x200);
+    ///
+    /// first.merge(&second);
+    /// assert_eq!(first.contended().map(|(address, _)| address).collect::<Vec<_>>(), [0x100]);
+    /// ```
+    pub fn contended(&self) -> impl Iterator<Item = (u64, &Counts)> + '_ {
+        self.regions().filter(|(_, counts)| counts.writers != 0 && counts.cores.count_ones() > 1)
+    }
+
+    /// Add the counts of another heatmap, such as the one of another core. The other heatmap's regions are mapped onto
+    /// the regions of this one, so it should not have larger regions.
+    pub fn merge(&mut self, other: &Self) {
+        for (address, counts) in other.regions() {
+            self.regions.entry(address >> self.bits).or_default().add(counts);
+        }
+    }
+
+    /// Export as comma separated values with a header, one accessed region per row in ascending order. The cores column
+    /// is the number of cores that accessed the region.
+    pub fn to_csv(&self) -> String {
+        self.regions().fold(String::from("address,reads,writes,executes,cores\n"), |mut csv, (address, counts)| {
+            let _ = writeln!(csv, "{address:#x},{},{},{},{}", counts.reads, counts.writes, counts.executes, counts.cores.count_ones());
+            csv
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    fn region(&mut self, core: u64, address: u64) -> &mut Counts {
+        let counts = self.regions.entry(address >> self.bits).or_default();
+        counts.cores |= 1 << (core % 64);
+        counts
+    }
+}
+
+/// Memory which records the accesses made through it in a heatmap.
+pub(crate) struct Heated<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub heatmap: &'a RefCell<Heatmap>,
+    pub core: u64
+}
+
+impl<'a> MemoryAccess for Heated<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        self.heatmap.borrow_mut().read(self.core, frame.address);
+        self.memory.get(frame, r#virtual)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.heatmap.borrow_mut().write(self.core, frame.address);
+        self.memory.set(frame, r#virtual, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let (heatmap, core) = (self.heatmap, self.core);
+        self.memory.synchronise(&mut |memory| operation(&mut Heated { memory, heatmap, core }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
@@ -3,9 +3,9 @@
 //! The instruction encoding and decoding format involved an intermediate format. Instructions involve 2 mandatory
 //! driver bytes and an optional register byte.
 //! - The driver bytes are encoded & decoded through the [Driver] structure which depends on the [Driver0Encoding] &
-//! [Driver1Encoding] traits.
+//!   [Driver1Encoding] traits.
 //! - The register byte is encoded & decoded through the [Registers] structure which depends on the [RegisterEncoding]
-//! trait.
+//!   trait.
 //!
 //! Once the instruction data has been decoded into the intermediates, data is conditioned and extracted into a more
 //! intuitive instruction structure.
@@ -25,6 +25,8 @@
 //! | No       | Register  | Dynamic Operand     | 3 bits   | Dynamically addressable operand.                                |
 //!
 //! Immediate 0..8 quantized to 0, 1, 2, 4 and 8.
+//!
+//! The driver bytes may be preceded by [prefixes](prefix), which carry what does not fit in the fields above.
 //! 
 //! # Extension
 //! Groups which house an instruction set.
@@ -39,16 +41,32 @@
 
 #![allow(clippy::unusual_byte_groupings)]
 
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod iterator;
 pub mod operand;
 pub mod operation;
-
+pub mod prefix;
+pub mod validation;
+#[cfg(feature = "proptest")]
+mod arbitrary;
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::io::Read;
 use emulator::processor::processor::instruction::operand::OperandsPresence;
 use crate::number;
-use super::instruction::operand::{Destination, Dynamic, Operand, Operands, OperandsConstructError};
-use super::instruction::operation::{Extension, ExtensionFromCodeInvalid, Operation};
-use crate::utility::{Coded, Encodable};
+use super::instruction::operand::{Destination, Dynamic, Operand, Operands, OperandsConstructError, REGISTER_ADDRESSING};
+use super::instruction::operation::{Extension, ExtensionFromCodeInvalid};
+use super::instruction::prefix::{Prefixes, PrefixError, MAX_PREFIXES_BYTES};
+use crate::utility::{Coded, Encodable, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // region: Binary processor bit masks
 pub const DRIVER0_EXTENSION_MASK           : u8 = 0b111111_0_0;
@@ -62,9 +80,14 @@ pub const REGISTERS_STATIC_OPERAND_MASK    : u8 = 0b00_111_000;
 pub const REGISTERS_DYNAMIC_OPERAND_MASK   : u8 = 0b00_000_111;
 // endregion
 
+/// Largest number of bytes an encoded instruction can occupy. This is made of every prefix, the 2 driver bytes, the
+/// registers byte and a quad sized immediate.
+pub const MAX_INSTRUCTION_BYTES: usize = MAX_PREFIXES_BYTES + 2 + 1 + number::QUAD_SIZE;
+
 /// Structured data from the driver bytes. All data generated by inherent functions are unchecked. Contains utility
 /// functions for coding driver bytes.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Driver {
     /// Operation extension
     pub extension: u8,
@@ -345,6 +368,7 @@ impl Driver1Encoding for u8 {
 
 /// Register byte encoding.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Registers {
     pub width: u8,
     pub x_static: u8,
@@ -483,7 +507,8 @@ impl RegistersEncoding for u8 {
 // endregion
 
 /// Structure containing information about the operands of an instruction.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Data {
     /// Width of operands when dereferenced and for storing result.
     pub width: number::Size,
@@ -497,6 +522,7 @@ pub struct Data {
 #[derive(Debug)]
 pub enum DataConstructError {
     /// Error caused when reading from stream.
+    #[cfg(feature = "std")]
     StreamRead(io::Error),
     /// Stream did not contain enough bytes.
     Length,
@@ -509,7 +535,39 @@ pub enum DataConstructError {
     ///
     /// This error is not produced if there are no operands because the destination is encoded as a boolean in the
     /// instruction.
-    Destination
+    Destination,
+    /// The width field of the registers byte is not a supported exponent.
+    Width
+}
+
+impl Display for DataConstructError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            #[cfg(feature = "std")]
+            Self::StreamRead(_) => "failed to read the registers byte",
+            Self::Length => "stream ended before the registers byte",
+            Self::Operands(_) => "failed to construct the operands",
+            Self::Destination => "destination can't be used with the operands",
+            Self::Width => "width is not a supported exponent"
+        })
+    }
+}
+
+impl Error for DataConstructError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::StreamRead(error) => Some(error),
+            Self::Operands(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<OperandsConstructError> for DataConstructError {
+    fn from(value: OperandsConstructError) -> Self {
+        Self::Operands(value)
+    }
 }
 
 impl Data {
@@ -520,18 +578,17 @@ impl Data {
     /// use std::io::Cursor;
     /// use atln_processor::emulator::processor::processor::instruction::{Data, Driver};
     /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
-    /// use atln_processor::emulator::processor::processor::instruction::operation::{Coded, Extension};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
     /// use atln_processor::emulator::processor::processor::instruction::operand::Destination;
+    /// use atln_processor::utility::Coded;
     ///
-    /// let mut extension = Extension::Arithmetic(Arithmetic::Add);
+    /// let extension = Extension::Arithmetic(Arithmetic::Add);
     /// let extension_code = extension.code();
-    ///
-    /// let operation = extension.operation();
-    /// let operation_code = operation.code();
+    /// let operation_code = extension.operation_code();
     ///
     /// let data = Data::new(
-    ///     &mut Cursor::new([ 00_000_000 ]),
-    ///     operation,
+    ///     &mut Cursor::new([ 0b00_000_000 ]),
+    ///     &extension.presence().unwrap(),
     ///     &Driver {
     ///         extension: extension_code,
     ///         operation: operation_code,
@@ -545,6 +602,7 @@ impl Data {
     ///
     /// assert_eq!(data.destination, Destination::Static);
     /// ```
+    #[cfg(feature = "std")]
     pub fn new(stream: &mut impl Read, presence: &OperandsPresence, driver: &Driver) -> Result<Self, DataConstructError> {
         // Decode registers byte.
         let mut data_encoded = [0u8; 1];
@@ -554,23 +612,38 @@ impl Data {
         };
 
         let registers = Registers::new(data_encoded[0]);
-        let destination = if driver.dynamic_destination { Destination::Dynamic } else { Destination::Static };
+        let operands = Operands::new(stream, presence, &registers, driver)?;
+        Self::construct(&registers, driver, operands)
+    }
 
-        // operands extracting here
-        let operands = match Operands::new(stream, presence, &registers, driver) {
-            Ok(value) => value,
-            Err(error) => return Err(DataConstructError::Operands(error))
-        };
+    /// Same as [Data::new] but decodes from a slice starting at the registers byte. The number of bytes used is returned
+    /// along with the data.
+    pub fn decode_slice(bytes: &[u8], presence: &OperandsPresence, driver: &Driver) -> Result<(Self, usize), DataConstructError> {
+        let (registers, immediate) = bytes.split_first().ok_or(DataConstructError::Length)?;
+        let registers = Registers::new(*registers);
+        let (operands, length) = Operands::decode_slice(immediate, presence, &registers, driver)?;
+
+        Ok((Self::construct(&registers, driver, operands)?, 1 + length))
+    }
+
+    /// Validate the operands against the rest of the instruction and construct the data.
+    fn construct(registers: &Registers, driver: &Driver, operands: Operands) -> Result<Self, DataConstructError> {
+        let destination = if driver.dynamic_destination { Destination::Dynamic } else { Destination::Static };
 
         // Prevent the invalid instruction configuration which involves pointing to a constant dynamic operand as the
         // destination operand.
-        if let Some(x_dynamic) = operands.x_dynamic() { if let Destination::Dynamic = destination { if let Dynamic::Constant(_) = x_dynamic {
+        if let Some(x_dynamic) = operands.x_dynamic() { if let Destination::Dynamic = destination { if x_dynamic.is_constant() {
             return Err(DataConstructError::Destination);
         }}}
 
+        let width = match number::Size::from_exponent(registers.width) {
+            Some(width) => width,
+            None => return Err(DataConstructError::Width)
+        };
+
         // Construct data.
         Ok(Data {
-            width: number::Size::from_exponent(registers.width).unwrap(),
+            width,
             destination,
             synchronous: driver.synchronise,
             operands
@@ -578,7 +651,23 @@ impl Data {
     }
 }
 
-#[derive(Debug, Default)]
+/// An operation along with its operands. Instructions compare and hash by their operation and operands, where
+/// immediates are compared by value regardless of their width, so they can be used as keys of maps.
+/// ```
+/// use std::collections::HashSet;
+/// use atln_processor::emulator::processor::processor::instruction::Instruction;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let program = assemble("add.q r1, 3\nadd.q r1, 3\nhalt").unwrap();
+/// let (first, length) = Instruction::decode_slice(&program).unwrap();
+/// let (second, _) = Instruction::decode_slice(&program[length..]).unwrap();
+/// assert_eq!(first, second);
+///
+/// let distinct = HashSet::from([first, second.clone(), second]);
+/// assert_eq!(distinct.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Instruction {
     extension: Extension,
     data: Option<Data>
@@ -587,13 +676,85 @@ pub struct Instruction {
 #[derive(Debug)]
 pub enum DecodeError {
     /// Stream failed to read.
+    #[cfg(feature = "std")]
     StreamRead(io::Error),
     /// Not enough bytes.
     Length,
     /// The extension and or operation are invalid.
     InvalidCode(ExtensionFromCodeInvalid),
     /// Failed to construct the data field of the instruction.
-    Data(DataConstructError)
+    Data(DataConstructError),
+    /// A prefix is invalid or does not apply to the instruction.
+    Prefix(PrefixError),
+    /// Only returned by strict decoding. The instruction decoded but encodes differently, because a field it ignores is
+    /// set or a prefix is redundant. The offset is of the first byte which differs from the canonical encoding.
+    NonCanonical { offset: usize }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            #[cfg(feature = "std")]
+            Self::StreamRead(_) => "failed to read the driver bytes",
+            Self::Length => "stream ended before the driver bytes",
+            Self::InvalidCode(_) => "instruction has an invalid operation",
+            Self::Data(_) => "failed to decode the instruction's operands",
+            Self::Prefix(_) => "instruction has an invalid prefix",
+            Self::NonCanonical { .. } => "instruction is not encoded canonically"
+        })
+    }
+}
+
+impl Error for DecodeError {
+    /// Errors chain down to the reason decoding failed.
+    /// ```
+    /// use std::error::Error;
+    /// use std::io::Cursor;
+    /// use atln_processor::emulator::processor::processor::instruction::{DataConstructError, DecodeError, Instruction};
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{DynamicConstructError, OperandsConstructError, ReadImmediateError};
+    ///
+    /// // add.b r0, [?] with the immediate missing.
+    /// let error = Instruction::decode(&mut Cursor::new([0b000000_0_0, 0b0000_11_00, 0])).unwrap_err();
+    ///
+    /// assert!(matches!(error, DecodeError::Data(DataConstructError::Operands(OperandsConstructError::Dynamic(DynamicConstructError::Immediate(ReadImmediateError::Length))))));
+    /// assert_eq!(error.to_string(), "failed to decode the instruction's operands");
+    ///
+    /// let mut chain = Vec::new();
+    /// let mut source = error.source();
+    /// while let Some(error) = source {
+    ///     chain.push(error.to_string());
+    ///     source = error.source();
+    /// }
+    ///
+    /// assert_eq!(chain, [
+    ///     "failed to construct the operands",
+    ///     "failed to construct the dynamic operand",
+    ///     "failed to read the dynamic operand's immediate",
+    ///     "stream ended before the immediate"
+    /// ]);
+    /// ```
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::StreamRead(error) => Some(error),
+            Self::InvalidCode(error) => Some(error),
+            Self::Data(error) => Some(error),
+            Self::Prefix(error) => Some(error),
+            Self::Length | Self::NonCanonical { .. } => None
+        }
+    }
+}
+
+impl From<ExtensionFromCodeInvalid> for DecodeError {
+    fn from(value: ExtensionFromCodeInvalid) -> Self {
+        Self::InvalidCode(value)
+    }
+}
+
+impl From<DataConstructError> for DecodeError {
+    fn from(value: DataConstructError) -> Self {
+        Self::Data(value)
+    }
 }
 
 /// Caused by using a destination which corresponds to an operand that is not provided.
@@ -607,11 +768,23 @@ pub enum DestinationError {
     Dynamic
 }
 
+impl Display for DestinationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Data => "instruction has no operands",
+            Self::Static => "destination is the static operand but it is not present",
+            Self::Dynamic => "destination is the dynamic operand but it is not present"
+        })
+    }
+}
+
+impl Error for DestinationError {}
+
 impl Instruction {
     /// Use the driver, registers, and immediate to encode into a dynamic number of bytes. Encoding is variable
     /// length. The data is not validated here. To use an immediate, registers must be of the [Some] variant. If an
     /// immediate is [Some] and registers is [None] then [None] will also be returned.
-    pub fn encode_driver_registers_immediate(driver: &mut Driver, registers: Option<&Registers>, immediate: Option<&number::Data>) -> Option<Vec<u8>> {
+    pub fn encode_driver_registers_immediate(driver: &Driver, registers: Option<&Registers>, immediate: Option<&number::Data>) -> Option<Vec<u8>> {
         let mut encoded = Vec::new();
 
         encoded.extend(driver.encode());
@@ -624,82 +797,218 @@ impl Instruction {
     }
 
     // Decode an encoded binary stream into a processor instruction. TODO: Tests
+    #[cfg(feature = "std")]
     pub fn decode(stream: &mut impl Read) -> Result<Self, DecodeError> {
+        let (prefixes, driver0) = Prefixes::decode(stream)?;
+
         // Decode driver bytes.
-        let mut encoded_driver = [0u8; 2];
+        let mut driver1 = [0u8; 1];
 
-        match stream.read(&mut encoded_driver) {
-            Ok(length) => if length != encoded_driver.len() { return Err(DecodeError::Length) },
+        match stream.read(&mut driver1) {
+            Ok(length) => if length != driver1.len() { return Err(DecodeError::Length) },
             Err(error) => return Err(DecodeError::StreamRead(error))
         };
 
-        let driver = Driver::new(encoded_driver);
+        let mut driver = Driver::new([driver0, driver1[0]]);
+        driver.extension = prefixes.extension_code(driver.extension);
 
-        let extension =  match Extension::from_codes(driver.extension, driver.operation) {
-            Ok(operation) => operation,
-            Err(error) => return Err(DecodeError::InvalidCode(error))
-        };
+        let extension = Extension::from_codes(driver.extension, driver.operation)?;
 
         // Decode data bytes.
-        let operation = extension.operation();
-        
-        if let Some(presence) = operation.presence() {
-            let data: Option<Data> = match Data::new(stream, &presence, &driver) {
-                Ok(some) => Some(some),
-                Err(error) => return Err(DecodeError::Data(error))
-            };
+        let mut data = match extension.presence() {
+            Some(presence) => Some(Data::new(stream, &presence, &driver)?),
+            None => None
+        };
+
+        prefixes.apply(data.as_mut()).map_err(DecodeError::Prefix)?;
 
-            // Construction
-            return Ok(Self {
-                extension,
-                data
-            })
-        }
-        
         Ok(Self {
             extension,
-            data: None
+            data
         })
     }
 
+    /// Decode an instruction from the start of a slice without going through [Read]. The number of bytes the
+    /// instruction occupies is returned along with it, so the next instruction starts at that offset.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::{DecodeError, Instruction};
+    ///
+    /// // add.b r1, [10] followed by halt.
+    /// let bytes = [0b000000_0_0, 0b0000_11_00, 0b00_001_000, 10, 0b000010_0_0, 0b0000_00_00];
+    ///
+    /// let (add, length) = Instruction::decode_slice(&bytes).unwrap();
+    /// assert_eq!(add.to_string(), "add.b r1, [10]");
+    /// assert_eq!(length, 4);
+    ///
+    /// let (halt, length) = Instruction::decode_slice(&bytes[4..]).unwrap();
+    /// assert_eq!(halt.to_string(), "halt");
+    /// assert_eq!(length, 2);
+    ///
+    /// assert!(matches!(Instruction::decode_slice(&bytes[..1]), Err(DecodeError::Length)));
+    /// ```
+    pub fn decode_slice(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let (prefixes, prefixes_length) = Prefixes::decode_slice(bytes)?;
+        let bytes = &bytes[prefixes_length..];
+
+        let mut driver = match bytes {
+            [driver0, driver1, ..] => Driver::new([*driver0, *driver1]),
+            _ => return Err(DecodeError::Length)
+        };
+        driver.extension = prefixes.extension_code(driver.extension);
+
+        let extension = Extension::from_codes(driver.extension, driver.operation)?;
+        let (mut data, length) = match extension.presence() {
+            Some(presence) => {
+                let (data, length) = Data::decode_slice(&bytes[2..], &presence, &driver)?;
+                (Some(data), length)
+            },
+            None => (None, 0)
+        };
+
+        prefixes.apply(data.as_mut()).map_err(DecodeError::Prefix)?;
+        Ok((Self { extension, data }, prefixes_length + 2 + length))
+    }
+
+    /// Decode an instruction from the start of a slice like [Instruction::decode_slice], but reject encodings which are
+    /// not exactly the bytes the instruction encodes to. The decoder otherwise ignores fields that have no meaning for
+    /// the instruction, so this verifies that an assembler produced byte exact output.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::{DecodeError, Instruction};
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let program = assemble("add.q r1, 5\nadd.w r2, r3\nhalt").unwrap();
+    /// let mut offset = 0;
+    /// while offset < program.len() { offset += Instruction::decode_slice_strict(&program[offset..]).unwrap().1; }
+    ///
+    /// // Halt takes no operands, so the addressing bits of the second driver byte are meaningless.
+    /// let halt = [0b000010_0_0, 0b0000_01_00];
+    /// assert_eq!(Instruction::decode_slice(&halt).unwrap().0.to_string(), "halt");
+    /// assert!(matches!(Instruction::decode_slice_strict(&halt), Err(DecodeError::NonCanonical { offset: 1 })));
+    ///
+    /// // Register addressing has no immediate, so its immediate exponent must be 0.
+    /// let add = [0b000000_0_0, 0b0000_00_01, 0b00_001_010];
+    /// assert!(matches!(Instruction::decode_slice_strict(&add), Err(DecodeError::NonCanonical { offset: 1 })));
+    /// ```
+    pub fn decode_slice_strict(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let (instruction, length) = Self::decode_slice(bytes)?;
+        instruction.canonical(&bytes[..length])?;
+        Ok((instruction, length))
+    }
+
+    /// Number of bytes an instruction occupies after its prefixes, worked out from its driver bytes and the operands its
+    /// operation expects without decoding the operands. The operation's [presence](Extension::presence) is [None] if it
+    /// takes no operands.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::Instruction;
+    /// use atln_processor::emulator::processor::processor::instruction::operand::OperandsPresence;
+    ///
+    /// // add.b r1, [10] and halt.
+    /// assert_eq!(Instruction::encoded_length([0b000000_0_0, 0b0000_11_00], Some(&OperandsPresence::AllPresent)), 4);
+    /// assert_eq!(Instruction::encoded_length([0b000010_0_0, 0b0000_00_00], None), 2);
+    ///
+    /// // A quad immediate with register addressing is not read.
+    /// assert_eq!(Instruction::encoded_length([0b000000_0_0, 0b0000_00_11], Some(&OperandsPresence::AllPresent)), 3);
+    /// ```
+    pub fn encoded_length(driver: [u8; 2], presence: Option<&OperandsPresence>) -> usize {
+        let driver = Driver::new(driver);
+        let presence = match presence {
+            Some(presence) => presence,
+            None => return 2
+        };
+
+        let immediate = if presence.expects_dynamic() && driver.addressing != REGISTER_ADDRESSING { 1 << driver.immediate_exponent } else { 0 };
+        2 + 1 + immediate
+    }
+
+    /// Number of bytes the instruction at the start of a slice occupies, including its prefixes, without decoding its
+    /// operands. The slice only needs to hold the prefixes and driver bytes.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::{DecodeError, Instruction};
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let program = assemble("add.q r1, 0x1234\nadd.w r2, [r3 + r4]\nhalt").unwrap();
+    /// let mut offset = 0;
+    /// while offset < program.len() {
+    ///     let length = Instruction::length_slice(&program[offset..offset + 2]).unwrap();
+    ///     assert_eq!(length, Instruction::decode_slice(&program[offset..]).unwrap().1);
+    ///     offset += length;
+    /// }
+    ///
+    /// assert!(matches!(Instruction::length_slice(&program[..1]), Err(DecodeError::Length)));
+    /// ```
+    pub fn length_slice(bytes: &[u8]) -> Result<usize, DecodeError> {
+        let (prefixes, prefixes_length) = Prefixes::decode_slice(bytes)?;
+        let driver = match &bytes[prefixes_length..] {
+            [driver0, driver1, ..] => [*driver0, *driver1],
+            _ => return Err(DecodeError::Length)
+        };
+
+        let decoded = Driver::new(driver);
+        let extension = Extension::from_codes(prefixes.extension_code(decoded.extension), decoded.operation)?;
+        Ok(prefixes_length + Self::encoded_length(driver, extension.presence().as_ref()))
+    }
+
+    /// Number of bytes the instruction occupies once encoded, without encoding it.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::Instruction;
+    /// use atln_processor::programming::assembler::assemble;
+    /// use atln_processor::utility::Encodable;
+    ///
+    /// for line in ["halt", "add.q r1, 5", "add.q r1, 0x123456789", "sync add.d [r6 + r1], r2", "add.b r1, [pc + 2]"] {
+    ///     let (instruction, length) = Instruction::decode_slice(&assemble(line).unwrap()).unwrap();
+    ///     assert_eq!(instruction.encoded_len(), length);
+    ///     assert_eq!(instruction.encoded_len(), instruction.encode().len());
+    /// }
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        let prefixes = Prefixes::new(self.extension.code(), self.data.as_ref()).encoded_len();
+        let data = match &self.data {
+            Some(data) => 1 + data.operands.x_dynamic().and_then(Dynamic::immediate).map_or(0, |immediate| immediate.size() as usize),
+            None => 0
+        };
+
+        prefixes + 2 + data
+    }
+
+    /// Check that the bytes an instruction was decoded from are the bytes it encodes to.
+    pub(crate) fn canonical(&self, bytes: &[u8]) -> Result<(), DecodeError> {
+        let encoded = self.encode();
+        if encoded == bytes { return Ok(()) }
+
+        let offset = encoded.iter().zip(bytes).position(|(encoded, byte)| encoded != byte).unwrap_or(encoded.len().min(bytes.len()));
+        Err(DecodeError::NonCanonical { offset })
+    }
+
     /// Get the operand that the destination property corresponds to.
     /// ```
     /// use atln_processor::emulator::processor::processor::instruction::{Data, Instruction, DestinationError};
     /// use atln_processor::emulator::processor::processor::instruction::operand::{AllPresent, Dynamic, Operands, Operand, Destination};
     /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::executor::Executor;
     /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
     /// use atln_processor::number;
     ///
-    /// let x_static = Instruction {
-    ///     extension: Extension::Arithmetic(Arithmetic::Add),
-    ///     data: Some(Data {
-    ///         width: number::Size::Byte,
-    ///         destination: Destination::Static,
-    ///         synchronous: false,
-    ///         operands: Operands::AllPresent(AllPresent {
-    ///             x_static: 0,
-    ///             x_dynamic: Dynamic::Register(1)
-    ///         })
+    /// let x_static = Instruction::new(Extension::Arithmetic(Arithmetic::Add), Some(Data {
+    ///     width: number::Size::Byte,
+    ///     destination: Destination::Static,
+    ///     synchronous: false,
+    ///     operands: Operands::AllPresent(AllPresent {
+    ///         x_static: 0,
+    ///         x_dynamic: Dynamic::Register(1)
     ///     })
-    /// };
+    /// })).unwrap();
     ///
-    /// let x_dynamic = Instruction {
-    ///     extension: Extension::Arithmetic(Arithmetic::Add),
-    ///     data: Some(Data {
-    ///         width: number::Size::Byte,
-    ///         destination: Destination::Dynamic,
-    ///         synchronous: false,
-    ///         operands: Operands::AllPresent(AllPresent {
-    ///             x_static: 0,
-    ///             x_dynamic: Dynamic::Register(1)
-    ///         })
+    /// let x_dynamic = Instruction::new(Extension::Arithmetic(Arithmetic::Add), Some(Data {
+    ///     width: number::Size::Byte,
+    ///     destination: Destination::Dynamic,
+    ///     synchronous: false,
+    ///     operands: Operands::AllPresent(AllPresent {
+    ///         x_static: 0,
+    ///         x_dynamic: Dynamic::Register(1)
     ///     })
-    /// };
+    /// })).unwrap();
     ///
-    /// let no_operands = Instruction {
-    ///     extension: Extension::Arithmetic(Arithmetic::Add),
-    ///     data: None
-    /// };
+    /// let no_operands = Instruction::new(Extension::Executor(Executor::Halt), None).unwrap();
     ///
     /// assert!(matches!(x_static.destination().unwrap(), Operand::Static(_)));
     /// assert!(!matches!(x_dynamic.destination().unwrap(), Operand::Static(_)));
@@ -719,21 +1028,61 @@ impl Instruction {
             Destination::Dynamic => match data.operands.x_dynamic() {
                 Some(x_dynamic) => Operand::Dynamic(x_dynamic.clone()),
                 None => return Err(DestinationError::Dynamic)
-            }
+            },
+            Destination::Target(target) => Operand::Static(target)
         })
     }
     
     /// Construct a new instruction from the potentially incompatible extension and data. If the presence of the 
-    /// operation isn't equal to the presence of the data, then [None] is returned. Otherwise, the instruction in [Some]
-    /// is returned.
+    /// operation isn't equal to the presence of the data, then [None] is returned. [Destination::Target] is also only
+    /// compatible with both operands. Otherwise, the instruction in [Some] is returned.
     /// ```
-    /// // TODO: Test
+    /// use atln_processor::emulator::processor::processor::instruction::{Data, Instruction};
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{AllPresent, Destination, Dynamic, Operands};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::executor::Executor;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+    /// use atln_processor::number;
+    ///
+    /// let data = Data {
+    ///     width: number::Size::Byte,
+    ///     destination: Destination::Static,
+    ///     synchronous: false,
+    ///     operands: Operands::AllPresent(AllPresent {
+    ///         x_static: 0,
+    ///         x_dynamic: Dynamic::Register(1)
+    ///     })
+    /// };
+    ///
+    /// assert!(Instruction::new(Extension::Arithmetic(Arithmetic::Add), Some(data.clone())).is_some());
+    /// assert!(Instruction::new(Extension::Arithmetic(Arithmetic::Add), None).is_none());
+    /// assert!(Instruction::new(Extension::Executor(Executor::Halt), Some(data)).is_none());
+    /// assert!(Instruction::new(Extension::Executor(Executor::Halt), None).is_some());
     /// ```
     pub fn new(extension: Extension, data: Option<Data>) -> Option<Self> {
-        if !matches!(extension.operation().presence(), _data) { return None; }
+        let compatible = match (extension.presence(), &data) {
+            (None, None) => true,
+            (Some(presence), Some(data)) => presence == OperandsPresence::from(data.operands.clone())
+                && (presence == OperandsPresence::AllPresent || !matches!(data.destination, Destination::Target(_))),
+            _ => false
+        };
+
+        if !compatible { return None; }
         Some(Self { extension, data })
     }
     
+    /// Whether executing this instruction can change which instruction is executed next. Blocks of pre-decoded
+    /// instructions end after an instruction that diverts.
+    pub fn diverts(&self) -> bool {
+        self.extension.diverts()
+    }
+
+    /// Whether the instruction is executed as a synchronised operation, either because its synchronise bit is set or
+    /// because its operation always is.
+    pub fn synchronised(&self) -> bool {
+        self.data.as_ref().is_some_and(|data| data.synchronous) || matches!(&self.extension, Extension::Data(data) if data.synchronises())
+    }
+
     pub fn extension(&self) -> &Extension {
         &self.extension
     }
@@ -743,6 +1092,52 @@ impl Instruction {
     } 
 }
 
+impl Display for Instruction {
+    /// Assembly form of the instruction. The mnemonic is suffixed with the width when there are operands, and the
+    /// destination operand is always written first. A target register is written before both operands. Synchronous
+    /// instructions are prefixed with `sync`.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::builder::InstructionBuilder;
+    /// use atln_processor::emulator::processor::processor::instruction::operand::Dynamic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::executor::Executor;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+    /// use atln_processor::number;
+    /// use atln_processor::number::Size;
+    ///
+    /// let add = InstructionBuilder::new()
+    ///     .extension(Extension::Arithmetic(Arithmetic::Add))
+    ///     .static_register(2)
+    ///     .dynamic(Dynamic::Memory(number::Data::Byte(10)));
+    ///
+    /// assert_eq!(add.clone().build().unwrap().to_string(), "add.b r2, [10]");
+    /// assert_eq!(add.clone().width(Size::Word).destination_dynamic().synchronous().build().unwrap().to_string(), "sync add.w [10], r2");
+    ///
+    /// let target = add.dynamic(Dynamic::Register(1)).target(3).build().unwrap();
+    /// assert_eq!(target.to_string(), "add.b r3, r2, r1");
+    ///
+    /// let halt = InstructionBuilder::new().extension(Extension::Executor(Executor::Halt)).build().unwrap();
+    /// assert_eq!(halt.to_string(), "halt");
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let data = match &self.data {
+            Some(data) => data,
+            None => return write!(f, "{}", self.extension)
+        };
+
+        if data.synchronous { f.write_str("sync ")?; }
+        write!(f, "{}.{}", self.extension, data.width.representation())?;
+
+        match (&data.operands, &data.destination) {
+            (Operands::AllPresent(all), Destination::Static) => write!(f, " r{}, {}", all.x_static, all.x_dynamic),
+            (Operands::AllPresent(all), Destination::Dynamic) => write!(f, " {}, r{}", all.x_dynamic, all.x_static),
+            (Operands::AllPresent(all), Destination::Target(target)) => write!(f, " r{target}, r{}, {}", all.x_static, all.x_dynamic),
+            (Operands::Static(x_static), _) => write!(f, " r{x_static}"),
+            (Operands::Dynamic(x_dynamic), _) => write!(f, " {x_dynamic}")
+        }
+    }
+}
+
 impl Encodable<Vec<u8>> for Instruction {
     /// ```
     /// use atln_processor::emulator::processor::processor::instruction::{Driver, Instruction, Registers};
@@ -751,7 +1146,7 @@ impl Encodable<Vec<u8>> for Instruction {
     /// use atln_processor::emulator::processor::processor::instruction::operation::ARITHMETIC_CODE;
     /// use atln_processor::number;
     ///
-    /// let mut driver = Driver {
+    /// let driver = Driver {
     ///     extension: ARITHMETIC_CODE,
     ///     operation: ADD_CODE,
     ///     synchronise: true,
@@ -768,7 +1163,7 @@ impl Encodable<Vec<u8>> for Instruction {
     ///
     /// let target = [ 0b000000_1_0, 0b0000_10_00, 0b00_001_000, 0b00001010 ];
     ///
-    /// assert_eq!(Instruction::encode_driver_registers_immediate(&mut driver, Some(&registers), Some(&number::Data::Byte(10))).unwrap(), target);
+    /// assert_eq!(Instruction::encode_driver_registers_immediate(&driver, Some(&registers), Some(&number::Data::Byte(10))).unwrap(), target);
     /// ```
     fn encode(&self) -> Vec<u8> {
         let mut synchronise = false;
@@ -782,15 +1177,15 @@ impl Encodable<Vec<u8>> for Instruction {
             synchronise = data.synchronous;
             dynamic_destination = match data.destination {
                 Destination::Dynamic => true,
-                Destination::Static => false
+                Destination::Static | Destination::Target(_) => false
             };
 
             let mut x_dynamic_code = 0;
             if let Some(x_dynamic) = data.operands.x_dynamic() {
-                x_dynamic_code = x_dynamic.register().unwrap_or(0);
-                immediate = x_dynamic.immediate().cloned();
+                x_dynamic_code = x_dynamic.register_field();
+                immediate = x_dynamic.immediate();
 
-                if let Some(immediate) = x_dynamic.immediate() { immediate_exponent = immediate.clone().exponent() }
+                if let Some(immediate) = &immediate { immediate_exponent = immediate.clone().exponent() }
                 addressing = x_dynamic.addressing();
             }
 
@@ -801,21 +1196,88 @@ impl Encodable<Vec<u8>> for Instruction {
             });
         }
 
-        let mut driver = Driver {
+        let driver = Driver {
             extension: self.extension.code(),
-            operation: self.extension.operation().code(),
+            operation: self.extension.operation_code(),
             synchronise,
             dynamic_destination,
             addressing,
             immediate_exponent
         };
 
-        // Unwrapping should not fail because the processor is a controlled environment. There is no risk of an
-        // immediate being present with a lack of [Registers]. Output of [encode_driver_registers_immediate] can safely
-        // be unwrapped.
-        if let Some(registers) = registers {
-            if let Some(immediate) = immediate { Instruction::encode_driver_registers_immediate(&mut driver, Some(&registers), Some(&immediate)).unwrap() }
-            else { Instruction::encode_driver_registers_immediate(&mut driver, Some(&registers), None).unwrap() }
-        } else { Instruction::encode_driver_registers_immediate(&mut driver, None, None).unwrap() }
+        // An immediate is only ever taken from the data which also creates the [Registers], so
+        // [Instruction::encode_driver_registers_immediate] always returns [Some].
+        let mut encoded = Prefixes::new(self.extension.code(), self.data.as_ref()).encode();
+        encoded.extend(Instruction::encode_driver_registers_immediate(&driver, registers.as_ref(), immediate.as_ref()).expect("Immediate should only be present with registers"));
+        encoded
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use utility::Encodable;
+    use super::Instruction;
+
+    /// Check the bytes of every driver pair followed by each of the registers bytes, filling the rest of the longest
+    /// instruction with one byte.
+    fn sweep(registers: &[u8], fill: u8, mut check: impl FnMut(&[u8; 11])) {
+        for driver in 0..=u16::MAX {
+            for &registers in registers {
+                let mut bytes = [fill; 11];
+                bytes[..2].copy_from_slice(&driver.to_le_bytes());
+                bytes[2] = registers;
+                check(&bytes);
+            }
+        }
+    }
+
+    /// Every driver pair with a selection of registers bytes and immediates must decode to an instruction or an error,
+    /// including when the stream is cut short at any length.
+    #[test]
+    fn decode_never_panics() {
+        for immediate in [0x00, 0xFF] {
+            sweep(&[0x00, 0x55, 0xAA, 0xFF], immediate, |bytes| for length in 0..=bytes.len() {
+                let _ = Instruction::decode(&mut Cursor::new(&bytes[..length]));
+            });
+        }
     }
-}
\ No newline at end of file
+
+    /// The length worked out from the driver bytes must be the length of every instruction that decodes.
+    #[test]
+    fn length_matches_decode() {
+        sweep(&[0x00, 0xE3, 0x1C], 0x5A, |bytes| if let Ok((_, length)) = Instruction::decode_slice(bytes) {
+            assert_eq!(Instruction::length_slice(bytes).unwrap(), length, "bytes {bytes:02x?}");
+        });
+    }
+
+    #[test]
+    fn slice_matches_stream() {
+        sweep(&[0x00, 0xE3, 0x1C], 0x5A, |bytes| {
+            let mut stream = Cursor::new(&bytes[..]);
+            match (Instruction::decode(&mut stream), Instruction::decode_slice(bytes)) {
+                (Ok(streamed), Ok((sliced, length))) => {
+                    assert_eq!(streamed, sliced);
+                    assert_eq!(streamed.encode(), sliced.encode());
+                    assert_eq!(stream.position() as usize, length);
+                },
+                (Err(_), Err(_)) => (),
+                (streamed, sliced) => panic!("Decoding {:?} differs: {:?} and {:?}", bytes, streamed, sliced)
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        // add.w r1, [r2 + 10]
+        let bytes = [0b000000_0_0, 0b0000_01_01, 0b01_001_010, 10, 0];
+        let (instruction, _) = Instruction::decode_slice(&bytes).unwrap();
+
+        let json = serde_json::to_string(&instruction).unwrap();
+        let deserialized: Instruction = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized, instruction);
+        assert_eq!(deserialized.encode(), bytes);
+    }
+}
@@ -0,0 +1,249 @@
+//! Generation of random instructions and their parts for property testing with proptest.
+//!
+//! Strategies only produce valid combinations, meaning everything generated for an [Instruction] can be encoded and
+//! decoded back into the same instruction. Operands always match the presence expected by the operation, the
+//! destination is never a constant, and synchronous instructions never use register addressing.
+
+use proptest::prelude::*;
+use number;
+use super::{Data, Driver, Instruction, Registers};
+use super::operand::{AllPresent, Destination, Dynamic, Indexed, Offset, Operands, OperandsPresence, CONSTANT_ADDRESSING, REGISTER_ADDRESSING};
+use super::operation::Extension;
+use super::operation::arithmetic::Arithmetic;
+use super::operation::checksum::Checksum;
+use super::operation::condition::Condition;
+use super::operation::control::Control;
+use super::operation::conversion::Conversion;
+use super::operation::crypto::Crypto;
+use super::operation::data::Data as DataOperation;
+use super::operation::executor::Executor;
+use super::operation::machine::Machine;
+use utility::Coded;
+
+/// Number of general purpose registers that a register code can select.
+const REGISTERS: u8 = 8;
+
+impl Arbitrary for number::Size {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![Just(Self::Byte), Just(Self::Word), Just(Self::Dual), Just(Self::Quad)].boxed()
+    }
+}
+
+impl Arbitrary for number::Data {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            any::<u8>().prop_map(Self::Byte),
+            any::<u16>().prop_map(Self::Word),
+            any::<u32>().prop_map(Self::Dual),
+            any::<u64>().prop_map(Self::Quad)
+        ].boxed()
+    }
+}
+
+impl Arbitrary for Extension {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            Just(Self::Arithmetic(Arithmetic::Add)),
+            Just(Self::Arithmetic(Arithmetic::Subtract)),
+            Just(Self::Arithmetic(Arithmetic::Min)),
+            Just(Self::Arithmetic(Arithmetic::Max)),
+            Just(Self::Arithmetic(Arithmetic::SignedMin)),
+            Just(Self::Arithmetic(Arithmetic::SignedMax)),
+            Just(Self::Arithmetic(Arithmetic::Absolute)),
+            Just(Self::Arithmetic(Arithmetic::Negate)),
+            Just(Self::Data(DataOperation::LoadLinked)),
+            Just(Self::Data(DataOperation::StoreConditional)),
+            Just(Self::Data(DataOperation::Fence)),
+            Just(Self::Executor(Executor::Halt)),
+            Just(Self::Executor(Executor::Divert)),
+            Just(Self::Executor(Executor::Signal)),
+            Just(Self::Executor(Executor::Resume)),
+            Just(Self::Executor(Executor::HostCall)),
+            Just(Self::Executor(Executor::Trap)),
+            Just(Self::Condition(Condition::Compare)),
+            Just(Self::Condition(Condition::MoveZero)),
+            Just(Self::Condition(Condition::MoveNotZero)),
+            Just(Self::Condition(Condition::MoveCarry)),
+            Just(Self::Condition(Condition::MoveNotCarry)),
+            Just(Self::Condition(Condition::MoveSign)),
+            Just(Self::Condition(Condition::MoveNotSign)),
+            Just(Self::Condition(Condition::MoveOverflow)),
+            Just(Self::Condition(Condition::MoveNotOverflow)),
+            Just(Self::Conversion(Conversion::SignExtend)),
+            Just(Self::Conversion(Conversion::ZeroExtend)),
+            Just(Self::Conversion(Conversion::Truncate)),
+            Just(Self::Conversion(Conversion::SwapBytes)),
+            Just(Self::Checksum(Checksum::Crc16)),
+            Just(Self::Checksum(Checksum::Crc32)),
+            Just(Self::Checksum(Checksum::Crc32C)),
+            Just(Self::Crypto(Crypto::AesEncrypt)),
+            Just(Self::Crypto(Crypto::AesEncryptLast)),
+            Just(Self::Crypto(Crypto::AesDecrypt)),
+            Just(Self::Crypto(Crypto::AesDecryptLast)),
+            Just(Self::Crypto(Crypto::Sha256)),
+            Just(Self::Machine(Machine::Identify)),
+            Just(Self::Machine(Machine::ReadCounter)),
+            Just(Self::Control(Control::ReadRegister)),
+            Just(Self::Control(Control::WriteRegister))
+        ].boxed()
+    }
+}
+
+impl Arbitrary for Driver {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Drivers with codes of an existing operation. Register addressing is never synchronous and constant addressing
+    /// never has a dynamic destination.
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Extension>(), 0..4u8, 0..4u8, any::<bool>(), any::<bool>())
+            .prop_map(|(extension, addressing, immediate_exponent, synchronise, dynamic_destination)| Self {
+                extension: extension.code(),
+                operation: extension.operation_code(),
+                synchronise: synchronise && addressing != REGISTER_ADDRESSING,
+                dynamic_destination: dynamic_destination && addressing != CONSTANT_ADDRESSING,
+                addressing,
+                immediate_exponent
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Registers {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..4u8, 0..REGISTERS, 0..REGISTERS)
+            .prop_map(|(width, x_static, x_dynamic)| Self { width, x_static, x_dynamic })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Dynamic {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            (0..REGISTERS).prop_map(Self::Register),
+            (0..REGISTERS, any::<number::Data>()).prop_map(|(register, offset)| Self::Offset(Offset { register, offset })),
+            any::<number::Data>().prop_map(Self::Constant),
+            any::<number::Data>().prop_map(Self::Signed),
+            any::<number::Data>().prop_map(Self::Memory),
+            any::<number::Data>().prop_map(Self::Relative),
+            (0..REGISTERS).prop_map(Self::PostIncrement),
+            (0..REGISTERS).prop_map(Self::PreDecrement),
+            (0..REGISTERS, 0..REGISTERS).prop_map(|(base, index)| Self::Indexed(Indexed { base, index }))
+        ].boxed()
+    }
+}
+
+impl Arbitrary for Operands {
+    /// The presence the operands must match, or any presence if [None].
+    type Parameters = Option<OperandsPresence>;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(presence: Self::Parameters) -> Self::Strategy {
+        let all = (0..REGISTERS, any::<Dynamic>())
+            .prop_map(|(x_static, x_dynamic)| Self::AllPresent(AllPresent { x_static, x_dynamic }));
+        let x_static = (0..REGISTERS).prop_map(Self::Static);
+        let x_dynamic = any::<Dynamic>().prop_map(Self::Dynamic);
+
+        match presence {
+            Some(OperandsPresence::AllPresent) => all.boxed(),
+            Some(OperandsPresence::Static) => x_static.boxed(),
+            Some(OperandsPresence::Dynamic) => x_dynamic.boxed(),
+            None => prop_oneof![all, x_static, x_dynamic].boxed()
+        }
+    }
+}
+
+impl Arbitrary for Data {
+    /// The presence the operands must match, or any presence if [None].
+    type Parameters = Option<OperandsPresence>;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(presence: Self::Parameters) -> Self::Strategy {
+        (any::<number::Size>(), any_with::<Operands>(presence), any::<bool>(), proptest::option::of(0..REGISTERS), any::<bool>())
+            .prop_map(|(width, operands, dynamic_destination, target, synchronous)| {
+                // Only a present dynamic operand that is not a constant can be the destination. It is preferred when
+                // there is no static operand. A target needs both operands.
+                let writable = operands.x_dynamic().is_some_and(|x_dynamic| !x_dynamic.is_constant());
+                let dynamic_destination = writable && (dynamic_destination || operands.x_static().is_none());
+                let destination = match target {
+                    _ if dynamic_destination => Destination::Dynamic,
+                    Some(target) if matches!(operands, Operands::AllPresent(_)) => Destination::Target(target),
+                    _ => Destination::Static
+                };
+                let synchronous = synchronous && !matches!(operands.x_dynamic(), Some(Dynamic::Register(_)));
+
+                Self { width, destination, synchronous, operands }
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Instruction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<Extension>()
+            .prop_flat_map(|extension| {
+                let data = match extension.presence() {
+                    Some(presence) => any_with::<Data>(Some(presence)).prop_map(Some).boxed(),
+                    None => Just(None).boxed()
+                };
+
+                (Just(extension), data)
+            })
+            .prop_map(|(extension, data)| Self { extension, data })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use proptest::prelude::*;
+    use utility::Encodable;
+    use super::super::{Driver, Instruction, Registers};
+
+    proptest! {
+        #[test]
+        fn instruction_round_trip(instruction in any::<Instruction>()) {
+            let encoded = instruction.encode();
+            let decoded = Instruction::decode(&mut Cursor::new(&encoded)).unwrap();
+
+            prop_assert_eq!(decoded.encode(), encoded);
+            prop_assert_eq!(decoded, instruction);
+        }
+
+        #[test]
+        fn encoding_is_canonical(instruction in any::<Instruction>()) {
+            let encoded = instruction.encode();
+            prop_assert_eq!(instruction.encoded_len(), encoded.len());
+            prop_assert_eq!(Instruction::decode_slice_strict(&encoded).unwrap(), (instruction, encoded.len()));
+        }
+
+        #[test]
+        fn driver_round_trip(driver in any::<Driver>()) {
+            prop_assert_eq!(Driver::new(driver.encode()), driver);
+        }
+
+        #[test]
+        fn registers_round_trip(registers in any::<Registers>()) {
+            prop_assert_eq!(Registers::new(registers.encode()), registers);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Fluent construction of instructions without nesting [Data] and [Operands] literals.
+
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::processor::processor::Registers;
+use number::Size;
+use super::{Data, Instruction};
+use super::operand::{AllPresent, Destination, Dynamic, Operands, OperandsPresence, Static};
+use super::operation::Extension;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// No extension was given.
+    Extension,
+    /// The operation expects the static operand but it was not given.
+    MissingStatic,
+    /// The operation expects the dynamic operand but it was not given.
+    MissingDynamic,
+    /// The static operand was given but the operation does not use it.
+    UnexpectedStatic,
+    /// The dynamic operand was given but the operation does not use it.
+    UnexpectedDynamic,
+    /// Width, destination or synchronisation was given to an operation without operands.
+    UnexpectedData,
+    /// A register code does not fit in the 3 bits of the registers byte.
+    Register,
+    /// The dynamic destination was used without a dynamic operand or with a constant one.
+    Destination,
+    /// A synchronous instruction used register addressing.
+    SynchronousAddressing,
+    /// The target register was given to an operation without both operands or together with the dynamic destination.
+    Target
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Extension => "no extension was given",
+            Self::MissingStatic => "operation expects the static operand",
+            Self::MissingDynamic => "operation expects the dynamic operand",
+            Self::UnexpectedStatic => "operation does not use the static operand",
+            Self::UnexpectedDynamic => "operation does not use the dynamic operand",
+            Self::UnexpectedData => "operation has no operands to configure",
+            Self::Register => "register code does not exist",
+            Self::Destination => "dynamic destination must be a writable dynamic operand",
+            Self::SynchronousAddressing => "synchronous instruction used register addressing",
+            Self::Target => "target register needs both operands and the static destination"
+        })
+    }
+}
+
+impl Error for BuildError {}
+
+/// Builder for an [Instruction] which validates the combination of fields once [InstructionBuilder::build] is called.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::builder::{BuildError, InstructionBuilder};
+/// use atln_processor::emulator::processor::processor::instruction::operand::{Destination, Dynamic};
+/// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+/// use atln_processor::emulator::processor::processor::instruction::operation::executor::Executor;
+/// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+/// use atln_processor::number;
+/// use atln_processor::number::Size;
+///
+/// let add = InstructionBuilder::new()
+///     .extension(Extension::Arithmetic(Arithmetic::Add))
+///     .width(Size::Word)
+///     .static_register(2)
+///     .dynamic(Dynamic::Memory(number::Data::Word(10)))
+///     .destination_dynamic()
+///     .build()
+///     .unwrap();
+///
+/// let data = add.data().as_ref().unwrap();
+/// assert_eq!(data.width, Size::Word);
+/// assert_eq!(data.destination, Destination::Dynamic);
+///
+/// let halt = InstructionBuilder::new().extension(Extension::Executor(Executor::Halt)).build().unwrap();
+/// assert!(halt.data().is_none());
+///
+/// // Addition needs both operands.
+/// let missing = InstructionBuilder::new().extension(Extension::Arithmetic(Arithmetic::Add)).static_register(2).build();
+/// assert!(matches!(missing, Err(BuildError::MissingDynamic)));
+///
+/// // Constants can't be written to.
+/// let constant = InstructionBuilder::new()
+///     .extension(Extension::Arithmetic(Arithmetic::Add))
+///     .static_register(2)
+///     .dynamic(Dynamic::Constant(number::Data::Byte(1)))
+///     .destination_dynamic()
+///     .build();
+/// assert!(matches!(constant, Err(BuildError::Destination)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InstructionBuilder {
+    extension: Option<Extension>,
+    width: Option<Size>,
+    x_static: Option<Static>,
+    x_dynamic: Option<Dynamic>,
+    dynamic_destination: bool,
+    target: Option<Static>,
+    synchronous: bool
+}
+
+impl InstructionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn extension(mut self, extension: Extension) -> Self {
+        self.extension = Some(extension);
+        self
+    }
+
+    /// Width of the operands. Defaults to [Size::Byte] if the operation has operands.
+    pub fn width(mut self, width: Size) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn static_register(mut self, register: Static) -> Self {
+        self.x_static = Some(register);
+        self
+    }
+
+    pub fn dynamic(mut self, x_dynamic: Dynamic) -> Self {
+        self.x_dynamic = Some(x_dynamic);
+        self
+    }
+
+    /// Store the result where the dynamic operand points instead of in the static operand.
+    pub fn destination_dynamic(mut self) -> Self {
+        self.dynamic_destination = true;
+        self
+    }
+
+    /// Store the result in a register other than the operands, so neither of them is overwritten.
+    pub fn target(mut self, register: Static) -> Self {
+        self.target = Some(register);
+        self
+    }
+
+    pub fn synchronous(mut self) -> Self {
+        self.synchronous = true;
+        self
+    }
+
+    /// Validate the fields and construct the instruction.
+    pub fn build(self) -> Result<Instruction, BuildError> {
+        let extension = self.extension.ok_or(BuildError::Extension)?;
+
+        let presence = match extension.presence() {
+            Some(presence) => presence,
+            None => {
+                if self.x_static.is_some() { return Err(BuildError::UnexpectedStatic) }
+                if self.x_dynamic.is_some() { return Err(BuildError::UnexpectedDynamic) }
+                if self.width.is_some() || self.dynamic_destination || self.target.is_some() || self.synchronous { return Err(BuildError::UnexpectedData) }
+
+                return Ok(Instruction { extension, data: None });
+            }
+        };
+
+        let registers = Registers::default().len() as u8;
+        if self.x_static.is_some_and(|register| register >= registers) { return Err(BuildError::Register) }
+        if self.target.is_some_and(|register| register >= registers) { return Err(BuildError::Register) }
+        if self.x_dynamic.as_ref().and_then(Dynamic::register).is_some_and(|register| register >= registers) { return Err(BuildError::Register) }
+        if matches!(&self.x_dynamic, Some(Dynamic::Indexed(indexed)) if indexed.index >= registers) { return Err(BuildError::Register) }
+
+        let operands = match (presence, self.x_static, self.x_dynamic) {
+            (OperandsPresence::AllPresent, Some(x_static), Some(x_dynamic)) => Operands::AllPresent(AllPresent { x_static, x_dynamic }),
+            (OperandsPresence::Static, Some(x_static), None) => Operands::Static(x_static),
+            (OperandsPresence::Dynamic, None, Some(x_dynamic)) => Operands::Dynamic(x_dynamic),
+            (presence, x_static, x_dynamic) => return Err(match (presence.expects_static(), x_static.is_some(), x_dynamic.is_some()) {
+                (true, false, _) => BuildError::MissingStatic,
+                (false, true, _) => BuildError::UnexpectedStatic,
+                (_, _, true) => BuildError::UnexpectedDynamic,
+                _ => BuildError::MissingDynamic
+            })
+        };
+
+        let destination = match (self.dynamic_destination, self.target) {
+            (true, Some(_)) => return Err(BuildError::Target),
+            (true, None) => {
+                if operands.x_dynamic().is_none_or(Dynamic::is_constant) { return Err(BuildError::Destination) }
+                Destination::Dynamic
+            },
+            (false, Some(target)) => {
+                if !matches!(operands, Operands::AllPresent(_)) { return Err(BuildError::Target) }
+                Destination::Target(target)
+            },
+            (false, None) => Destination::Static
+        };
+
+        if self.synchronous && matches!(operands.x_dynamic(), Some(Dynamic::Register(_))) { return Err(BuildError::SynchronousAddressing) }
+
+        Ok(Instruction {
+            extension,
+            data: Some(Data {
+                width: self.width.unwrap_or_default(),
+                destination,
+                synchronous: self.synchronous,
+                operands
+            })
+        })
+    }
+}
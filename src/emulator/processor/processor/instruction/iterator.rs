@@ -0,0 +1,244 @@
+//! Linear decoding of consecutive instructions from a stream.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Read;
+use std::mem;
+use std::vec::Vec;
+use super::{DecodeError, Instruction};
+
+/// Reader that counts the bytes it has read and keeps the bytes read since they were last cleared. Bytes given back
+/// with [Counted::unread] are read again before the rest of the stream.
+#[derive(Debug)]
+struct Counted<R> {
+    inner: R,
+    count: u64,
+    recorded: Vec<u8>,
+    unread: VecDeque<u8>
+}
+
+impl<R> Counted<R> {
+    fn unread(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().rev() { self.unread.push_front(byte); }
+        self.count -= bytes.len() as u64;
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let length = if self.unread.is_empty() { self.inner.read(buf)? } else { self.unread.read(buf)? };
+        self.count += length as u64;
+        self.recorded.extend_from_slice(&buf[..length]);
+        Ok(length)
+    }
+}
+
+/// Iterator over the instructions of a stream along with the offset each instruction starts at. Iteration ends when the
+/// stream ends between instructions. An instruction that fails to decode is yielded as an error, after which the
+/// iterator ends because the start of the next instruction is unknown.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::iterator::InstructionIterator;
+///
+/// // add.b r1, [10], halt, then a truncated instruction.
+/// let bytes = [0b000000_0_0, 0b0000_11_00, 0b00_001_000, 10, 0b000010_0_0, 0b0000_00_00, 0b000000_0_0];
+///
+/// let mut instructions = InstructionIterator::new(&bytes[..]);
+/// let (offset, add) = instructions.next().unwrap().unwrap();
+/// assert_eq!((offset, add.to_string().as_str()), (0, "add.b r1, [10]"));
+/// let (offset, halt) = instructions.next().unwrap().unwrap();
+/// assert_eq!((offset, halt.to_string().as_str()), (4, "halt"));
+/// assert!(instructions.next().unwrap().is_err());
+/// assert!(instructions.next().is_none());
+///
+/// // Ending exactly after an instruction is not an error.
+/// assert_eq!(InstructionIterator::new(&bytes[..6]).count(), 2);
+/// ```
+#[derive(Debug)]
+pub struct InstructionIterator<R> {
+    stream: Counted<R>,
+    strict: bool,
+    finished: bool
+}
+
+impl<R: Read> InstructionIterator<R> {
+    pub fn new(stream: R) -> Self {
+        Self { stream: Counted { inner: stream, count: 0, recorded: Vec::new(), unread: VecDeque::new() }, strict: false, finished: false }
+    }
+
+    /// Reject instructions which are not encoded canonically, in the same way as [Instruction::decode_slice_strict].
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::DecodeError;
+    /// use atln_processor::emulator::processor::processor::instruction::iterator::InstructionIterator;
+    ///
+    /// // halt, then a halt with meaningless addressing bits.
+    /// let bytes = [0b000010_0_0, 0b0000_00_00, 0b000010_0_0, 0b0000_01_00];
+    /// assert_eq!(InstructionIterator::new(&bytes[..]).filter(Result::is_ok).count(), 2);
+    ///
+    /// let mut instructions = InstructionIterator::new(&bytes[..]).strict();
+    /// assert!(instructions.next().unwrap().is_ok());
+    /// assert!(matches!(instructions.next().unwrap(), Err(DecodeError::NonCanonical { offset: 1 })));
+    /// ```
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Keep going after bytes which are not a valid instruction by treating some of them as data, so images with
+    /// corrupted code or data between the code can still be inspected.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::iterator::{InstructionIterator, Recovery};
+    ///
+    /// // halt, a byte of data, then halt.
+    /// let bytes = [0b000010_0_0, 0b0000_00_00, 0xFF, 0b000010_0_0, 0b0000_00_00];
+    /// assert_eq!(InstructionIterator::new(&bytes[..]).filter(Result::is_ok).count(), 1);
+    ///
+    /// let listing: Vec<String> = InstructionIterator::new(&bytes[..])
+    ///     .recover(Recovery::SkipByte)
+    ///     .map(|(offset, item)| format!("{offset}: {item}"))
+    ///     .collect();
+    ///
+    /// assert_eq!(listing, ["0: halt", "2: .byte 0xff", "3: halt"]);
+    /// ```
+    pub fn recover(self, recovery: Recovery) -> Recovering<R> {
+        Recovering { instructions: self, recovery }
+    }
+
+    /// Offset of the next instruction from where the stream started.
+    pub fn offset(&self) -> u64 {
+        self.stream.count
+    }
+
+    pub fn into_inner(self) -> R {
+        self.stream.inner
+    }
+
+    /// Decode the next instruction. [None] is returned if the stream ended between instructions.
+    fn decode(&mut self) -> Option<Result<Instruction, DecodeError>> {
+        let offset = self.stream.count;
+        self.stream.recorded.clear();
+
+        let decoded = Instruction::decode(&mut self.stream).and_then(|instruction| {
+            if self.strict { instruction.canonical(&self.stream.recorded)?; }
+            Ok(instruction)
+        });
+
+        // Nothing being read means the stream ended between instructions.
+        if matches!(decoded, Err(DecodeError::Length)) && self.stream.count == offset { return None }
+        Some(decoded)
+    }
+}
+
+impl<R: Read> Iterator for InstructionIterator<R> {
+    type Item = Result<(u64, Instruction), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished { return None }
+
+        let offset = self.stream.count;
+        let decoded = self.decode();
+        if !matches!(decoded, Some(Ok(_))) { self.finished = true; }
+
+        decoded.map(|decoded| decoded.map(|instruction| (offset, instruction)))
+    }
+}
+
+/// What to treat as data when bytes are not a valid instruction, before trying to decode again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Only the first byte.
+    SkipByte,
+    /// Every byte up to the next offset which is a multiple of the alignment, for code which is known to be aligned.
+    Align(u64)
+}
+
+impl Recovery {
+    /// Number of bytes to skip at an offset.
+    fn skip(&self, offset: u64) -> u64 {
+        match *self {
+            Self::SkipByte => 1,
+            Self::Align(alignment) => alignment.max(1) - offset % alignment.max(1)
+        }
+    }
+}
+
+/// Something found in a stream by a [Recovering] iterator.
+#[derive(Debug)]
+pub enum Disassembled {
+    Instruction(Instruction),
+    /// Bytes skipped because they did not decode into a valid instruction, along with why.
+    Data { bytes: Vec<u8>, error: DecodeError }
+}
+
+impl Display for Disassembled {
+    /// The instruction in assembly, or the bytes of data as a `.byte` directive.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instruction(instruction) => write!(f, "{instruction}"),
+            Self::Data { bytes, .. } => {
+                f.write_str(".byte")?;
+                for (index, byte) in bytes.iter().enumerate() { write!(f, "{} {byte:#04x}", if index == 0 { "" } else { "," })?; }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Iterator over the instructions of a stream which skips bytes that do not decode according to a [Recovery] policy,
+/// instead of ending. Created with [InstructionIterator::recover]. Iteration ends once the stream ends, or if reading
+/// from it fails.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::iterator::{Disassembled, InstructionIterator, Recovery};
+///
+/// // halt, 2 bytes of data, then halt at the next multiple of 2 and a truncated instruction.
+/// let bytes = [0b000010_0_0, 0b0000_00_00, 0xFF, 0xFF, 0b000010_0_0, 0b0000_00_00, 0b000000_0_0];
+///
+/// let listing: Vec<String> = InstructionIterator::new(&bytes[..])
+///     .recover(Recovery::Align(2))
+///     .map(|(offset, item)| format!("{offset}: {item}"))
+///     .collect();
+///
+/// assert_eq!(listing, ["0: halt", "2: .byte 0xff, 0xff", "4: halt", "6: .byte 0x00"]);
+/// ```
+#[derive(Debug)]
+pub struct Recovering<R> {
+    instructions: InstructionIterator<R>,
+    recovery: Recovery
+}
+
+impl<R: Read> Iterator for Recovering<R> {
+    type Item = (u64, Disassembled);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instructions = &mut self.instructions;
+        if instructions.finished { return None }
+
+        let offset = instructions.stream.count;
+        let error = match instructions.decode() {
+            Some(Ok(instruction)) => return Some((offset, Disassembled::Instruction(instruction))),
+            Some(Err(error)) => error,
+            None => {
+                instructions.finished = true;
+                return None
+            }
+        };
+
+        // Read up to the bytes that are skipped, then give back whatever the decoder read past them.
+        let stream = &mut instructions.stream;
+        let skip = self.recovery.skip(offset) as usize;
+        let missing = skip.saturating_sub(stream.recorded.len()) as u64;
+        if missing > 0 && io::copy(&mut stream.by_ref().take(missing), &mut io::sink()).is_err() { instructions.finished = true; }
+
+        let mut bytes = mem::take(&mut stream.recorded);
+        let rest = bytes.split_off(skip.min(bytes.len()));
+        stream.unread(&rest);
+
+        if bytes.is_empty() {
+            instructions.finished = true;
+            return None
+        }
+
+        Some((offset, Disassembled::Data { bytes, error }))
+    }
+}
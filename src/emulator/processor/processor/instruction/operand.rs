@@ -2,16 +2,22 @@
 //! 
 //! The static operand is a simple and optional register field which can be used as the destination.
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
 use std::io::Read;
 use emulator::memory;
-use emulator::memory::{Frame, Memory};
+use emulator::memory::{Frame, MemoryAccess};
 use emulator::processor::processor;
 use number::{Data, Size};
 
 use crate::emulator::processor::processor::instruction::{Driver, Registers};
 use crate::number;
-use crate::number::{BYTE_SIZE, DUAL_SIZE, QUAD_SIZE, WORD_SIZE};
+use crate::number::QUAD_SIZE;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // region: Constants
 pub const REGISTER_ADDRESSING    : u8 = 0;
@@ -22,6 +28,25 @@ pub const IMMEDIATE_EXPONENT_BYTE: u8 = 0;
 pub const IMMEDIATE_EXPONENT_WORD: u8 = 1;
 pub const IMMEDIATE_EXPONENT_DUAL: u8 = 2;
 pub const IMMEDIATE_EXPONENT_QUAD: u8 = 3;
+/// Dynamic register field which makes constant addressing sign extend the immediate instead of zero extending it.
+/// Constant addressing does not use a register, so the field is free to select a variant of the mode.
+pub const SIGNED_CONSTANT_REGISTER: u8 = 1;
+/// Dynamic register field which turns memory addressing into program counter relative addressing. Memory addressing
+/// does not use a register, so the field is free to select a variant of the mode.
+pub const RELATIVE_MEMORY_REGISTER: u8 = 1;
+/// Dynamic register field which turns memory addressing into post increment addressing. The base register is the byte
+/// immediate.
+pub const POST_INCREMENT_MEMORY_REGISTER: u8 = 2;
+/// Dynamic register field which turns memory addressing into pre decrement addressing. The base register is the byte
+/// immediate.
+pub const PRE_DECREMENT_MEMORY_REGISTER: u8 = 3;
+/// Dynamic register field which turns memory addressing into indexed addressing. The byte immediate holds the base
+/// register in its low 4 bits and the index register in its high 4 bits.
+pub const INDEXED_MEMORY_REGISTER: u8 = 4;
+/// Bits the index register is shifted by in the immediate of indexed addressing.
+pub const INDEXED_INDEX_SHIFT: u8 = 4;
+/// Bits of the immediate of indexed addressing holding the base register.
+pub const INDEXED_BASE_MASK: u8 = 0b0000_1111;
 // endregion
 
 // region: Single
@@ -31,24 +56,131 @@ pub const IMMEDIATE_EXPONENT_QUAD: u8 = 3;
 pub type Static = u8;
 
 /// Allows dereferencing a memory address by reading the value from a register then adding an offset.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Offset {
     pub register: u8,
     pub offset: number::Data
 }
 
+/// Allows dereferencing a memory address by adding the value of an index register multiplied by the access width to the
+/// value of a base register. The index counts elements rather than bytes, so the same index register can walk arrays
+/// of any width.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Indexed {
+    pub base: u8,
+    pub index: u8
+}
+
 /// Either a register code or immediate value addressing mode. Being dynamic means this gives the programmer freedom to 
 /// pick either of the addressing modes.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Dynamic {
     /// Read value from register.
     Register(u8),
     /// Read value from register, add an offset to it, then use the sum to dereference memory.
     Offset(Offset),
-    /// Read value from immediate as data.
+    /// Read value from immediate as data. The immediate is zero extended or truncated to the operating width.
     Constant(number::Data),
+    /// Read value from immediate as data sign extended to the operating width, so small negative numbers fit in a
+    /// small immediate. Encoded as constant addressing with the dynamic register field set to
+    /// [SIGNED_CONSTANT_REGISTER].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Both constants are the byte 0xFF, but only the signed one fills the word.
+    /// let program = assemble("add.w r1, 0xFF\nadd.w r2, -1\nhalt").unwrap();
+    /// assert_eq!(program[3], program[7]);
+    ///
+    /// let mut core = Core::default();
+    /// core.run(&mut Memory::from(program), &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[1..3], [0xFF, 0xFFFF]);
+    /// ```
+    Signed(number::Data),
     /// Read value from memory address by addressing it with the immediate.
-    Memory(number::Data)
+    Memory(number::Data),
+    /// Read value from memory at the address of the next instruction plus the immediate. The immediate is sign extended,
+    /// so data can be placed before or after the code using it. Encoded as memory addressing with the dynamic register
+    /// field set to [RELATIVE_MEMORY_REGISTER].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // The data byte follows the halt, 2 bytes after the end of the add.
+    /// let mut program = assemble("add.b r1, [pc + 2]\nhalt").unwrap();
+    /// program.push(9);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.run(&mut memory, &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[1], 9);
+    /// ```
+    Relative(number::Data),
+    /// Read value from memory at the address in a register, then add the width of the access to the register. Encoded
+    /// as memory addressing with the dynamic register field set to [POST_INCREMENT_MEMORY_REGISTER].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Sum the 3 words at 16 by walking r1 over them.
+    /// let mut program = assemble("add.w r2, [r1]+\nadd.w r2, [r1]+\nadd.w r2, [r1]+\nhalt").unwrap();
+    /// program.resize(16, 0);
+    /// program.extend([1, 0, 2, 0, 3, 0]);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 16;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[1..3], [22, 6]);
+    /// ```
+    PostIncrement(u8),
+    /// Subtract the width of the access from a register, then read value from memory at the address in the register.
+    /// Together with [Dynamic::PostIncrement] this makes a stack growing downwards. Encoded as memory addressing with the
+    /// dynamic register field set to [PRE_DECREMENT_MEMORY_REGISTER].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Push r2 onto the stack at r7, then pop it into r3.
+    /// let mut program = assemble("add.q -[r7], r2\nadd.q r3, [r7]+\nhalt").unwrap();
+    /// program.resize(32, 0);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.registers[2] = 5;
+    /// core.context.registers[7] = 32;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    /// assert_eq!(memory.bytes[24], 5);
+    /// assert_eq!((core.context.registers[3], core.context.registers[7]), (5, 32));
+    /// ```
+    PreDecrement(u8),
+    /// Read value from memory at the address in a base register plus an index register multiplied by the width of the
+    /// access. Encoded as memory addressing with the dynamic register field set to [INDEXED_MEMORY_REGISTER].
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Load element 2 of the dual array at 16.
+    /// let mut program = assemble("add.d r3, [r1 + r2]\nhalt").unwrap();
+    /// program.resize(16, 0);
+    /// program.extend([1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 16;
+    /// core.context.registers[2] = 2;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[3], 3);
+    /// ```
+    Indexed(Indexed)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -61,12 +193,51 @@ pub enum ReadImmediateError {
     Exponent
 }
 
+impl Display for ReadImmediateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Read => "failed to read the immediate from the stream",
+            Self::Length => "stream ended before the immediate",
+            Self::Exponent => "immediate exponent is larger than 3"
+        })
+    }
+}
+
+impl Error for ReadImmediateError {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DynamicConstructError {
     /// The immediate exponent is out of bounds. 3 is the largest exponent for immediate.
     Immediate(ReadImmediateError),
     /// The addressing mode does not exist.
-    Addressing
+    Addressing,
+    /// The addressing mode takes a register from the immediate, but the immediate is not a byte.
+    RegisterImmediate
+}
+
+impl Display for DynamicConstructError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Immediate(_) => f.write_str("failed to read the dynamic operand's immediate"),
+            Self::Addressing => f.write_str("addressing mode does not exist"),
+            Self::RegisterImmediate => f.write_str("register immediate is not a byte")
+        }
+    }
+}
+
+impl Error for DynamicConstructError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Immediate(error) => Some(error),
+            Self::Addressing | Self::RegisterImmediate => None
+        }
+    }
+}
+
+impl From<ReadImmediateError> for DynamicConstructError {
+    fn from(value: ReadImmediateError) -> Self {
+        Self::Immediate(value)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -81,6 +252,32 @@ pub enum DynamicReadError {
     ConstantTargetInvalid
 }
 
+impl Display for DynamicReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Overflow => "offset address overflowed",
+            Self::Memory(_) => "failed to access the dynamic operand's memory",
+            Self::InvalidRegisterIndex => "register code does not exist",
+            Self::ConstantTargetInvalid => "constant operand can't be written to"
+        })
+    }
+}
+
+impl Error for DynamicReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Memory(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<memory::GetError> for DynamicReadError {
+    fn from(value: memory::GetError) -> Self {
+        Self::Memory(value)
+    }
+}
+
 impl Dynamic {
     /// Read the immediate based on the exponent. The number of bytes read from the stream is based on using the
     /// immediate exponent as a power of 2.
@@ -102,24 +299,22 @@ impl Dynamic {
     /// assert!(matches!(Dynamic::read_immediate(IMMEDIATE_EXPONENT_DUAL, &mut Cursor::new(dual.to_le_bytes())).unwrap(), number::Data::Dual(_dual)));
     /// assert!(matches!(Dynamic::read_immediate(IMMEDIATE_EXPONENT_QUAD, &mut Cursor::new(quad.to_le_bytes())).unwrap(), number::Data::Quad(_quad)));
     /// ```
+    #[cfg(feature = "std")]
     pub fn read_immediate(exponent: u8, stream: &mut impl Read) -> Result<number::Data, ReadImmediateError> {
-        let mut quad_buffer = [0u8; QUAD_SIZE as usize];
-
-        let buffer: &mut [u8] = match exponent {
-            IMMEDIATE_EXPONENT_BYTE => &mut quad_buffer[0..BYTE_SIZE as usize],
-            IMMEDIATE_EXPONENT_WORD => &mut quad_buffer[0..WORD_SIZE as usize],
-            IMMEDIATE_EXPONENT_DUAL => &mut quad_buffer[0..DUAL_SIZE as usize],
-            IMMEDIATE_EXPONENT_QUAD => &mut quad_buffer[0..QUAD_SIZE as usize],
-            _ => return Err(ReadImmediateError::Exponent)
+        let mut quad_buffer = [0u8; QUAD_SIZE];
+
+        let size = match number::Size::from_exponent(exponent) {
+            Some(size) => size,
+            None => return Err(ReadImmediateError::Exponent)
         };
 
+        let buffer = &mut quad_buffer[0..size.size() as usize];
         match stream.read(buffer) {
             Ok(length) => if length != buffer.len() { return Err(ReadImmediateError::Length) },
             Err(_) => return Err(ReadImmediateError::Read)
         };
 
-        // Unwrapping is safe here because the exponent is validated when creating the buffer.
-        Ok(number::Data::from_exponent_selecting(exponent, u64::from_le_bytes(quad_buffer)).unwrap())
+        Ok(number::Data::from_size_selecting(&size, u64::from_le_bytes(quad_buffer)))
     }
 
     /// Create a new dynamic operand from codes. Not all the codes may be used. Returns [None] if the addressing code
@@ -128,7 +323,8 @@ impl Dynamic {
     /// The immediate is expected to start where the immediate bytes would be. The immediate exponent is
     /// used to calculate how many immediate bytes should be read. These bytes will only be read if not in Register
     /// addressing mode.
-    /// - The register is only used by the Register and Offset addressing modes.
+    /// - The register is only used by the Register and Offset addressing modes, and to select [Dynamic::Relative],
+    ///   [Dynamic::PostIncrement], [Dynamic::PreDecrement] or [Dynamic::Indexed] with memory addressing.
     /// ```
     /// use std::io::Cursor;
     /// use atln_processor::number;
@@ -153,86 +349,279 @@ impl Dynamic {
     /// assert!(matches!(constant, Dynamic::Constant(number::Data::Byte(0))));
     /// assert!(matches!(memory, Dynamic::Memory(number::Data::Dual(0b00111111_00001111_00111111_00001111))));
     /// ```
+    #[cfg(feature = "std")]
     pub fn new(register: u8, addressing: u8, immediate_exponent: u8, immediate_stream: &mut impl Read) -> Result<Self, DynamicConstructError> {
         if addressing == REGISTER_ADDRESSING { return Ok(Self::Register(register)) }
 
-        let immediate = match Self::read_immediate(immediate_exponent, immediate_stream) {
-            Ok(immediate) => immediate,
-            Err(error) => return Err(DynamicConstructError::Immediate(error))
-        };
+        let immediate = Self::read_immediate(immediate_exponent, immediate_stream)?;
+        Self::with_immediate(register, addressing, immediate)
+    }
+
+    /// Same as [Dynamic::new] but takes the immediate straight from the start of a slice. The number of immediate bytes
+    /// used is returned along with the operand.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{Dynamic, MEMORY_ADDRESSING, REGISTER_ADDRESSING, IMMEDIATE_EXPONENT_WORD};
+    /// use atln_processor::number;
+    ///
+    /// assert_eq!(Dynamic::decode_slice(5, REGISTER_ADDRESSING, 0, &[]).unwrap(), (Dynamic::Register(5), 0));
+    /// assert_eq!(Dynamic::decode_slice(0, MEMORY_ADDRESSING, IMMEDIATE_EXPONENT_WORD, &[10, 0, 99]).unwrap(), (Dynamic::Memory(number::Data::Word(10)), 2));
+    /// assert!(Dynamic::decode_slice(0, MEMORY_ADDRESSING, IMMEDIATE_EXPONENT_WORD, &[10]).is_err());
+    /// ```
+    pub fn decode_slice(register: u8, addressing: u8, immediate_exponent: u8, bytes: &[u8]) -> Result<(Self, usize), DynamicConstructError> {
+        if addressing == REGISTER_ADDRESSING { return Ok((Self::Register(register), 0)) }
+
+        let size = number::Size::from_exponent(immediate_exponent).ok_or(ReadImmediateError::Exponent)?;
+        let length = size.size() as usize;
+        let immediate = bytes.get(..length).ok_or(ReadImmediateError::Length)?;
 
+        let mut quad_buffer = [0u8; QUAD_SIZE];
+        quad_buffer[..length].copy_from_slice(immediate);
+        let immediate = number::Data::from_size_selecting(&size, u64::from_le_bytes(quad_buffer));
+
+        Ok((Self::with_immediate(register, addressing, immediate)?, length))
+    }
+
+    /// Create an operand that uses an immediate from its addressing mode.
+    fn with_immediate(register: u8, addressing: u8, immediate: number::Data) -> Result<Self, DynamicConstructError> {
         Ok(match addressing {
             OFFSET_ADDRESSING => Self::Offset(Offset {
                 register,
                 offset: immediate,
             }),
+            CONSTANT_ADDRESSING if register == SIGNED_CONSTANT_REGISTER => Self::Signed(immediate),
             CONSTANT_ADDRESSING => Self::Constant(immediate),
+            MEMORY_ADDRESSING if register == RELATIVE_MEMORY_REGISTER => Self::Relative(immediate),
+            MEMORY_ADDRESSING if register == POST_INCREMENT_MEMORY_REGISTER => Self::PostIncrement(Self::register_immediate(immediate)?),
+            MEMORY_ADDRESSING if register == PRE_DECREMENT_MEMORY_REGISTER => Self::PreDecrement(Self::register_immediate(immediate)?),
+            MEMORY_ADDRESSING if register == INDEXED_MEMORY_REGISTER => {
+                let registers = Self::register_immediate(immediate)?;
+                Self::Indexed(Indexed { base: registers & INDEXED_BASE_MASK, index: registers >> INDEXED_INDEX_SHIFT })
+            },
             MEMORY_ADDRESSING => Self::Memory(immediate),
             _ => return Err(DynamicConstructError::Addressing)
         })
     }
 
+    /// Register code held by the immediate of an addressing mode.
+    fn register_immediate(immediate: number::Data) -> Result<u8, DynamicConstructError> {
+        match immediate {
+            number::Data::Byte(register) => Ok(register),
+            _ => Err(DynamicConstructError::RegisterImmediate)
+        }
+    }
+
+    /// Whether the operand is a constant, which can't be written to.
+    pub fn is_constant(&self) -> bool {
+        matches!(self, Self::Constant(_) | Self::Signed(_))
+    }
+
     pub fn addressing(&self) -> u8 {
         match self {
             Self::Register(_) => REGISTER_ADDRESSING,
             Self::Offset(_) => OFFSET_ADDRESSING,
-            Self::Constant(_) => CONSTANT_ADDRESSING,
-            Self::Memory(_) => MEMORY_ADDRESSING
+            Self::Constant(_) | Self::Signed(_) => CONSTANT_ADDRESSING,
+            Self::Memory(_) | Self::Relative(_) | Self::PostIncrement(_) | Self::PreDecrement(_) | Self::Indexed(_) => MEMORY_ADDRESSING
         }
     }
 
-    pub fn immediate(&self) -> Option<&number::Data> {
+    pub fn immediate(&self) -> Option<number::Data> {
         Some(match self {
             Self::Register(_) => return None,
-            Self::Offset(offset) => &offset.offset,
-            Self::Constant(constant) => constant,
-            Self::Memory(memory) => memory
+            Self::Offset(offset) => offset.offset.clone(),
+            Self::Constant(constant) | Self::Signed(constant) => constant.clone(),
+            Self::Memory(memory) => memory.clone(),
+            Self::Relative(displacement) => displacement.clone(),
+            Self::PostIncrement(register) | Self::PreDecrement(register) => number::Data::Byte(*register),
+            Self::Indexed(indexed) => number::Data::Byte(indexed.index << INDEXED_INDEX_SHIFT | indexed.base & INDEXED_BASE_MASK)
         })
     }
 
-    /// Get the register code if the addressing includes one. Addressing modes [Register] and [Offset] support this
-    /// function and will return an instance of [Some] otherwise [None] will be returned.
+    /// Get the register code if the addressing includes one. Addressing modes [Self::Register], [Self::Offset],
+    /// [Self::PostIncrement], [Self::PreDecrement] and [Self::Indexed] support this function and will return an instance
+    /// of [Some] otherwise [None] will be returned. The base register is returned for [Self::Indexed].
     pub fn register(&self) -> Option<u8> {
         Some(match self {
             Self::Register(register) => *register,
             Self::Offset(offset) => offset.register,
+            Self::PostIncrement(register) | Self::PreDecrement(register) => *register,
+            Self::Indexed(indexed) => indexed.base,
             _ => return None
         })
     }
 
-    /// Try to read the value from the target of this operand.
+    /// Value of the dynamic register field when encoded. This is the register code if there is one.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{Dynamic, POST_INCREMENT_MEMORY_REGISTER, RELATIVE_MEMORY_REGISTER};
+    /// use atln_processor::number::Data;
+    ///
+    /// assert_eq!(Dynamic::Register(5).register_field(), 5);
+    /// assert_eq!(Dynamic::Memory(Data::Byte(4)).register_field(), 0);
+    /// assert_eq!(Dynamic::Relative(Data::Byte(4)).register_field(), RELATIVE_MEMORY_REGISTER);
+    /// assert_eq!(Dynamic::PostIncrement(5).register_field(), POST_INCREMENT_MEMORY_REGISTER);
     /// ```
-    /// // TODO: Test
+    pub fn register_field(&self) -> u8 {
+        match self {
+            Self::Signed(_) => SIGNED_CONSTANT_REGISTER,
+            Self::Relative(_) => RELATIVE_MEMORY_REGISTER,
+            Self::PostIncrement(_) => POST_INCREMENT_MEMORY_REGISTER,
+            Self::PreDecrement(_) => PRE_DECREMENT_MEMORY_REGISTER,
+            Self::Indexed(_) => INDEXED_MEMORY_REGISTER,
+            _ => self.register().unwrap_or(0)
+        }
+    }
+
+    /// Get the memory address this operand points to when accessing data of a size. [None] is returned for the
+    /// addressing modes which do not point into memory. Relative addresses are taken from the program counter, which
+    /// points at the next instruction while an instruction executes. Getting the address never modifies registers, see
+    /// [Dynamic::update] for that.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::Context;
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{Dynamic, Indexed, Offset};
+    /// use atln_processor::number::{Data, Size};
+    ///
+    /// let mut context = Context::default();
+    /// context.registers[1] = 12;
+    /// context.program_counter = 100;
+    ///
+    /// let offset = Dynamic::Offset(Offset { register: 1, offset: Data::Byte(4) });
+    /// assert_eq!(offset.address(&Size::Byte, &context), Ok(Some(16)));
+    /// assert_eq!(Dynamic::Relative(Data::Byte(0xFC)).address(&Size::Byte, &context), Ok(Some(96)));
+    /// assert_eq!(Dynamic::PostIncrement(1).address(&Size::Dual, &context), Ok(Some(12)));
+    /// assert_eq!(Dynamic::PreDecrement(1).address(&Size::Dual, &context), Ok(Some(8)));
+    /// assert_eq!(Dynamic::Indexed(Indexed { base: 1, index: 1 }).address(&Size::Word, &context), Ok(Some(36)));
+    /// assert_eq!(Dynamic::Register(1).address(&Size::Byte, &context), Ok(None));
     /// ```
-    pub fn read(&self, size: &Size, memory: &Memory, translate: bool, registers: &processor::Registers) -> Result<Cow<Data>, DynamicReadError> {
+    pub fn address(&self, size: &Size, context: &processor::Context) -> Result<Option<u64>, DynamicReadError> {
+        let register = |register: u8| context.registers.get(register as usize).copied().ok_or(DynamicReadError::InvalidRegisterIndex);
+
         Ok(match self {
-            Self::Register(register) => Cow::Owned(Data::from_size_selecting(&size, *registers.get(*register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)?)),
-            Self::Offset(offset) => {
-                let register_dereferenced = *registers.get(offset.register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)?;
-                let address = register_dereferenced.checked_add(offset.offset.quad()).ok_or(DynamicReadError::Overflow)?;
-                Cow::Owned(memory.get(Frame { size: size.clone(), address }, translate).map_err(DynamicReadError::Memory)?)
+            Self::Offset(offset) => Some(register(offset.register)?.checked_add(offset.offset.quad()).ok_or(DynamicReadError::Overflow)?),
+            Self::Memory(address) => Some(address.quad()),
+            Self::Relative(displacement) => Some(context.program_counter.checked_add_signed(displacement.signed()).ok_or(DynamicReadError::Overflow)?),
+            Self::PostIncrement(base) => Some(register(*base)?),
+            Self::PreDecrement(base) => Some(register(*base)?.checked_sub(size.size() as u64).ok_or(DynamicReadError::Overflow)?),
+            Self::Indexed(indexed) => {
+                let offset = register(indexed.index)?.checked_mul(size.size() as u64).ok_or(DynamicReadError::Overflow)?;
+                Some(register(indexed.base)?.checked_add(offset).ok_or(DynamicReadError::Overflow)?)
             },
-            Self::Constant(immediate) => Cow::Borrowed(immediate),
-            Self::Memory(address) => Cow::Owned(memory.get(Frame { size: size.clone(), address: address.quad() }, translate).map_err(DynamicReadError::Memory)?)
+            Self::Register(_) | Self::Constant(_) | Self::Signed(_) => None
+        })
+    }
+
+    /// Apply the change an addressing mode makes to its base register. Executors call this once after the instruction
+    /// executed, so reading and then writing the same operand accesses the same address. The base register is updated
+    /// after the operation wrote its result, so an operation storing to the base register has its result replaced.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::Context;
+    /// use atln_processor::emulator::processor::processor::instruction::operand::Dynamic;
+    /// use atln_processor::number::Size;
+    ///
+    /// let mut context = Context::default();
+    /// context.registers[1] = 12;
+    ///
+    /// Dynamic::PostIncrement(1).update(&Size::Quad, &mut context);
+    /// assert_eq!(context.registers[1], 20);
+    ///
+    /// Dynamic::PreDecrement(1).update(&Size::Word, &mut context);
+    /// assert_eq!(context.registers[1], 18);
+    /// ```
+    pub fn update(&self, size: &Size, context: &mut processor::Context) {
+        let (base, increment) = match self {
+            Self::PostIncrement(base) => (base, true),
+            Self::PreDecrement(base) => (base, false),
+            _ => return
+        };
+
+        let Some(register) = context.registers.get_mut(*base as usize) else { return };
+        *register = if increment { register.wrapping_add(size.size() as u64) } else { register.wrapping_sub(size.size() as u64) };
+    }
+
+    /// Try to read the value from the target of this operand. Offset addressing reads from a base address held in a
+    /// register plus a displacement, which is how fields of a structure or elements of an array are accessed.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Context;
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{Dynamic, DynamicReadError, Offset};
+    /// use atln_processor::number::{Data, Size};
+    ///
+    /// // A structure at address 8 with a word field at offset 2.
+    /// let memory = Memory::from(vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0x34, 0x12]);
+    /// let mut context = Context::default();
+    /// context.registers[1] = 8;
+    /// context.registers[7] = u64::MAX;
+    ///
+    /// let field = Dynamic::Offset(Offset { register: 1, offset: Data::Byte(2) });
+    /// assert_eq!(field.read(&Size::Word, &memory, &context).unwrap().into_owned(), Data::Word(0x1234));
+    /// assert_eq!(Dynamic::Register(1).read(&Size::Byte, &memory, &context).unwrap().into_owned(), Data::Byte(8));
+    ///
+    /// let overflow = Dynamic::Offset(Offset { register: 7, offset: Data::Byte(1) });
+    /// assert_eq!(overflow.read(&Size::Byte, &memory, &context), Err(DynamicReadError::Overflow));
+    /// ```
+    pub fn read(&self, size: &Size, memory: &dyn MemoryAccess, context: &processor::Context) -> Result<Cow<'_, Data>, DynamicReadError> {
+        Ok(match self {
+            Self::Register(register) => Cow::Owned(Data::from_size_selecting(size, *context.registers.get(*register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)?)),
+            Self::Constant(immediate) => Cow::Owned(Data::from_size_selecting(size, immediate.quad())),
+            Self::Signed(immediate) => Cow::Owned(Data::from_size_selecting(size, immediate.signed() as u64)),
+            _ => {
+                let address = self.address(size, context)?.expect("Memory addressing modes always have an address");
+                Cow::Owned(memory.get(Frame { size: size.clone(), address }, context.virtual_mode).map_err(DynamicReadError::Memory)?)
+            }
         })
     }
     
-    pub fn write(&self, size: &Size, memory: &mut Memory, translate: bool, registers: &mut processor::Registers, value: Data) -> Result<(), DynamicReadError> {
+    pub fn write(&self, size: &Size, memory: &mut dyn MemoryAccess, context: &mut processor::Context, value: Data) -> Result<(), DynamicReadError> {
         match self {
-            Self::Register(register) => *registers.get_mut(*register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)? = value.quad(),
-            Self::Offset(offset) => {
-                let register_dereferenced = *registers.get(offset.register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)?;
-                let address = register_dereferenced.checked_add(offset.offset.quad()).ok_or(DynamicReadError::Overflow)?;
-                memory.set(Frame { size: size.clone(), address }, translate, value).map_err(DynamicReadError::Memory)?;
-            },
-            Self::Constant(_) => return Err(DynamicReadError::ConstantTargetInvalid),
-            Self::Memory(address) => memory.set(Frame { size: size.clone(), address: address.quad() }, translate, value).map_err(DynamicReadError::Memory)?
+            Self::Register(register) => *context.registers.get_mut(*register as usize).ok_or(DynamicReadError::InvalidRegisterIndex)? = value.quad(),
+            Self::Constant(_) | Self::Signed(_) => return Err(DynamicReadError::ConstantTargetInvalid),
+            _ => {
+                let address = self.address(size, context)?.expect("Memory addressing modes always have an address");
+                memory.set(Frame { size: size.clone(), address }, context.virtual_mode, value).map_err(DynamicReadError::Memory)?;
+            }
         };
         
         Ok(())
     }
 }
 
+impl Display for Dynamic {
+    /// Registers are written as `r0`, constants as plain numbers and memory dereferences in brackets like `[10]`,
+    /// `[r1 + 10]` or `[pc - 4]`. Post increment is written as `[r1]+`, pre decrement as `-[r1]` and an index register
+    /// scaled by the width as `[r1 + r2]`.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{Dynamic, Indexed, Offset};
+    /// use atln_processor::number;
+    ///
+    /// assert_eq!(Dynamic::Register(3).to_string(), "r3");
+    /// assert_eq!(Dynamic::Offset(Offset { register: 1, offset: number::Data::Word(10) }).to_string(), "[r1 + 10]");
+    /// assert_eq!(Dynamic::Constant(number::Data::Byte(5)).to_string(), "5");
+    /// assert_eq!(Dynamic::Signed(number::Data::Byte(0xFB)).to_string(), "-5");
+    /// assert_eq!(Dynamic::Signed(number::Data::Byte(5)).to_string(), "+5");
+    /// assert_eq!(Dynamic::Memory(number::Data::Quad(64)).to_string(), "[64]");
+    /// assert_eq!(Dynamic::Relative(number::Data::Byte(0xFC)).to_string(), "[pc - 4]");
+    /// assert_eq!(Dynamic::Relative(number::Data::Byte(0)).to_string(), "[pc]");
+    /// assert_eq!(Dynamic::PostIncrement(2).to_string(), "[r2]+");
+    /// assert_eq!(Dynamic::PreDecrement(7).to_string(), "-[r7]");
+    /// assert_eq!(Dynamic::Indexed(Indexed { base: 1, index: 2 }).to_string(), "[r1 + r2]");
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(register) => write!(f, "r{register}"),
+            Self::Offset(offset) => write!(f, "[r{} + {}]", offset.register, offset.offset),
+            Self::Constant(constant) => write!(f, "{constant}"),
+            Self::Signed(constant) => write!(f, "{:+}", constant.signed()),
+            Self::Memory(address) => write!(f, "[{address}]"),
+            Self::Relative(displacement) => match displacement.signed() {
+                0 => f.write_str("[pc]"),
+                displacement if displacement < 0 => write!(f, "[pc - {}]", displacement.unsigned_abs()),
+                displacement => write!(f, "[pc + {displacement}]")
+            },
+            Self::PostIncrement(base) => write!(f, "[r{base}]+"),
+            Self::PreDecrement(base) => write!(f, "-[r{base}]"),
+            Self::Indexed(indexed) => write!(f, "[r{} + r{}]", indexed.base, indexed.index)
+        }
+    }
+}
+
 /// Operands provide the operation the arguments necessary for computing, There are 2 types of operands, static and 
 /// dynamic operands.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -244,20 +633,40 @@ pub enum Operand {
 
 // region: Instruction ready operand parameter that contains addressing for a different modes of having operands.
 /// All operands.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AllPresent {
     pub x_static: Static,
     pub x_dynamic: Dynamic
 }
 
 /// An operand selector to indicate an operand to point to.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Destination {
     Static,
-    Dynamic
+    Dynamic,
+    /// A register other than the operands, so neither of them is overwritten. Encoded with the
+    /// [target prefix](super::prefix).
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let program = assemble("add.b r3, r1, r2\nhalt").unwrap();
+    /// assert_eq!(program[..5], [0b111111_00, 3, 0b000000_0_0, 0b0000_00_00, 0b00_001_010]);
+    ///
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 4;
+    /// core.context.registers[2] = 5;
+    /// core.run(&mut Memory::from(program), &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[1..4], [4, 5, 9]);
+    /// ```
+    Target(Static)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OperandsPresence {
     AllPresent,
     Static,
@@ -289,7 +698,8 @@ impl OperandsPresence {
 }
 
 /// Multi configuration of operands for a processor.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Operands {
     AllPresent(AllPresent),
     Static(Static),
@@ -306,6 +716,30 @@ pub enum OperandsConstructError {
     SynchronousAddressing
 }
 
+impl Display for OperandsConstructError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dynamic(_) => "failed to construct the dynamic operand",
+            Self::SynchronousAddressing => "synchronous instruction used register addressing"
+        })
+    }
+}
+
+impl Error for OperandsConstructError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Dynamic(error) => Some(error),
+            Self::SynchronousAddressing => None
+        }
+    }
+}
+
+impl From<DynamicConstructError> for OperandsConstructError {
+    fn from(value: DynamicConstructError) -> Self {
+        Self::Dynamic(value)
+    }
+}
+
 impl Operands {
     /// Create a new operands set from
     /// - A stream which will be used to retrieve the immediate bytes if necessary.
@@ -317,28 +751,40 @@ impl Operands {
     /// ```
     /// // TODO: Complete test
     /// ```
+    #[cfg(feature = "std")]
     pub fn new(stream: &mut impl Read, presence: &OperandsPresence, registers: &Registers, driver: &Driver) -> Result<Self, OperandsConstructError> {
-        // Create the dynamic operand
-        let x_dynamic = if presence.expects_dynamic() {
-            Some(match Dynamic::new(registers.x_dynamic, driver.addressing, driver.immediate_exponent, stream) {
-                Ok(operand) => operand,
-                Err(error) => return Err(OperandsConstructError::Dynamic(error))
-            })
-        } else { None };
+        Self::construct(presence, registers, driver, || Dynamic::new(registers.x_dynamic, driver.addressing, driver.immediate_exponent, stream))
+    }
 
-        // Do not allow the processor to be synchronous and use the register or constant addressing mode in the same
-        // core.
-        if let Some(value) = &x_dynamic { if let Dynamic::Register(_) = value { if driver.synchronise { return Err(OperandsConstructError::SynchronousAddressing) }}}
+    /// Same as [Operands::new] but takes the immediate straight from the start of a slice. The number of immediate bytes
+    /// used is returned along with the operands.
+    pub fn decode_slice(bytes: &[u8], presence: &OperandsPresence, registers: &Registers, driver: &Driver) -> Result<(Self, usize), OperandsConstructError> {
+        let mut length = 0;
+        let operands = Self::construct(presence, registers, driver, || {
+            let (x_dynamic, immediate_length) = Dynamic::decode_slice(registers.x_dynamic, driver.addressing, driver.immediate_exponent, bytes)?;
+            length = immediate_length;
+            Ok(x_dynamic)
+        })?;
+
+        Ok((operands, length))
+    }
 
-        // Construct operand field.
-        Ok(match presence {
+    /// Construct the operands expected by the presence. The dynamic operand is only created if it is expected.
+    fn construct(presence: &OperandsPresence, registers: &Registers, driver: &Driver, x_dynamic: impl FnOnce() -> Result<Dynamic, DynamicConstructError>) -> Result<Self, OperandsConstructError> {
+        let operands = match presence {
             OperandsPresence::AllPresent => Operands::AllPresent(AllPresent {
                 x_static: registers.x_static,
-                x_dynamic: x_dynamic.unwrap()
+                x_dynamic: x_dynamic()?
             }),
             OperandsPresence::Static => Operands::Static(registers.x_static),
-            OperandsPresence::Dynamic => Operands::Dynamic(x_dynamic.unwrap())
-        })
+            OperandsPresence::Dynamic => Operands::Dynamic(x_dynamic()?)
+        };
+
+        // Do not allow the processor to be synchronous and use the register or constant addressing mode in the same
+        // core.
+        if let Some(Dynamic::Register(_)) = operands.x_dynamic() { if driver.synchronise { return Err(OperandsConstructError::SynchronousAddressing) }}
+
+        Ok(operands)
     }
 
     /// Try to get the static operand.
@@ -1,23 +1,51 @@
-use std::borrow::Cow;
-use std::fmt::Debug;
-use emulator::memory::{GetError, Memory};
-use emulator::processor;
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use emulator::memory::MemoryAccess;
 use emulator::processor::processor::instruction::Data;
-use emulator::processor::processor::instruction::operand::{Dynamic, DynamicReadError, Static};
-use emulator::processor::processor::{Context, Ports, Registers};
+use emulator::processor::processor::instruction::operand::DynamicReadError;
+use emulator::processor::processor::{Context, Ports};
 use number;
-use crate::emulator::processor::processor::instruction;
 use crate::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
-use crate::utility::Coded;
+use crate::emulator::processor::processor::instruction::operation::checksum::Checksum;
+use crate::emulator::processor::processor::instruction::operation::condition::Condition;
+use crate::emulator::processor::processor::instruction::operation::control::Control;
+use crate::emulator::processor::processor::instruction::operation::conversion::Conversion;
+use crate::emulator::processor::processor::instruction::operation::crypto::Crypto;
+use crate::emulator::processor::processor::instruction::operation::custom::Custom;
+use crate::emulator::processor::processor::instruction::operation::data::Data as DataOperation;
+use crate::emulator::processor::processor::instruction::operation::executor::Executor;
+use crate::emulator::processor::processor::instruction::operation::machine::Machine;
+use crate::utility::{Coded, FromRepresentation, Representable};
 
 use super::operand::OperandsPresence;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 pub mod arithmetic;
+pub mod checksum;
+pub mod condition;
+pub mod control;
+pub mod conversion;
+pub mod crypto;
+pub mod custom;
+pub mod data;
+pub mod executor;
+pub mod machine;
+pub mod metadata;
 
 // Extension identifier codes
 
 pub const ARITHMETIC_CODE: u8 = 0;
 pub const DATA_CODE      : u8 = 1;
+pub const EXECUTOR_CODE  : u8 = 2;
+pub const CONDITION_CODE : u8 = 3;
+pub const CONVERSION_CODE: u8 = 4;
+pub const CHECKSUM_CODE  : u8 = 5;
+pub const CRYPTO_CODE    : u8 = 6;
+pub const MACHINE_CODE   : u8 = 7;
+pub const CONTROL_CODE   : u8 = 8;
 
 // Operation
 
@@ -36,6 +64,88 @@ pub enum OperationExecuteError<CustomError> {
     InvalidStaticRegister
 }
 
+impl<CustomError: Display> Display for OperationExecuteError<CustomError> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data(true) => f.write_str("operation expected operands but none were given"),
+            Self::Data(false) => f.write_str("operation expected no operands but some were given"),
+            Self::Operand(presence) => write!(f, "operation expected {presence:?} operands"),
+            Self::DynamicRead(_) => f.write_str("failed to access the dynamic operand"),
+            Self::Custom(error) => write!(f, "{error}"),
+            Self::InvalidStaticRegister => f.write_str("static register code does not exist")
+        }
+    }
+}
+
+impl<CustomError: Error + 'static> Error for OperationExecuteError<CustomError> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::DynamicRead(error) => Some(error),
+            Self::Custom(error) => error.source(),
+            _ => None
+        }
+    }
+}
+
+impl<CustomError> From<DynamicReadError> for OperationExecuteError<CustomError> {
+    fn from(value: DynamicReadError) -> Self {
+        Self::DynamicRead(value)
+    }
+}
+
+impl<CustomError> OperationExecuteError<CustomError> {
+    /// Convert the custom error while keeping every other variant.
+    pub fn map_custom<T>(self, map: impl FnOnce(CustomError) -> T) -> OperationExecuteError<T> {
+        match self {
+            Self::Data(expected) => OperationExecuteError::Data(expected),
+            Self::Operand(presence) => OperationExecuteError::Operand(presence),
+            Self::DynamicRead(error) => OperationExecuteError::DynamicRead(error),
+            Self::Custom(error) => OperationExecuteError::Custom(map(error)),
+            Self::InvalidStaticRegister => OperationExecuteError::InvalidStaticRegister
+        }
+    }
+}
+
+/// Errors unique to the operations of each extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionError {
+    Arithmetic(arithmetic::ExecuteError),
+    Data(data::ExecuteError),
+    Conversion(conversion::ExecuteError),
+    Checksum(checksum::ExecuteError),
+    Crypto(crypto::ExecuteError),
+    Control(control::ExecuteError),
+    Custom(custom::ExecuteError)
+}
+
+impl Display for ExtensionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Arithmetic(_) => f.write_str("arithmetic operation failed"),
+            Self::Data(_) => f.write_str("data operation failed"),
+            Self::Conversion(_) => f.write_str("conversion operation failed"),
+            Self::Checksum(_) => f.write_str("checksum operation failed"),
+            Self::Crypto(_) => f.write_str("crypto operation failed"),
+            Self::Control(_) => f.write_str("control operation failed"),
+            Self::Custom(_) => f.write_str("custom operation failed")
+        }
+    }
+}
+
+impl Error for ExtensionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Arithmetic(error) => Some(error),
+            Self::Data(error) => Some(error),
+            Self::Conversion(error) => Some(error),
+            Self::Checksum(error) => Some(error),
+            Self::Crypto(error) => Some(error),
+            Self::Control(error) => Some(error),
+            Self::Custom(error) => Some(error)
+        }
+    }
+}
+
 pub struct AllPresent<'a> {
     pub r#static: u64,
     pub dynamic: Cow<'a, number::Data>
@@ -43,7 +153,7 @@ pub struct AllPresent<'a> {
 
 pub trait Operation<'a>: Coded<u8> + Default {
     type CustomError: Debug + Clone + PartialEq + Eq;
-    fn execute(&self, data: Option<&Data>, memory: &mut Memory, context: &mut Context, ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>>;
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>>;
 
     /// Get which operands are expected. [None] indicates that the operation does not expect any operands.
     fn presence(&self) -> Option<OperandsPresence>;
@@ -63,12 +173,35 @@ pub enum ExtensionFromCodeInvalid {
     Operation
 }
 
+impl Display for ExtensionFromCodeInvalid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Extension => "extension code does not exist",
+            Self::Operation => "operation code does not exist in the extension"
+        })
+    }
+}
+
+impl Error for ExtensionFromCodeInvalid {}
+
 /// Contains groups of operations which are categorized by extension. This allows for operations to have duplicate
 /// names and also allows for the operation set to extended in the future without breaking code that is already
 /// compiled for the architecture.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Extension {
     Arithmetic(Arithmetic),
+    Data(DataOperation),
+    Executor(Executor),
+    Condition(Condition),
+    Conversion(Conversion),
+    Checksum(Checksum),
+    Crypto(Crypto),
+    Machine(Machine),
+    Control(Control),
+    /// Operation of a [custom extension](custom). Never serialized, because the extension is only known at runtime.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Custom)
 }
 
 impl Default for Extension {
@@ -78,7 +211,8 @@ impl Default for Extension {
 }
 
 impl Extension {
-    /// Create an extension containing and operation with the extension and operation codes.
+    /// Create an extension containing and operation with the extension and operation codes. Codes of registered
+    /// [custom extensions](custom) are included.
     pub fn from_codes(extension: ExtensionCode, operation: OperationCode) -> Result<Self, ExtensionFromCodeInvalid> {
         let invalid_operation = Err(ExtensionFromCodeInvalid::Operation);
 
@@ -87,22 +221,166 @@ impl Extension {
                 Some(operation) => operation,
                 None => return invalid_operation
             }),
+            DATA_CODE => Self::Data(match DataOperation::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            EXECUTOR_CODE => Self::Executor(match Executor::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            CONDITION_CODE => Self::Condition(match Condition::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            CONVERSION_CODE => Self::Conversion(match Conversion::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            CHECKSUM_CODE => Self::Checksum(match Checksum::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            CRYPTO_CODE => Self::Crypto(match Crypto::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            MACHINE_CODE => Self::Machine(match Machine::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            CONTROL_CODE => Self::Control(match Control::from_code(operation) {
+                Some(operation) => operation,
+                None => return invalid_operation
+            }),
+            #[cfg(feature = "std")]
+            _ => match custom::registered(extension) {
+                Some(custom) => Self::Custom(Custom::new(custom, operation)?),
+                None => return Err(ExtensionFromCodeInvalid::Extension)
+            },
+            #[cfg(not(feature = "std"))]
             _ => return Err(ExtensionFromCodeInvalid::Extension)
         })
     }
 
-    /// Retrieve the underlying operation trait.
-    pub fn operation(&self) -> &impl Operation {
+    /// Code of the operation inside the extension.
+    pub fn operation_code(&self) -> OperationCode {
         match self {
-            Self::Arithmetic(arithmetic) => arithmetic
+            Self::Arithmetic(arithmetic) => arithmetic.code(),
+            Self::Data(data) => data.code(),
+            Self::Executor(executor) => executor.code(),
+            Self::Condition(condition) => condition.code(),
+            Self::Conversion(conversion) => conversion.code(),
+            Self::Checksum(checksum) => checksum.code(),
+            Self::Crypto(crypto) => crypto.code(),
+            Self::Machine(machine) => machine.code(),
+            Self::Control(control) => control.code(),
+            Self::Custom(custom) => custom.operation()
         }
     }
+
+    /// Get which operands the operation expects. See [Operation::presence].
+    pub fn presence(&self) -> Option<OperandsPresence> {
+        match self {
+            Self::Arithmetic(arithmetic) => arithmetic.presence(),
+            Self::Data(data) => data.presence(),
+            Self::Executor(executor) => executor.presence(),
+            Self::Condition(condition) => condition.presence(),
+            Self::Conversion(conversion) => conversion.presence(),
+            Self::Checksum(checksum) => checksum.presence(),
+            Self::Crypto(crypto) => crypto.presence(),
+            Self::Machine(machine) => machine.presence(),
+            Self::Control(control) => control.presence(),
+            Self::Custom(custom) => custom.extension().presence(custom.operation())
+        }
+    }
+
+    /// Whether the operation can change which instruction is executed next. See [Instruction::diverts](super::Instruction::diverts).
+    pub fn diverts(&self) -> bool {
+        match self {
+            Self::Arithmetic(_) | Self::Data(_) | Self::Condition(_) | Self::Conversion(_) | Self::Checksum(_) | Self::Crypto(_) | Self::Machine(_) | Self::Control(_) => false,
+            Self::Executor(_) => true,
+            Self::Custom(custom) => custom.extension().diverts(custom.operation())
+        }
+    }
+
+    /// Execute the operation. See [Operation::execute].
+    pub fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, ports: &mut Ports) -> Result<(), OperationExecuteError<ExtensionError>> {
+        match self {
+            Self::Arithmetic(arithmetic) => arithmetic.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Arithmetic)),
+            Self::Data(operation) => operation.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Data)),
+            Self::Executor(executor) => executor.execute(data, memory, context, ports).map_err(|error| error.map_custom(|never| match never {})),
+            Self::Condition(condition) => condition.execute(data, memory, context, ports).map_err(|error| error.map_custom(|never| match never {})),
+            Self::Conversion(conversion) => conversion.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Conversion)),
+            Self::Checksum(checksum) => checksum.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Checksum)),
+            Self::Crypto(crypto) => crypto.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Crypto)),
+            Self::Machine(machine) => machine.execute(data, memory, context, ports).map_err(|error| error.map_custom(|never| match never {})),
+            Self::Control(control) => control.execute(data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Control)),
+            Self::Custom(custom) => custom.extension().execute(custom.operation(), data, memory, context, ports).map_err(|error| error.map_custom(ExtensionError::Custom))
+        }
+    }
+}
+
+impl Display for Extension {
+    /// The mnemonic of the operation.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Arithmetic(arithmetic) => f.write_str(&arithmetic.representation()),
+            Self::Data(data) => f.write_str(&data.representation()),
+            Self::Executor(executor) => f.write_str(&executor.representation()),
+            Self::Condition(condition) => f.write_str(&condition.representation()),
+            Self::Conversion(conversion) => f.write_str(&conversion.representation()),
+            Self::Checksum(checksum) => f.write_str(&checksum.representation()),
+            Self::Crypto(crypto) => f.write_str(&crypto.representation()),
+            Self::Machine(machine) => f.write_str(&machine.representation()),
+            Self::Control(control) => f.write_str(&control.representation()),
+            Self::Custom(custom) => f.write_str(&custom.mnemonic())
+        }
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Extension {
+    /// Find the operation with a mnemonic in any extension. Built in extensions are searched before registered
+    /// [custom extensions](custom).
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+    /// use atln_processor::utility::FromRepresentation;
+    ///
+    /// assert_eq!(Extension::from_representation("sub".into()), Some(Extension::Arithmetic(Arithmetic::Subtract)));
+    /// assert_eq!(Extension::from_representation("mul".into()), None);
+    /// ```
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        if let Some(arithmetic) = Arithmetic::from_representation(string.clone()) { return Some(Self::Arithmetic(arithmetic)) }
+        if let Some(data) = DataOperation::from_representation(string.clone()) { return Some(Self::Data(data)) }
+        if let Some(executor) = Executor::from_representation(string.clone()) { return Some(Self::Executor(executor)) }
+        if let Some(condition) = Condition::from_representation(string.clone()) { return Some(Self::Condition(condition)) }
+        if let Some(conversion) = Conversion::from_representation(string.clone()) { return Some(Self::Conversion(conversion)) }
+        if let Some(checksum) = Checksum::from_representation(string.clone()) { return Some(Self::Checksum(checksum)) }
+        if let Some(crypto) = Crypto::from_representation(string.clone()) { return Some(Self::Crypto(crypto)) }
+        if let Some(machine) = Machine::from_representation(string.clone()) { return Some(Self::Machine(machine)) }
+        if let Some(control) = Control::from_representation(string.clone()) { return Some(Self::Control(control)) }
+
+        #[cfg(feature = "std")]
+        return custom::from_mnemonic(&string).map(Self::Custom);
+        #[cfg(not(feature = "std"))]
+        None
+    }
 }
 
 impl Coded<u8> for Extension {
     fn code(&self) -> u8 {
         match self {
-            Self::Arithmetic(_) => ARITHMETIC_CODE
+            Self::Arithmetic(_) => ARITHMETIC_CODE,
+            Self::Data(_) => DATA_CODE,
+            Self::Executor(_) => EXECUTOR_CODE,
+            Self::Condition(_) => CONDITION_CODE,
+            Self::Conversion(_) => CONVERSION_CODE,
+            Self::Checksum(_) => CHECKSUM_CODE,
+            Self::Crypto(_) => CRYPTO_CODE,
+            Self::Machine(_) => MACHINE_CODE,
+            Self::Control(_) => CONTROL_CODE,
+            Self::Custom(custom) => custom.extension().code()
         }
     }
 }
@@ -123,9 +401,9 @@ mod extension_test {
 
     #[test]
     fn operation() {
-        let mut extension = Extension::from_codes(ARITHMETIC_CODE, ADD_CODE).unwrap();
-        let operation_generic = extension.operation();
+        let extension = Extension::from_codes(ARITHMETIC_CODE, ADD_CODE).unwrap();
 
-        // assert_eq!(operation_generic.expects_static(), Arithmetic::Add.expects_static());
+        assert_eq!(extension.operation_code(), ADD_CODE);
+        assert_eq!(extension.presence(), Arithmetic::Add.presence());
     }
 }
\ No newline at end of file
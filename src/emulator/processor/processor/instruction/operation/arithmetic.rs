@@ -1,23 +1,51 @@
-use emulator::memory::Memory;
-use emulator::processor::processor::{Context, Ports, Registers};
-use emulator::processor::processor::instruction::operand::{Destination, Dynamic};
+//! Operations which calculate with integers.
+//!
+//! `add` and `sub` combine the static register with the dynamic operand. `minu` and `maxu` select the smaller or larger
+//! of the two as unsigned integers, and `mins` and `maxs` as signed integers. The result is written to the destination.
+//!
+//! `abs` and `neg` take a single signed value. Like a move, they load the dynamic operand into the static or target
+//! register, or store the static register to the dynamic operand with the dynamic destination. They fault on the one
+//! value of each width which has no positive counterpart.
+
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
 use number;
-use number::CheckedAdd;
-use crate::emulator::processor;
+use number::{CheckedAdd, CheckedSub};
 use crate::emulator::processor::processor::instruction::Data;
 use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
 use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // region: Constants
-pub const ADD_CODE     : u8 = 0;
-pub const SUBTRACT_CODE: u8 = 1;
+pub const ADD_CODE       : u8 = 0;
+pub const SUBTRACT_CODE  : u8 = 1;
+pub const MIN_CODE       : u8 = 2;
+pub const MAX_CODE       : u8 = 3;
+pub const SIGNED_MIN_CODE: u8 = 4;
+pub const SIGNED_MAX_CODE: u8 = 5;
+pub const ABSOLUTE_CODE  : u8 = 6;
+pub const NEGATE_CODE    : u8 = 7;
 // endregion
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Arithmetic {
     #[default]
     Add,
-    Subtract
+    Subtract,
+    Min,
+    Max,
+    SignedMin,
+    SignedMax,
+    Absolute,
+    Negate
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -26,26 +54,73 @@ pub enum ExecuteError {
     Overflow
 }
 
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Overflow => "arithmetic overflowed"
+        })
+    }
+}
+
+impl Error for ExecuteError {}
+
+/// Fit a signed result into a width, if it is in range.
+fn fit_signed(size: &number::Size, value: i64) -> Option<number::Data> {
+    let data = number::Data::from_size_selecting(size, value as u64);
+    (data.signed() == value).then_some(data)
+}
+
 impl<'a> Operation<'a> for Arithmetic {
     type CustomError = ExecuteError;
 
-    fn execute(&self, data: Option<&Data>, memory: &mut Memory, context: &mut Context, ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let program = assemble("minu.b r1, r3\nmins.b r2, r3\nabs.b r4, r3\nneg.b r5, r2\nsub.b r6, r1\nhalt").unwrap();
+    /// let mut memory = Memory::from(program);
+    ///
+    /// let mut core = Core::default();
+    /// core.context.registers[1..4].copy_from_slice(&[5, 5, 0xFE]);
+    /// core.context.registers[6] = 7;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// // 0xFE is 254 unsigned and -2 signed.
+    /// assert_eq!(core.context.registers[1..7], [5, 0xFE, 0xFE, 2, 2, 2]);
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
         let data = data.ok_or(OperationExecuteError::Data(true))?;
         let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
         let r#static = number::Data::from_size_selecting(&data.width, *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?);
-        let dynamic = all_operands.x_dynamic.read(&data.width, memory, context.virtual_mode, &context.registers).map_err(OperationExecuteError::DynamicRead)?;
+        let dynamic = all_operands.x_dynamic.read(&data.width, memory, context).map_err(OperationExecuteError::DynamicRead)?;
+
+        let overflow = OperationExecuteError::Custom(ExecuteError::Overflow);
 
         let result = match self {
-            Self::Add => r#static.checked_add(dynamic.into_owned()).ok_or(OperationExecuteError::Custom(ExecuteError::Overflow))?,
-            // Self::Subtract => r#static.checked_sub(dynamic.quad()).ok_or(OperationExecuteError::Custom(ExecuteError::Overflow))?
-            _ => todo!()
+            Self::Add => r#static.checked_add(dynamic.into_owned()).ok_or(overflow)?,
+            Self::Subtract => r#static.checked_sub(dynamic.into_owned()).ok_or(overflow)?,
+            Self::Min => if dynamic.quad() < r#static.quad() { dynamic.into_owned() } else { r#static },
+            Self::Max => if dynamic.quad() > r#static.quad() { dynamic.into_owned() } else { r#static },
+            Self::SignedMin => if dynamic.signed() < r#static.signed() { dynamic.into_owned() } else { r#static },
+            Self::SignedMax => if dynamic.signed() > r#static.signed() { dynamic.into_owned() } else { r#static },
+            Self::Absolute | Self::Negate => {
+                let operand = if data.destination == Destination::Dynamic { r#static } else { dynamic.into_owned() };
+                let value = match self {
+                    Self::Absolute => operand.signed().checked_abs(),
+                    _ => operand.signed().checked_neg()
+                };
+
+                value.and_then(|value| fit_signed(&data.width, value)).ok_or(overflow)?
+            }
         };
         
         match data.destination {
             Destination::Static => *context.registers.get_mut(all_operands.x_static as usize).unwrap() = result.quad(),
             Destination::Dynamic => all_operands.x_dynamic
-                .write(&data.width, memory, context.virtual_mode, &mut context.registers, result)
-                .map_err(OperationExecuteError::DynamicRead)?
+                .write(&data.width, memory, context, result)
+                .map_err(OperationExecuteError::DynamicRead)?,
+            Destination::Target(target) => *context.registers.get_mut(target as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = result.quad()
         };
         
         Ok(())
@@ -59,8 +134,14 @@ impl<'a> Operation<'a> for Arithmetic {
 impl Coded<u8> for Arithmetic {
     fn code(&self) -> u8 {
         match self {
-            Self::Add      => ADD_CODE,
-            Self::Subtract => SUBTRACT_CODE
+            Self::Add       => ADD_CODE,
+            Self::Subtract  => SUBTRACT_CODE,
+            Self::Min       => MIN_CODE,
+            Self::Max       => MAX_CODE,
+            Self::SignedMin => SIGNED_MIN_CODE,
+            Self::SignedMax => SIGNED_MAX_CODE,
+            Self::Absolute  => ABSOLUTE_CODE,
+            Self::Negate    => NEGATE_CODE
         }
     }
 }
@@ -68,9 +149,47 @@ impl Coded<u8> for Arithmetic {
 impl Arithmetic {
     pub fn from_code(code: u8) -> Option<Self> {
         Some(match code {
-            ADD_CODE      => Self::Add,
-            SUBTRACT_CODE => Self::Subtract,
+            ADD_CODE        => Self::Add,
+            SUBTRACT_CODE   => Self::Subtract,
+            MIN_CODE        => Self::Min,
+            MAX_CODE        => Self::Max,
+            SIGNED_MIN_CODE => Self::SignedMin,
+            SIGNED_MAX_CODE => Self::SignedMax,
+            ABSOLUTE_CODE   => Self::Absolute,
+            NEGATE_CODE     => Self::Negate,
             _ => return None
         })
     }
-}
\ No newline at end of file
+}
+
+impl<'a> Representable<'a> for Arithmetic {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Add       => "add",
+            Self::Subtract  => "sub",
+            Self::Min       => "minu",
+            Self::Max       => "maxu",
+            Self::SignedMin => "mins",
+            Self::SignedMax => "maxs",
+            Self::Absolute  => "abs",
+            Self::Negate    => "neg"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Arithmetic {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "add" => Self::Add,
+            "sub" => Self::Subtract,
+            "minu" => Self::Min,
+            "maxu" => Self::Max,
+            "mins" => Self::SignedMin,
+            "maxs" => Self::SignedMax,
+            "abs" => Self::Absolute,
+            "neg" => Self::Negate,
+            _ => return None
+        })
+    }
+}
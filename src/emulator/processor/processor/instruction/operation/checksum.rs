@@ -0,0 +1,196 @@
+//! Operations which update a running checksum.
+//!
+//! Each operation feeds the bytes of the dynamic operand, read at the width of the instruction, into the checksum held
+//! in the static register, lowest byte first, and writes the updated checksum to the static or target register. All of
+//! them are reflected CRCs computed a byte at a time through a table.
+//!
+//! | Mnemonic | Checksum    | Polynomial   |
+//! | -------- | ----------- | ------------ |
+//! | `crc16`  | CRC-16/ARC  | `0x8005`     |
+//! | `crc32`  | CRC-32      | `0x04C11DB7` |
+//! | `crc32c` | CRC-32C     | `0x1EDC6F41` |
+//!
+//! The initial value and the final inversion are left to guest software. The standard CRC-32 of some data is obtained by
+//! starting with `0xFFFFFFFF` and inverting the result, while CRC-16/ARC starts with 0 and is not inverted.
+
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const CRC16_CODE : u8 = 0;
+pub const CRC32_CODE : u8 = 1;
+pub const CRC32C_CODE: u8 = 2;
+
+/// Reflected CRC-16/ARC polynomial.
+pub const CRC16_POLYNOMIAL : u64 = 0xA001;
+/// Reflected CRC-32 polynomial.
+pub const CRC32_POLYNOMIAL : u64 = 0xEDB8_8320;
+/// Reflected CRC-32C polynomial.
+pub const CRC32C_POLYNOMIAL: u64 = 0x82F6_3B78;
+// endregion
+
+static CRC16_TABLE : [u64; 256] = table(CRC16_POLYNOMIAL);
+static CRC32_TABLE : [u64; 256] = table(CRC32_POLYNOMIAL);
+static CRC32C_TABLE: [u64; 256] = table(CRC32C_POLYNOMIAL);
+
+/// Remainder of every byte for a reflected polynomial.
+const fn table(polynomial: u64) -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        let mut remainder = byte as u64;
+        let mut bit = 0;
+
+        while bit < 8 {
+            remainder = if remainder & 1 == 1 { remainder >> 1 ^ polynomial } else { remainder >> 1 };
+            bit += 1;
+        }
+
+        table[byte] = remainder;
+        byte += 1;
+    }
+
+    table
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Checksum {
+    #[default]
+    Crc16,
+    Crc32,
+    Crc32C
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The checksum can only be written to a register, but the dynamic destination was set.
+    Destination
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Destination => "checksum can't be written to the dynamic operand"
+        })
+    }
+}
+
+impl Error for ExecuteError {}
+
+impl Checksum {
+    /// Feed bytes into a checksum. Bits of the checksum above its width are discarded.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operation::checksum::Checksum;
+    ///
+    /// assert_eq!(!Checksum::Crc32.update(0xFFFF_FFFF, b"123456789") & 0xFFFF_FFFF, 0xCBF4_3926);
+    /// assert_eq!(!Checksum::Crc32C.update(0xFFFF_FFFF, b"123456789") & 0xFFFF_FFFF, 0xE306_9283);
+    /// assert_eq!(Checksum::Crc16.update(0, b"123456789"), 0xBB3D);
+    /// ```
+    pub fn update(&self, checksum: u64, bytes: &[u8]) -> u64 {
+        let (table, mask) = match self {
+            Self::Crc16 => (&CRC16_TABLE, 0xFFFF),
+            Self::Crc32 => (&CRC32_TABLE, 0xFFFF_FFFF),
+            Self::Crc32C => (&CRC32C_TABLE, 0xFFFF_FFFF)
+        };
+
+        bytes.iter().fold(checksum & mask, |checksum, &byte| table[((checksum ^ byte as u64) & 0xFF) as usize] ^ checksum >> 8)
+    }
+}
+
+impl<'a> Operation<'a> for Checksum {
+    type CustomError = ExecuteError;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // "1234" followed by "5678" and "9" in three steps.
+    /// let mut program = assemble("crc32.d r1, [32]\ncrc32.d r1, [36]\ncrc32.b r1, [40]\nhalt").unwrap();
+    /// program.resize(32, 0);
+    /// program.extend(b"123456789");
+    ///
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 0xFFFF_FFFF;
+    /// core.run(&mut Memory::from(program), &mut Default::default(), None);
+    /// assert_eq!(!core.context.registers[1] & 0xFFFF_FFFF, 0xCBF4_3926);
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        let checksum = *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+
+        let register = match data.destination {
+            Destination::Static => all_operands.x_static,
+            Destination::Target(target) => target,
+            Destination::Dynamic => return Err(OperationExecuteError::Custom(ExecuteError::Destination))
+        };
+
+        let value = all_operands.x_dynamic.read(&data.width, memory, context)?;
+        let bytes = value.quad().to_le_bytes();
+        let checksum = self.update(checksum, &bytes[..data.width.size() as usize]);
+
+        *context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = checksum;
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(OperandsPresence::AllPresent)
+    }
+}
+
+impl Coded<u8> for Checksum {
+    fn code(&self) -> u8 {
+        match self {
+            Self::Crc16  => CRC16_CODE,
+            Self::Crc32  => CRC32_CODE,
+            Self::Crc32C => CRC32C_CODE
+        }
+    }
+}
+
+impl Checksum {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            CRC16_CODE  => Self::Crc16,
+            CRC32_CODE  => Self::Crc32,
+            CRC32C_CODE => Self::Crc32C,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Checksum {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Crc16  => "crc16",
+            Self::Crc32  => "crc32",
+            Self::Crc32C => "crc32c"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Checksum {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "crc16"  => Self::Crc16,
+            "crc32"  => Self::Crc32,
+            "crc32c" => Self::Crc32C,
+            _ => return None
+        })
+    }
+}
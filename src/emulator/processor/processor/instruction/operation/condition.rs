@@ -0,0 +1,256 @@
+//! Operations which compare values and select between them without diverting.
+//!
+//! # Flags
+//! `cmp` subtracts the dynamic operand from the static register at the width of the instruction and stores what the
+//! subtraction produced in the [Flags] of the context, discarding the difference. Only `cmp` writes the flags.
+//!
+//! # Conditional moves
+//! Each move tests a single flag. When the condition holds, the move behaves like an ordinary move to the destination,
+//! so it loads the dynamic operand into the static or target register, or stores the static register to the dynamic
+//! operand if the dynamic destination is set. When the condition does not hold, no operand is accessed.
+//!
+//! | Mnemonic | Condition         |
+//! | -------- | ----------------- |
+//! | `movz`   | Zero is set       |
+//! | `movnz`  | Zero is clear     |
+//! | `movc`   | Carry is set      |
+//! | `movnc`  | Carry is clear    |
+//! | `movs`   | Sign is set       |
+//! | `movns`  | Sign is clear     |
+//! | `movo`   | Overflow is set   |
+//! | `movno`  | Overflow is clear |
+
+use alloc::borrow::Cow;
+use core::convert::Infallible;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
+use number;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const COMPARE_CODE          : u8 = 0;
+pub const MOVE_ZERO_CODE        : u8 = 1;
+pub const MOVE_NOT_ZERO_CODE    : u8 = 2;
+pub const MOVE_CARRY_CODE       : u8 = 3;
+pub const MOVE_NOT_CARRY_CODE   : u8 = 4;
+pub const MOVE_SIGN_CODE        : u8 = 5;
+pub const MOVE_NOT_SIGN_CODE    : u8 = 6;
+pub const MOVE_OVERFLOW_CODE    : u8 = 7;
+pub const MOVE_NOT_OVERFLOW_CODE: u8 = 8;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Condition {
+    /// Set the flags from subtracting the dynamic operand from the static register.
+    #[default]
+    Compare,
+    MoveZero,
+    MoveNotZero,
+    MoveCarry,
+    MoveNotCarry,
+    MoveSign,
+    MoveNotSign,
+    MoveOverflow,
+    MoveNotOverflow
+}
+
+/// Results of the last comparison.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let mut memory = Memory::from(assemble("cmp.b r1, 2\nhalt").unwrap());
+/// let mut core = Core::default();
+/// core.context.registers[1] = 1;
+/// core.run(&mut memory, &mut Default::default(), None);
+///
+/// // 1 - 2 borrows and is negative, but fits in a signed byte.
+/// let flags = core.context.flags;
+/// assert!(!flags.zero && flags.carry && flags.sign && !flags.overflow);
+/// assert_eq!(flags.to_string(), "-cs-");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Flags {
+    /// The values were equal.
+    pub zero: bool,
+    /// The subtraction borrowed, meaning the static register was below the dynamic operand when both are unsigned.
+    pub carry: bool,
+    /// The highest bit of the difference was set.
+    pub sign: bool,
+    /// The difference of the values as signed integers does not fit in the width.
+    pub overflow: bool
+}
+
+impl Flags {
+    /// Flags of subtracting `right` from `left` at a width.
+    pub fn compare(size: &number::Size, left: u64, right: u64) -> Self {
+        let left = number::Data::from_size_selecting(size, left);
+        let right = number::Data::from_size_selecting(size, right);
+        let difference = number::Data::from_size_selecting(size, left.quad().wrapping_sub(right.quad()));
+        let signed = left.signed() as i128 - right.signed() as i128;
+
+        Self {
+            zero: left == right,
+            carry: left.quad() < right.quad(),
+            sign: difference.signed() < 0,
+            overflow: signed != difference.signed() as i128
+        }
+    }
+}
+
+impl Display for Flags {
+    /// The letter of every set flag in the order zero, carry, sign and overflow, with clear flags as `-`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (set, letter) in [(self.zero, 'z'), (self.carry, 'c'), (self.sign, 's'), (self.overflow, 'o')] {
+            write!(f, "{}", if set { letter } else { '-' })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Condition {
+    /// Whether the move happens with the flags, or [None] for `cmp`.
+    pub fn holds(&self, flags: &Flags) -> Option<bool> {
+        Some(match self {
+            Self::Compare         => return None,
+            Self::MoveZero        => flags.zero,
+            Self::MoveNotZero     => !flags.zero,
+            Self::MoveCarry       => flags.carry,
+            Self::MoveNotCarry    => !flags.carry,
+            Self::MoveSign        => flags.sign,
+            Self::MoveNotSign     => !flags.sign,
+            Self::MoveOverflow    => flags.overflow,
+            Self::MoveNotOverflow => !flags.overflow
+        })
+    }
+}
+
+impl<'a> Operation<'a> for Condition {
+    type CustomError = Infallible;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // r3 = max(r1, r2) as unsigned quads, without a branch.
+    /// let mut memory = Memory::from(assemble("add.q r3, r1, 0\ncmp.q r1, r2\nmovc.q r3, r2\nhalt").unwrap());
+    ///
+    /// for (a, b) in [(3, 9), (9, 3)] {
+    ///     let mut core = Core::default();
+    ///     core.context.registers[1] = a;
+    ///     core.context.registers[2] = b;
+    ///     core.run(&mut memory, &mut Default::default(), None);
+    ///     assert_eq!(core.context.registers[3], 9);
+    /// }
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        let r#static = *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+
+        match self.holds(&context.flags) {
+            None => {
+                let dynamic = all_operands.x_dynamic.read(&data.width, memory, context)?;
+                context.flags = Flags::compare(&data.width, r#static, dynamic.quad());
+                return Ok(())
+            },
+            Some(false) => return Ok(()),
+            Some(true) => {}
+        }
+
+        match data.destination {
+            Destination::Static => context.registers[all_operands.x_static as usize] = all_operands.x_dynamic.read(&data.width, memory, context)?.quad(),
+            Destination::Dynamic => all_operands.x_dynamic.write(&data.width, memory, context, number::Data::from_size_selecting(&data.width, r#static))?,
+            Destination::Target(target) => {
+                let value = all_operands.x_dynamic.read(&data.width, memory, context)?.quad();
+                *context.registers.get_mut(target as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = value;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(OperandsPresence::AllPresent)
+    }
+}
+
+impl Coded<u8> for Condition {
+    fn code(&self) -> u8 {
+        match self {
+            Self::Compare         => COMPARE_CODE,
+            Self::MoveZero        => MOVE_ZERO_CODE,
+            Self::MoveNotZero     => MOVE_NOT_ZERO_CODE,
+            Self::MoveCarry       => MOVE_CARRY_CODE,
+            Self::MoveNotCarry    => MOVE_NOT_CARRY_CODE,
+            Self::MoveSign        => MOVE_SIGN_CODE,
+            Self::MoveNotSign     => MOVE_NOT_SIGN_CODE,
+            Self::MoveOverflow    => MOVE_OVERFLOW_CODE,
+            Self::MoveNotOverflow => MOVE_NOT_OVERFLOW_CODE
+        }
+    }
+}
+
+impl Condition {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            COMPARE_CODE           => Self::Compare,
+            MOVE_ZERO_CODE         => Self::MoveZero,
+            MOVE_NOT_ZERO_CODE     => Self::MoveNotZero,
+            MOVE_CARRY_CODE        => Self::MoveCarry,
+            MOVE_NOT_CARRY_CODE    => Self::MoveNotCarry,
+            MOVE_SIGN_CODE         => Self::MoveSign,
+            MOVE_NOT_SIGN_CODE     => Self::MoveNotSign,
+            MOVE_OVERFLOW_CODE     => Self::MoveOverflow,
+            MOVE_NOT_OVERFLOW_CODE => Self::MoveNotOverflow,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Condition {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Compare         => "cmp",
+            Self::MoveZero        => "movz",
+            Self::MoveNotZero     => "movnz",
+            Self::MoveCarry       => "movc",
+            Self::MoveNotCarry    => "movnc",
+            Self::MoveSign        => "movs",
+            Self::MoveNotSign     => "movns",
+            Self::MoveOverflow    => "movo",
+            Self::MoveNotOverflow => "movno"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Condition {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "cmp"   => Self::Compare,
+            "movz"  => Self::MoveZero,
+            "movnz" => Self::MoveNotZero,
+            "movc"  => Self::MoveCarry,
+            "movnc" => Self::MoveNotCarry,
+            "movs"  => Self::MoveSign,
+            "movns" => Self::MoveNotSign,
+            "movo"  => Self::MoveOverflow,
+            "movno" => Self::MoveNotOverflow,
+            _ => return None
+        })
+    }
+}
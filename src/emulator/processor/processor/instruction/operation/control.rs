@@ -0,0 +1,247 @@
+//! Operations which access the model specific registers of a core.
+//!
+//! Model specific registers hold control state which has no place among the general registers. `rdmsr` reads the
+//! register selected by the dynamic operand into the static or target register, and `wrmsr` writes the static register
+//! to the register selected by the dynamic operand. Both are privileged, so they fault while the core is in
+//! [user mode](Context::user_mode).
+//!
+//! Some registers give access to state the core already has, and the rest are stored in [Context::model_specific]
+//! without affecting the core. The owner of the core can read them from there to drive features it implements, such as
+//! a timer or a page table walker. Registers holding a mode only keep bit 0.
+//!
+//! | Register | Name                          | Value                                                             |
+//! | -------- | ----------------------------- | ----------------------------------------------------------------- |
+//! | 0        | [USER_MODE_REGISTER]          | [Context::user_mode].                                             |
+//! | 1        | [VIRTUAL_MODE_REGISTER]       | [Context::virtual_mode].                                          |
+//! | 2        | [INTERRUPTS_ENABLED_REGISTER] | Whether pending interrupts are delivered.                         |
+//! | 3        | [INTERRUPTS_PENDING_REGISTER] | One bit for each interrupt that was raised but not delivered yet. |
+//! | 4        | [INTERRUPT_TABLE_REGISTER]    | Address of the interrupt handler table.                           |
+//! | 5        | [RETURN_ADDRESS_REGISTER]     | Address `resume` continues from.                                  |
+//! | 6        | [TIMER_COMPARE_REGISTER]      | Cycle count at which a timer should expire.                       |
+//! | 7        | [PAGE_ROOT_REGISTER]          | Address of the root of a page table.                              |
+//! | 8 - 11   | [DEBUG_ADDRESS_REGISTER]      | Addresses of the [breakpoints](super::super::super::debug).       |
+//! | 12       | [DEBUG_CONTROL_REGISTER]      | What each breakpoint matches.                                     |
+//! | 13       | [DEBUG_STATUS_REGISTER]       | One bit for each breakpoint that matched.                         |
+//! | 14       | [TLB_FLUSH_PAGE_REGISTER]     | Writing a virtual address flushes the TLB entry of its page.      |
+//! | 15       | [TLB_FLUSH_ALL_REGISTER]      | Writing anything flushes every TLB entry.                         |
+//! | 16       | [INTERRUPT_MASK_REGISTER]     | One bit for each interrupt that is not delivered while it is set. |
+//! | 17 - 31  |                               | Free for the owner of the core.                                   |
+//!
+//! Registers past [MODEL_SPECIFIC_REGISTERS] do not exist, and accessing them faults.
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::{Address, MemoryAccess};
+use emulator::memory::tlb::Flush;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const READ_REGISTER_CODE : u8 = 0;
+pub const WRITE_REGISTER_CODE: u8 = 1;
+
+/// Number of model specific registers.
+pub const MODEL_SPECIFIC_REGISTERS: usize = 32;
+
+pub const USER_MODE_REGISTER         : u64 = 0;
+pub const VIRTUAL_MODE_REGISTER      : u64 = 1;
+pub const INTERRUPTS_ENABLED_REGISTER: u64 = 2;
+pub const INTERRUPTS_PENDING_REGISTER: u64 = 3;
+pub const INTERRUPT_TABLE_REGISTER   : u64 = 4;
+pub const RETURN_ADDRESS_REGISTER    : u64 = 5;
+pub const TIMER_COMPARE_REGISTER     : u64 = 6;
+pub const PAGE_ROOT_REGISTER         : u64 = 7;
+/// First of the breakpoint address registers.
+pub const DEBUG_ADDRESS_REGISTER     : u64 = 8;
+pub const DEBUG_CONTROL_REGISTER     : u64 = 12;
+pub const DEBUG_STATUS_REGISTER      : u64 = 13;
+pub const TLB_FLUSH_PAGE_REGISTER    : u64 = 14;
+pub const TLB_FLUSH_ALL_REGISTER     : u64 = 15;
+pub const INTERRUPT_MASK_REGISTER    : u64 = 16;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Control {
+    /// Read a model specific register.
+    #[default]
+    ReadRegister,
+    /// Write a model specific register.
+    WriteRegister
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The operation is privileged but the core was in user mode.
+    Privileged,
+    /// There is no model specific register with the index.
+    Register(u64)
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Privileged => f.write_str("privileged operation executed in user mode"),
+            Self::Register(index) => write!(f, "no model specific register with index {}", index)
+        }
+    }
+}
+
+impl Error for ExecuteError {}
+
+/// Value of a model specific register, or [None] if it does not exist.
+/// ```
+/// use atln_processor::emulator::processor::processor::Context;
+/// use atln_processor::emulator::processor::processor::instruction::operation::control::{read_register, VIRTUAL_MODE_REGISTER};
+///
+/// let context = Context { virtual_mode: true, ..Default::default() };
+/// assert_eq!(read_register(&context, VIRTUAL_MODE_REGISTER), Some(1));
+/// assert_eq!(read_register(&context, 31), Some(0));
+/// assert_eq!(read_register(&context, 32), None);
+/// ```
+pub fn read_register(context: &Context, index: u64) -> Option<u64> {
+    Some(match index {
+        USER_MODE_REGISTER => context.user_mode as u64,
+        VIRTUAL_MODE_REGISTER => context.virtual_mode as u64,
+        INTERRUPTS_ENABLED_REGISTER => context.interrupts.enabled as u64,
+        INTERRUPTS_PENDING_REGISTER => context.interrupts.pending,
+        INTERRUPT_TABLE_REGISTER => context.interrupts.table,
+        RETURN_ADDRESS_REGISTER => context.interrupts.return_address,
+        INTERRUPT_MASK_REGISTER => context.interrupts.mask,
+        TLB_FLUSH_PAGE_REGISTER | TLB_FLUSH_ALL_REGISTER => 0,
+        _ => *context.model_specific.get(usize::try_from(index).ok()?)?
+    })
+}
+
+/// Write a model specific register. Returns [None] without changing anything if the register does not exist.
+/// ```
+/// use atln_processor::emulator::processor::processor::Context;
+/// use atln_processor::emulator::processor::processor::instruction::operation::control::{write_register, INTERRUPT_TABLE_REGISTER, TIMER_COMPARE_REGISTER};
+///
+/// let mut context = Context::default();
+/// write_register(&mut context, INTERRUPT_TABLE_REGISTER, 0x100).unwrap();
+/// write_register(&mut context, TIMER_COMPARE_REGISTER, 5000).unwrap();
+///
+/// assert_eq!(context.interrupts.table, 0x100);
+/// assert_eq!(context.model_specific[TIMER_COMPARE_REGISTER as usize], 5000);
+/// ```
+pub fn write_register(context: &mut Context, index: u64, value: u64) -> Option<()> {
+    match index {
+        USER_MODE_REGISTER => context.user_mode = value & 1 == 1,
+        VIRTUAL_MODE_REGISTER => context.virtual_mode = value & 1 == 1,
+        INTERRUPTS_ENABLED_REGISTER => context.interrupts.enabled = value & 1 == 1,
+        INTERRUPTS_PENDING_REGISTER => context.interrupts.pending = value,
+        INTERRUPT_TABLE_REGISTER => context.interrupts.table = value,
+        RETURN_ADDRESS_REGISTER => context.interrupts.return_address = value,
+        INTERRUPT_MASK_REGISTER => context.interrupts.mask = value,
+        TLB_FLUSH_PAGE_REGISTER => context.flush = Some(match context.flush {
+            Some(Flush::Page(address)) if address.extract_page() != value.extract_page() => Flush::All,
+            Some(Flush::All) => Flush::All,
+            _ => Flush::Page(value)
+        }),
+        TLB_FLUSH_ALL_REGISTER => context.flush = Some(Flush::All),
+        _ => *context.model_specific.get_mut(usize::try_from(index).ok()?)? = value
+    }
+
+    Some(())
+}
+
+impl<'a> Operation<'a> for Control {
+    type CustomError = ExecuteError;
+
+    /// The destination of `wrmsr` is ignored, since it always writes a model specific register.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::{Core, Exception, Status};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::{ExtensionError, OperationExecuteError};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::control::ExecuteError;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut memory = Memory::from(assemble("wrmsr.q r1, 9\nrdmsr.q r2, 9\nwrmsr.q r3, 0\nrdmsr.q r4, 9").unwrap());
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 42;
+    /// core.context.registers[3] = 1;
+    ///
+    /// let status = core.run(&mut memory, &mut Default::default(), None);
+    /// assert_eq!(core.context.registers[2], 42);
+    /// assert!(core.context.user_mode);
+    /// assert!(matches!(status, Status::Faulted(Exception::Execute(OperationExecuteError::Custom(ExtensionError::Control(ExecuteError::Privileged))))));
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        if context.user_mode { return Err(OperationExecuteError::Custom(ExecuteError::Privileged)) }
+
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        let index = all_operands.x_dynamic.read(&data.width, memory, context)?.quad();
+
+        match self {
+            Self::ReadRegister => {
+                let register = match data.destination {
+                    Destination::Static | Destination::Dynamic => all_operands.x_static,
+                    Destination::Target(target) => target
+                };
+
+                let value = read_register(context, index).ok_or(OperationExecuteError::Custom(ExecuteError::Register(index)))?;
+                *context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = value;
+            },
+            Self::WriteRegister => {
+                let value = *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+                write_register(context, index, value).ok_or(OperationExecuteError::Custom(ExecuteError::Register(index)))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(OperandsPresence::AllPresent)
+    }
+}
+
+impl Coded<u8> for Control {
+    fn code(&self) -> u8 {
+        match self {
+            Self::ReadRegister  => READ_REGISTER_CODE,
+            Self::WriteRegister => WRITE_REGISTER_CODE
+        }
+    }
+}
+
+impl Control {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            READ_REGISTER_CODE  => Self::ReadRegister,
+            WRITE_REGISTER_CODE => Self::WriteRegister,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Control {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::ReadRegister  => "rdmsr",
+            Self::WriteRegister => "wrmsr"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Control {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "rdmsr" => Self::ReadRegister,
+            "wrmsr" => Self::WriteRegister,
+            _ => return None
+        })
+    }
+}
@@ -0,0 +1,168 @@
+//! Operations which change the width or byte order of a value.
+//!
+//! `sext` and `zext` read the dynamic operand at the width of the instruction and extend it to the whole static or
+//! target register, filling the upper bits with its sign bit or with zeros. `trunc` clears every bit of the static
+//! register above the width of the instruction. `bswap` reverses the order of the bytes of the dynamic operand at the
+//! width of the instruction and loads them into the static or target register. With the dynamic destination, `bswap`
+//! instead stores the static register with its bytes reversed, which converts between little and big endian data.
+
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
+use number;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const SIGN_EXTEND_CODE: u8 = 0;
+pub const ZERO_EXTEND_CODE: u8 = 1;
+pub const TRUNCATE_CODE   : u8 = 2;
+pub const SWAP_BYTES_CODE : u8 = 3;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Conversion {
+    #[default]
+    SignExtend,
+    ZeroExtend,
+    Truncate,
+    SwapBytes
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The operation can only load into a register, but the dynamic destination was set.
+    Destination
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Destination => "operation can't write to the dynamic operand"
+        })
+    }
+}
+
+impl Error for ExecuteError {}
+
+impl<'a> Operation<'a> for Conversion {
+    type CustomError = ExecuteError;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut program = assemble("sext.b r2, r1\nzext.b r3, r1\ntrunc.w r4\nbswap.d [24], r5\nhalt").unwrap();
+    /// program.resize(28, 0);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 0xFE;
+    /// core.context.registers[4] = 0x12345;
+    /// core.context.registers[5] = 0x11223344;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// assert_eq!(core.context.registers[2..5], [-2i64 as u64, 0xFE, 0x2345]);
+    /// assert_eq!(memory.bytes[24..28], [0x11, 0x22, 0x33, 0x44]);
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+
+        if let Self::Truncate = self {
+            let register = data.operands.x_static().ok_or(OperationExecuteError::Operand(OperandsPresence::Static))?;
+            let register = context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+
+            *register = number::Data::from_size_selecting(&data.width, *register).quad();
+            return Ok(())
+        }
+
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        let r#static = *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+
+        let register = match data.destination {
+            Destination::Static => all_operands.x_static,
+            Destination::Target(target) => target,
+            Destination::Dynamic => {
+                if !matches!(self, Self::SwapBytes) { return Err(OperationExecuteError::Custom(ExecuteError::Destination)) }
+
+                let value = number::Data::from_size_selecting(&data.width, r#static).swap_bytes();
+                all_operands.x_dynamic.write(&data.width, memory, context, value)?;
+                return Ok(())
+            }
+        };
+
+        let value = all_operands.x_dynamic.read(&data.width, memory, context)?;
+        let value = match self {
+            Self::SignExtend => value.signed() as u64,
+            Self::SwapBytes => value.swap_bytes().quad(),
+            _ => value.quad()
+        };
+
+        *context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = value;
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(match self {
+            Self::Truncate => OperandsPresence::Static,
+            _ => OperandsPresence::AllPresent
+        })
+    }
+}
+
+impl Coded<u8> for Conversion {
+    fn code(&self) -> u8 {
+        match self {
+            Self::SignExtend => SIGN_EXTEND_CODE,
+            Self::ZeroExtend => ZERO_EXTEND_CODE,
+            Self::Truncate   => TRUNCATE_CODE,
+            Self::SwapBytes  => SWAP_BYTES_CODE
+        }
+    }
+}
+
+impl Conversion {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            SIGN_EXTEND_CODE => Self::SignExtend,
+            ZERO_EXTEND_CODE => Self::ZeroExtend,
+            TRUNCATE_CODE    => Self::Truncate,
+            SWAP_BYTES_CODE  => Self::SwapBytes,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Conversion {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::SignExtend => "sext",
+            Self::ZeroExtend => "zext",
+            Self::Truncate   => "trunc",
+            Self::SwapBytes  => "bswap"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Conversion {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "sext"  => Self::SignExtend,
+            "zext"  => Self::ZeroExtend,
+            "trunc" => Self::Truncate,
+            "bswap" => Self::SwapBytes,
+            _ => return None
+        })
+    }
+}
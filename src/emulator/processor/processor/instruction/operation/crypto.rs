@@ -0,0 +1,415 @@
+//! Operations which accelerate AES and SHA-256.
+//!
+//! # AES
+//! The AES operations perform a single round on a 128 bit state held in the static register and the register after it,
+//! with the low register holding the first 8 bytes of the state. The round key is the dynamic operand, either as the
+//! register pair starting at its register or as 16 bytes in memory at its address. The result is written to the static
+//! register pair, or the pair starting at the target register.
+//!
+//! | Mnemonic     | Round                                                      |
+//! | ------------ | ---------------------------------------------------------- |
+//! | `aesenc`     | Shift rows, substitute bytes, mix columns, add round key.  |
+//! | `aesenclast` | Shift rows, substitute bytes, add round key.               |
+//! | `aesdec`     | Inverse of each step of `aesenc` in the same order.        |
+//! | `aesdeclast` | Inverse of each step of `aesenclast` in the same order.    |
+//!
+//! Decryption uses the equivalent inverse cipher, so its round keys must have the inverse column mixing applied. Key
+//! expansion is left to guest software.
+//!
+//! # SHA-256
+//! `sha256` compresses one 64 byte block into a SHA-256 state. The static register holds the address of the state, which
+//! is 8 duals in memory, and the block is in memory at the address of the dynamic operand. Padding is left to guest
+//! software.
+//!
+//! Every access to memory is a quad, so the state, the block and round keys in memory must be aligned to 8 bytes.
+
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::{Frame, MemoryAccess};
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::{Destination, Dynamic, DynamicReadError};
+use number;
+use number::Size;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const AES_ENCRYPT_CODE     : u8 = 0;
+pub const AES_ENCRYPT_LAST_CODE: u8 = 1;
+pub const AES_DECRYPT_CODE     : u8 = 2;
+pub const AES_DECRYPT_LAST_CODE: u8 = 3;
+pub const SHA256_CODE          : u8 = 4;
+
+/// Bytes in an AES state or round key.
+pub const AES_BLOCK_BYTES: usize = 16;
+/// Bytes in a SHA-256 message block.
+pub const SHA256_BLOCK_BYTES: usize = 64;
+/// Bytes in a SHA-256 state.
+pub const SHA256_STATE_BYTES: usize = 32;
+
+/// AES substitution box.
+pub static SUBSTITUTION: [u8; 256] = [
+    0x63, 0x7C, 0x77, 0x7B, 0xF2, 0x6B, 0x6F, 0xC5, 0x30, 0x01, 0x67, 0x2B, 0xFE, 0xD7, 0xAB, 0x76,
+    0xCA, 0x82, 0xC9, 0x7D, 0xFA, 0x59, 0x47, 0xF0, 0xAD, 0xD4, 0xA2, 0xAF, 0x9C, 0xA4, 0x72, 0xC0,
+    0xB7, 0xFD, 0x93, 0x26, 0x36, 0x3F, 0xF7, 0xCC, 0x34, 0xA5, 0xE5, 0xF1, 0x71, 0xD8, 0x31, 0x15,
+    0x04, 0xC7, 0x23, 0xC3, 0x18, 0x96, 0x05, 0x9A, 0x07, 0x12, 0x80, 0xE2, 0xEB, 0x27, 0xB2, 0x75,
+    0x09, 0x83, 0x2C, 0x1A, 0x1B, 0x6E, 0x5A, 0xA0, 0x52, 0x3B, 0xD6, 0xB3, 0x29, 0xE3, 0x2F, 0x84,
+    0x53, 0xD1, 0x00, 0xED, 0x20, 0xFC, 0xB1, 0x5B, 0x6A, 0xCB, 0xBE, 0x39, 0x4A, 0x4C, 0x58, 0xCF,
+    0xD0, 0xEF, 0xAA, 0xFB, 0x43, 0x4D, 0x33, 0x85, 0x45, 0xF9, 0x02, 0x7F, 0x50, 0x3C, 0x9F, 0xA8,
+    0x51, 0xA3, 0x40, 0x8F, 0x92, 0x9D, 0x38, 0xF5, 0xBC, 0xB6, 0xDA, 0x21, 0x10, 0xFF, 0xF3, 0xD2,
+    0xCD, 0x0C, 0x13, 0xEC, 0x5F, 0x97, 0x44, 0x17, 0xC4, 0xA7, 0x7E, 0x3D, 0x64, 0x5D, 0x19, 0x73,
+    0x60, 0x81, 0x4F, 0xDC, 0x22, 0x2A, 0x90, 0x88, 0x46, 0xEE, 0xB8, 0x14, 0xDE, 0x5E, 0x0B, 0xDB,
+    0xE0, 0x32, 0x3A, 0x0A, 0x49, 0x06, 0x24, 0x5C, 0xC2, 0xD3, 0xAC, 0x62, 0x91, 0x95, 0xE4, 0x79,
+    0xE7, 0xC8, 0x37, 0x6D, 0x8D, 0xD5, 0x4E, 0xA9, 0x6C, 0x56, 0xF4, 0xEA, 0x65, 0x7A, 0xAE, 0x08,
+    0xBA, 0x78, 0x25, 0x2E, 0x1C, 0xA6, 0xB4, 0xC6, 0xE8, 0xDD, 0x74, 0x1F, 0x4B, 0xBD, 0x8B, 0x8A,
+    0x70, 0x3E, 0xB5, 0x66, 0x48, 0x03, 0xF6, 0x0E, 0x61, 0x35, 0x57, 0xB9, 0x86, 0xC1, 0x1D, 0x9E,
+    0xE1, 0xF8, 0x98, 0x11, 0x69, 0xD9, 0x8E, 0x94, 0x9B, 0x1E, 0x87, 0xE9, 0xCE, 0x55, 0x28, 0xDF,
+    0x8C, 0xA1, 0x89, 0x0D, 0xBF, 0xE6, 0x42, 0x68, 0x41, 0x99, 0x2D, 0x0F, 0xB0, 0x54, 0xBB, 0x16
+];
+
+/// SHA-256 round constants.
+const SHA256_ROUNDS: [u32; 64] = [
+    0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5, 0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
+    0xD807AA98, 0x12835B01, 0x243185BE, 0x550C7DC3, 0x72BE5D74, 0x80DEB1FE, 0x9BDC06A7, 0xC19BF174,
+    0xE49B69C1, 0xEFBE4786, 0x0FC19DC6, 0x240CA1CC, 0x2DE92C6F, 0x4A7484AA, 0x5CB0A9DC, 0x76F988DA,
+    0x983E5152, 0xA831C66D, 0xB00327C8, 0xBF597FC7, 0xC6E00BF3, 0xD5A79147, 0x06CA6351, 0x14292967,
+    0x27B70A85, 0x2E1B2138, 0x4D2C6DFC, 0x53380D13, 0x650A7354, 0x766A0ABB, 0x81C2C92E, 0x92722C85,
+    0xA2BFE8A1, 0xA81A664B, 0xC24B8B70, 0xC76C51A3, 0xD192E819, 0xD6990624, 0xF40E3585, 0x106AA070,
+    0x19A4C116, 0x1E376C08, 0x2748774C, 0x34B0BCB5, 0x391C0CB3, 0x4ED8AA4A, 0x5B9CCA4F, 0x682E6FF3,
+    0x748F82EE, 0x78A5636F, 0x84C87814, 0x8CC70208, 0x90BEFFFA, 0xA4506CEB, 0xBEF9A3F7, 0xC67178F2
+];
+// endregion
+
+static INVERSE_SUBSTITUTION: [u8; 256] = invert(&SUBSTITUTION);
+
+const fn invert(substitution: &[u8; 256]) -> [u8; 256] {
+    let mut inverse = [0; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        inverse[substitution[byte] as usize] = byte as u8;
+        byte += 1;
+    }
+
+    inverse
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Crypto {
+    #[default]
+    AesEncrypt,
+    AesEncryptLast,
+    AesDecrypt,
+    AesDecryptLast,
+    Sha256
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The result can only be written to registers, but the dynamic destination was set.
+    Destination,
+    /// The dynamic operand is neither a register nor in memory.
+    Operand
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Destination => "result can't be written to the dynamic operand",
+            Self::Operand => "dynamic operand is neither a register nor in memory"
+        })
+    }
+}
+
+impl Error for ExecuteError {}
+
+/// Multiply by x in the AES field.
+fn double(byte: u8) -> u8 {
+    byte << 1 ^ if byte & 0x80 != 0 { 0x1B } else { 0 }
+}
+
+/// Multiply two elements of the AES field.
+fn multiply(mut left: u8, mut right: u8) -> u8 {
+    let mut product = 0;
+
+    while right != 0 {
+        if right & 1 != 0 { product ^= left }
+        left = double(left);
+        right >>= 1;
+    }
+
+    product
+}
+
+/// Rotate each row of the state left by its index, or right when inverse.
+fn shift_rows(state: &[u8; AES_BLOCK_BYTES], inverse: bool) -> [u8; AES_BLOCK_BYTES] {
+    let mut shifted = [0; AES_BLOCK_BYTES];
+
+    for column in 0..4 {
+        for row in 0..4 {
+            let source = if inverse { (column + 4 - row) % 4 } else { (column + row) % 4 };
+            shifted[column * 4 + row] = state[source * 4 + row];
+        }
+    }
+
+    shifted
+}
+
+fn mix_columns(state: &mut [u8; AES_BLOCK_BYTES], inverse: bool) {
+    let factors: [u8; 4] = if inverse { [14, 11, 13, 9] } else { [2, 3, 1, 1] };
+
+    for column in state.chunks_exact_mut(4) {
+        let original = [column[0], column[1], column[2], column[3]];
+        for (row, byte) in column.iter_mut().enumerate() {
+            *byte = (0..4).fold(0, |mixed, index| mixed ^ multiply(original[(row + index) % 4], factors[index]));
+        }
+    }
+}
+
+impl Crypto {
+    /// Perform an AES round on a state. [None] is returned for `sha256`.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::operation::crypto::Crypto;
+    ///
+    /// // The first round of the AES-128 example in FIPS 197, appendix B.
+    /// let state = [0x19, 0x3D, 0xE3, 0xBE, 0xA0, 0xF4, 0xE2, 0x2B, 0x9A, 0xC6, 0x8D, 0x2A, 0xE9, 0xF8, 0x48, 0x08];
+    /// let key = [0xA0, 0xFA, 0xFE, 0x17, 0x88, 0x54, 0x2C, 0xB1, 0x23, 0xA3, 0x39, 0x39, 0x2A, 0x6C, 0x76, 0x05];
+    /// let next = [0xA4, 0x9C, 0x7F, 0xF2, 0x68, 0x9F, 0x35, 0x2B, 0x6B, 0x5B, 0xEA, 0x43, 0x02, 0x6A, 0x50, 0x49];
+    ///
+    /// assert_eq!(Crypto::AesEncrypt.aes_round(&state, &key), Some(next));
+    /// ```
+    pub fn aes_round(&self, state: &[u8; AES_BLOCK_BYTES], key: &[u8; AES_BLOCK_BYTES]) -> Option<[u8; AES_BLOCK_BYTES]> {
+        let (inverse, last) = match self {
+            Self::AesEncrypt => (false, false),
+            Self::AesEncryptLast => (false, true),
+            Self::AesDecrypt => (true, false),
+            Self::AesDecryptLast => (true, true),
+            Self::Sha256 => return None
+        };
+
+        let substitution = if inverse { &INVERSE_SUBSTITUTION } else { &SUBSTITUTION };
+        let mut state = shift_rows(state, inverse);
+        for byte in state.iter_mut() { *byte = substitution[*byte as usize] }
+        if !last { mix_columns(&mut state, inverse) }
+        for (byte, key) in state.iter_mut().zip(key) { *byte ^= key }
+        Some(state)
+    }
+}
+
+/// Compress a block into a SHA-256 state.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::operation::crypto::sha256_compress;
+///
+/// // The padded block of the message "abc".
+/// let mut block = [0u8; 64];
+/// block[..4].copy_from_slice(&[b'a', b'b', b'c', 0x80]);
+/// block[63] = 24;
+///
+/// let mut state = [0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19];
+/// sha256_compress(&mut state, &block);
+/// assert_eq!(state, [0xBA7816BF, 0x8F01CFEA, 0x414140DE, 0x5DAE2223, 0xB00361A3, 0x96177A9C, 0xB410FF61, 0xF20015AD]);
+/// ```
+pub fn sha256_compress(state: &mut [u32; 8], block: &[u8; SHA256_BLOCK_BYTES]) {
+    let mut schedule = [0u32; 64];
+    for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) { *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) }
+
+    for index in 16..64 {
+        let early = schedule[index - 15];
+        let late = schedule[index - 2];
+        let sigma0 = early.rotate_right(7) ^ early.rotate_right(18) ^ early >> 3;
+        let sigma1 = late.rotate_right(17) ^ late.rotate_right(19) ^ late >> 10;
+        schedule[index] = schedule[index - 16].wrapping_add(sigma0).wrapping_add(schedule[index - 7]).wrapping_add(sigma1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (constant, word) in SHA256_ROUNDS.iter().zip(schedule) {
+        let sum1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = e & f ^ !e & g;
+        let first = h.wrapping_add(sum1).wrapping_add(choice).wrapping_add(*constant).wrapping_add(word);
+        let sum0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = a & b ^ a & c ^ b & c;
+        let second = sum0.wrapping_add(majority);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(first);
+        d = c;
+        c = b;
+        b = a;
+        a = first.wrapping_add(second);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) { *word = word.wrapping_add(value) }
+}
+
+/// Read consecutive quads from memory into bytes.
+fn load(memory: &dyn MemoryAccess, context: &Context, address: u64, bytes: &mut [u8]) -> Result<(), DynamicReadError> {
+    for (index, chunk) in bytes.chunks_exact_mut(8).enumerate() {
+        let frame = Frame { address: address.wrapping_add(index as u64 * 8), size: Size::Quad };
+        chunk.copy_from_slice(&memory.get(frame, context.virtual_mode).map_err(DynamicReadError::Memory)?.quad().to_le_bytes());
+    }
+
+    Ok(())
+}
+
+/// Write bytes to memory as consecutive quads.
+fn store(memory: &mut dyn MemoryAccess, context: &Context, address: u64, bytes: &[u8]) -> Result<(), DynamicReadError> {
+    for (index, chunk) in bytes.chunks_exact(8).enumerate() {
+        let frame = Frame { address: address.wrapping_add(index as u64 * 8), size: Size::Quad };
+        let value = u64::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7]]);
+        memory.set(frame, context.virtual_mode, number::Data::Quad(value)).map_err(DynamicReadError::Memory)?;
+    }
+
+    Ok(())
+}
+
+/// Bytes of the register pair starting at a register.
+fn pair(context: &Context, register: u8) -> Option<[u8; AES_BLOCK_BYTES]> {
+    let low = *context.registers.get(register as usize)?;
+    let high = *context.registers.get(register as usize + 1)?;
+
+    let mut bytes = [0; AES_BLOCK_BYTES];
+    bytes[..8].copy_from_slice(&low.to_le_bytes());
+    bytes[8..].copy_from_slice(&high.to_le_bytes());
+    Some(bytes)
+}
+
+impl<'a> Operation<'a> for Crypto {
+    type CustomError = ExecuteError;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Hash "abc" with the state at 64 and the padded block at 128.
+    /// let mut program = assemble("sha256 r1, [128]\nhalt").unwrap();
+    /// program.resize(64, 0);
+    /// for word in [0x6A09E667u32, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19] {
+    ///     program.extend(word.to_le_bytes());
+    /// }
+    /// program.resize(128, 0);
+    /// program.extend([b'a', b'b', b'c', 0x80]);
+    /// program.resize(191, 0);
+    /// program.push(24);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.context.registers[1] = 64;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// assert_eq!(memory.bytes[64..68], 0xBA7816BFu32.to_le_bytes());
+    /// assert_eq!(memory.bytes[92..96], 0xF20015ADu32.to_le_bytes());
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+
+        let register = match data.destination {
+            Destination::Static => all_operands.x_static,
+            Destination::Target(target) => target,
+            Destination::Dynamic => return Err(OperationExecuteError::Custom(ExecuteError::Destination))
+        };
+
+        let address = all_operands.x_dynamic.address(&data.width, context)?;
+
+        if let Self::Sha256 = self {
+            let state_address = *context.registers.get(all_operands.x_static as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+            let block_address = address.ok_or(OperationExecuteError::Custom(ExecuteError::Operand))?;
+
+            let mut block = [0; SHA256_BLOCK_BYTES];
+            load(memory, context, block_address, &mut block)?;
+            let mut bytes = [0; SHA256_STATE_BYTES];
+            load(memory, context, state_address, &mut bytes)?;
+
+            let mut state = [0u32; 8];
+            for (word, bytes) in state.iter_mut().zip(bytes.chunks_exact(4)) { *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) }
+            sha256_compress(&mut state, &block);
+            for (bytes, word) in bytes.chunks_exact_mut(4).zip(state) { bytes.copy_from_slice(&word.to_le_bytes()) }
+
+            store(memory, context, state_address, &bytes)?;
+            return Ok(())
+        }
+
+        let state = pair(context, all_operands.x_static).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+        let key = match (&all_operands.x_dynamic, address) {
+            (Dynamic::Register(key), _) => pair(context, *key).ok_or(OperationExecuteError::DynamicRead(DynamicReadError::InvalidRegisterIndex))?,
+            (_, Some(address)) => {
+                let mut key = [0; AES_BLOCK_BYTES];
+                load(memory, context, address, &mut key)?;
+                key
+            },
+            _ => return Err(OperationExecuteError::Custom(ExecuteError::Operand))
+        };
+
+        let result = self.aes_round(&state, &key).expect("Operation should be an AES round");
+        if register as usize + 1 >= context.registers.len() { return Err(OperationExecuteError::InvalidStaticRegister) }
+
+        context.registers[register as usize] = u64::from_le_bytes([result[0], result[1], result[2], result[3], result[4], result[5], result[6], result[7]]);
+        context.registers[register as usize + 1] = u64::from_le_bytes([result[8], result[9], result[10], result[11], result[12], result[13], result[14], result[15]]);
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(OperandsPresence::AllPresent)
+    }
+}
+
+impl Coded<u8> for Crypto {
+    fn code(&self) -> u8 {
+        match self {
+            Self::AesEncrypt     => AES_ENCRYPT_CODE,
+            Self::AesEncryptLast => AES_ENCRYPT_LAST_CODE,
+            Self::AesDecrypt     => AES_DECRYPT_CODE,
+            Self::AesDecryptLast => AES_DECRYPT_LAST_CODE,
+            Self::Sha256         => SHA256_CODE
+        }
+    }
+}
+
+impl Crypto {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            AES_ENCRYPT_CODE      => Self::AesEncrypt,
+            AES_ENCRYPT_LAST_CODE => Self::AesEncryptLast,
+            AES_DECRYPT_CODE      => Self::AesDecrypt,
+            AES_DECRYPT_LAST_CODE => Self::AesDecryptLast,
+            SHA256_CODE           => Self::Sha256,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Crypto {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::AesEncrypt     => "aesenc",
+            Self::AesEncryptLast => "aesenclast",
+            Self::AesDecrypt     => "aesdec",
+            Self::AesDecryptLast => "aesdeclast",
+            Self::Sha256         => "sha256"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Crypto {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "aesenc"     => Self::AesEncrypt,
+            "aesenclast" => Self::AesEncryptLast,
+            "aesdec"     => Self::AesDecrypt,
+            "aesdeclast" => Self::AesDecryptLast,
+            "sha256"     => Self::Sha256,
+            _ => return None
+        })
+    }
+}
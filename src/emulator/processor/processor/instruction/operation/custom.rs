@@ -0,0 +1,266 @@
+//! Extensions defined outside of this crate.
+//!
+//! A [CustomExtension] supplies the mnemonics, operand presence and behaviour of its operations. Once registered, its
+//! instructions are decoded, encoded, assembled and executed like those of the built in extensions, held in
+//! [Extension::Custom](super::Extension::Custom). Operations receive the same state as built in ones, which is the
+//! context of the core executing them, its memory and its ports.
+//!
+//! Extension codes below [FIRST_CUSTOM_CODE] are reserved for the architecture. Custom extensions use the codes above,
+//! which are encoded with the [extension prefix](super::super::prefix), except for those with all of their low 6 bits
+//! set. Only one extension can be registered for each code.
+//!
+//! The registry is global to the process and only available with the `std` feature. Without it, custom extensions can
+//! still be executed by constructing [Custom] directly, but they are never decoded or assembled.
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::Data;
+use emulator::processor::processor::instruction::operand::OperandsPresence;
+use emulator::processor::processor::instruction::prefix::PREFIX_EXTENSION;
+use super::{ExtensionCode, ExtensionFromCodeInvalid, OperationCode, OperationExecuteError};
+
+/// Lowest extension code a custom extension can use.
+pub const FIRST_CUSTOM_CODE: ExtensionCode = 64;
+
+/// Failure of a custom operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecuteError {
+    /// Reason given by the operation.
+    pub reason: Cow<'static, str>
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl Error for ExecuteError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// The extension code is reserved for the architecture or can't be encoded.
+    Reserved,
+    /// Another extension is already registered with the code.
+    Registered
+}
+
+impl Display for RegisterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Reserved => "extension code is reserved",
+            Self::Registered => "extension code is already registered"
+        })
+    }
+}
+
+impl Error for RegisterError {}
+
+/// A group of operations defined outside of this crate.
+/// ```
+/// use std::borrow::Cow;
+/// use std::sync::Arc;
+/// use atln_processor::emulator::memory::{Memory, MemoryAccess};
+/// use atln_processor::emulator::processor::processor::{Context, Core, Ports};
+/// use atln_processor::emulator::processor::processor::instruction::{Data, Instruction};
+/// use atln_processor::emulator::processor::processor::instruction::operand::OperandsPresence;
+/// use atln_processor::emulator::processor::processor::instruction::operation::{ExtensionCode, OperationCode, OperationExecuteError};
+/// use atln_processor::emulator::processor::processor::instruction::operation::custom::{register, CustomExtension, ExecuteError};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// /// Doubles the static register.
+/// struct Double;
+///
+/// impl CustomExtension for Double {
+///     fn code(&self) -> ExtensionCode { 64 }
+///
+///     fn mnemonic(&self, operation: OperationCode) -> Option<Cow<'static, str>> {
+///         (operation == 0).then_some("dbl".into())
+///     }
+///
+///     fn operation(&self, mnemonic: &str) -> Option<OperationCode> {
+///         (mnemonic == "dbl").then_some(0)
+///     }
+///
+///     fn presence(&self, _: OperationCode) -> Option<OperandsPresence> {
+///         Some(OperandsPresence::Static)
+///     }
+///
+///     fn execute(&self, _: OperationCode, data: Option<&Data>, _: &mut dyn MemoryAccess, context: &mut Context, _: &mut Ports) -> Result<(), OperationExecuteError<ExecuteError>> {
+///         let data = data.ok_or(OperationExecuteError::Data(true))?;
+///         let register = data.operands.x_static().ok_or(OperationExecuteError::Operand(OperandsPresence::Static))?;
+///         let register = context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)?;
+///
+///         *register = register.checked_mul(2).ok_or(OperationExecuteError::Custom(ExecuteError { reason: "overflow".into() }))?;
+///         Ok(())
+///     }
+/// }
+///
+/// register(Arc::new(Double)).unwrap();
+///
+/// let program = assemble("dbl.q r1\nhalt").unwrap();
+/// let (instruction, _) = Instruction::decode_slice(&program).unwrap();
+/// assert_eq!(instruction.to_string(), "dbl.q r1");
+///
+/// let mut core = Core::default();
+/// core.context.registers[1] = 21;
+/// core.run(&mut Memory::from(program), &mut Default::default(), None);
+/// assert_eq!(core.context.registers[1], 42);
+/// ```
+pub trait CustomExtension: Send + Sync {
+    /// Extension code, which must be at least [FIRST_CUSTOM_CODE].
+    fn code(&self) -> ExtensionCode;
+
+    /// Assembly mnemonic of an operation. [None] indicates that the operation code does not exist in the extension.
+    fn mnemonic(&self, operation: OperationCode) -> Option<Cow<'static, str>>;
+
+    /// Operation code of a mnemonic, if the extension has it.
+    fn operation(&self, mnemonic: &str) -> Option<OperationCode>;
+
+    /// Which operands an operation expects. See [Operation::presence](super::Operation::presence).
+    fn presence(&self, operation: OperationCode) -> Option<OperandsPresence>;
+
+    /// Whether an operation can change which instruction is executed next. See
+    /// [Instruction::diverts](super::super::Instruction::diverts).
+    fn diverts(&self, _operation: OperationCode) -> bool {
+        false
+    }
+
+    /// Execute an operation. See [Operation::execute](super::Operation::execute).
+    fn execute(&self, operation: OperationCode, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, ports: &mut Ports) -> Result<(), OperationExecuteError<ExecuteError>>;
+}
+
+/// An operation of a custom extension.
+#[derive(Clone)]
+pub struct Custom {
+    extension: Arc<dyn CustomExtension>,
+    operation: OperationCode
+}
+
+impl Custom {
+    /// Select an operation of an extension. The operation must have a mnemonic.
+    pub fn new(extension: Arc<dyn CustomExtension>, operation: OperationCode) -> Result<Self, ExtensionFromCodeInvalid> {
+        if extension.mnemonic(operation).is_none() { return Err(ExtensionFromCodeInvalid::Operation) }
+        Ok(Self { extension, operation })
+    }
+
+    pub fn extension(&self) -> &Arc<dyn CustomExtension> {
+        &self.extension
+    }
+
+    pub fn operation(&self) -> OperationCode {
+        self.operation
+    }
+
+    pub fn mnemonic(&self) -> Cow<'static, str> {
+        self.extension.mnemonic(self.operation).unwrap_or_default()
+    }
+}
+
+impl Debug for Custom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Custom")
+            .field("extension", &self.extension.code())
+            .field("operation", &self.operation)
+            .field("mnemonic", &self.mnemonic())
+            .finish()
+    }
+}
+
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        self.extension.code() == other.extension.code() && self.operation == other.operation
+    }
+}
+
+impl Eq for Custom {}
+
+impl Hash for Custom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.extension.code().hash(state);
+        self.operation.hash(state);
+    }
+}
+
+/// Whether custom extensions can use an extension code.
+pub fn is_custom_code(code: ExtensionCode) -> bool {
+    code >= FIRST_CUSTOM_CODE && code & PREFIX_EXTENSION != PREFIX_EXTENSION
+}
+
+#[cfg(feature = "std")]
+static REGISTRY: RwLock<Vec<Arc<dyn CustomExtension>>> = RwLock::new(Vec::new());
+
+/// Register an extension so that its instructions are decoded and assembled.
+/// ```
+/// use std::sync::Arc;
+/// use atln_processor::emulator::processor::processor::instruction::operation::custom::{register, unregister, RegisterError};
+/// # use std::borrow::Cow;
+/// # use atln_processor::emulator::memory::MemoryAccess;
+/// # use atln_processor::emulator::processor::processor::{Context, Ports};
+/// # use atln_processor::emulator::processor::processor::instruction::Data;
+/// # use atln_processor::emulator::processor::processor::instruction::operand::OperandsPresence;
+/// # use atln_processor::emulator::processor::processor::instruction::operation::{ExtensionCode, OperationCode, OperationExecuteError};
+/// # use atln_processor::emulator::processor::processor::instruction::operation::custom::{CustomExtension, ExecuteError};
+/// #
+/// # struct Nothing(ExtensionCode);
+/// #
+/// # impl CustomExtension for Nothing {
+/// #     fn code(&self) -> ExtensionCode { self.0 }
+/// #     fn mnemonic(&self, _: OperationCode) -> Option<Cow<'static, str>> { None }
+/// #     fn operation(&self, _: &str) -> Option<OperationCode> { None }
+/// #     fn presence(&self, _: OperationCode) -> Option<OperandsPresence> { None }
+/// #     fn execute(&self, _: OperationCode, _: Option<&Data>, _: &mut dyn MemoryAccess, _: &mut Context, _: &mut Ports) -> Result<(), OperationExecuteError<ExecuteError>> { Ok(()) }
+/// # }
+///
+/// assert_eq!(register(Arc::new(Nothing(2))), Err(RegisterError::Reserved));
+/// assert_eq!(register(Arc::new(Nothing(127))), Err(RegisterError::Reserved));
+///
+/// register(Arc::new(Nothing(65))).unwrap();
+/// assert_eq!(register(Arc::new(Nothing(65))), Err(RegisterError::Registered));
+///
+/// assert!(unregister(65).is_some());
+/// register(Arc::new(Nothing(65))).unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub fn register(extension: Arc<dyn CustomExtension>) -> Result<(), RegisterError> {
+    if !is_custom_code(extension.code()) { return Err(RegisterError::Reserved) }
+
+    let mut registry = REGISTRY.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if registry.iter().any(|registered| registered.code() == extension.code()) { return Err(RegisterError::Registered) }
+
+    registry.push(extension);
+    Ok(())
+}
+
+/// Remove the extension registered with a code. Instructions already holding its operations keep working.
+#[cfg(feature = "std")]
+pub fn unregister(code: ExtensionCode) -> Option<Arc<dyn CustomExtension>> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let index = registry.iter().position(|registered| registered.code() == code)?;
+    Some(registry.remove(index))
+}
+
+/// The extension registered with a code.
+#[cfg(feature = "std")]
+pub fn registered(code: ExtensionCode) -> Option<Arc<dyn CustomExtension>> {
+    let registry = REGISTRY.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.iter().find(|registered| registered.code() == code).cloned()
+}
+
+/// Find the operation with a mnemonic in any registered extension.
+#[cfg(feature = "std")]
+pub(super) fn from_mnemonic(mnemonic: &str) -> Option<Custom> {
+    let registry = REGISTRY.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.iter()
+        .find_map(|extension| Some(Custom { operation: extension.operation(mnemonic)?, extension: extension.clone() }))
+}
@@ -0,0 +1,188 @@
+//! Operations which move data between registers and memory.
+//!
+//! # Load linked and store conditional
+//! `ll` loads from memory into the static register and places a reservation on the physical address that was read. A
+//! later `sc` to the same address stores the static register only if the reservation still holds, then replaces the
+//! static register with 0 if the store happened or 1 if it did not. Every `sc` clears the reservation.
+//!
+//! A reservation is broken by any write to the memory line holding it, which is detected through
+//! [MemoryAccess::write_count], and by the core taking an interrupt. `sc` is always executed as a synchronised
+//! operation, so it is atomic with respect to every instruction with the synchronise bit set.
+
+use alloc::borrow::Cow;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::memory::{GetError, MemoryAccess};
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction;
+use emulator::processor::processor::instruction::operand::{Dynamic, DynamicReadError, OperandsPresence};
+use number;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const LOAD_LINKED_CODE      : u8 = 0;
+pub const STORE_CONDITIONAL_CODE: u8 = 1;
+pub const FENCE_CODE            : u8 = 2;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Data {
+    /// Load the dynamic operand into the static register and reserve its address.
+    #[default]
+    LoadLinked,
+    /// Store the static register to the dynamic operand if its address is still reserved.
+    StoreConditional,
+    /// Make every earlier memory access visible to other cores before any later one. See the
+    /// [memory ordering model](crate::emulator::processor::processor::ordering).
+    Fence
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The dynamic operand does not point into memory, so it can't be reserved.
+    Address
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Address => "dynamic operand is not in memory"
+        })
+    }
+}
+
+impl Error for ExecuteError {}
+
+/// An address reserved by `ll`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reservation {
+    /// Physical address that was loaded.
+    pub address: u64,
+    /// Write count of the line holding the address before it was loaded.
+    pub writes: u64
+}
+
+/// Physical address of the dynamic operand.
+fn physical(dynamic: &Dynamic, size: &number::Size, memory: &dyn MemoryAccess, context: &Context) -> Result<u64, OperationExecuteError<ExecuteError>> {
+    let address = dynamic.address(size, context)?.ok_or(OperationExecuteError::Custom(ExecuteError::Address))?;
+    if !context.virtual_mode { return Ok(address) }
+
+    memory.translate_virtual(address).ok_or(OperationExecuteError::DynamicRead(DynamicReadError::Memory(GetError::PageFault)))
+}
+
+impl<'a> Operation<'a> for Data {
+    type CustomError = ExecuteError;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut program = assemble("ll.q r1, [32]\nadd.b r1, 1\nsc.q r1, [32]\nsc.q r2, [32]\nhalt").unwrap();
+    /// program.resize(40, 0);
+    /// program[32] = 5;
+    ///
+    /// let mut memory = Memory::from(program);
+    ///
+    /// let mut core = Core::default();
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// // The first store succeeds and the second fails because the reservation was used up.
+    /// assert_eq!(memory.bytes[32], 6);
+    /// assert_eq!(core.context.registers[1..3], [0, 1]);
+    /// ```
+    fn execute(&self, data: Option<&instruction::Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        if let Self::Fence = self {
+            if data.is_some() { return Err(OperationExecuteError::Data(false)) }
+
+            memory.fence();
+            return Ok(())
+        }
+
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        if all_operands.x_static as usize >= context.registers.len() { return Err(OperationExecuteError::InvalidStaticRegister) }
+
+        let address = physical(&all_operands.x_dynamic, &data.width, memory, context)?;
+
+        if let Self::LoadLinked = self {
+            let writes = memory.write_count(address);
+            let value = all_operands.x_dynamic.read(&data.width, memory, context)?;
+
+            context.registers[all_operands.x_static as usize] = value.quad();
+            context.reservation = Some(Reservation { address, writes });
+            return Ok(())
+        }
+
+        let reserved = context.reservation.take().is_some_and(|reservation| reservation.address == address && reservation.writes == memory.write_count(address));
+
+        if reserved {
+            let value = number::Data::from_size_selecting(&data.width, context.registers[all_operands.x_static as usize]);
+            all_operands.x_dynamic.write(&data.width, memory, context, value)?;
+        }
+
+        context.registers[all_operands.x_static as usize] = !reserved as u64;
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        match self {
+            Self::LoadLinked | Self::StoreConditional => Some(OperandsPresence::AllPresent),
+            Self::Fence => None
+        }
+    }
+}
+
+impl Coded<u8> for Data {
+    fn code(&self) -> u8 {
+        match self {
+            Self::LoadLinked       => LOAD_LINKED_CODE,
+            Self::StoreConditional => STORE_CONDITIONAL_CODE,
+            Self::Fence            => FENCE_CODE
+        }
+    }
+}
+
+impl Data {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            LOAD_LINKED_CODE       => Self::LoadLinked,
+            STORE_CONDITIONAL_CODE => Self::StoreConditional,
+            FENCE_CODE             => Self::Fence,
+            _ => return None
+        })
+    }
+
+    /// Whether the operation is always executed as a synchronised operation, regardless of the synchronise bit.
+    pub fn synchronises(&self) -> bool {
+        matches!(self, Self::StoreConditional)
+    }
+}
+
+impl<'a> Representable<'a> for Data {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::LoadLinked       => "ll",
+            Self::StoreConditional => "sc",
+            Self::Fence            => "fence"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Data {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "ll" => Self::LoadLinked,
+            "sc" => Self::StoreConditional,
+            "fence" => Self::Fence,
+            _ => return None
+        })
+    }
+}
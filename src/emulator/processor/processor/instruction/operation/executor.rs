@@ -1,43 +1,102 @@
-use crate::ExecutionContext;
-use crate::instruction::Data;
-use crate::instruction::operation::{Coded, Operation, OperationExecuteError};
+use alloc::borrow::Cow;
+use core::convert::Infallible;
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // region: Constants
-pub const HALT_CODE: u8 = 0;
-pub const DIVERT_CODE: u8 = 1;
+pub const HALT_CODE     : u8 = 0;
+pub const DIVERT_CODE   : u8 = 1;
+pub const SIGNAL_CODE   : u8 = 2;
+pub const RESUME_CODE   : u8 = 3;
+pub const HOST_CALL_CODE: u8 = 4;
+pub const TRAP_CODE     : u8 = 5;
 // endregion
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Operations which control the flow of execution.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Executor {
+    /// Stop the processor.
     #[default]
     Halt,
-    Divert
+    /// Continue execution from the address read from the dynamic operand.
+    Divert,
+    /// Send an inter-processor interrupt to the core whose index is read from the dynamic operand.
+    Signal,
+    /// Return from an interrupt handler to the interrupted instruction with its flags and mode and enable interrupts
+    /// again. Returning from a nested handler makes the handler it preempted the running one.
+    Resume,
+    /// Request a service from the host through [semihosting](crate::emulator::processor::processor::semihosting).
+    HostCall,
+    /// Enter the interrupt handler of the vector read from the dynamic operand, returning to the next instruction. This
+    /// is how guest software makes system calls. See
+    /// [interrupt](crate::emulator::processor::processor::interrupt#software-interrupts).
+    Trap
 }
 
 impl<'a> Operation<'a> for Executor {
-    fn expects_static(&mut self) -> bool {
-        false
-    }
+    type CustomError = Infallible;
 
-    fn expects_dynamic(&mut self) -> bool { 
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
         match self {
-            Self::Divert => true,
-            _ => false
-        }
-    }
+            Self::Halt => if data.is_some() { return Err(OperationExecuteError::Data(false)) },
+            Self::Divert => {
+                let data = data.ok_or(OperationExecuteError::Data(true))?;
+                let x_dynamic = data.operands.x_dynamic().ok_or(OperationExecuteError::Operand(OperandsPresence::Dynamic))?;
+                let target = x_dynamic.read(&data.width, memory, context).map_err(OperationExecuteError::DynamicRead)?;
+
+                context.program_counter = target.quad();
+            },
+            Self::Signal => {
+                let data = data.ok_or(OperationExecuteError::Data(true))?;
+                let x_dynamic = data.operands.x_dynamic().ok_or(OperationExecuteError::Operand(OperandsPresence::Dynamic))?;
+                let target = x_dynamic.read(&data.width, memory, context).map_err(OperationExecuteError::DynamicRead)?;
+
+                context.interrupts.signals.push(target.quad());
+            },
+            // The core enters the handler once the operation succeeded, since it reports the interrupt.
+            Self::Trap => {
+                let data = data.ok_or(OperationExecuteError::Data(true))?;
+                let x_dynamic = data.operands.x_dynamic().ok_or(OperationExecuteError::Operand(OperandsPresence::Dynamic))?;
+                let vector = x_dynamic.read(&data.width, memory, context).map_err(OperationExecuteError::DynamicRead)?;
+
+                context.interrupts.trap = Some(vector.quad() as u8);
+            },
+            // The core makes the call once the operation succeeded, since it needs the handler of the core.
+            Self::HostCall => if data.is_some() { return Err(OperationExecuteError::Data(false)) },
+            Self::Resume => {
+                if data.is_some() { return Err(OperationExecuteError::Data(false)) }
+
+                (context.program_counter, context.flags, context.user_mode) = context.interrupts.leave();
+            }
+        };
 
-    fn execute(&mut self, _code: u8, _data: Option<&Data>, context: &mut ExecutionContext) -> Result<(),
-        OperationExecuteError> {
-        context.accumulator = 100;
         Ok(())
     }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        match self {
+            Self::Halt | Self::Resume | Self::HostCall => None,
+            Self::Divert | Self::Signal | Self::Trap => Some(OperandsPresence::Dynamic)
+        }
+    }
 }
 
 impl Coded<u8> for Executor {
-    fn code(&mut self) -> u8 {
+    fn code(&self) -> u8 {
         match self {
-            Self::Halt => HALT_CODE,
-            Self::Divert => DIVERT_CODE
+            Self::Halt     => HALT_CODE,
+            Self::Divert   => DIVERT_CODE,
+            Self::Signal   => SIGNAL_CODE,
+            Self::Resume   => RESUME_CODE,
+            Self::HostCall => HOST_CALL_CODE,
+            Self::Trap     => TRAP_CODE
         }
     }
 }
@@ -45,9 +104,41 @@ impl Coded<u8> for Executor {
 impl Executor {
     pub fn from_code(code: u8) -> Option<Self> {
         Some(match code {
-            HALT_CODE => Self::Halt,
-            DIVERT_CODE => Self::Divert,
+            HALT_CODE      => Self::Halt,
+            DIVERT_CODE    => Self::Divert,
+            SIGNAL_CODE    => Self::Signal,
+            RESUME_CODE    => Self::Resume,
+            HOST_CALL_CODE => Self::HostCall,
+            TRAP_CODE      => Self::Trap,
             _ => return None
         })
     }
-}
\ No newline at end of file
+}
+
+impl<'a> Representable<'a> for Executor {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Halt     => "halt",
+            Self::Divert   => "divert",
+            Self::Signal   => "signal",
+            Self::Resume   => "resume",
+            Self::HostCall => "hcall",
+            Self::Trap     => "trap"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Executor {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "halt"   => Self::Halt,
+            "divert" => Self::Divert,
+            "signal" => Self::Signal,
+            "resume" => Self::Resume,
+            "hcall"  => Self::HostCall,
+            "trap"   => Self::Trap,
+            _ => return None
+        })
+    }
+}
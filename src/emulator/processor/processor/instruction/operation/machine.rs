@@ -0,0 +1,212 @@
+//! Operations which inspect the machine executing them.
+//!
+//! # Identification
+//! `cpuid` reads the leaf selected by the dynamic operand into the static or target register, even with the dynamic
+//! destination set. This lets guest software find out which optional extensions it can use. Leaves that do not exist
+//! read as 0.
+//!
+//! | Leaf  | Value                                                                                              |
+//! | ----- | -------------------------------------------------------------------------------------------------- |
+//! | 0     | Version of the emulator, with the major, minor and patch versions at bits 32, 16 and 0.            |
+//! | 1     | Index of the core within its [System](crate::emulator::system::System).                            |
+//! | 2 - 5 | Implemented extensions. Bit `n` of leaf `2 + k` is set if extension code `64 * k + n` exists.      |
+//!
+//! The extension leaves include [custom extensions](super::custom) registered when `cpuid` executes.
+//!
+//! # Performance counters
+//! `rdctr` reads the [Counters] entry selected by the dynamic operand into the static or target register, in the same
+//! way as `cpuid`. Counters only include instructions that completed before the one reading them. Counters that do not
+//! exist read as 0.
+//!
+//! | Counter | Value                                              |
+//! | ------- | -------------------------------------------------- |
+//! | 0       | Instructions retired.                              |
+//! | 1       | Cycles spent, according to the timing of the core. |
+
+use alloc::borrow::Cow;
+use core::convert::Infallible;
+use emulator::memory::MemoryAccess;
+use emulator::processor::processor::{Context, Ports};
+use emulator::processor::processor::instruction::operand::Destination;
+use crate::emulator::processor::processor::instruction::Data;
+use crate::emulator::processor::processor::instruction::operand::OperandsPresence;
+use crate::emulator::processor::processor::instruction::operation::{Coded, Extension, ExtensionFromCodeInvalid, Operation, OperationExecuteError};
+use crate::utility::{FromRepresentation, Representable};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+pub const IDENTIFY_CODE    : u8 = 0;
+pub const READ_COUNTER_CODE: u8 = 1;
+
+pub const VERSION_LEAF   : u64 = 0;
+pub const CORE_LEAF      : u64 = 1;
+/// First of the leaves holding the implemented extensions.
+pub const EXTENSIONS_LEAF: u64 = 2;
+/// Number of leaves holding the implemented extensions, which covers every extension code.
+pub const EXTENSIONS_LEAVES: u64 = 4;
+
+pub const INSTRUCTIONS_COUNTER: u64 = 0;
+pub const CYCLES_COUNTER      : u64 = 1;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Machine {
+    /// Read an identification leaf.
+    #[default]
+    Identify,
+    /// Read a performance counter.
+    ReadCounter
+}
+
+/// Performance counters which guest software can read.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::instruction::operation::machine::CYCLES_COUNTER;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let mut memory = Memory::from(assemble("add.b r1, 1\nadd.b r1, 1\nrdctr.b r2, 0\nhalt").unwrap());
+/// let mut core = Core::default();
+/// core.run(&mut memory, &mut Default::default(), None);
+///
+/// // Both additions completed before the counter was read.
+/// assert_eq!(core.context.registers[2], 2);
+/// assert_eq!(core.context.counters.instructions, 4);
+/// assert_eq!(core.context.counters.read(CYCLES_COUNTER), core.cycles);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Counters {
+    /// Number of instructions that completed without faulting.
+    pub instructions: u64,
+    /// Number of cycles spent by completed instructions.
+    pub cycles: u64
+}
+
+impl Counters {
+    /// Value of a counter, or 0 if it does not exist.
+    pub fn read(&self, counter: u64) -> u64 {
+        match counter {
+            INSTRUCTIONS_COUNTER => self.instructions,
+            CYCLES_COUNTER => self.cycles,
+            _ => 0
+        }
+    }
+}
+
+/// Version of the emulator as reported by leaf [VERSION_LEAF].
+pub fn version() -> u64 {
+    let part = |version: &str| version.parse::<u64>().unwrap_or(0) & 0xFFFF;
+    part(env!("CARGO_PKG_VERSION_MAJOR")) << 32 | part(env!("CARGO_PKG_VERSION_MINOR")) << 16 | part(env!("CARGO_PKG_VERSION_PATCH"))
+}
+
+/// Value of an identification leaf for a core.
+/// ```
+/// use atln_processor::emulator::processor::processor::Context;
+/// use atln_processor::emulator::processor::processor::instruction::operation::machine::{identify, CORE_LEAF, EXTENSIONS_LEAF};
+///
+/// let context = Context { identifier: 3, ..Default::default() };
+/// assert_eq!(identify(CORE_LEAF, &context), 3);
+///
+/// // The arithmetic, data and executor extensions always exist.
+/// assert_eq!(identify(EXTENSIONS_LEAF, &context) & 0b111, 0b111);
+/// assert_eq!(identify(u64::MAX, &context), 0);
+/// ```
+pub fn identify(leaf: u64, context: &Context) -> u64 {
+    match leaf {
+        VERSION_LEAF => version(),
+        CORE_LEAF => context.identifier,
+        _ if (EXTENSIONS_LEAF..EXTENSIONS_LEAF + EXTENSIONS_LEAVES).contains(&leaf) => {
+            let first = (leaf - EXTENSIONS_LEAF) * 64;
+
+            (0..64).filter(|bit| {
+                // Every extension has an operation, but not necessarily one with code 0.
+                let code = (first + bit) as u8;
+                !matches!(Extension::from_codes(code, 0), Err(ExtensionFromCodeInvalid::Extension))
+            }).fold(0, |bitmap, bit| bitmap | 1 << bit)
+        },
+        _ => 0
+    }
+}
+
+impl<'a> Operation<'a> for Machine {
+    type CustomError = Infallible;
+
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::machine::identify;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut memory = Memory::from(assemble("cpuid.b r1, 1\ncpuid.b r2, 2\nhalt").unwrap());
+    /// let mut core = Core::default();
+    /// core.context.identifier = 5;
+    /// core.run(&mut memory, &mut Default::default(), None);
+    ///
+    /// assert_eq!(core.context.registers[1], 5);
+    /// assert_eq!(core.context.registers[2], identify(2, &core.context));
+    /// ```
+    fn execute(&self, data: Option<&Data>, memory: &mut dyn MemoryAccess, context: &mut Context, _ports: &mut Ports) -> Result<(), OperationExecuteError<Self::CustomError>> {
+        let data = data.ok_or(OperationExecuteError::Data(true))?;
+        let all_operands = data.operands.all().ok_or(OperationExecuteError::Operand(OperandsPresence::AllPresent))?;
+        let selector = all_operands.x_dynamic.read(&data.width, memory, context)?.quad();
+
+        let register = match data.destination {
+            Destination::Static | Destination::Dynamic => all_operands.x_static,
+            Destination::Target(target) => target
+        };
+
+        let value = match self {
+            Self::Identify => identify(selector, context),
+            Self::ReadCounter => context.counters.read(selector)
+        };
+
+        *context.registers.get_mut(register as usize).ok_or(OperationExecuteError::InvalidStaticRegister)? = value;
+        Ok(())
+    }
+
+    fn presence(&self) -> Option<OperandsPresence> {
+        Some(OperandsPresence::AllPresent)
+    }
+}
+
+impl Coded<u8> for Machine {
+    fn code(&self) -> u8 {
+        match self {
+            Self::Identify    => IDENTIFY_CODE,
+            Self::ReadCounter => READ_COUNTER_CODE
+        }
+    }
+}
+
+impl Machine {
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            IDENTIFY_CODE     => Self::Identify,
+            READ_COUNTER_CODE => Self::ReadCounter,
+            _ => return None
+        })
+    }
+}
+
+impl<'a> Representable<'a> for Machine {
+    /// Assembly mnemonic of the operation.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Identify    => "cpuid",
+            Self::ReadCounter => "rdctr"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Machine {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "cpuid" => Self::Identify,
+            "rdctr" => Self::ReadCounter,
+            _ => return None
+        })
+    }
+}
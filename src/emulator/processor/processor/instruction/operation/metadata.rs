@@ -0,0 +1,114 @@
+//! Description of every operation the decoder accepts, for driving assemblers, disassemblers, fuzzers and documentation
+//! from the same tables the emulator uses.
+//!
+//! [operations] is built by decoding every pair of extension and operation codes with [Extension::from_codes], so it
+//! always agrees with the decoder and includes the operations of registered [custom extensions](super::custom).
+//!
+//! Immediates follow the same rules for every operation that takes a dynamic operand.
+//!
+//! | Rule                | Description                                                                          |
+//! |---------------------|--------------------------------------------------------------------------------------|
+//! | Widths              | 1, 2, 4 or 8 bytes, stored little endian after the registers byte.                   |
+//! | Constant addressing | The immediate is the value, zero extended or truncated to the operating width.       |
+//! | Signed constant     | The immediate is sign extended to the operating width.                               |
+//! | Offset addressing   | The immediate is added to the register to form the address.                          |
+//! | Destination         | A constant can't be the destination.                                                 |
+//! | Synchronous         | The dynamic operand must address memory, so constants and registers are not allowed. |
+
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use super::{Extension, ExtensionCode, ExtensionFromCodeInvalid, OperationCode};
+use super::{ARITHMETIC_CODE, CHECKSUM_CODE, CONDITION_CODE, CONTROL_CODE, CONVERSION_CODE, CRYPTO_CODE, DATA_CODE, EXECUTOR_CODE, MACHINE_CODE};
+use super::super::operand::OperandsPresence;
+use utility::Coded;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Everything known about an operation without executing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Metadata {
+    pub extension: ExtensionCode,
+    /// Name of a built in extension, or [None] for a custom extension.
+    pub extension_name: Option<Cow<'static, str>>,
+    pub operation: OperationCode,
+    pub mnemonic: Cow<'static, str>,
+    /// Operands the operation expects, or [None] if it takes none.
+    pub presence: Option<OperandsPresence>,
+    /// Whether the dynamic operand can hold an immediate, which is the case whenever it is expected.
+    pub immediate: bool,
+    /// Whether the operation can continue anywhere but the next instruction.
+    pub diverts: bool
+}
+
+impl Metadata {
+    pub fn new(extension: &Extension) -> Self {
+        let presence = extension.presence();
+
+        Self {
+            extension: extension.code(),
+            extension_name: name(extension.code()).map(Cow::Borrowed),
+            operation: extension.operation_code(),
+            mnemonic: extension.to_string().into(),
+            immediate: presence.as_ref().is_some_and(OperandsPresence::expects_dynamic),
+            presence,
+            diverts: extension.diverts()
+        }
+    }
+}
+
+/// Name of a built in extension.
+pub fn name(extension: ExtensionCode) -> Option<&'static str> {
+    Some(match extension {
+        ARITHMETIC_CODE => "arithmetic",
+        DATA_CODE => "data",
+        EXECUTOR_CODE => "executor",
+        CONDITION_CODE => "condition",
+        CONVERSION_CODE => "conversion",
+        CHECKSUM_CODE => "checksum",
+        CRYPTO_CODE => "crypto",
+        MACHINE_CODE => "machine",
+        CONTROL_CODE => "control",
+        _ => return None
+    })
+}
+
+/// Metadata of every operation, ordered by extension code and then operation code.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::operand::OperandsPresence;
+/// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+/// use atln_processor::emulator::processor::processor::instruction::operation::metadata::operations;
+/// use atln_processor::utility::FromRepresentation;
+///
+/// let operations = operations();
+/// let add = operations.iter().find(|operation| operation.mnemonic == "add").unwrap();
+/// assert_eq!((add.extension, add.operation), (0, 0));
+/// assert_eq!(add.extension_name.as_deref(), Some("arithmetic"));
+/// assert_eq!(add.presence, Some(OperandsPresence::AllPresent));
+/// assert!(add.immediate);
+///
+/// let halt = operations.iter().find(|operation| operation.mnemonic == "halt").unwrap();
+/// assert!(halt.presence.is_none() && !halt.immediate && halt.diverts);
+///
+/// // Mnemonics lead back to the same codes.
+/// for operation in &operations {
+///     let extension = Extension::from_representation(operation.mnemonic.clone()).unwrap();
+///     assert_eq!(extension, Extension::from_codes(operation.extension, operation.operation).unwrap());
+/// }
+/// ```
+pub fn operations() -> Vec<Metadata> {
+    let mut operations = Vec::new();
+
+    for extension in 0..=ExtensionCode::MAX {
+        for operation in 0..=OperationCode::MAX {
+            match Extension::from_codes(extension, operation) {
+                Ok(extension) => operations.push(Metadata::new(&extension)),
+                Err(ExtensionFromCodeInvalid::Extension) => break,
+                Err(ExtensionFromCodeInvalid::Operation) => ()
+            }
+        }
+    }
+
+    operations
+}
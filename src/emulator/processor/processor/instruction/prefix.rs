@@ -0,0 +1,251 @@
+//! Optional bytes in front of the driver bytes which extend what an instruction can express.
+//!
+//! A prefix is a byte holding [PREFIX_EXTENSION] in the extension field, where driver 0 would otherwise be. No extension
+//! uses that code. The low 2 bits of the byte select the kind of prefix and a payload byte follows it. Any number of
+//! prefixes may precede the driver bytes, but each kind may only appear once.
+//!
+//! | Kind | Name      | Payload                                                                 |
+//! | ---- | --------- | ----------------------------------------------------------------------- |
+//! | 0    | Target    | Register which receives the result, see [Destination::Target].         |
+//! | 1    | Width     | Width exponent replacing the width field of the registers byte.         |
+//! | 2    | Extension | High 2 bits of the extension code, above the 6 bits held by driver 0.   |
+//!
+//! Kind 3 is reserved and fails to decode.
+//!
+//! # Width
+//! The width field only holds exponents up to 3. The width prefix holds the whole exponent, leaving room for wider
+//! operands, and the width field must then be 0. No width is wider than a quad yet, so the prefix is never needed to
+//! encode an instruction and exponents past 3 fail to decode.
+//!
+//! # Extension
+//! The extension escape raises the number of extension codes from 64 to 256. Codes below 64 are always encoded without
+//! it. Codes with all of their low 6 bits set can't be encoded, because driver 0 would then read as a prefix.
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::io::Read;
+use super::{Data, DecodeError, Driver0Encoding, REGISTERS_STATIC_OPERAND_MASK};
+use super::operand::{Destination, Operands, Static};
+use number::Size;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// region: Constants
+/// Extension code which marks a byte as a prefix.
+pub const PREFIX_EXTENSION: u8 = 0b111111;
+pub const PREFIX_KIND_MASK: u8 = 0b000000_11;
+pub const TARGET_PREFIX   : u8 = 0;
+pub const WIDTH_PREFIX    : u8 = 1;
+pub const EXTENSION_PREFIX: u8 = 2;
+/// Bytes of a single prefix, which is the prefix byte and its payload.
+pub const PREFIX_BYTES    : usize = 2;
+/// Number of prefix kinds that can be used together.
+pub const PREFIX_KINDS    : usize = 3;
+/// Largest number of bytes the prefixes of an instruction can occupy.
+pub const MAX_PREFIXES_BYTES: usize = PREFIX_KINDS * PREFIX_BYTES;
+/// Bits of the extension code held by driver 0.
+pub const DRIVER_EXTENSION_BITS: u8 = 6;
+/// Largest payload of the extension prefix.
+pub const MAX_EXTENSION_PREFIX: u8 = 0b11;
+// endregion
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixError {
+    /// The prefix kind is reserved.
+    Kind,
+    /// A prefix kind appeared more than once.
+    Repeated,
+    /// The target prefix was used on an instruction without both operands or with the dynamic destination.
+    Target,
+    /// The target register code does not fit in the 3 bits of a register operand.
+    Register,
+    /// The width prefix was used on an instruction without operands, with a width field that is not 0 or with an
+    /// exponent no width has.
+    Width,
+    /// The extension prefix holds more than 2 bits.
+    Extension
+}
+
+impl Display for PrefixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Kind => "prefix kind is reserved",
+            Self::Repeated => "prefix kind appeared more than once",
+            Self::Target => "target prefix needs both operands and the static destination",
+            Self::Register => "target register code does not exist",
+            Self::Width => "width prefix does not apply to the instruction",
+            Self::Extension => "extension prefix is larger than 2 bits"
+        })
+    }
+}
+
+impl Error for PrefixError {}
+
+/// Whether a byte in the place of driver 0 is a prefix.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::prefix::is_prefix;
+///
+/// assert!(is_prefix(0b111111_0_0));
+/// assert!(!is_prefix(0b000000_0_0));
+/// ```
+pub fn is_prefix(byte: u8) -> bool {
+    byte.extract_extension() == PREFIX_EXTENSION
+}
+
+/// Every prefix of an instruction.
+/// ```
+/// use atln_processor::emulator::processor::processor::instruction::prefix::Prefixes;
+///
+/// let prefixes = Prefixes { target: Some(3), ..Default::default() };
+/// let encoded = prefixes.encode();
+/// assert_eq!(encoded, [0b111111_00, 3]);
+///
+/// // The driver bytes follow the prefixes.
+/// assert_eq!(Prefixes::decode_slice(&[0b111111_00, 3, 0, 0]).unwrap(), (prefixes, 2));
+/// assert_eq!(Prefixes::decode_slice(&[0, 0]).unwrap(), (Prefixes::default(), 0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Prefixes {
+    pub target: Option<Static>,
+    /// Width exponent.
+    pub width: Option<u8>,
+    /// High bits of the extension code.
+    pub extension: Option<u8>
+}
+
+impl Prefixes {
+    /// Add a decoded prefix.
+    fn add(&mut self, prefix: u8, payload: u8) -> Result<(), PrefixError> {
+        match prefix & PREFIX_KIND_MASK {
+            TARGET_PREFIX => {
+                if payload > REGISTERS_STATIC_OPERAND_MASK >> 3 { return Err(PrefixError::Register) }
+                Self::set(&mut self.target, payload)
+            },
+            WIDTH_PREFIX => Self::set(&mut self.width, payload),
+            EXTENSION_PREFIX => {
+                if payload > MAX_EXTENSION_PREFIX { return Err(PrefixError::Extension) }
+                Self::set(&mut self.extension, payload)
+            },
+            _ => Err(PrefixError::Kind)
+        }
+    }
+
+    /// Set a prefix which must not have appeared yet.
+    fn set(prefix: &mut Option<u8>, payload: u8) -> Result<(), PrefixError> {
+        if prefix.is_some() { return Err(PrefixError::Repeated) }
+        *prefix = Some(payload);
+        Ok(())
+    }
+
+    /// Decode the prefixes at the start of a slice. The number of bytes they occupy is returned along with them, which
+    /// is where the driver bytes start.
+    pub fn decode_slice(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let mut prefixes = Self::default();
+        let mut length = 0;
+
+        while let Some(&prefix) = bytes.get(length).filter(|&&byte| is_prefix(byte)) {
+            let payload = *bytes.get(length + 1).ok_or(DecodeError::Length)?;
+            prefixes.add(prefix, payload).map_err(DecodeError::Prefix)?;
+            length += PREFIX_BYTES;
+        }
+
+        Ok((prefixes, length))
+    }
+
+    /// Decode the prefixes at the start of a stream. A stream can't be rewound, so the first byte after the prefixes,
+    /// which is driver 0, is returned along with them.
+    #[cfg(feature = "std")]
+    pub fn decode(stream: &mut impl Read) -> Result<(Self, u8), DecodeError> {
+        let mut read = || {
+            let mut byte = [0u8; 1];
+            match stream.read(&mut byte) {
+                Ok(1) => Ok(byte[0]),
+                Ok(_) => Err(DecodeError::Length),
+                Err(error) => Err(DecodeError::StreamRead(error))
+            }
+        };
+
+        let mut prefixes = Self::default();
+        loop {
+            let byte = read()?;
+            if !is_prefix(byte) { return Ok((prefixes, byte)) }
+
+            let payload = read()?;
+            prefixes.add(byte, payload).map_err(DecodeError::Prefix)?;
+        }
+    }
+
+    /// Prefixes needed to encode an instruction with an extension code and data.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::prefix::Prefixes;
+    ///
+    /// assert_eq!(Prefixes::new(5, None), Prefixes::default());
+    /// assert_eq!(Prefixes::new(0b10_000101, None).extension, Some(0b10));
+    /// ```
+    pub fn new(extension: u8, data: Option<&Data>) -> Self {
+        Self {
+            target: match data.map(|data| &data.destination) {
+                Some(Destination::Target(target)) => Some(*target),
+                _ => None
+            },
+            width: None,
+            extension: Some(extension >> DRIVER_EXTENSION_BITS).filter(|&high| high != 0)
+        }
+    }
+
+    /// Full extension code from the extension field of driver 0.
+    pub fn extension_code(&self, field: u8) -> u8 {
+        self.extension.unwrap_or(0) << DRIVER_EXTENSION_BITS | field
+    }
+
+    /// Apply the prefixes to the decoded data of an instruction.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::{DecodeError, Instruction};
+    /// use atln_processor::emulator::processor::processor::instruction::prefix::PrefixError;
+    ///
+    /// // add.b r1, r2 with a width prefix making it a dual addition.
+    /// let (add, length) = Instruction::decode_slice(&[0b111111_01, 2, 0b000000_0_0, 0b0000_00_00, 0b00_001_010]).unwrap();
+    /// assert_eq!((add.to_string().as_str(), length), ("add.d r1, r2", 5));
+    ///
+    /// let repeated = [0b111111_01, 2, 0b111111_01, 2, 0b000000_0_0, 0b0000_00_00, 0b00_001_010];
+    /// assert!(matches!(Instruction::decode_slice(&repeated), Err(DecodeError::Prefix(PrefixError::Repeated))));
+    ///
+    /// // No custom extension is registered with code 64.
+    /// assert!(matches!(Instruction::decode_slice(&[0b111111_10, 1, 0, 0, 0]), Err(DecodeError::InvalidCode(_))));
+    /// ```
+    pub fn apply(self, data: Option<&mut Data>) -> Result<(), PrefixError> {
+        if self.target.is_none() && self.width.is_none() { return Ok(()) }
+        let Some(data) = data else { return Err(if self.target.is_some() { PrefixError::Target } else { PrefixError::Width }) };
+
+        if let Some(target) = self.target {
+            if !matches!(data.operands, Operands::AllPresent(_)) || data.destination != Destination::Static { return Err(PrefixError::Target) }
+            data.destination = Destination::Target(target);
+        }
+
+        if let Some(width) = self.width {
+            if data.width != Size::Byte { return Err(PrefixError::Width) }
+            data.width = Size::from_exponent(width).ok_or(PrefixError::Width)?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of bytes the prefixes occupy once encoded.
+    pub fn encoded_len(&self) -> usize {
+        [self.target, self.width, self.extension].iter().flatten().count() * PREFIX_BYTES
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let prefix = 0.set_extension(PREFIX_EXTENSION);
+        let mut encoded = Vec::new();
+
+        if let Some(target) = self.target { encoded.extend([prefix | TARGET_PREFIX, target]); }
+        if let Some(width) = self.width { encoded.extend([prefix | WIDTH_PREFIX, width]); }
+        if let Some(extension) = self.extension { encoded.extend([prefix | EXTENSION_PREFIX, extension]); }
+        encoded
+    }
+}
@@ -0,0 +1,116 @@
+//! Checks of whether an instruction makes sense before it is executed.
+//!
+//! Decoding and [building](super::builder) only guarantee that an instruction can be encoded. An instruction can still
+//! fault every time it executes or silently lose part of an immediate. [Instruction::validate] finds these problems
+//! up front so assemblers and other front ends can report them where the instruction was written.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::processor::processor::Registers;
+use number;
+use number::Size;
+use super::Instruction;
+use super::operand::{Destination, Dynamic};
+
+/// A problem with an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The result is stored in a constant, which faults.
+    ConstantDestination,
+    /// A constant has more significant bits than the operating width, so the upper bits are lost.
+    ImmediateTruncated { width: Size, immediate: number::Data },
+    /// An operand selects a register which does not exist, which faults.
+    Register(u8),
+    /// The instruction is synchronous but its dynamic operand is a register, which is not shared with other
+    /// processors.
+    SynchronousRegister
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConstantDestination => f.write_str("result is stored in a constant"),
+            Self::ImmediateTruncated { width, immediate } => write!(f, "constant {:#x} does not fit in {} bytes", immediate.quad(), width.size()),
+            Self::Register(register) => write!(f, "register r{register} does not exist"),
+            Self::SynchronousRegister => f.write_str("synchronous instruction addresses a register")
+        }
+    }
+}
+
+/// Whether a constant keeps its value once zero or sign extended or truncated to a width.
+fn fits(width: &Size, x_dynamic: &Dynamic) -> bool {
+    let bits = width.size() as u32 * 8;
+    if bits >= u64::BITS { return true }
+
+    match x_dynamic {
+        Dynamic::Constant(immediate) => immediate.quad() >> bits == 0,
+        Dynamic::Signed(immediate) => {
+            let value = immediate.signed();
+            value >> (bits - 1) == 0 || value >> (bits - 1) == -1
+        },
+        _ => true
+    }
+}
+
+impl Instruction {
+    /// Every problem found with the instruction, or an empty list if there are none.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::{Data, Instruction};
+    /// use atln_processor::emulator::processor::processor::instruction::operand::{AllPresent, Destination, Dynamic, Indexed, Operands};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::Extension;
+    /// use atln_processor::emulator::processor::processor::instruction::validation::Diagnostic;
+    /// use atln_processor::number;
+    /// use atln_processor::number::Size;
+    /// use atln_processor::programming::assembler::assemble_line;
+    ///
+    /// assert_eq!(assemble_line("add.w r1, -1").unwrap().unwrap().validate(), []);
+    /// assert_eq!(assemble_line("add.b r1, 300").unwrap().unwrap().validate(), [
+    ///     Diagnostic::ImmediateTruncated { width: Size::Byte, immediate: number::Data::Word(300) }
+    /// ]);
+    ///
+    /// let add = |destination, x_dynamic| Instruction::new(Extension::Arithmetic(Arithmetic::Add), Some(Data {
+    ///     width: Size::Quad,
+    ///     destination,
+    ///     synchronous: true,
+    ///     operands: Operands::AllPresent(AllPresent { x_static: 1, x_dynamic })
+    /// })).unwrap();
+    ///
+    /// assert_eq!(add(Destination::Dynamic, Dynamic::Constant(number::Data::Byte(5))).validate(), [Diagnostic::ConstantDestination]);
+    /// assert_eq!(add(Destination::Static, Dynamic::Register(2)).validate(), [Diagnostic::SynchronousRegister]);
+    /// assert_eq!(add(Destination::Static, Dynamic::Indexed(Indexed { base: 2, index: 12 })).validate(), [Diagnostic::Register(12)]);
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let data = match &self.data {
+            Some(data) => data,
+            None => return diagnostics
+        };
+
+        let registers = Registers::default().len();
+        let x_dynamic = data.operands.x_dynamic();
+        let selected = [
+            data.operands.x_static(),
+            match data.destination { Destination::Target(target) => Some(target), _ => None },
+            x_dynamic.and_then(Dynamic::register),
+            match x_dynamic { Some(Dynamic::Indexed(indexed)) => Some(indexed.index), _ => None }
+        ];
+
+        for &register in selected.iter().flatten() {
+            if register as usize >= registers && !diagnostics.contains(&Diagnostic::Register(register)) { diagnostics.push(Diagnostic::Register(register)); }
+        }
+
+        if let Some(x_dynamic) = x_dynamic {
+            if data.destination == Destination::Dynamic && x_dynamic.is_constant() { diagnostics.push(Diagnostic::ConstantDestination); }
+            if data.synchronous && matches!(x_dynamic, Dynamic::Register(_)) { diagnostics.push(Diagnostic::SynchronousRegister); }
+
+            if !fits(&data.width, x_dynamic) {
+                let immediate = x_dynamic.immediate().expect("Constants always have an immediate");
+                diagnostics.push(Diagnostic::ImmediateTruncated { width: data.width.clone(), immediate });
+            }
+        }
+
+        diagnostics
+    }
+}
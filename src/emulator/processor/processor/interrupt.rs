@@ -0,0 +1,221 @@
+//! Interrupting a core so it executes a handler before continuing.
+//!
+//! Every interrupt has a vector which selects its handler. The handler addresses are quads stored one after the other
+//! in a table in memory, starting at [Interrupts::table]. Interrupts are only delivered while [Interrupts::enabled] is
+//! set, and delivering one clears it so that the handler is not interrupted itself. The `resume` operation returns from
+//! the handler and enables interrupts again. Guest software can't read the flags, so they are saved when an interrupt
+//! is delivered and restored by `resume`. Handlers run privileged, and `resume` returns to user mode if the interrupted
+//! instruction was in it.
+//!
+//! Pending interrupts are delivered by [Core::step](super::Core::step) before fetching an instruction. Executing blocks
+//! directly does not deliver interrupts.
+//!
+//! # Priority and masking
+//! Every vector has a [priority](Interrupts::priorities), and the pending vector with the highest priority is delivered
+//! first, the lowest vector among equal priorities. Vectors whose bit is set in [Interrupts::mask] stay pending without
+//! being delivered until they are unmasked. Guest software writes the mask through a
+//! [model specific register](super::instruction::operation::control).
+//!
+//! # Nesting
+//! A handler may enable interrupts again to let more urgent interrupts preempt it. Only interrupts of a higher priority
+//! than the [running](Interrupts::running) handler are delivered, so a handler is never interrupted by itself or its
+//! peers. Delivering an interrupt while a handler runs saves the return state of that handler as a [Saved] frame, and
+//! once the nested handler resumes, the frame is restored so the outer handler can resume in turn. The return address
+//! register always holds the return address of the innermost handler. A handler which is left other than through
+//! `resume` keeps its priority running, masking interrupts of that priority and below.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The handler of vector 1 at 16 enables interrupts, letting vector 0 at 32 preempt it. The table is at 48.
+//! let mut program = assemble("halt").unwrap();
+//! program.resize(16, 0);
+//! program.extend(assemble("wrmsr.q r7, 2\nadd.b r1, 1\nresume").unwrap());
+//! program.resize(32, 0);
+//! program.extend(assemble("add.b r2, r1\nresume").unwrap());
+//! program.resize(48, 0);
+//! program.extend(32u64.to_le_bytes());
+//! program.extend(16u64.to_le_bytes());
+//!
+//! let mut core = Core::default();
+//! core.context.registers[7] = 1;
+//! let interrupts = &mut core.context.interrupts;
+//! interrupts.table = 48;
+//! interrupts.enabled = true;
+//! interrupts.set_priority(0, 2);
+//! interrupts.set_priority(1, 1);
+//! interrupts.raise(1);
+//!
+//! let mut memory = Memory::from(program);
+//! core.step(&mut memory, &mut Default::default());
+//! core.context.interrupts.raise(0);
+//! core.run(&mut memory, &mut Default::default(), None);
+//!
+//! // Vector 0 ran before the handler of vector 1 added to r1, and both handlers returned.
+//! assert_eq!((core.context.registers[1], core.context.registers[2]), (1, 0));
+//! assert_eq!(core.context.program_counter, 2);
+//! assert_eq!(core.context.interrupts.running, None);
+//! ```
+//!
+//! # Software interrupts
+//! The `trap` operation enters the handler of the vector in its operand through the same table, which is how guest
+//! software makes system calls. Traps are part of the instruction that makes them, so they are entered as soon as it
+//! completes regardless of whether interrupts are enabled, masked or of a lower priority than the running handler, and
+//! `resume` returns to the instruction after it. The vector is the low byte of the operand, so vectors past [VECTORS]
+//! which hardware can't raise can be trapped too. A handler address which can't be read faults the `trap` instruction.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! // The system call handler of vector 0x80 at 16 doubles r1. The table is at 0.
+//! let mut program = assemble("trap.b 0x80\nhalt").unwrap();
+//! program.resize(16, 0);
+//! program.extend(assemble("add.q r1, r1\nresume").unwrap());
+//! program.resize(0x80 * 8, 0);
+//! program.extend(16u64.to_le_bytes());
+//!
+//! let mut core = Core::default();
+//! core.context.registers[1] = 21;
+//! core.run(&mut Memory::from(program), &mut Default::default(), None);
+//!
+//! assert_eq!(core.context.registers[1], 42);
+//! ```
+
+use alloc::vec::Vec;
+use super::instruction::operation::condition::Flags;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of interrupt vectors.
+pub const VECTORS: u8 = 64;
+/// Vector raised on a core when another core signals it.
+pub const INTER_PROCESSOR_VECTOR: u8 = 0;
+/// Bytes of each handler address in the table.
+pub const HANDLER_BYTES: u64 = 8;
+
+/// Return state of a handler which was preempted by a nested interrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Saved {
+    pub return_address: u64,
+    pub return_flags: Flags,
+    pub return_user_mode: bool,
+    /// Priority of the preempted handler.
+    pub priority: u8
+}
+
+/// Interrupt state of a core.
+/// ```
+/// use atln_processor::emulator::processor::processor::interrupt::Interrupts;
+///
+/// let mut interrupts = Interrupts::default();
+/// interrupts.raise(5);
+/// interrupts.raise(2);
+/// interrupts.raise(7);
+/// assert_eq!(interrupts.next(), None);
+///
+/// interrupts.enabled = true;
+/// assert_eq!(interrupts.next(), Some(2));
+///
+/// interrupts.mask = 1 << 2;
+/// interrupts.set_priority(7, 1);
+/// assert_eq!(interrupts.next(), Some(7));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interrupts {
+    /// Whether pending interrupts are delivered.
+    pub enabled: bool,
+    /// One bit for each vector which was raised but not delivered yet.
+    pub pending: u64,
+    /// Address of the handler table.
+    pub table: u64,
+    /// Address of the instruction that was interrupted, which `resume` continues from.
+    pub return_address: u64,
+    /// Flags of the interrupted instruction, which `resume` restores.
+    pub return_flags: Flags,
+    /// Whether the interrupted instruction was in user mode, which `resume` restores.
+    pub return_user_mode: bool,
+    /// One bit for each vector which is not delivered while it is set.
+    pub mask: u64,
+    /// Priority of each vector by index, where higher priorities are delivered first. Vectors past the end have a
+    /// priority of 0.
+    pub priorities: Vec<u8>,
+    /// Priority of the innermost handler running, or [None] outside of handlers.
+    pub running: Option<u8>,
+    /// Return state of the handlers preempted by the innermost one, outermost first.
+    pub nested: Vec<Saved>,
+    /// Vector of the `trap` executed by the current instruction, which the core enters once the instruction completes.
+    pub trap: Option<u8>,
+    /// Cores that the `signal` operation was executed for. The owner of the cores, such as a
+    /// [System](crate::emulator::system::System), drains this and raises [INTER_PROCESSOR_VECTOR] on each of them.
+    pub signals: Vec<u64>
+}
+
+impl Interrupts {
+    /// Mark an interrupt as pending. Vectors past [VECTORS] are ignored.
+    pub fn raise(&mut self, vector: u8) {
+        if vector < VECTORS { self.pending |= 1 << vector; }
+    }
+
+    /// The interrupt which would be delivered next, if any.
+    pub fn next(&self) -> Option<u8> {
+        let deliverable = self.pending & !self.mask;
+        if !self.enabled || deliverable == 0 { return None }
+
+        (0..VECTORS)
+            .filter(|vector| deliverable & (1 << vector) != 0)
+            .filter(|&vector| self.running.is_none_or(|running| self.priority(vector) > running))
+            .min_by_key(|&vector| core::cmp::Reverse(self.priority(vector)))
+    }
+
+    pub fn priority(&self, vector: u8) -> u8 {
+        self.priorities.get(vector as usize).copied().unwrap_or(0)
+    }
+
+    /// Set the priority of a vector. Vectors past [VECTORS] are ignored.
+    pub fn set_priority(&mut self, vector: u8, priority: u8) {
+        if vector >= VECTORS { return }
+        if self.priorities.len() <= vector as usize { self.priorities.resize(vector as usize + 1, 0) }
+        self.priorities[vector as usize] = priority;
+    }
+
+    /// Start handling an interrupt taken at an instruction, saving the return state of the running handler if there is
+    /// one and disabling interrupts.
+    pub fn enter(&mut self, vector: u8, address: u64, flags: Flags, user_mode: bool) {
+        if let Some(priority) = self.running {
+            self.nested.push(Saved { return_address: self.return_address, return_flags: self.return_flags, return_user_mode: self.return_user_mode, priority });
+        }
+
+        if vector < VECTORS { self.pending &= !(1 << vector) }
+        self.return_address = address;
+        self.return_flags = flags;
+        self.return_user_mode = user_mode;
+        self.running = Some(self.priority(vector));
+        self.enabled = false;
+    }
+
+    /// Finish handling the innermost interrupt, enabling interrupts and restoring the return state of the handler it
+    /// preempted. Returns the address, flags and mode to resume.
+    pub fn leave(&mut self) -> (u64, Flags, bool) {
+        let resumed = (self.return_address, self.return_flags, self.return_user_mode);
+        self.enabled = true;
+        self.running = None;
+
+        if let Some(saved) = self.nested.pop() {
+            self.return_address = saved.return_address;
+            self.return_flags = saved.return_flags;
+            self.return_user_mode = saved.return_user_mode;
+            self.running = Some(saved.priority);
+        }
+
+        resumed
+    }
+
+    /// Address in the table holding the handler address of a vector.
+    pub fn handler(&self, vector: u8) -> u64 {
+        self.table.wrapping_add(vector as u64 * HANDLER_BYTES)
+    }
+}
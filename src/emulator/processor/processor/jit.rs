@@ -0,0 +1,356 @@
+//! Just in time compilation of hot blocks to native code through Cranelift.
+//!
+//! Blocks are executed through the interpreter until they have been executed [Jit::threshold] times. A hot block is then
+//! translated into a native function operating directly on the register file. Only blocks made entirely of
+//! instructions the translator understands are compiled, anything else keeps running on the interpreter. Compiled
+//! blocks are cached with the same write count invalidation as decoded blocks, so self modifying writes cause the block
+//! to be decoded and compiled again.
+//!
+//! A compiled function returns the index of the first instruction it could not complete. When that is not the end of
+//! the block, the remaining instructions are executed by the interpreter so error behaviour is identical.
+//!
+//! Compiled instructions are not seen by the tracer, the pipeline or the heatmap of a core, so blocks always run on the
+//! interpreter while any of them is enabled. Neither are they checked against execute breakpoints, so blocks containing
+//! the address of one also run on the interpreter.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::mem;
+use cranelift_codegen::ir::{types, AbiParam, InstBuilder, MemFlags, Type};
+use cranelift_codegen::settings;
+use cranelift_codegen::settings::Configurable;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Module, ModuleError};
+use emulator::memory::MemoryAccess;
+use number::Size;
+use super::{Core, Ports, Registers, Status};
+use super::block::Block;
+use super::cache::Cache;
+use super::debug;
+use super::instruction::Instruction;
+use super::instruction::operand::{Destination, Dynamic};
+use super::instruction::operation::Extension;
+use super::instruction::operation::arithmetic::Arithmetic;
+
+/// Default number of interpreted executions before a block is compiled.
+pub const DEFAULT_THRESHOLD: u32 = 16;
+
+/// Native entry point of a compiled block. It receives the register file and returns the number of instructions
+/// completed.
+type Function = extern "C" fn(*mut u64) -> u32;
+
+/// A block translated to native code.
+#[derive(Debug, Clone, Copy)]
+pub struct Compiled {
+    function: Function,
+    /// Number of instructions in the block.
+    pub instructions: u32
+}
+
+#[derive(Debug)]
+pub enum CompileError {
+    /// The host architecture is not supported by Cranelift.
+    Host(&'static str),
+    Module(Box<ModuleError>)
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host(reason) => write!(f, "host is not supported: {reason}"),
+            Self::Module(_) => f.write_str("failed to compile block")
+        }
+    }
+}
+
+impl Error for CompileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Host(_) => None,
+            Self::Module(error) => Some(error.as_ref())
+        }
+    }
+}
+
+/// A register to register addition, which is the form of instruction the translator understands.
+struct Add {
+    width: Type,
+    x_static: u8,
+    x_dynamic: u8,
+    destination: u8
+}
+
+impl Add {
+    fn from_instruction(instruction: &Instruction) -> Option<Self> {
+        if !matches!(instruction.extension(), Extension::Arithmetic(Arithmetic::Add)) { return None }
+
+        let data = instruction.data().as_ref()?;
+        let all = data.operands.all()?;
+        let x_dynamic = match all.x_dynamic {
+            Dynamic::Register(register) => register,
+            _ => return None
+        };
+
+        let destination = match data.destination {
+            Destination::Static => all.x_static,
+            Destination::Dynamic => x_dynamic,
+            Destination::Target(target) => target
+        };
+
+        // The compiled code indexes the register file without checking, so register codes from instructions that were
+        // not decoded from memory must be checked here.
+        if [all.x_static, x_dynamic, destination].iter().any(|&register| register as usize >= mem::size_of::<Registers>() / 8) { return None }
+
+        Some(Self {
+            width: match data.width {
+                Size::Byte => types::I8,
+                Size::Word => types::I16,
+                Size::Dual => types::I32,
+                Size::Quad => types::I64
+            },
+            x_static: all.x_static,
+            x_dynamic,
+            destination
+        })
+    }
+}
+
+pub struct Jit {
+    module: JITModule,
+    context: FunctionBuilderContext,
+    /// Compiled blocks keyed by their physical start address. [None] marks blocks that cannot be compiled.
+    compiled: Cache<Option<Compiled>>,
+    /// Interpreted executions of each block keyed by their physical start address.
+    counts: HashMap<u64, u32>,
+    /// Number of interpreted executions before a block is compiled.
+    pub threshold: u32
+}
+
+impl Jit {
+    /// Create a compiler targeting the host machine.
+    pub fn new() -> Result<Self, CompileError> {
+        let mut flags = settings::builder();
+        flags.set("use_colocated_libcalls", "false").expect("Flag should exist");
+        flags.set("is_pic", "false").expect("Flag should exist");
+
+        let isa = cranelift_native::builder()
+            .map_err(CompileError::Host)?
+            .finish(settings::Flags::new(flags))
+            .map_err(|error| CompileError::Module(Box::new(ModuleError::from(error))))?;
+
+        Ok(Self {
+            module: JITModule::new(JITBuilder::with_isa(isa, default_libcall_names())),
+            context: FunctionBuilderContext::new(),
+            compiled: Cache::default(),
+            counts: HashMap::new(),
+            threshold: DEFAULT_THRESHOLD
+        })
+    }
+
+    /// Translate a block into native code. [None] is returned if the block contains an instruction the translator does
+    /// not understand.
+    pub fn compile(&mut self, block: &Block) -> Result<Option<Compiled>, CompileError> {
+        let adds = match block.instructions.iter().map(|(instruction, _)| Add::from_instruction(instruction)).collect::<Option<Vec<_>>>() {
+            Some(adds) => adds,
+            None => return Ok(None)
+        };
+
+        let mut context = self.module.make_context();
+        context.func.signature.params.push(AbiParam::new(self.module.target_config().pointer_type()));
+        context.func.signature.returns.push(AbiParam::new(types::I32));
+
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut self.context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let registers = builder.block_params(entry)[0];
+
+        for (index, add) in adds.iter().enumerate() {
+            let mut x_static = builder.ins().load(types::I64, MemFlags::trusted(), registers, add.x_static as i32 * 8);
+            let mut x_dynamic = builder.ins().load(types::I64, MemFlags::trusted(), registers, add.x_dynamic as i32 * 8);
+            if add.width != types::I64 {
+                x_static = builder.ins().ireduce(add.width, x_static);
+                x_dynamic = builder.ins().ireduce(add.width, x_dynamic);
+            }
+
+            // Overflowing leaves the instruction to the interpreter.
+            let (mut sum, overflow) = builder.ins().uadd_overflow(x_static, x_dynamic);
+            let completed = builder.create_block();
+            let bail = builder.create_block();
+            builder.ins().brif(overflow, bail, &[], completed, &[]);
+
+            builder.switch_to_block(bail);
+            builder.seal_block(bail);
+            let index = builder.ins().iconst(types::I32, index as i64);
+            builder.ins().return_(&[index]);
+
+            builder.switch_to_block(completed);
+            builder.seal_block(completed);
+            if add.width != types::I64 { sum = builder.ins().uextend(types::I64, sum); }
+            builder.ins().store(MemFlags::trusted(), sum, registers, add.destination as i32 * 8);
+        }
+
+        let length = builder.ins().iconst(types::I32, adds.len() as i64);
+        builder.ins().return_(&[length]);
+        builder.finalize();
+
+        let id = self.module.declare_anonymous_function(&context.func.signature).map_err(|error| CompileError::Module(Box::new(error)))?;
+        self.module.define_function(id, &mut context).map_err(|error| CompileError::Module(Box::new(error)))?;
+        self.module.clear_context(&mut context);
+        self.module.finalize_definitions().map_err(|error| CompileError::Module(Box::new(error)))?;
+
+        // Safety: The function was built with the signature of [Function] above.
+        let function = unsafe { mem::transmute::<*const u8, Function>(self.module.get_finalized_function(id)) };
+        Ok(Some(Compiled { function, instructions: adds.len() as u32 }))
+    }
+
+    /// Execute a block, compiling it once it is hot. Cold blocks, blocks that cannot be compiled and blocks run while the
+    /// core has a tracer, a pipeline, a heatmap or an execute breakpoint within the block are executed by
+    /// [Core::execute_block].
+    pub fn execute_block(&mut self, core: &mut Core, block: &Block, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Result<Status, CompileError> {
+        if instrumented(core) || debug::executes_within(&core.context, block.start, block.length) { return Ok(core.execute_block(block, memory, ports)) }
+
+        let physical = if core.context.virtual_mode { memory.translate_virtual(block.start) } else { Some(block.start) };
+        let physical = match physical {
+            Some(physical) => physical,
+            None => return Ok(core.execute_block(block, memory, ports))
+        };
+
+        let compiled = match self.compiled.get(memory, physical) {
+            Some(entry) => entry.value,
+            None => {
+                let count = self.counts.entry(physical).or_insert(0);
+                *count += 1;
+                if *count < self.threshold { return Ok(core.execute_block(block, memory, ports)) }
+
+                self.counts.remove(&physical);
+                let compiled = self.compile(block)?;
+                self.compiled.insert(memory, physical, compiled, block.length);
+                compiled
+            }
+        };
+
+        let compiled = match compiled {
+            Some(compiled) => compiled,
+            None => return Ok(core.execute_block(block, memory, ports))
+        };
+
+        let completed = (compiled.function)(core.context.registers.as_mut_ptr()) as usize;
+        let mut address = block.start;
+        for (instruction, length) in &block.instructions[..completed] {
+            core.observe(address, instruction);
+            core.retire(instruction);
+            address = address.wrapping_add(*length);
+        }
+
+        core.context.program_counter = address;
+
+        Ok(core.execute_instructions(address, &block.instructions[completed..], memory, ports))
+    }
+}
+
+/// Whether a core records executed instructions through its tracer, pipeline or heatmap, which only the interpreter
+/// feeds.
+fn instrumented(core: &Core) -> bool {
+    #[cfg(feature = "heatmap")]
+    if core.heatmap.is_some() { return true }
+
+    core.tracer.is_some() || core.pipeline.is_some()
+}
+
+#[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
+mod test {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use emulator::differential::{lockstep, Interpreter};
+    use emulator::memory::Memory;
+    use emulator::processor::processor::Core;
+    use emulator::processor::processor::debug::Watch;
+    use emulator::processor::processor::instruction::operation::control::{DEBUG_ADDRESS_REGISTER, DEBUG_CONTROL_REGISTER, DEBUG_STATUS_REGISTER};
+    use emulator::processor::processor::trace::Tracer;
+    use programming::assembler::assemble;
+    use super::Jit;
+
+    #[test]
+    fn matches_interpreter() {
+        // add.b r1, r2 then add.w r3, r1
+        let mut memory = Memory::from(vec![0, 0, 0b00_001_010, 0, 0, 0b01_011_001]);
+        let mut interpreted = Core::default();
+        interpreted.context.registers[2] = 3;
+        let mut compiled = interpreted.clone();
+
+        let mut jit = Jit::new().unwrap();
+        jit.threshold = 2;
+
+        for _ in 0..4 {
+            let block = interpreted.decode_block(&memory, 0).unwrap();
+            interpreted.execute_block(&block, &mut memory, &mut Default::default());
+            jit.execute_block(&mut compiled, &block, &mut memory, &mut Default::default()).unwrap();
+        }
+
+        assert_eq!(interpreted, compiled);
+        assert_eq!(interpreted.cycles, compiled.cycles);
+        assert_eq!(compiled.context.registers[3], 3 + 6 + 9 + 12);
+    }
+
+    #[test]
+    fn lockstep_with_interpreter() {
+        // The loop body becomes hot and is compiled, while the blocks run once stay in the interpreter.
+        let memory = Memory::from(assemble("loop: add.q r1, r2\nadd.b r2, 1\ncmp.q r2, 20\nmovz.q r3, exit\nmovnz.q r3, loop\ndivert r3\nexit: halt").unwrap());
+        let mut jit = Jit::new().unwrap();
+        jit.threshold = 2;
+
+        assert!(lockstep(&Core::default(), &memory, &mut Interpreter, &mut jit, 10_000).is_ok());
+    }
+
+    #[test]
+    fn traces_hot_blocks() {
+        // add.b r1, r2 then add.w r3, r1, executed well past the threshold.
+        let mut memory = Memory::from(vec![0, 0, 0b00_001_010, 0, 0, 0b01_011_001]);
+        let tracer = Arc::new(Mutex::new(Tracer::new(Box::new(io::sink()))));
+        let mut core = Core { tracer: Some(tracer.clone()), ..Default::default() };
+
+        let mut jit = Jit::new().unwrap();
+        jit.threshold = 2;
+
+        for _ in 0..8 {
+            let block = core.decode_block(&memory, 0).unwrap();
+            jit.execute_block(&mut core, &block, &mut memory, &mut Default::default()).unwrap();
+        }
+
+        assert_eq!(tracer.lock().unwrap().records, 16);
+        assert_eq!(core.context.counters.instructions, 16);
+    }
+
+    #[test]
+    fn interprets_blocks_with_execute_breakpoints() {
+        // add.b r1, r2 then add.w r3, r1, with a breakpoint on the second.
+        let mut memory = Memory::from(vec![0, 0, 0b00_001_010, 0, 0, 0b01_011_001]);
+        let mut core = Core::default();
+        core.context.model_specific[DEBUG_ADDRESS_REGISTER as usize] = 3;
+        core.context.model_specific[DEBUG_CONTROL_REGISTER as usize] = Watch::Execute.control(0);
+
+        let mut jit = Jit::new().unwrap();
+        jit.threshold = 1;
+
+        let block = core.decode_block(&memory, 0).unwrap();
+        jit.execute_block(&mut core, &block, &mut memory, &mut Default::default()).unwrap();
+
+        assert_eq!(core.context.model_specific[DEBUG_STATUS_REGISTER as usize], 1);
+    }
+
+    #[test]
+    fn declines_memory_operands() {
+        // add.b r1, [4]
+        let memory = Memory::from(vec![0, 0b0000_11_00, 0b00_001_000, 4, 0]);
+        let mut core = Core::default();
+        let block = core.decode_block(&memory, 0).unwrap();
+
+        assert!(Jit::new().unwrap().compile(&block).unwrap().is_none());
+    }
+}
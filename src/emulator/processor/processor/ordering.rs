@@ -0,0 +1,207 @@
+//! The memory ordering model and a store buffer for simulating weak ordering.
+//!
+//! # Model
+//! Guest software may rely on the following and nothing more.
+//! - Every core observes its own accesses in program order.
+//! - Every aligned access is single copy atomic, so no core ever observes part of another core's store.
+//! - Stores of a core to the same address are observed by other cores in program order.
+//! - Accesses to different addresses may be observed by other cores in any order. A load may complete before an earlier
+//!   store, and two stores may become visible in the opposite order.
+//! - `fence` makes every earlier access visible to other cores before any later one.
+//! - Synchronised instructions, including `sc`, are atomic with respect to every other synchronised instruction and
+//!   order accesses like `fence`.
+//!
+//! The emulator is normally stronger than this. Cores taking turns on a single thread are sequentially consistent, and
+//! the threaded schedule only reorders what the host does. Guest software that relies on more than the model
+//! guarantees therefore often works anyway, which is what the weak ordering mode exposes.
+//!
+//! # Weak ordering
+//! When a core has a [StoreBuffer], its stores are held back in the buffer instead of being written to memory. Loads
+//! see the core's own buffered stores, but other cores do not see them until they are committed. Once the buffer is
+//! full, a buffered store is picked pseudo randomly and committed, so stores to different addresses reach memory out of
+//! order. Stores to overlapping addresses are always committed in program order. The buffer is emptied by `fence`,
+//! before synchronised instructions, when an interrupt is taken and when the core stops.
+//!
+//! Stores are checked for faults when they are buffered, so a store that would fault still faults on the instruction
+//! that made it. Instructions are fetched from memory, so code must not be modified through buffered stores without a
+//! `fence`.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use emulator::memory::{Frame, GetError, MemoryAccess};
+use number;
+use utility::splitmix64;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Stores held back before they are written to memory.
+/// ```
+/// use atln_processor::emulator::memory::{Frame, Memory, MemoryAccess};
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::ordering::StoreBuffer;
+/// use atln_processor::number::{Data, Size};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let mut program = assemble("add.b [16], r1\nadd.b r2, [16]\nfence\nhalt").unwrap();
+/// program.resize(24, 0);
+///
+/// let mut memory = Memory::from(program);
+/// let mut core = Core::default();
+/// core.store_buffer = Some(StoreBuffer::new(4, 0));
+/// core.context.registers[1] = 7;
+///
+/// // The store is only in the buffer, but the core still loads what it stored.
+/// core.step(&mut memory, &mut Default::default());
+/// core.step(&mut memory, &mut Default::default());
+/// assert_eq!(memory.bytes[16], 0);
+/// assert_eq!(core.context.registers[2], 7);
+///
+/// core.step(&mut memory, &mut Default::default());
+/// assert_eq!(memory.bytes[16], 7);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StoreBuffer {
+    /// Number of stores held before one is committed.
+    pub capacity: usize,
+    /// State of the generator picking which store to commit.
+    state: u64,
+    stores: VecDeque<Store>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Store {
+    frame: Frame,
+    r#virtual: bool,
+    value: number::Data
+}
+
+impl Store {
+    fn overlaps(&self, frame: &Frame) -> bool {
+        self.frame.address < frame.max_address() && frame.address < self.frame.max_address()
+    }
+}
+
+impl StoreBuffer {
+    /// Create an empty buffer. The same seed always commits stores in the same order, so failures can be reproduced.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::emulator::processor::processor::ordering::StoreBuffer;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let mut program = assemble("add.b [16], r1\nadd.b [17], r1\nhalt").unwrap();
+    /// program.resize(24, 0);
+    ///
+    /// let mut memory = Memory::from(program);
+    /// let mut core = Core::default();
+    /// core.store_buffer = Some(StoreBuffer::new(1, 0));
+    /// core.context.registers[1] = 1;
+    ///
+    /// // With this seed, the second store reaches memory before the first.
+    /// core.step(&mut memory, &mut Default::default());
+    /// core.step(&mut memory, &mut Default::default());
+    /// assert_eq!(memory.bytes[16..18], [0, 1]);
+    ///
+    /// core.step(&mut memory, &mut Default::default());
+    /// assert_eq!(memory.bytes[16..18], [1, 1]);
+    /// ```
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        Self { capacity, state: seed, stores: VecDeque::new() }
+    }
+
+    /// Number of stores waiting to be committed.
+    pub fn len(&self) -> usize {
+        self.stores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stores.is_empty()
+    }
+
+    /// Write every buffered store to memory in program order.
+    pub fn drain(&mut self, memory: &mut dyn MemoryAccess) {
+        // Stores were checked when they were buffered. They can only fail now if the page mappings changed since, in
+        // which case the store is lost like it would be on hardware that does not check again.
+        for store in self.stores.drain(..) { let _ = memory.set(store.frame, store.r#virtual, store.value); }
+    }
+
+    /// Commit a pseudo randomly picked store that has no older store overlapping it. The oldest store is always a
+    /// candidate.
+    fn commit_one(&mut self, memory: &mut dyn MemoryAccess) {
+        let stores = &self.stores;
+        let candidates: Vec<usize> = (0..stores.len())
+            .filter(|&index| !stores.iter().take(index).any(|older| older.overlaps(&stores[index].frame)))
+            .collect();
+
+        let index = candidates[(self.next() % candidates.len() as u64) as usize];
+        if let Some(store) = self.stores.remove(index) { let _ = memory.set(store.frame, store.r#virtual, store.value); }
+    }
+
+    /// Advance the generator with [splitmix64].
+    fn next(&mut self) -> u64 {
+        splitmix64(&mut self.state)
+    }
+}
+
+/// Memory seen through a store buffer. Stores are buffered and loads are forwarded from the buffer.
+pub(crate) struct Buffered<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub buffer: &'a mut StoreBuffer
+}
+
+impl<'a> MemoryAccess for Buffered<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let overlapping = |store: &&Store| store.r#virtual == r#virtual && store.overlaps(&frame);
+        let newest = self.buffer.stores.iter().rev().find(overlapping);
+
+        match newest {
+            Some(store) if store.frame == frame => {
+                self.memory.get(frame, r#virtual)?;
+                Ok(store.value.clone())
+            },
+            // A partial overlap can't be forwarded. Other cores may not see the buffered stores yet, but this core must,
+            // so the value is assembled from memory with the overlapping stores applied on top.
+            Some(_) => {
+                let mut bytes = self.memory.get(frame.clone(), r#virtual)?.quad().to_le_bytes();
+
+                for store in self.buffer.stores.iter().filter(overlapping) {
+                    let value = store.value.quad().to_le_bytes();
+                    for offset in 0..store.frame.size.size() as u64 {
+                        let address = store.frame.address + offset;
+                        if address >= frame.address && address < frame.max_address() { bytes[(address - frame.address) as usize] = value[offset as usize]; }
+                    }
+                }
+
+                Ok(number::Data::from_size_selecting(&frame.size, u64::from_le_bytes(bytes)))
+            },
+            None => self.memory.get(frame, r#virtual)
+        }
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.memory.get(frame.clone(), r#virtual)?;
+        self.buffer.stores.push_back(Store { frame, r#virtual, value });
+        if self.buffer.stores.len() > self.buffer.capacity { self.buffer.commit_one(self.memory); }
+        Ok(())
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        self.buffer.drain(self.memory);
+        self.memory.synchronise(operation)
+    }
+
+    fn fence(&mut self) {
+        self.buffer.drain(self.memory);
+        self.memory.fence()
+    }
+}
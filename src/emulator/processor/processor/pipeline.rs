@@ -0,0 +1,188 @@
+//! Simulation of a classic in-order pipeline, for studying how the order of instructions affects throughput.
+//!
+//! The pipeline has [STAGES] stages, which are fetch, decode, execute, memory and writeback, and each instruction enters
+//! it in the cycle after the one before unless it stalls. The simulation only counts cycles and does not affect how
+//! instructions execute or the [cycles](super::Core::cycles) of the core. Two kinds of hazards stall the pipeline.
+//!
+//! - An instruction reading a register which an instruction ahead of it writes waits for the value. With
+//!   [forwarding](Pipeline::forwarding), results are passed straight from the execute stage, so only a value read from
+//!   memory stalls the instruction right after it for a cycle. Without forwarding, readers wait for the writeback stage,
+//!   which writes the register before it is read in the same cycle.
+//! - An instruction which diverts anywhere but the next instruction flushes the instructions fetched after it, which
+//!   costs [Pipeline::branch_penalty] cycles. With a [predictor](Pipeline::predictor), only instructions which diverted
+//!   somewhere else than predicted flush the pipeline.
+//!
+//! Registers are read from the static operand and from the registers of the dynamic operand. The destination register
+//! is assumed to be written by every instruction with operands except those of the executor extension, and post
+//! increment and pre decrement addressing write their register as well. Values are read from memory by instructions
+//! whose dynamic operand dereferences memory and is not their destination.
+
+use super::instruction::Instruction;
+use super::instruction::operand::{Destination, Dynamic, MEMORY_ADDRESSING, OFFSET_ADDRESSING};
+use super::instruction::operation::Extension;
+use super::predictor::Predictor;
+
+/// Number of pipeline stages.
+pub const STAGES: u64 = 5;
+
+/// Stall cycles and flushes counted by a [Pipeline].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    pub instructions: u64,
+    /// Cycles lost waiting for registers.
+    pub data_stalls: u64,
+    /// Instructions which diverted unpredicted and flushed the pipeline.
+    pub flushes: u64,
+    /// Cycles lost to flushes.
+    pub flush_cycles: u64
+}
+
+impl Statistics {
+    /// Cycles taken to complete every instruction, including filling the pipeline.
+    pub fn cycles(&self) -> u64 {
+        if self.instructions == 0 { return 0 }
+        self.instructions + self.data_stalls + self.flush_cycles + STAGES - 1
+    }
+
+    /// Average number of cycles per instruction, which approaches 1 without stalls.
+    pub fn cycles_per_instruction(&self) -> f64 {
+        if self.instructions == 0 { return 0.0 }
+        self.cycles() as f64 / self.instructions as f64
+    }
+}
+
+/// Registers an instruction in flight writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Writer {
+    /// One bit for each register.
+    registers: u8,
+    /// Whether the value comes from memory, which makes it available a stage later.
+    load: bool
+}
+
+/// Pipeline fed with the instructions a core executes. Set it as [Core::pipeline](super::Core::pipeline) to enable it.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::pipeline::Pipeline;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // The second instruction uses the value the first loads, and the third and fourth are independent.
+/// let mut program = assemble("add.q r1, [32]\nadd.q r2, r1\nadd.q r3, 1\nadd.q r4, 1\nhalt").unwrap();
+/// program.resize(40, 0);
+///
+/// for (forwarding, stalls) in [(true, 1), (false, 2)] {
+///     let mut core = Core::default();
+///     core.pipeline = Some(Pipeline::new(forwarding, 2));
+///     core.run(&mut Memory::from(program.clone()), &mut Default::default(), None);
+///
+///     let statistics = core.pipeline.unwrap().statistics;
+///     assert_eq!(statistics.instructions, 5);
+///     assert_eq!(statistics.data_stalls, stalls);
+///     assert_eq!(statistics.cycles(), 5 + stalls + 4);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    /// Whether results are forwarded to the instructions after them without waiting for writeback.
+    pub forwarding: bool,
+    /// Cycles lost each time the pipeline is flushed.
+    pub branch_penalty: u64,
+    /// Predicts where instructions which may divert continue from, or [None] to predict they continue with the next
+    /// instruction.
+    pub predictor: Option<Predictor>,
+    pub statistics: Statistics,
+    /// Writers among the last two instructions to enter the pipeline, most recent first, or [None] for bubbles.
+    writers: [Option<Writer>; 2]
+}
+
+impl Default for Pipeline {
+    /// Pipeline with forwarding which resolves branches in the execute stage, so a flush discards 2 instructions.
+    fn default() -> Self {
+        Self::new(true, 2)
+    }
+}
+
+impl Pipeline {
+    pub fn new(forwarding: bool, branch_penalty: u64) -> Self {
+        Self { forwarding, branch_penalty, predictor: None, statistics: Statistics::default(), writers: [None; 2] }
+    }
+
+    /// Account for an instruction at an address which completed. The target is where it diverted to, or [None] if it
+    /// continued with the next instruction.
+    pub fn record(&mut self, address: u64, instruction: &Instruction, target: Option<u64>) {
+        let (reads, writer) = registers(instruction);
+        let waits = |writer: Option<Writer>| writer.is_some_and(|writer| writer.registers & reads != 0);
+
+        let stalls = if self.forwarding {
+            match self.writers[0] {
+                Some(writer) if writer.load && waits(Some(writer)) => 1,
+                _ => 0
+            }
+        } else if waits(self.writers[0]) {
+            2
+        } else if waits(self.writers[1]) {
+            1
+        } else {
+            0
+        };
+
+        self.statistics.instructions += 1;
+        self.statistics.data_stalls += stalls;
+        self.advance(stalls);
+        self.writers = [Some(writer), self.writers[0]];
+
+        let flush = match &mut self.predictor {
+            Some(predictor) if instruction.diverts() => !predictor.record(address, target),
+            _ => target.is_some()
+        };
+
+        if flush {
+            self.statistics.flushes += 1;
+            self.statistics.flush_cycles += self.branch_penalty;
+            self.advance(self.branch_penalty);
+        }
+    }
+
+    /// Forget the state of the pipeline and the statistics. The predictor keeps its training.
+    pub fn reset(&mut self) {
+        self.statistics = Statistics::default();
+        self.writers = [None; 2];
+    }
+
+    /// Insert bubbles into the pipeline.
+    fn advance(&mut self, bubbles: u64) {
+        for _ in 0..bubbles.min(self.writers.len() as u64) { self.writers = [None, self.writers[0]]; }
+    }
+}
+
+/// Registers an instruction reads, one bit for each, along with what it writes.
+fn registers(instruction: &Instruction) -> (u8, Writer) {
+    let data = match instruction.data() {
+        Some(data) => data,
+        None => return (0, Writer::default())
+    };
+
+    let bit = |register: u8| 1u8.checked_shl(register as u32).unwrap_or(0);
+    let x_static = data.operands.x_static();
+    let x_dynamic = data.operands.x_dynamic();
+
+    let mut reads = x_static.map_or(0, bit) | x_dynamic.and_then(Dynamic::register).map_or(0, bit);
+    if let Some(Dynamic::Indexed(indexed)) = x_dynamic { reads |= bit(indexed.index); }
+
+    let memory = x_dynamic.is_some_and(|x_dynamic| matches!(x_dynamic.addressing(), OFFSET_ADDRESSING | MEMORY_ADDRESSING));
+    let destination = match data.destination {
+        Destination::Static => x_static,
+        Destination::Dynamic => if memory { None } else { x_dynamic.and_then(Dynamic::register) },
+        Destination::Target(target) => Some(target)
+    };
+
+    let mut writes = match instruction.extension() {
+        Extension::Executor(_) => 0,
+        _ => destination.map_or(0, bit)
+    };
+
+    if let Some(Dynamic::PostIncrement(register) | Dynamic::PreDecrement(register)) = x_dynamic { writes |= bit(*register); }
+
+    (reads, Writer { registers: writes, load: memory && !matches!(data.destination, Destination::Dynamic) })
+}
@@ -0,0 +1,146 @@
+//! Models of branch predictors, for studying how the layout of guest code affects prediction.
+//!
+//! The architecture has no conditional branches. Instead, code computes the address to continue from, such as with a
+//! conditional move, and diverts to it. Every instruction which may divert is therefore predicted as a branch, which is
+//! taken when it continues anywhere but the next instruction. A prediction is only correct if a taken branch also
+//! continues from the address the branch target buffer remembers for it, which is where it went the last time it was
+//! taken.
+//!
+//! Set a [Predictor] as [Pipeline::predictor](super::pipeline::Pipeline::predictor) so only mispredicted branches flush
+//! the pipeline.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use utility::Map;
+
+/// How a predictor decides whether a branch is taken.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::pipeline::Pipeline;
+/// use atln_processor::emulator::processor::processor::predictor::{Predictor, Scheme};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // The loop diverts back 3 times before leaving it and halting.
+/// let program = assemble("loop: add.q r1, 1\ncmp.q r1, 4\nmovz.q r3, exit\nmovnz.q r3, loop\ndivert r3\nexit: halt").unwrap();
+///
+/// for (scheme, correct) in [(Scheme::Static { taken: false }, 2), (Scheme::TwoBit { bits: 4 }, 3), (Scheme::Gshare { bits: 4 }, 1)] {
+///     let mut pipeline = Pipeline::default();
+///     pipeline.predictor = Some(Predictor::new(scheme));
+///
+///     let mut core = Core::default();
+///     core.pipeline = Some(pipeline);
+///     core.run(&mut Memory::from(program.clone()), &mut Default::default(), None);
+///
+///     let pipeline = core.pipeline.unwrap();
+///     let accuracy = pipeline.predictor.unwrap().accuracy;
+///     assert_eq!(accuracy.predictions, 5);
+///     assert_eq!(accuracy.correct, correct);
+///     assert_eq!(pipeline.statistics.flushes, accuracy.mispredictions());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// Always predict the same direction.
+    Static { taken: bool },
+    /// A table of 2 bit saturating counters indexed by the low `bits` bits of the branch address.
+    TwoBit { bits: u32 },
+    /// A table of 2 bit saturating counters indexed by the branch address exclusive or the outcomes of the last `bits`
+    /// branches.
+    Gshare { bits: u32 }
+}
+
+/// How often the predictions were correct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Accuracy {
+    pub predictions: u64,
+    pub correct: u64
+}
+
+impl Accuracy {
+    /// Fraction of correct predictions, or 1 before any prediction.
+    pub fn rate(&self) -> f64 {
+        if self.predictions == 0 { return 1.0 }
+        self.correct as f64 / self.predictions as f64
+    }
+
+    pub fn mispredictions(&self) -> u64 {
+        self.predictions - self.correct
+    }
+}
+
+/// Branch predictor along with the accuracy of its predictions so far.
+/// ```
+/// use atln_processor::emulator::processor::processor::predictor::{Predictor, Scheme};
+///
+/// let mut predictor = Predictor::new(Scheme::TwoBit { bits: 4 });
+///
+/// // A loop branch at 8 taken back to 0 three times before falling through.
+/// let outcomes = [Some(0), Some(0), Some(0), None].map(|target| predictor.record(8, target));
+/// assert_eq!(outcomes, [false, true, true, false]);
+/// assert_eq!(predictor.accuracy.correct, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Predictor {
+    pub scheme: Scheme,
+    pub accuracy: Accuracy,
+    /// Counters of the 2 bit schemes, where 2 and 3 predict taken. They start out at 1.
+    counters: Vec<u8>,
+    /// Outcomes of the most recent branches, with the latest in bit 0.
+    history: u64,
+    /// Branch target buffer holding where each branch address went the last time it was taken.
+    targets: Map<u64, u64>
+}
+
+impl Predictor {
+    /// Create a predictor. Counter tables have 2 to the power of `bits` entries, which is capped at 2 to the power of 24.
+    pub fn new(scheme: Scheme) -> Self {
+        let entries = match scheme {
+            Scheme::Static { .. } => 0,
+            Scheme::TwoBit { bits } | Scheme::Gshare { bits } => 1 << bits.min(24)
+        };
+
+        Self { scheme, accuracy: Accuracy::default(), counters: vec![1; entries], history: 0, targets: Map::new() }
+    }
+
+    /// Whether the branch at an address is predicted to be taken.
+    pub fn predict(&self, address: u64) -> bool {
+        match self.scheme {
+            Scheme::Static { taken } => taken,
+            _ => self.counters[self.index(address)] >= 2
+        }
+    }
+
+    /// Predict the branch at an address, then train the predictor with where it went, which is [None] if it continued
+    /// with the next instruction. Returns whether the prediction was correct.
+    pub fn record(&mut self, address: u64, target: Option<u64>) -> bool {
+        let taken = target.is_some();
+        let correct = match (self.predict(address), target) {
+            (true, Some(target)) => self.targets.get(&address) == Some(&target),
+            (predicted, _) => predicted == taken
+        };
+
+        if let Scheme::TwoBit { .. } | Scheme::Gshare { .. } = self.scheme {
+            let index = self.index(address);
+            let counter = &mut self.counters[index];
+            *counter = if taken { (*counter + 1).min(3) } else { counter.saturating_sub(1) };
+        }
+
+        if let Some(target) = target { self.targets.insert(address, target); }
+        self.history = self.history << 1 | taken as u64;
+        self.accuracy.predictions += 1;
+        self.accuracy.correct += correct as u64;
+        correct
+    }
+
+    /// Entry of the counter table for the branch at an address.
+    fn index(&self, address: u64) -> usize {
+        let mask = self.counters.len() as u64 - 1;
+        let index = match self.scheme {
+            Scheme::Gshare { .. } => address ^ self.history,
+            _ => address
+        };
+
+        (index & mask) as usize
+    }
+}
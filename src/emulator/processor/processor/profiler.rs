@@ -0,0 +1,88 @@
+//! Execution counts of guest code for finding hot spots.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use super::instruction::Instruction;
+use super::instruction::operation::{ExtensionCode, OperationCode};
+use utility::{Coded, Map};
+
+/// Executions of a single address.
+#[derive(Debug, Clone)]
+pub struct HotSpot {
+    pub address: u64,
+    pub count: u64,
+    /// The instruction most recently executed at the address. Its [std::fmt::Display] form is the disassembly.
+    pub instruction: Arc<Instruction>
+}
+
+/// Counts how many times each address and each operation was executed.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    /// Executions keyed by instruction address.
+    pub addresses: Map<u64, u64>,
+    /// Executions keyed by extension and operation code.
+    pub operations: Map<(ExtensionCode, OperationCode), u64>,
+    /// The instruction most recently executed at each address.
+    instructions: Map<u64, Arc<Instruction>>
+}
+
+impl Profiler {
+    /// Count an execution of an instruction at an address.
+    pub fn record(&mut self, address: u64, instruction: &Arc<Instruction>) {
+        let extension = instruction.extension();
+
+        *self.addresses.entry(address).or_insert(0) += 1;
+        *self.operations.entry((extension.code(), extension.operation_code())).or_insert(0) += 1;
+        self.instructions.insert(address, instruction.clone());
+    }
+
+    /// Every executed address sorted from the most to the least executed. Addresses with equal counts are in ascending
+    /// order.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::emulator::processor::processor::profiler::Profiler;
+    ///
+    /// let mut memory = Memory::from(vec![
+    ///     // add.b r1, r2
+    ///     0b000000_0_0, 0b0000_00_00, 0b00_001_010,
+    ///     // divert 0
+    ///     0b000010_0_0, 0b0001_10_00, 0b00_000_000, 0
+    /// ]);
+    ///
+    /// let mut core = Core::default();
+    /// core.profiler = Some(Profiler::default());
+    /// for _ in 0..3 { core.step(&mut memory, &mut Default::default()); }
+    ///
+    /// let hot_spots = core.profiler.unwrap().hot_spots();
+    /// assert_eq!(hot_spots[0].address, 0);
+    /// assert_eq!(hot_spots[0].count, 2);
+    /// assert_eq!(hot_spots[1].address, 3);
+    /// assert_eq!(hot_spots[1].count, 1);
+    /// ```
+    pub fn hot_spots(&self) -> Vec<HotSpot> {
+        let mut hot_spots: Vec<HotSpot> = self.addresses.iter()
+            .map(|(address, count)| HotSpot {
+                address: *address,
+                count: *count,
+                instruction: self.instructions[address].clone()
+            })
+            .collect();
+
+        hot_spots.sort_by(|a, b| b.count.cmp(&a.count).then(a.address.cmp(&b.address)));
+        hot_spots
+    }
+
+    /// Execution counts of each operation sorted from the most to the least executed.
+    pub fn hot_operations(&self) -> Vec<((ExtensionCode, OperationCode), u64)> {
+        let mut operations: Vec<_> = self.operations.iter().map(|(codes, count)| (*codes, *count)).collect();
+        operations.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        operations
+    }
+
+    pub fn reset(&mut self) {
+        self.addresses.clear();
+        self.operations.clear();
+        self.instructions.clear();
+    }
+}
@@ -0,0 +1,273 @@
+//! Services the host provides to guest programs which have no operating system to ask.
+//!
+//! A guest requests a service by executing `hcall` with the number of the call in `r0` and its arguments in `r1` to
+//! `r3`. The core passes the request to its [Semihosting] handler, which is only available with the `std` feature, and
+//! the result is written to `r0`. Calls which fail on the host return [FAILED], while calls which can't be made at all
+//! fault the core with [CallError].
+//!
+//! | Call             | Arguments                       | Result                                                      |
+//! | ---------------- | ------------------------------- | ----------------------------------------------------------- |
+//! | [EXIT_CALL]      | Exit code                       | The core halts.                                             |
+//! | [PRINT_CALL]     | Address, length                 | Bytes written to the output.                                |
+//! | [READ_LINE_CALL] | Address, capacity               | Bytes of the next input line stored, or 0 once input ended. |
+//! | [OPEN_CALL]      | Path address, path length, mode | Handle of the opened host file.                             |
+//! | [READ_CALL]      | Handle, address, length         | Bytes read from the file, or 0 at its end.                  |
+//! | [WRITE_CALL]     | Handle, address, length         | Bytes written to the file.                                  |
+//! | [CLOSE_CALL]     | Handle                          | 0.                                                          |
+//! | [CLOCK_CALL]     |                                 | Microseconds since the handler was created.                 |
+//!
+//! Transfers are limited to [MAX_TRANSFER] bytes a call, and the part of a line past the capacity is discarded. Paths
+//! are UTF-8 and opened relative to the working directory of the host, with the permissions of the emulator, so
+//! semihosting should only be enabled for trusted programs.
+
+#[cfg(feature = "std")]
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::vec;
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "std")]
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::time::Instant;
+#[cfg(feature = "std")]
+use emulator::memory::{Frame, MemoryAccess};
+#[cfg(feature = "std")]
+use number;
+#[cfg(feature = "std")]
+use super::Context;
+
+// region: Constants
+pub const EXIT_CALL     : u64 = 0;
+pub const PRINT_CALL    : u64 = 1;
+pub const READ_LINE_CALL: u64 = 2;
+pub const OPEN_CALL     : u64 = 3;
+pub const READ_CALL     : u64 = 4;
+pub const WRITE_CALL    : u64 = 5;
+pub const CLOSE_CALL    : u64 = 6;
+pub const CLOCK_CALL    : u64 = 7;
+
+/// Open a file for reading.
+pub const READ_MODE  : u64 = 0;
+/// Open a file for writing, creating it or discarding what it held.
+pub const WRITE_MODE : u64 = 1;
+/// Open a file for writing at its end, creating it if needed.
+pub const APPEND_MODE: u64 = 2;
+
+/// Result of a call which failed on the host.
+pub const FAILED: u64 = u64::MAX;
+/// Most bytes a single call transfers.
+pub const MAX_TRANSFER: u64 = 0x1_0000;
+// endregion
+
+/// Reason for a host call being impossible to make.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The core has no semihosting handler.
+    Unavailable,
+    /// There is no call with the number.
+    Call(u64),
+    /// Guest memory at the address could not be accessed.
+    Memory(u64)
+}
+
+impl Display for CallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => f.write_str("no semihosting handler is available"),
+            Self::Call(call) => write!(f, "no host call with number {}", call),
+            Self::Memory(address) => write!(f, "guest memory at {:#x} could not be accessed", address)
+        }
+    }
+}
+
+impl Error for CallError {}
+
+/// Handler providing host calls with the standard streams and files of the host. A core uses it once it is set as
+/// [Core::semihosting](super::Core::semihosting).
+/// ```
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::{Core, Status};
+/// use atln_processor::emulator::processor::processor::semihosting::{Semihosting, EXIT_CALL, PRINT_CALL, READ_LINE_CALL};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let mut program = assemble("hcall\nhcall\nhcall").unwrap();
+/// program.resize(32, 0);
+/// program.extend(b"hello");
+/// program.resize(64, 0);
+///
+/// let mut memory = Memory::from(program);
+///
+/// let semihosting = Arc::new(Mutex::new(Semihosting::new(Box::new(io::sink()), Box::new(&b"line\nrest"[..]))));
+/// let mut core = Core::default();
+/// core.semihosting = Some(semihosting.clone());
+///
+/// core.context.registers[..3].copy_from_slice(&[PRINT_CALL, 32, 5]);
+/// core.step(&mut memory, &mut Default::default());
+/// assert_eq!(core.context.registers[0], 5);
+///
+/// core.context.registers[..3].copy_from_slice(&[READ_LINE_CALL, 32, 16]);
+/// core.step(&mut memory, &mut Default::default());
+/// assert_eq!(&memory.bytes[32..37], b"line\n");
+///
+/// core.context.registers[..2].copy_from_slice(&[EXIT_CALL, 3]);
+/// assert!(matches!(core.step(&mut memory, &mut Default::default()), Status::Halted));
+/// assert_eq!(semihosting.lock().unwrap().exit_code, Some(3));
+/// ```
+#[cfg(feature = "std")]
+pub struct Semihosting {
+    /// Where printed bytes are written to.
+    pub output: Box<dyn Write + Send>,
+    /// Where lines are read from.
+    pub input: Box<dyn BufRead + Send>,
+    /// Code passed to [EXIT_CALL], once the guest made it.
+    pub exit_code: Option<u64>,
+    /// Host files opened by the guest, indexed by their handle. Closing a file empties its slot for reuse.
+    files: Vec<Option<File>>,
+    /// When the handler was created, which [CLOCK_CALL] counts from.
+    start: Instant
+}
+
+#[cfg(feature = "std")]
+impl Default for Semihosting {
+    /// Handler using the standard output and input of the host.
+    fn default() -> Self {
+        Self::new(Box::new(io::stdout()), Box::new(BufReader::new(io::stdin())))
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Semihosting {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Semihosting")
+            .field("exit_code", &self.exit_code)
+            .field("files", &self.files)
+            .field("start", &self.start)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Semihosting {
+    pub fn new(output: Box<dyn Write + Send>, input: Box<dyn BufRead + Send>) -> Self {
+        Self { output, input, exit_code: None, files: Vec::new(), start: Instant::now() }
+    }
+
+    /// Make the call requested by the registers of a core and write its result to `r0`. Returns whether the guest
+    /// exited.
+    pub fn call(&mut self, context: &mut Context, memory: &mut dyn MemoryAccess) -> Result<bool, CallError> {
+        let [call, first, second, third] = [0, 1, 2, 3].map(|register| context.registers[register]);
+
+        let result = match call {
+            EXIT_CALL => {
+                self.exit_code = Some(first);
+                return Ok(true)
+            },
+            PRINT_CALL => {
+                let bytes = load(memory, context, first, second)?;
+                self.output.write_all(&bytes).and_then(|_| self.output.flush()).map_or(FAILED, |_| bytes.len() as u64)
+            },
+            READ_LINE_CALL => {
+                let mut line = Vec::new();
+                match self.input.read_until(b'\n', &mut line) {
+                    Ok(_) => {
+                        line.truncate(second.min(MAX_TRANSFER) as usize);
+                        store(memory, context, first, &line)?;
+                        line.len() as u64
+                    },
+                    Err(_) => FAILED
+                }
+            },
+            OPEN_CALL => {
+                let path = load(memory, context, first, second)?;
+                self.open(&path, third).unwrap_or(FAILED)
+            },
+            READ_CALL => {
+                let mut buffer = vec![0; third.min(MAX_TRANSFER) as usize];
+                match self.file(first).and_then(|file| file.read(&mut buffer).ok()) {
+                    Some(count) => {
+                        store(memory, context, second, &buffer[..count])?;
+                        count as u64
+                    },
+                    None => FAILED
+                }
+            },
+            WRITE_CALL => {
+                let bytes = load(memory, context, second, third)?;
+                self.file(first).and_then(|file| file.write(&bytes).ok()).map_or(FAILED, |count| count as u64)
+            },
+            CLOSE_CALL => match usize::try_from(first).ok().and_then(|handle| self.files.get_mut(handle)).and_then(Option::take) {
+                Some(_) => 0,
+                None => FAILED
+            },
+            CLOCK_CALL => self.start.elapsed().as_micros() as u64,
+            _ => return Err(CallError::Call(call))
+        };
+
+        context.registers[0] = result;
+        Ok(false)
+    }
+
+    /// Open a host file and return its handle.
+    fn open(&mut self, path: &[u8], mode: u64) -> Option<u64> {
+        let path = core::str::from_utf8(path).ok()?;
+        let mut options = OpenOptions::new();
+
+        match mode {
+            READ_MODE => options.read(true),
+            WRITE_MODE => options.write(true).create(true).truncate(true),
+            APPEND_MODE => options.append(true).create(true),
+            _ => return None
+        };
+
+        let file = Some(options.open(path).ok()?);
+        let handle = match self.files.iter().position(Option::is_none) {
+            Some(handle) => {
+                self.files[handle] = file;
+                handle
+            },
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            }
+        };
+
+        Some(handle as u64)
+    }
+
+    fn file(&mut self, handle: u64) -> Option<&mut File> {
+        self.files.get_mut(usize::try_from(handle).ok()?)?.as_mut()
+    }
+}
+
+/// Read up to [MAX_TRANSFER] bytes of guest memory.
+#[cfg(feature = "std")]
+fn load(memory: &dyn MemoryAccess, context: &Context, address: u64, length: u64) -> Result<Vec<u8>, CallError> {
+    let mut bytes = vec![0; length.min(MAX_TRANSFER) as usize];
+    let read = memory.read_bytes(address, context.virtual_mode, &mut bytes);
+
+    if read < bytes.len() { return Err(CallError::Memory(address.wrapping_add(read as u64))) }
+    Ok(bytes)
+}
+
+/// Write bytes to guest memory.
+#[cfg(feature = "std")]
+fn store(memory: &mut dyn MemoryAccess, context: &Context, address: u64, bytes: &[u8]) -> Result<(), CallError> {
+    for (offset, byte) in bytes.iter().enumerate() {
+        let address = address.wrapping_add(offset as u64);
+        memory.set(Frame { address, size: number::Size::Byte }, context.virtual_mode, number::Data::Byte(*byte)).map_err(|_| CallError::Memory(address))?;
+    }
+
+    Ok(())
+}
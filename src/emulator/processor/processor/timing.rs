@@ -0,0 +1,207 @@
+//! Cycle costs of instructions.
+//!
+//! Every executed instruction costs the cycles of its operation plus the cycles of the addressing mode used by its
+//! dynamic operand. Costs are configurable so the performance of guest algorithms can be compared under different
+//! hardware assumptions.
+//!
+//! Memory is uniformly fast unless [Timing::memory] gives regions of physical memory a [Latency]. Each access the
+//! instruction makes to such a region then costs extra cycles, so the effect of placing data in slow or fast memory can
+//! be measured. Devices behind ports are given a latency through [Timing::ports].
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::emulator::processor::processor::timing::Latency;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let mut program = assemble("add.q r1, [64]\nadd.q r1, [128]\nhalt").unwrap();
+//! program.resize(136, 0);
+//!
+//! // Memory from 128 is slow, taking 10 cycles for each access plus a cycle for every 4 bytes.
+//! let mut core = Core::default();
+//! core.timing.memory.push(Latency { range: 128..256, read: 10, write: 10, bandwidth: 4 });
+//! core.run(&mut Memory::from(program), &mut Default::default(), None);
+//!
+//! assert_eq!(core.cycles, 3 + 3 + 1 + 12);
+//! ```
+
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ops::Range;
+use emulator::memory::{Frame, GetError, MemoryAccess};
+use number;
+use super::Ports;
+use super::instruction::Instruction;
+use super::instruction::operand::{CONSTANT_ADDRESSING, MEMORY_ADDRESSING, OFFSET_ADDRESSING, REGISTER_ADDRESSING};
+use super::instruction::operation::{ExtensionCode, OperationCode};
+use utility::{Coded, Map};
+
+/// Number of addressing modes the dynamic operand supports.
+pub const ADDRESSING_MODES: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    /// Cycles of specific operations keyed by their extension and operation codes.
+    pub operations: Map<(ExtensionCode, OperationCode), u64>,
+    /// Cycles of operations without an entry in [Timing::operations].
+    pub default_operation: u64,
+    /// Additional cycles of each addressing mode, indexed by the addressing code.
+    pub addressing: [u64; ADDRESSING_MODES],
+    /// Regions of physical memory which take extra cycles to access. When regions overlap, the first one containing an
+    /// access is used.
+    pub memory: Vec<Latency>,
+    /// Additional cycles of an instruction for each port it writes, indexed by the port.
+    pub ports: [u64; 8]
+}
+
+/// Extra cycles taken by accesses to a region of memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Latency {
+    /// Physical addresses of the region. An access is in the region if its first byte is.
+    pub range: Range<u64>,
+    /// Cycles before a read is answered.
+    pub read: u64,
+    /// Cycles before a write is accepted.
+    pub write: u64,
+    /// Bytes transferred each cycle after the latency, or 0 if the transfer itself takes no time.
+    pub bandwidth: u64
+}
+
+impl Latency {
+    /// Cycles an access of a number of bytes takes.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::timing::Latency;
+    ///
+    /// let latency = Latency { range: 0..64, read: 4, write: 1, bandwidth: 2 };
+    /// assert_eq!(latency.cost(false, 8), 8);
+    /// assert_eq!(latency.cost(true, 1), 2);
+    /// ```
+    pub fn cost(&self, write: bool, bytes: u64) -> u64 {
+        let latency = if write { self.write } else { self.read };
+        let transfer = if self.bandwidth == 0 { 0 } else { bytes.div_ceil(self.bandwidth) };
+        latency + transfer
+    }
+}
+
+impl Default for Timing {
+    /// Every operation costs a single cycle and dereferencing memory costs 2 more.
+    fn default() -> Self {
+        let mut addressing = [0; ADDRESSING_MODES];
+        addressing[REGISTER_ADDRESSING as usize] = 0;
+        addressing[OFFSET_ADDRESSING as usize] = 2;
+        addressing[CONSTANT_ADDRESSING as usize] = 0;
+        addressing[MEMORY_ADDRESSING as usize] = 2;
+
+        Self {
+            operations: Map::new(),
+            default_operation: 1,
+            addressing,
+            memory: Vec::new(),
+            ports: [0; 8]
+        }
+    }
+}
+
+impl Timing {
+    /// Number of cycles it takes to execute an instruction.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::Instruction;
+    /// use atln_processor::emulator::processor::processor::instruction::operation::{ARITHMETIC_CODE, Extension};
+    /// use atln_processor::emulator::processor::processor::instruction::operation::arithmetic::ADD_CODE;
+    /// use atln_processor::emulator::processor::processor::timing::Timing;
+    /// use std::io::Cursor;
+    ///
+    /// // add.b r1, [4]
+    /// let instruction = Instruction::decode(&mut Cursor::new([0b000000_0_0, 0b0000_11_00, 0b00_001_000, 4])).unwrap();
+    /// let mut timing = Timing::default();
+    /// assert_eq!(timing.cost(&instruction), 3);
+    ///
+    /// timing.operations.insert((ARITHMETIC_CODE, ADD_CODE), 4);
+    /// assert_eq!(timing.cost(&instruction), 6);
+    /// ```
+    pub fn cost(&self, instruction: &Instruction) -> u64 {
+        let extension = instruction.extension();
+        let operation = self.operations
+            .get(&(extension.code(), extension.operation_code()))
+            .copied()
+            .unwrap_or(self.default_operation);
+
+        let addressing = instruction.data().as_ref()
+            .and_then(|data| data.operands.x_dynamic())
+            .and_then(|x_dynamic| self.addressing.get(x_dynamic.addressing() as usize))
+            .copied()
+            .unwrap_or(0);
+
+        operation + addressing
+    }
+
+    /// Cycles an access to memory takes beyond the cost of its instruction.
+    pub fn access(&self, address: u64, write: bool, bytes: u64) -> u64 {
+        access(&self.memory, address, write, bytes)
+    }
+
+    /// Cycles taken by the ports which changed value.
+    pub fn port_writes(&self, before: &Ports, after: &Ports) -> u64 {
+        before.iter().zip(after).zip(self.ports).filter(|((before, after), _)| before != after).map(|(_, cost)| cost).sum()
+    }
+}
+
+/// Cycles an access takes in the first region containing it.
+fn access(regions: &[Latency], address: u64, write: bool, bytes: u64) -> u64 {
+    regions.iter()
+        .find(|latency| latency.range.contains(&address))
+        .map_or(0, |latency| latency.cost(write, bytes))
+}
+
+/// Memory whose accesses add the latency of their region to a count of cycles. Virtual addresses are translated here
+/// so the latency is of the physical memory accessed.
+pub(crate) struct Timed<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub regions: &'a [Latency],
+    pub cycles: &'a Cell<u64>
+}
+
+impl<'a> Timed<'a> {
+    /// Translate a frame to a physical one and count the cycles of accessing it. Alignment is checked first like memory
+    /// does.
+    fn physical(&self, frame: Frame, r#virtual: bool, write: bool) -> Result<Frame, GetError> {
+        let frame = if r#virtual {
+            if !frame.is_aligned() { return Err(GetError::UnalignedFrame) }
+            Frame { address: self.memory.translate_virtual(frame.address).ok_or(GetError::PageFault)?, ..frame }
+        } else {
+            frame
+        };
+
+        let cost = access(self.regions, frame.address, write, frame.size.size() as u64);
+        self.cycles.set(self.cycles.get().wrapping_add(cost));
+        Ok(frame)
+    }
+}
+
+impl<'a> MemoryAccess for Timed<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let frame = self.physical(frame, r#virtual, false)?;
+        self.memory.get(frame, false)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        let frame = self.physical(frame, r#virtual, true)?;
+        self.memory.set(frame, false, value)
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let (regions, cycles) = (self.regions, self.cycles);
+        self.memory.synchronise(&mut |memory| operation(&mut Timed { memory, regions, cycles }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
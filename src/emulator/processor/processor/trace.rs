@@ -0,0 +1,255 @@
+//! Export of the instructions a core executes, for analysis scripts and trace viewers outside the emulator.
+//!
+//! Once a [Tracer] is set as [Core::tracer](super::Core::tracer), every instruction fetched by
+//! [Core::step](super::Core::step) or executed in a block is written as a [Record] on its own line of JSON. A record
+//! holds the state after the instruction executed, along with the memory accesses it made in order.
+//!
+//! ```text
+//! {"core":0,"address":4,"instruction":"add.q [64], r1","registers":[0,5,0,0,0,0,0,0],"flags":{"zero":false,"carry":false,"sign":false,"overflow":false},"next":8,"cycles":4,"memory":[{"write":false,"address":64,"size":8,"value":0},{"write":true,"address":64,"size":8,"value":5}],"status":"running"}
+//! ```
+//!
+//! The status is `running`, `halted` or `faulted`, and faulted records also hold the exception as text in `fault`. The
+//! next address is where the core continues from, which is the address of the instruction itself if it faulted.
+//! Accesses are recorded as they leave the core, so stores held in a [StoreBuffer](super::ordering::StoreBuffer) are
+//! recorded once they drain, and only accesses which succeeded are recorded. Instructions run as native code with the
+//! `jit` feature are not recorded.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write as _;
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use emulator::memory::{Frame, GetError, MemoryAccess};
+#[cfg(feature = "std")]
+use number;
+use super::{Context, Registers, Status};
+use super::instruction::Instruction;
+use super::instruction::operation::condition::Flags;
+
+/// A successful memory access made by an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Access {
+    pub write: bool,
+    pub address: u64,
+    /// Number of bytes accessed.
+    pub size: u8,
+    /// Value read or written.
+    pub value: u64
+}
+
+/// An executed instruction and the state of the core after it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Record {
+    /// Identifier of the core which executed the instruction.
+    pub core: u64,
+    pub address: u64,
+    /// Assembly form of the instruction.
+    pub instruction: String,
+    pub registers: Registers,
+    pub flags: Flags,
+    /// Address the core continues from.
+    pub next: u64,
+    /// Cycles of the core after the instruction.
+    pub cycles: u64,
+    pub memory: Vec<Access>,
+    pub halted: bool,
+    /// Exception the instruction faulted with, as text.
+    pub fault: Option<String>
+}
+
+impl Record {
+    pub fn new(address: u64, instruction: &Instruction, context: &Context, cycles: u64, memory: Vec<Access>, status: &Status) -> Self {
+        let fault = match status {
+            Status::Faulted(exception) => Some(exception.to_string()),
+            _ => None
+        };
+
+        Self {
+            core: context.identifier,
+            address,
+            instruction: instruction.to_string(),
+            registers: context.registers,
+            flags: context.flags,
+            next: if fault.is_some() { address } else { context.program_counter },
+            cycles,
+            memory,
+            halted: matches!(status, Status::Halted),
+            fault
+        }
+    }
+
+    /// Encode as a single line of JSON without the line break.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::trace::{Access, Record};
+    ///
+    /// let record = Record {
+    ///     instruction: "add.b r1, [8]".into(),
+    ///     memory: vec![Access { write: false, address: 8, size: 1, value: 2 }],
+    ///     fault: Some("\"quoted\"".into()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(record.to_json(), concat!(
+    ///     r#"{"core":0,"address":0,"instruction":"add.b r1, [8]","registers":[0,0,0,0,0,0,0,0],"#,
+    ///     r#""flags":{"zero":false,"carry":false,"sign":false,"overflow":false},"next":0,"cycles":0,"#,
+    ///     r#""memory":[{"write":false,"address":8,"size":1,"value":2}],"status":"faulted","fault":"\"quoted\""}"#
+    /// ));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"core\":{},\"address\":{},\"instruction\":", self.core, self.address);
+        string(&mut json, &self.instruction);
+
+        let registers = self.registers.iter().map(u64::to_string).collect::<Vec<_>>().join(",");
+        let Flags { zero, carry, sign, overflow } = self.flags;
+        let _ = write!(json, ",\"registers\":[{registers}],\"flags\":{{\"zero\":{zero},\"carry\":{carry},\"sign\":{sign},\"overflow\":{overflow}}}");
+        let _ = write!(json, ",\"next\":{},\"cycles\":{},\"memory\":[", self.next, self.cycles);
+
+        for (index, access) in self.memory.iter().enumerate() {
+            if index > 0 { json.push(','); }
+            let _ = write!(json, "{{\"write\":{},\"address\":{},\"size\":{},\"value\":{}}}", access.write, access.address, access.size, access.value);
+        }
+
+        json.push_str("],\"status\":");
+        match &self.fault {
+            Some(fault) => {
+                json.push_str("\"faulted\",\"fault\":");
+                string(&mut json, fault);
+            },
+            None => json.push_str(if self.halted { "\"halted\"" } else { "\"running\"" })
+        }
+
+        json.push('}');
+        json
+    }
+}
+
+/// Append text as a quoted JSON string.
+fn string(json: &mut String, text: &str) {
+    json.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            character if character.is_control() => { let _ = write!(json, "\\u{:04x}", character as u32); },
+            character => json.push(character)
+        }
+    }
+
+    json.push('"');
+}
+
+/// Writes records as JSON lines. A core uses it once it is set as [Core::tracer](super::Core::tracer).
+/// ```
+/// use std::io;
+/// use std::sync::{Arc, Mutex};
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::emulator::processor::processor::trace::Tracer;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let tracer = Arc::new(Mutex::new(Tracer::new(Box::new(io::sink()))));
+/// let mut core = Core::default();
+/// core.tracer = Some(tracer.clone());
+/// core.run(&mut Memory::from(assemble("add.b r1, 1\nhalt").unwrap()), &mut Default::default(), None);
+///
+/// let mut tracer = tracer.lock().unwrap();
+/// assert_eq!(tracer.records, 2);
+/// tracer.finish().unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub struct Tracer {
+    pub output: Box<dyn Write + Send>,
+    /// Number of records written.
+    pub records: u64,
+    /// First error writing to the output, after which nothing more is written.
+    pub error: Option<io::Error>
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tracer")
+            .field("records", &self.records)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Tracer {
+    pub fn new(output: Box<dyn Write + Send>) -> Self {
+        Self { output, records: 0, error: None }
+    }
+
+    /// Write a record on its own line.
+    pub fn record(&mut self, record: &Record) {
+        if self.error.is_some() { return }
+
+        match writeln!(self.output, "{}", record.to_json()) {
+            Ok(()) => self.records += 1,
+            Err(error) => self.error = Some(error)
+        }
+    }
+
+    /// Flush the output, returning the first error that occurred while writing if there was one.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() { return Err(error) }
+        self.output.flush()
+    }
+}
+
+/// Memory seen by an instruction while it is traced, which records the accesses that succeed.
+#[cfg(feature = "std")]
+pub(crate) struct Recorded<'a> {
+    pub memory: &'a mut dyn MemoryAccess,
+    pub accesses: &'a RefCell<Vec<Access>>
+}
+
+#[cfg(feature = "std")]
+impl<'a> Recorded<'a> {
+    fn record(&self, frame: &Frame, write: bool, value: &number::Data) {
+        self.accesses.borrow_mut().push(Access { write, address: frame.address, size: frame.size.size(), value: value.quad() });
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> MemoryAccess for Recorded<'a> {
+    fn get(&self, frame: Frame, r#virtual: bool) -> Result<number::Data, GetError> {
+        let value = self.memory.get(frame.clone(), r#virtual)?;
+        self.record(&frame, false, &value);
+        Ok(value)
+    }
+
+    fn set(&mut self, frame: Frame, r#virtual: bool, value: number::Data) -> Result<(), GetError> {
+        self.memory.set(frame.clone(), r#virtual, value.clone())?;
+        self.record(&frame, true, &value);
+        Ok(())
+    }
+
+    fn translate_virtual(&self, r#virtual: u64) -> Option<u64> {
+        self.memory.translate_virtual(r#virtual)
+    }
+
+    fn write_count(&self, address: u64) -> u64 {
+        self.memory.write_count(address)
+    }
+
+    fn synchronise(&mut self, operation: &mut dyn FnMut(&mut dyn MemoryAccess)) {
+        let accesses = self.accesses;
+        self.memory.synchronise(&mut |memory| operation(&mut Recorded { memory, accesses }))
+    }
+
+    fn fence(&mut self) {
+        self.memory.fence()
+    }
+}
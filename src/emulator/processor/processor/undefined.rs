@@ -0,0 +1,68 @@
+//! Emulating instructions in software when their extension or operation code does not exist.
+//!
+//! Once [Core::step](super::Core::step) fails to decode an instruction because of an
+//! [invalid code](super::instruction::DecodeError::InvalidCode), it passes the instruction to the [Hook] set as
+//! [Core::undefined](super::Core::undefined) before faulting. The handler receives the two driver bytes as they are
+//! encoded, without extension prefixes applied, and up to
+//! [MAX_INSTRUCTION_BYTES](super::instruction::MAX_INSTRUCTION_BYTES) bytes from the start of the instruction including
+//! its prefixes. It can then emulate the instruction, such as one added to a later version of the architecture, or
+//! decline and let the core fault. Emulated instructions do not count towards the cycles or performance counters of the
+//! core.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use emulator::memory::MemoryAccess;
+use super::{Context, Ports};
+
+/// Emulates instructions which could not be decoded. This is implemented for closures taking the same arguments.
+pub trait Handler: Send + Sync {
+    /// Emulate the instruction at the program counter. Returns the number of bytes the instruction occupies, which the
+    /// program counter is advanced by, or [None] to fault. A handler emulating a diverting instruction sets the program
+    /// counter itself and returns 0.
+    fn handle(&self, driver: [u8; 2], stream: &[u8], context: &mut Context, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Option<u64>;
+}
+
+impl<F> Handler for F where F: Fn([u8; 2], &[u8], &mut Context, &mut dyn MemoryAccess, &mut Ports) -> Option<u64> + Send + Sync {
+    fn handle(&self, driver: [u8; 2], stream: &[u8], context: &mut Context, memory: &mut dyn MemoryAccess, ports: &mut Ports) -> Option<u64> {
+        self(driver, stream, context, memory, ports)
+    }
+}
+
+/// Handler shared by a core and its clones.
+/// ```
+/// use atln_processor::emulator::memory::{Memory, MemoryAccess};
+/// use atln_processor::emulator::processor::processor::{Context, Core, Ports, Status};
+/// use atln_processor::emulator::processor::processor::undefined::Hook;
+///
+/// // Extension 9 does not exist, so it is emulated with operation 0 incrementing r1. Halt follows.
+/// let mut memory = Memory::from(vec![0b001001_0_0, 0, 0b001001_0_0, 0, 0b000010_0_0, 0]);
+/// let mut core = Core::default();
+/// core.undefined = Some(Hook::new(|driver: [u8; 2], _: &[u8], context: &mut Context, _: &mut dyn MemoryAccess, _: &mut Ports| {
+///     if driver != [0b001001_0_0, 0] { return None }
+///     context.registers[1] += 1;
+///     Some(2)
+/// }));
+///
+/// assert!(matches!(core.run(&mut memory, &mut Default::default(), None), Status::Halted));
+/// assert_eq!(core.context.registers[1], 2);
+///
+/// // Without the handler, the instruction faults.
+/// core.undefined = None;
+/// core.context.program_counter = 0;
+/// assert!(matches!(core.step(&mut memory, &mut Default::default()), Status::Faulted(_)));
+/// ```
+#[derive(Clone)]
+pub struct Hook(pub Arc<dyn Handler>);
+
+impl Hook {
+    pub fn new(handler: impl Handler + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl Debug for Hook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
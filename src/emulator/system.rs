@@ -0,0 +1,246 @@
+//! Multiple cores sharing memory and ports.
+//!
+//! # Synchronisation
+//! Instructions with the synchronise bit set must not have their memory accesses interleaved with those of other
+//! synchronised instructions. Under [Schedule::RoundRobin] the cores take turns on a single host thread, so every
+//! instruction is atomic. Under [Schedule::Threaded] the memory is moved into [SharedMemory] for the duration of the run
+//! and the cores access it concurrently. Ordinary accesses are individually atomic and synchronised instructions hold
+//! the lock of the shared memory while they execute. Ports are read before and written back after every instruction,
+//! so each port is only ever updated as a whole byte.
+//!
+//! What cores may observe of each other's accesses is described by the
+//! [memory ordering model](crate::emulator::processor::processor::ordering). Giving cores a
+//! [StoreBuffer](crate::emulator::processor::processor::ordering::StoreBuffer) simulates the weakest ordering it allows.
+//!
+//! # Inter-processor interrupts
+//! A core interrupts another by executing `signal` with the index of the target core, which raises
+//! [INTER_PROCESSOR_VECTOR] on the target. Under [Schedule::RoundRobin] signals are delivered at the end of the sending
+//! core's turn. Under [Schedule::Threaded] they are posted to the target's mailbox and picked up before its next
+//! instruction. Signals for cores that do not exist are ignored, and so are signals for cores that already stopped
+//! until they are restarted by the host. There is no memory mapped doorbell; guest software uses the operation instead.
+//!
+//! # Background execution
+//! With the `std` feature, [System::spawn] runs a system on its own thread and returns a
+//! [Controller](controller::Controller) which pauses, inspects and interrupts it from other threads.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use core::mem;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+#[cfg(feature = "std")]
+use std::thread;
+use emulator::event::{Event, Subscriber};
+use emulator::memory::Memory;
+#[cfg(feature = "std")]
+use emulator::memory::shared::SharedMemory;
+use emulator::processor::processor::{Budget, Core, Ports, Status};
+use emulator::processor::processor::interrupt::INTER_PROCESSOR_VECTOR;
+
+#[cfg(feature = "std")]
+pub mod controller;
+
+/// How cores are given time to execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    /// Cores take turns on the calling thread, each executing up to this many instructions per turn.
+    RoundRobin(u64),
+    /// Every core runs on its own host thread, concurrently with the others. Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    Threaded
+}
+
+/// Cores sharing a memory and ports.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Status;
+/// use atln_processor::emulator::system::{Schedule, System};
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // Every core adds its r2 into the same quad in memory.
+/// let mut program = assemble("sync add.q [16], r2\nhalt").unwrap();
+/// program.resize(24, 0);
+///
+/// for schedule in [Schedule::RoundRobin(1), Schedule::Threaded] {
+///     let mut system = System::new(4, Memory::from(program.clone()));
+///     for (index, core) in system.cores.iter_mut().enumerate() { core.context.registers[2] = index as u64 + 1; }
+///
+///     system.run(schedule);
+///
+///     assert!(system.statuses().iter().all(|status| matches!(status, Status::Halted)));
+///     assert_eq!(system.memory.bytes[16], 1 + 2 + 3 + 4);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct System {
+    pub cores: Vec<Core>,
+    pub memory: Memory,
+    pub ports: Ports,
+    /// Status of each core after its last instruction.
+    statuses: Vec<Status>
+}
+
+impl System {
+    /// Create a system with a number of cores which all start executing from address 0. Each core is identified by its
+    /// index.
+    pub fn new(cores: usize, memory: Memory) -> Self {
+        Self {
+            cores: (0..cores).map(|index| {
+                let mut core = Core::default();
+                core.context.identifier = index as u64;
+                core
+            }).collect(),
+            memory,
+            ports: Ports::default(),
+            statuses: (0..cores).map(|_| Status::Running).collect()
+        }
+    }
+
+    /// Status of each core. [Status::Running] is reported for cores that have not executed yet or whose turn ended before
+    /// they stopped.
+    pub fn statuses(&self) -> &[Status] {
+        &self.statuses
+    }
+
+    /// Whether any core can keep executing.
+    pub fn is_running(&self) -> bool {
+        self.statuses.iter().any(Status::is_running)
+    }
+
+    /// Call a function with the [events](crate::emulator::event) of every core. Cores added later are not subscribed.
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use atln_processor::emulator::event::Event;
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::system::{Schedule, System};
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let halted = Arc::new(Mutex::new(Vec::new()));
+    /// let received = halted.clone();
+    ///
+    /// let mut system = System::new(2, Memory::from(assemble("halt").unwrap()));
+    /// system.subscribe(move |event| if let Event::Halt { core } = event { received.lock().unwrap().push(*core) });
+    /// system.run(Schedule::RoundRobin(1));
+    ///
+    /// assert_eq!(*halted.lock().unwrap(), [0, 1]);
+    /// ```
+    pub fn subscribe(&mut self, subscriber: impl Fn(&Event) + Send + Sync + 'static) {
+        let subscriber: Subscriber = Arc::new(subscriber);
+        for core in &mut self.cores { core.events.add(subscriber.clone()); }
+    }
+
+    /// Raise [INTER_PROCESSOR_VECTOR] on a core, as if another core had signalled it. Returns whether the core exists.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::system::System;
+    ///
+    /// let mut system = System::new(2, Memory::from(vec![0; 8]));
+    /// assert!(system.signal(1));
+    /// assert!(!system.signal(2));
+    /// assert_eq!(system.cores[1].context.interrupts.pending, 1);
+    /// ```
+    pub fn signal(&mut self, target: u64) -> bool {
+        let Some(core) = usize::try_from(target).ok().and_then(|target| self.cores.get_mut(target)) else { return false };
+        core.context.interrupts.raise(INTER_PROCESSOR_VECTOR);
+        true
+    }
+
+    /// Give every running core a turn of up to `quantum` instructions, in order of their index. Returns whether any core
+    /// can keep executing.
+    pub fn round(&mut self, quantum: u64) -> bool {
+        for index in 0..self.cores.len() {
+            if !self.statuses[index].is_running() { continue }
+
+            let core = &mut self.cores[index];
+            self.statuses[index] = match core.run(&mut self.memory, &mut self.ports, Some(Budget::Instructions(quantum))) {
+                Status::BudgetExhausted => Status::Running,
+                stopped => stopped
+            };
+
+            for target in core::mem::take(&mut self.cores[index].context.interrupts.signals) { self.signal(target); }
+        }
+
+        self.is_running()
+    }
+
+    /// Execute until every core halts or faults.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Status;
+    /// use atln_processor::emulator::system::{Schedule, System};
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// // Core 0 signals core 1, which spins until its interrupt handler at 24 halts it.
+    /// let mut program = assemble("signal r1\nhalt").unwrap();
+    /// program.resize(16, 0);
+    /// program.extend(assemble("divert r3").unwrap());
+    /// program.resize(24, 0);
+    /// program.extend(assemble("add.b r2, 1\nhalt").unwrap());
+    /// program.resize(40, 0);
+    /// program.extend(24u64.to_le_bytes());
+    ///
+    /// for schedule in [Schedule::RoundRobin(1), Schedule::Threaded] {
+    ///     let mut system = System::new(2, Memory::from(program.clone()));
+    ///     system.cores[0].context.registers[1] = 1;
+    ///
+    ///     let waiting = &mut system.cores[1].context;
+    ///     waiting.program_counter = 16;
+    ///     waiting.registers[3] = 16;
+    ///     waiting.interrupts.table = 40;
+    ///     waiting.interrupts.enabled = true;
+    ///
+    ///     system.run(schedule);
+    ///
+    ///     assert!(system.statuses().iter().all(|status| matches!(status, Status::Halted)));
+    ///     assert_eq!(system.cores[1].context.registers[2], 1);
+    /// }
+    /// ```
+    pub fn run(&mut self, schedule: Schedule) {
+        match schedule {
+            Schedule::RoundRobin(quantum) => while self.round(quantum) {},
+            #[cfg(feature = "std")]
+            Schedule::Threaded => self.run_threaded()
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn run_threaded(&mut self) {
+        let memory = SharedMemory::from(mem::take(&mut self.memory));
+        let ports = self.ports.map(AtomicU8::new);
+        let mailboxes: Vec<AtomicU64> = self.cores.iter().map(|_| AtomicU64::new(0)).collect();
+        let cores = self.cores.iter_mut().zip(&mut self.statuses).zip(&mailboxes);
+
+        thread::scope(|scope| {
+            for ((core, status), mailbox) in cores {
+                let mut memory = memory.clone();
+                let ports = &ports;
+                let mailboxes = &mailboxes;
+
+                scope.spawn(move || {
+                    while status.is_running() {
+                        core.context.interrupts.pending |= mailbox.swap(0, Ordering::AcqRel);
+
+                        let before: Ports = ports.each_ref().map(|port| port.load(Ordering::Acquire));
+                        let mut after = before;
+                        *status = core.step(&mut memory, &mut after);
+
+                        for ((port, before), after) in ports.iter().zip(before).zip(after) {
+                            if before != after { port.store(after, Ordering::Release); }
+                        }
+
+                        for target in core.context.interrupts.signals.drain(..) {
+                            let Some(target) = usize::try_from(target).ok().and_then(|target| mailboxes.get(target)) else { continue };
+                            target.fetch_or(1 << INTER_PROCESSOR_VECTOR, Ordering::AcqRel);
+                        }
+                    }
+                });
+            }
+        });
+
+        for (core, mailbox) in self.cores.iter_mut().zip(mailboxes) { core.context.interrupts.pending |= mailbox.into_inner(); }
+        self.memory = memory.to_memory();
+        self.ports = ports.map(AtomicU8::into_inner);
+    }
+}
@@ -0,0 +1,180 @@
+//! Running a system on a background thread which is controlled through a channel.
+//!
+//! [System::spawn] moves a system onto its own thread, where the cores take turns in the same way as under
+//! [Schedule::RoundRobin](super::Schedule::RoundRobin). The returned [Controller] sends commands which the thread
+//! handles between rounds, so a user interface can pause, inspect and interrupt the guest without waiting for it to
+//! halt. Once every core has stopped, or while the system is paused, the thread sleeps until the next command.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use std::panic;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::thread;
+use std::thread::JoinHandle;
+use emulator::processor::processor::Context;
+use super::System;
+
+/// State of a spawned system at the moment it was queried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub paused: bool,
+    /// Whether any core can keep executing.
+    pub running: bool,
+    /// Execution context of each core.
+    pub contexts: Vec<Context>,
+    /// Cycles spent by each core.
+    pub cycles: Vec<u64>
+}
+
+/// Request handled by the thread of a spawned system.
+#[derive(Debug)]
+enum Command {
+    Pause,
+    Resume,
+    Stop,
+    /// Raise a vector on a core.
+    Interrupt { core: u64, vector: u8 },
+    State(Sender<State>),
+    /// Read bytes of memory at an address.
+    Memory { address: u64, length: usize, reply: Sender<Vec<u8>> }
+}
+
+/// Handle to a system running on a background thread. Dropping the controller stops the thread without returning the
+/// system.
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Status;
+/// use atln_processor::emulator::system::System;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// // The core spins until the handler of vector 2 at 16 halts it. The handler table is at 32.
+/// let mut program = assemble("divert r3").unwrap();
+/// program.resize(16, 0);
+/// program.extend(assemble("add.b r2, 1\nhalt").unwrap());
+/// program.resize(48, 0);
+/// program.extend(16u64.to_le_bytes());
+///
+/// let mut system = System::new(1, Memory::from(program));
+/// system.cores[0].context.interrupts.table = 32;
+/// system.cores[0].context.interrupts.enabled = true;
+///
+/// let controller = system.spawn(64);
+/// controller.pause();
+/// assert!(controller.state().unwrap().paused);
+/// assert_eq!(controller.read_memory(48, 1).unwrap(), [16]);
+///
+/// controller.inject_interrupt(0, 2);
+/// controller.resume();
+/// while controller.state().unwrap().running { thread::sleep(Duration::from_millis(1)); }
+///
+/// let system = controller.stop();
+/// assert!(matches!(system.statuses()[0], Status::Halted));
+/// assert_eq!(system.cores[0].context.registers[2], 1);
+/// ```
+#[derive(Debug)]
+pub struct Controller {
+    commands: Sender<Command>,
+    thread: JoinHandle<System>
+}
+
+impl Controller {
+    /// Stop executing after the current round, until [Controller::resume] is called.
+    pub fn pause(&self) {
+        let _ = self.commands.send(Command::Pause);
+    }
+
+    pub fn resume(&self) {
+        let _ = self.commands.send(Command::Resume);
+    }
+
+    /// Raise an interrupt vector on a core between rounds. Cores that do not exist are ignored, and so are cores that
+    /// already stopped.
+    pub fn inject_interrupt(&self, core: u64, vector: u8) {
+        let _ = self.commands.send(Command::Interrupt { core, vector });
+    }
+
+    /// State of the system once the current round ends, or [None] if the thread is no longer running because it
+    /// panicked.
+    pub fn state(&self) -> Option<State> {
+        let (reply, state) = mpsc::channel();
+        self.commands.send(Command::State(reply)).ok()?;
+        state.recv().ok()
+    }
+
+    /// Bytes of memory starting at a physical address, once the current round ends. Fewer bytes are returned if the
+    /// memory ends first, and [None] if the thread is no longer running.
+    pub fn read_memory(&self, address: u64, length: usize) -> Option<Vec<u8>> {
+        let (reply, bytes) = mpsc::channel();
+        self.commands.send(Command::Memory { address, length, reply }).ok()?;
+        bytes.recv().ok()
+    }
+
+    /// Stop the thread after the current round and return the system. A panic on the thread is resumed on the caller.
+    pub fn stop(self) -> System {
+        let _ = self.commands.send(Command::Stop);
+        self.thread.join().unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+}
+
+impl System {
+    /// Move the system onto a background thread where it starts executing immediately, with each core executing up to
+    /// `quantum` instructions per turn. See [controller](crate::emulator::system::controller).
+    pub fn spawn(self, quantum: u64) -> Controller {
+        let (commands, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || self.serve(quantum, receiver));
+        Controller { commands, thread }
+    }
+
+    /// Execute rounds while handling commands until told to stop or the controller is dropped.
+    fn serve(mut self, quantum: u64, commands: Receiver<Command>) -> Self {
+        let mut paused = false;
+
+        loop {
+            let command = if paused || !self.is_running() {
+                match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break
+                }
+            } else {
+                match commands.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => break
+                }
+            };
+
+            match command {
+                Some(Command::Pause) => paused = true,
+                Some(Command::Resume) => paused = false,
+                Some(Command::Stop) => break,
+                Some(Command::Interrupt { core, vector }) => {
+                    let core = usize::try_from(core).ok().and_then(|core| self.cores.get_mut(core));
+                    if let Some(core) = core { core.context.interrupts.raise(vector); }
+                },
+                Some(Command::State(reply)) => { let _ = reply.send(self.state(paused)); },
+                Some(Command::Memory { address, length, reply }) => {
+                    let mut bytes = vec![0; length];
+                    let read = self.memory.read_bytes(address, false, &mut bytes);
+                    bytes.truncate(read);
+                    let _ = reply.send(bytes);
+                },
+                None => { self.round(quantum); }
+            }
+        }
+
+        self
+    }
+
+    fn state(&self, paused: bool) -> State {
+        State {
+            paused,
+            running: self.is_running(),
+            contexts: self.cores.iter().map(|core| core.context.clone()).collect(),
+            cycles: self.cores.iter().map(|core| core.cycles).collect()
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Golden test vectors which record how this emulator executes single instructions, for other implementations of the
+//! instruction set to check themselves against.
+//!
+//! A vector is a [conformance] test. Its program is a preamble which loads every register with a fixed value, the
+//! instruction under test and a halt, followed by a block of data at [DATA_ADDRESS] that the registers point into.
+//! Its manifest starts with comments holding the assembly and encoding of the instruction, followed by the complete
+//! state the core finished in: the status, every register, the program counter, the flags and every range of memory
+//! that differs from the loaded program. The directories written by [write_directory] can be checked with
+//! [conformance::run_directory] or the `atln test` command.
+//!
+//! [enumerate] covers every operation with every width and a representative operand of each addressing mode, and
+//! [sample] draws random instructions from a seed, so the same seed always produces the same vectors. Only instructions
+//! which decode back into themselves are generated. The values read by `rdctr` and `cpuid` are those of this emulator,
+//! so implementations with other counters or identifiers can skip those vectors.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use emulator::conformance;
+use emulator::conformance::{Expectation, Outcome, MANIFEST_EXTENSION, PROGRAM_EXTENSION};
+use emulator::diff;
+use emulator::processor::processor::Registers;
+use emulator::processor::processor::instruction::Instruction;
+use emulator::processor::processor::instruction::builder::InstructionBuilder;
+use emulator::processor::processor::instruction::operand::{Dynamic, Indexed, Offset};
+use emulator::processor::processor::instruction::operation::metadata;
+use emulator::processor::processor::instruction::operation::Extension;
+use number;
+use number::Size;
+use programming::assembler::assemble;
+use utility::{splitmix64, Encodable};
+
+/// Memory given to every vector. Addresses past it fault, so immediates are just as likely to fault as not.
+pub const MEMORY_BYTES: usize = 0x1000;
+/// Instructions a vector may execute, which leaves room for an instruction that diverts into the data and keeps going.
+pub const BUDGET: u64 = 64;
+/// Address of the data the registers point into.
+pub const DATA_ADDRESS: u64 = 0x100;
+/// Bytes of data at [DATA_ADDRESS].
+pub const DATA_BYTES: usize = 0x200;
+
+/// Value every register holds before the instruction under test, an address inside the data.
+pub fn register(index: u8) -> u64 {
+    DATA_ADDRESS + 0x10 * index as u64
+}
+
+/// Data at [DATA_ADDRESS], a pattern with no two neighbouring bytes alike.
+fn data() -> Vec<u8> {
+    (0..DATA_BYTES).map(|index| (index as u8).wrapping_mul(0x9D) ^ 0x5A).collect()
+}
+
+/// An instruction together with the state it finishes in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vector {
+    /// Name of the files the vector is written to.
+    pub name: String,
+    pub instruction: Instruction,
+    /// Encoding of the instruction.
+    pub bytes: Vec<u8>,
+    /// Flat program loaded at address 0.
+    pub program: Vec<u8>,
+    pub expectation: Expectation
+}
+
+impl Vector {
+    /// Run the instruction from the fixed initial state and record the state it finishes in.
+    /// ```
+    /// use atln_processor::emulator::conformance::{check, Outcome};
+    /// use atln_processor::emulator::vectors::{register, Vector};
+    /// use atln_processor::emulator::processor::processor::instruction::Instruction;
+    /// use atln_processor::programming::assembler::assemble;
+    ///
+    /// let (instruction, _) = Instruction::decode_slice(&assemble("add.q r1, r2").unwrap()).unwrap();
+    /// let vector = Vector::new("add".into(), instruction);
+    ///
+    /// assert_eq!(vector.expectation.outcome, Outcome::Halted);
+    /// assert!(vector.expectation.registers.contains(&(1, register(1) + register(2))));
+    /// assert!(vector.manifest().starts_with("# add.q r1, r2\n# 00 00 ca\n"));
+    /// assert_eq!(check(&vector.program, &vector.expectation), Ok(vec![]));
+    /// ```
+    pub fn new(name: String, instruction: Instruction) -> Self {
+        let bytes = instruction.encode();
+
+        let mut preamble = String::new();
+        for index in 0..Registers::default().len() as u8 { let _ = writeln!(preamble, "add.q r{index}, {}", register(index)); }
+
+        let mut program = assemble(&preamble).expect("Preamble should assemble");
+        program.extend(&bytes);
+        program.extend(assemble("halt").expect("Halt should assemble"));
+        program.resize(DATA_ADDRESS as usize, 0);
+        program.extend(data());
+
+        let (core, memory, status) = conformance::execute(&program, MEMORY_BYTES, BUDGET).expect("Program should fit in memory");
+        let mut loaded = program.clone();
+        loaded.resize(MEMORY_BYTES, 0);
+
+        let expectation = Expectation {
+            memory_bytes: MEMORY_BYTES,
+            budget: BUDGET,
+            outcome: Outcome::of(&status),
+            registers: core.context.registers.iter().enumerate().map(|(index, &value)| (index as u8, value)).collect(),
+            program_counter: Some(core.context.program_counter),
+            flags: Some(core.context.flags),
+            memory: diff::memory(0, &loaded, &memory.bytes).into_iter().map(|range| (range.address, range.after)).collect()
+        };
+
+        Self { name, instruction, bytes, program, expectation }
+    }
+
+    /// Manifest of the vector, with the assembly and encoding of the instruction as comments.
+    pub fn manifest(&self) -> String {
+        let mut manifest = format!("# {}\n#", self.instruction);
+        for byte in &self.bytes { let _ = write!(manifest, " {byte:02x}"); }
+        let _ = write!(manifest, "\n{}", self.expectation);
+        manifest
+    }
+}
+
+/// Whether an instruction decodes back into itself, which is what makes its encoding valid.
+fn canonical(instruction: &Instruction) -> bool {
+    Instruction::decode_slice(&instruction.encode()).is_ok_and(|(decoded, _)| decoded == *instruction)
+}
+
+/// Instruction of an operation with a width and dynamic operand, or [None] if the combination is not valid. The result
+/// goes where the dynamic operand points when `dynamic_destination` is set.
+fn instruction(extension: Extension, width: Size, x_static: u8, x_dynamic: Dynamic, dynamic_destination: bool, synchronous: bool) -> Option<Instruction> {
+    let presence = extension.presence();
+    let mut builder = InstructionBuilder::new().extension(extension);
+
+    if let Some(presence) = presence {
+        builder = builder.width(width);
+        if presence.expects_static() { builder = builder.static_register(x_static); }
+        if presence.expects_dynamic() { builder = builder.dynamic(x_dynamic); }
+        if dynamic_destination { builder = builder.destination_dynamic(); }
+        if synchronous { builder = builder.synchronous(); }
+    } else if dynamic_destination || synchronous || width != Size::Byte {
+        return None;
+    }
+
+    builder.build().ok().filter(canonical)
+}
+
+/// Dynamic operands of every addressing mode, pointing into the data wherever they address memory.
+fn dynamics() -> Vec<Dynamic> {
+    vec![
+        Dynamic::Register(2),
+        Dynamic::Offset(Offset { register: 3, offset: number::Data::Byte(4) }),
+        Dynamic::Constant(number::Data::Byte(0x7F)),
+        Dynamic::Signed(number::Data::Byte(0x80)),
+        Dynamic::Memory(number::Data::Word(DATA_ADDRESS as u16 + 0xA0)),
+        Dynamic::Relative(number::Data::Word(DATA_ADDRESS as u16)),
+        Dynamic::PostIncrement(4),
+        Dynamic::PreDecrement(5),
+        Dynamic::Indexed(Indexed { base: 6, index: 1 })
+    ]
+}
+
+/// Every operation with every width and addressing mode, with the result stored in the static operand and, where
+/// possible, where the dynamic operand points. Operations without operands appear once.
+/// ```
+/// use atln_processor::emulator::vectors::enumerate;
+///
+/// let instructions = enumerate();
+/// let assembly: Vec<String> = instructions.iter().map(ToString::to_string).collect();
+/// assert!(assembly.contains(&"add.q r1, r2".to_string()));
+/// assert!(assembly.contains(&"sub.w [r6 + r1], r1".to_string()));
+/// assert_eq!(assembly.iter().filter(|assembly| *assembly == "halt").count(), 1);
+/// ```
+pub fn enumerate() -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+
+    for operation in metadata::operations() {
+        let Ok(extension) = Extension::from_codes(operation.extension, operation.operation) else { continue };
+
+        if operation.presence.is_none() {
+            instructions.extend(instruction(extension, Size::Byte, 0, Dynamic::Register(0), false, false));
+            continue;
+        }
+
+        for width in [Size::Byte, Size::Word, Size::Dual, Size::Quad] {
+            for x_dynamic in dynamics() {
+                for dynamic_destination in [false, true] {
+                    let candidate = instruction(extension.clone(), width.clone(), 1, x_dynamic.clone(), dynamic_destination, false);
+                    if candidate.as_ref().is_some_and(|candidate| !instructions.contains(candidate)) { instructions.extend(candidate); }
+                }
+            }
+        }
+    }
+
+    instructions
+}
+
+/// Random generator for [sample], advanced with [splitmix64].
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        splitmix64(&mut self.0)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn register(&mut self) -> u8 {
+        self.below(Registers::default().len() as u64) as u8
+    }
+
+    fn size(&mut self) -> Size {
+        [Size::Byte, Size::Word, Size::Dual, Size::Quad][self.below(4) as usize].clone()
+    }
+
+    fn immediate(&mut self) -> number::Data {
+        let value = self.next();
+
+        match self.size() {
+            Size::Byte => number::Data::Byte(value as u8),
+            Size::Word => number::Data::Word(value as u16),
+            Size::Dual => number::Data::Dual(value as u32),
+            Size::Quad => number::Data::Quad(value)
+        }
+    }
+
+    fn dynamic(&mut self) -> Dynamic {
+        match self.below(9) {
+            0 => Dynamic::Register(self.register()),
+            1 => Dynamic::Offset(Offset { register: self.register(), offset: self.immediate() }),
+            2 => Dynamic::Constant(self.immediate()),
+            3 => Dynamic::Signed(self.immediate()),
+            4 => Dynamic::Memory(self.immediate()),
+            5 => Dynamic::Relative(self.immediate()),
+            6 => Dynamic::PostIncrement(self.register()),
+            7 => Dynamic::PreDecrement(self.register()),
+            _ => Dynamic::Indexed(Indexed { base: self.register(), index: self.register() })
+        }
+    }
+}
+
+/// Random valid instructions with random registers and immediates. The same seed always gives the same instructions.
+/// ```
+/// use atln_processor::emulator::vectors::sample;
+///
+/// assert_eq!(sample(7, 50).len(), 50);
+/// assert_eq!(sample(7, 50), sample(7, 50));
+/// assert_ne!(sample(7, 50), sample(8, 50));
+/// ```
+pub fn sample(seed: u64, count: usize) -> Vec<Instruction> {
+    let operations = metadata::operations();
+    let mut random = Random(seed);
+    let mut instructions = Vec::with_capacity(count);
+
+    while instructions.len() < count {
+        let operation = &operations[random.below(operations.len() as u64) as usize];
+        let Ok(extension) = Extension::from_codes(operation.extension, operation.operation) else { continue };
+        let has_data = operation.presence.is_some();
+
+        let width = if has_data { random.size() } else { Size::Byte };
+        let (x_static, x_dynamic) = (random.register(), random.dynamic());
+        let dynamic_destination = has_data && random.below(2) == 0;
+        let synchronous = has_data && random.below(4) == 0;
+
+        instructions.extend(instruction(extension, width, x_static, x_dynamic, dynamic_destination, synchronous));
+    }
+
+    instructions
+}
+
+/// Vectors of instructions named by their position and mnemonic, such as `0003-add`.
+pub fn vectors(instructions: Vec<Instruction>) -> Vec<Vector> {
+    instructions.into_iter().enumerate().map(|(index, instruction)| {
+        let name = format!("{index:04}-{}", instruction.extension());
+        Vector::new(name, instruction)
+    }).collect()
+}
+
+/// Write the program and manifest of every vector into a directory, creating it if needed.
+/// ```
+/// use std::fs;
+/// use atln_processor::emulator::conformance::run_directory;
+/// use atln_processor::emulator::vectors::{sample, vectors, write_directory};
+///
+/// let directory = std::env::temp_dir().join("atln-vectors-example");
+/// write_directory(&directory, &vectors(sample(1, 20))).unwrap();
+///
+/// let reports = run_directory(&directory).unwrap();
+/// fs::remove_dir_all(&directory).unwrap();
+///
+/// assert_eq!(reports.len(), 20);
+/// assert!(reports.iter().all(|report| report.passed()));
+/// ```
+pub fn write_directory(directory: &Path, vectors: &[Vector]) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+
+    for vector in vectors {
+        let path = directory.join(&vector.name);
+        fs::write(path.with_extension(PROGRAM_EXTENSION), &vector.program)?;
+        fs::write(path.with_extension(MANIFEST_EXTENSION), vector.manifest())?;
+    }
+
+    Ok(())
+}
+
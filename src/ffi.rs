@@ -0,0 +1,194 @@
+//! C interface for embedding the emulator in programs written in other languages.
+//!
+//! A machine is created with [atln_machine_new] and handed around as an opaque pointer until it is released with
+//! [atln_machine_free]. Every function accepts a null machine and treats it as a failure. The matching declarations are
+//! in `include/atln_processor.h`, which is generated with `cbindgen --config cbindgen.toml --output
+//! include/atln_processor.h`. The dynamic library other programs link against is built with `cargo rustc --lib
+//! --release --features ffi --crate-type cdylib`.
+//!
+//! ```
+//! use atln_processor::ffi::*;
+//!
+//! unsafe {
+//!     let machine = atln_machine_new(16);
+//!
+//!     // add.b r1, r2 then halt.
+//!     let program = [0b000000_0_0, 0b0000_00_00, 0b00_001_010, 0b000010_0_0, 0b0000_00_00];
+//!     assert_eq!(atln_machine_load(machine, 0, program.as_ptr(), program.len()), program.len());
+//!     assert!(atln_machine_set_register(machine, 2, 5));
+//!
+//!     assert_eq!(atln_machine_run(machine, 10), AtlnStatus::Halted);
+//!
+//!     let mut value = 0;
+//!     assert!(atln_machine_register(machine, 1, &mut value));
+//!     assert_eq!(value, 5);
+//!     assert_eq!(atln_machine_program_counter(machine), 5);
+//!
+//!     atln_machine_free(machine);
+//! }
+//! ```
+
+use core::ptr;
+use core::slice;
+use emulator::memory::Memory;
+use emulator::processor::processor::{Budget, Core, Ports, Status};
+
+/// Outcome of executing instructions.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtlnStatus {
+    Running,
+    Halted,
+    Faulted,
+    BudgetExhausted,
+    /// The machine pointer was null.
+    InvalidMachine
+}
+
+impl From<Status> for AtlnStatus {
+    fn from(value: Status) -> Self {
+        match value {
+            Status::Running => Self::Running,
+            Status::Halted => Self::Halted,
+            Status::Faulted(_) => Self::Faulted,
+            Status::BudgetExhausted => Self::BudgetExhausted
+        }
+    }
+}
+
+/// A core with its own memory and ports. Only ever accessed through a pointer from C.
+pub struct AtlnMachine {
+    core: Core,
+    memory: Memory,
+    ports: Ports
+}
+
+/// Create a machine with zeroed memory of the given size. The machine must be released with [atln_machine_free].
+#[no_mangle]
+pub extern "C" fn atln_machine_new(memory_size: usize) -> *mut AtlnMachine {
+    Box::into_raw(Box::new(AtlnMachine {
+        core: Core::default(),
+        memory: Memory::from(vec![0u8; memory_size]),
+        ports: Ports::default()
+    }))
+}
+
+/// Release a machine.
+///
+/// # Safety
+/// The machine must have come from [atln_machine_new] and must not be used after this.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_free(machine: *mut AtlnMachine) {
+    if !machine.is_null() { drop(Box::from_raw(machine)); }
+}
+
+/// Copy `length` bytes into physical memory starting at `address` and start executing from there. Returns the number of
+/// bytes that fit in memory.
+///
+/// # Safety
+/// The machine must be valid or null, and `bytes` must point to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_load(machine: *mut AtlnMachine, address: u64, bytes: *const u8, length: usize) -> usize {
+    let machine = match machine.as_mut() {
+        Some(machine) => machine,
+        None => return 0
+    };
+
+    if bytes.is_null() { return 0 }
+
+    let written = machine.memory.write_bytes(address, false, slice::from_raw_parts(bytes, length));
+    machine.core.context.program_counter = address;
+    written
+}
+
+/// Execute a single instruction.
+///
+/// # Safety
+/// The machine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_step(machine: *mut AtlnMachine) -> AtlnStatus {
+    match machine.as_mut() {
+        Some(machine) => machine.core.step(&mut machine.memory, &mut machine.ports).into(),
+        None => AtlnStatus::InvalidMachine
+    }
+}
+
+/// Execute until the core stops or `instructions` have been executed.
+///
+/// # Safety
+/// The machine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_run(machine: *mut AtlnMachine, instructions: u64) -> AtlnStatus {
+    match machine.as_mut() {
+        Some(machine) => machine.core.run(&mut machine.memory, &mut machine.ports, Some(Budget::Instructions(instructions))).into(),
+        None => AtlnStatus::InvalidMachine
+    }
+}
+
+/// Store the value of a register in `value`. Returns false if the machine is null or the register does not exist.
+///
+/// # Safety
+/// The machine must be valid or null, and `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_register(machine: *const AtlnMachine, index: usize, value: *mut u64) -> bool {
+    let register = machine.as_ref().and_then(|machine| machine.core.context.registers.get(index));
+    match (register, value.is_null()) {
+        (Some(register), false) => { ptr::write(value, *register); true },
+        _ => false
+    }
+}
+
+/// Set the value of a register. Returns false if the machine is null or the register does not exist.
+///
+/// # Safety
+/// The machine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_set_register(machine: *mut AtlnMachine, index: usize, value: u64) -> bool {
+    match machine.as_mut().and_then(|machine| machine.core.context.registers.get_mut(index)) {
+        Some(register) => { *register = value; true },
+        None => false
+    }
+}
+
+/// Address of the next instruction, or 0 if the machine is null.
+///
+/// # Safety
+/// The machine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_program_counter(machine: *const AtlnMachine) -> u64 {
+    machine.as_ref().map_or(0, |machine| machine.core.context.program_counter)
+}
+
+/// Number of cycles executed, or 0 if the machine is null.
+///
+/// # Safety
+/// The machine must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_cycles(machine: *const AtlnMachine) -> u64 {
+    machine.as_ref().map_or(0, |machine| machine.core.cycles)
+}
+
+/// Copy up to `length` bytes of physical memory starting at `address` into `buffer`. Returns the number of bytes
+/// copied, which is less than `length` if the range leaves memory.
+///
+/// # Safety
+/// The machine must be valid or null, and `buffer` must point to `length` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_read_memory(machine: *const AtlnMachine, address: u64, buffer: *mut u8, length: usize) -> usize {
+    match (machine.as_ref(), buffer.is_null()) {
+        (Some(machine), false) => machine.memory.read_bytes(address, false, slice::from_raw_parts_mut(buffer, length)),
+        _ => 0
+    }
+}
+
+/// Copy `length` bytes from `bytes` into physical memory starting at `address`. Returns the number of bytes written.
+///
+/// # Safety
+/// The machine must be valid or null, and `bytes` must point to `length` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn atln_machine_write_memory(machine: *mut AtlnMachine, address: u64, bytes: *const u8, length: usize) -> usize {
+    match (machine.as_mut(), bytes.is_null()) {
+        (Some(machine), false) => machine.memory.write_bytes(address, false, slice::from_raw_parts(bytes, length)),
+        _ => 0
+    }
+}
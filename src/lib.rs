@@ -1,4 +1,36 @@
+#![allow(clippy::module_inception)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(not(feature = "std"), macro_use)] extern crate alloc;
+#[cfg(feature = "std")] extern crate core;
+#[cfg(feature = "jit")] extern crate cranelift_codegen;
+#[cfg(feature = "jit")] extern crate cranelift_frontend;
+#[cfg(feature = "jit")] extern crate cranelift_jit;
+#[cfg(feature = "jit")] extern crate cranelift_module;
+#[cfg(feature = "jit")] extern crate cranelift_native;
+#[cfg(feature = "proptest")] extern crate proptest;
+#[cfg(feature = "serde")] extern crate serde;
+#[cfg(feature = "tracing")] extern crate tracing;
+#[cfg(feature = "wasm")] extern crate wasm_bindgen;
+#[cfg(any(feature = "server", all(test, feature = "serde")))] extern crate serde_json;
+
 pub mod emulator;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod number;
 pub mod utility;
-pub mod programming;
\ No newline at end of file
+pub mod prelude;
+pub mod programming;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// Shorter paths for the most used types, which are also in the prelude along with the utility traits.
+
+pub use emulator::memory::{Memory, MemoryAccess};
+pub use emulator::processor::processor::{Context, Core, Ports, Status};
+pub use emulator::processor::processor::instruction::{Data, Instruction};
+pub use emulator::processor::processor::instruction::operand::{Dynamic, Operands, OperandsPresence};
+pub use emulator::processor::processor::instruction::operation::Extension;
+pub use emulator::system::System;
@@ -4,8 +4,17 @@
 
 // Constants
 
-use utility::ReadAll;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+use core::hash::{Hash, Hasher};
+use utility::{FromRepresentation, ReadAll, Representable};
 use crate::emulator::processor::processor::instruction::operand::{IMMEDIATE_EXPONENT_BYTE, IMMEDIATE_EXPONENT_DUAL, IMMEDIATE_EXPONENT_QUAD, IMMEDIATE_EXPONENT_WORD};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 pub const BYTE_SIZE: usize = 1;
 pub const WORD_SIZE: usize = 2;
@@ -36,7 +45,8 @@ impl<T> ArrayBounds for [T] {
 
 /// Absolute modes.
 /// Base type variants for representing an absolute value.
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Size {
     #[default]
     Byte,
@@ -80,9 +90,22 @@ impl Size {
         })
     }
 
-    /// Create from an exponent of 2. The maximum supported exponent is 3.
+    /// Create from an exponent of 2. The maximum supported exponent is 3, [None] is returned for anything larger.
+    /// ```
+    /// use atln_processor::number::Size;
+    ///
+    /// assert_eq!(Size::from_exponent(3), Some(Size::Quad));
+    /// assert_eq!(Size::from_exponent(4), None);
+    /// assert_eq!(Size::from_exponent(u8::MAX), None);
+    /// ```
     pub fn from_exponent(exponent: u8) -> Option<Self> {
-        Self::from_size(2u8.pow(exponent as u32) as usize)
+        Some(match exponent {
+            IMMEDIATE_EXPONENT_BYTE => Size::Byte,
+            IMMEDIATE_EXPONENT_WORD => Size::Word,
+            IMMEDIATE_EXPONENT_DUAL => Size::Dual,
+            IMMEDIATE_EXPONENT_QUAD => Size::Quad,
+            _ => return None
+        })
     }
 
     pub fn exponent(&self) -> u8 {
@@ -105,10 +128,35 @@ impl Size {
     }
 }
 
+impl<'a> Representable<'a> for Size {
+    /// Suffix used in assembly for operations of this width.
+    fn representation(&self) -> Cow<'a, str> {
+        match self {
+            Self::Byte => "b",
+            Self::Word => "w",
+            Self::Dual => "d",
+            Self::Quad => "q"
+        }.into()
+    }
+}
+
+impl<'a> FromRepresentation<'a> for Size {
+    fn from_representation(string: Cow<'a, str>) -> Option<Self> {
+        Some(match &*string {
+            "b" => Self::Byte,
+            "w" => Self::Word,
+            "d" => Self::Dual,
+            "q" => Self::Quad,
+            _ => return None
+        })
+    }
+}
+
 /// Variable absolute data type.
 /// Complete variants that annotate numbers with their type in the same enum allowing for the data type to be changed
 /// during runtime.
 #[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Data {
     Byte(u8),
     Word(u16),
@@ -116,6 +164,13 @@ pub enum Data {
     Quad(u64)
 }
 
+impl Display for Data {
+    /// The value in decimal regardless of the variant.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.quad())
+    }
+}
+
 impl Data {
     pub fn to_le_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
@@ -144,6 +199,53 @@ impl Data {
         }
     }
 
+    /// Get the data as a signed quad by sign extending it from its variant.
+    /// ```
+    /// use atln_processor::number::Data;
+    ///
+    /// assert_eq!(Data::Byte(0xFC).signed(), -4);
+    /// assert_eq!(Data::Word(0xFC).signed(), 252);
+    /// ```
+    pub fn signed(&self) -> i64 {
+        match *self {
+            Self::Byte(value) => value as i8 as i64,
+            Self::Word(value) => value as i16 as i64,
+            Self::Dual(value) => value as i32 as i64,
+            Self::Quad(value) => value as i64
+        }
+    }
+
+    /// Fit a signed number into the smallest variant which sign extends back to it. This is the signed counterpart of
+    /// [Data::from_quad_selecting].
+    /// ```
+    /// use atln_processor::number::Data;
+    ///
+    /// assert_eq!(Data::from_signed_selecting(-4), Data::Byte(0xFC));
+    /// assert!(matches!(Data::from_signed_selecting(200), Data::Word(200)));
+    /// ```
+    pub fn from_signed_selecting(signed: i64) -> Self {
+        if let Ok(value) = i8::try_from(signed) { return Self::Byte(value as u8) }
+        if let Ok(value) = i16::try_from(signed) { return Self::Word(value as u16) }
+        if let Ok(value) = i32::try_from(signed) { return Self::Dual(value as u32) }
+        Self::Quad(signed as u64)
+    }
+
+    /// Reverse the order of the bytes within the variant.
+    /// ```
+    /// use atln_processor::number::Data;
+    ///
+    /// assert_eq!(Data::Word(0x1234).swap_bytes(), Data::Word(0x3412));
+    /// assert_eq!(Data::Byte(0x12).swap_bytes(), Data::Byte(0x12));
+    /// ```
+    pub fn swap_bytes(&self) -> Self {
+        match *self {
+            Self::Byte(value) => Self::Byte(value),
+            Self::Word(value) => Self::Word(value.swap_bytes()),
+            Self::Dual(value) => Self::Dual(value.swap_bytes()),
+            Self::Quad(value) => Self::Quad(value.swap_bytes())
+        }
+    }
+
     pub fn quad_buffer(&self) -> [u8; 8] {
         self.quad().to_le_bytes()
     }
@@ -237,6 +339,13 @@ impl PartialEq for Data {
     }
 }
 
+impl Hash for Data {
+    /// Hashes the value regardless of the variant, so data that compares equal hashes the same.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.quad().hash(state);
+    }
+}
+
 impl ReadAll<[u8]> for Data {
     /// Read bytes of the stored number type into a slice reference.
     /// ```
@@ -288,6 +397,17 @@ pub enum ExtractError {
     EndOutOfBounds
 }
 
+impl Display for ExtractError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::StartOutOfBounds => "start byte index is out of bounds",
+            Self::EndOutOfBounds => "selection extends past the end of the number"
+        })
+    }
+}
+
+impl Error for ExtractError {}
+
 // region: Data to number conversion
 impl From<Data> for u8 {
     fn from(value: Data) -> Self {
@@ -309,7 +429,7 @@ impl From<Data> for u32 {
 
 impl From<Data> for u64 {
     fn from(value: Data) -> Self {
-        value.quad() as u64
+        value.quad()
     }
 }
 // endregion
@@ -328,4 +448,26 @@ impl CheckedAdd for Data {
             Self::Quad(v) => Data::Quad(v.checked_add(u64::from(factor))?)
         })
     }
+}
+
+pub trait CheckedSub: Sized {
+    fn checked_sub(self, factor: Data) -> Option<Data>;
+}
+
+impl CheckedSub for Data {
+    /// ```
+    /// use atln_processor::number::{CheckedSub, Data};
+    ///
+    /// assert_eq!(Data::Word(7).checked_sub(Data::Word(5)), Some(Data::Word(2)));
+    /// assert_eq!(Data::Byte(5).checked_sub(Data::Byte(7)), None);
+    /// ```
+    fn checked_sub(self, factor: Data) -> Option<Data> {
+        if self.size() != factor.size() { panic!("Type error: Cannot do checked subtract when the data types are not equal"); }
+        Some(match self {
+            Self::Byte(v) => Data::Byte(v.checked_sub(u8::from(factor))?),
+            Self::Word(v) => Data::Word(v.checked_sub(u16::from(factor))?),
+            Self::Dual(v) => Data::Dual(v.checked_sub(u32::from(factor))?),
+            Self::Quad(v) => Data::Quad(v.checked_sub(u64::from(factor))?)
+        })
+    }
 }
\ No newline at end of file
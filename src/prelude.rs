@@ -0,0 +1,25 @@
+//! Types and traits needed by most embedders, for importing with a glob.
+//!
+//! The traits are included so methods such as [Coded::code] and [Encodable::encode] can be called without naming the
+//! utility module. Every item stays available at its full path as well.
+//! ```
+//! use atln_processor::prelude::*;
+//!
+//! let program = assemble("add.q r1, 3\nhalt").unwrap();
+//! let (instruction, length) = Instruction::decode_slice(&program).unwrap();
+//! assert_eq!(instruction.encode(), program[..length]);
+//!
+//! let mut core = Core::default();
+//! let status = core.run(&mut Memory::from(program), &mut Default::default(), None);
+//! assert!(matches!(status, Status::Halted));
+//! assert_eq!(core.context.registers[1], 3);
+//! ```
+
+pub use emulator::memory::{Memory, MemoryAccess};
+pub use emulator::processor::processor::{Context, Core, Ports, Status};
+pub use emulator::processor::processor::instruction::{Data, Instruction};
+pub use emulator::processor::processor::instruction::operand::{Dynamic, Operands, OperandsPresence};
+pub use emulator::processor::processor::instruction::operation::Extension;
+pub use emulator::system::System;
+pub use programming::assembler::assemble;
+pub use utility::{Coded, Encodable, FromRepresentation, Representable};
@@ -1 +1,6 @@
-pub mod lexer;
\ No newline at end of file
+pub mod assembler;
+pub mod debug;
+pub mod lexer;
+pub mod linker;
+pub mod object;
+pub mod optimizer;
\ No newline at end of file
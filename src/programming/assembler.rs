@@ -0,0 +1,950 @@
+//! Line based assembler for the textual form produced by the [Display] implementation of [Instruction].
+//!
+//! Every line holds at most one instruction written as `[sync ]mnemonic[.width] [destination, source]`. A third operand
+//! such as `add.b r3, r1, r2` stores the result in the first register and leaves both sources unchanged. Comments start
+//! with `;` and run to the end of the line. Numbers are decimal or hexadecimal with a `0x` prefix, and immediates are
+//! encoded with the smallest size that holds them. Constants written with a sign such as `-1` are sign extended to the
+//! width of the operation instead of zero extended. Memory relative to the next instruction is written as `[pc + 8]` or
+//! `[pc - 8]`, where the displacement must be a number. Registers which step over memory are written as `[r1]+` to add
+//! the access width after the access and `-[r1]` to subtract it before. `[r1 + r2]` indexes an array at `r1` with `r2`
+//! scaled by the access width.
+//!
+//! A line may start with a label such as `loop:`, which names the address of whatever follows it. Labels can be used in
+//! place of any immediate and are always encoded as quads so they can be patched once the address is known. Labels are
+//! local to their file unless exported with `.global name`. See [assemble_object] and the [linker](super::linker) for
+//! building programs out of multiple files. Programs are assembled in two passes. The first lays out every line and
+//! records the address of each label, and the second patches every use of a label once all of them are known, so
+//! labels can be used before they are defined.
+//!
+//! Numbers and labels can be combined into [expressions](expression) such as `buffer + 4 * INDEX` or `(1 << 12) - 1`.
+//! An expression which only uses numbers and constants is worked out while assembling and encoded like a number, while
+//! one which adds a number to a label is patched or relocated like the label.
+//!
+//! Directives place data and control where the following lines go. Addresses are relative to the start of the file,
+//! which is address 0 for [assemble].
+//!
+//! | Directive            | Effect                                                                                     |
+//! |----------------------|--------------------------------------------------------------------------------------------|
+//! | `.global name`       | Makes the label visible to other files.                                                    |
+//! | `.equ SIZE, 4 * 8`   | Names a constant, which can be used anywhere a number can once it has been defined.        |
+//! | `.org 0x100`         | Fills with zeros up to the address, which can't be before the current one.                 |
+//! | `.byte 1, -1, label` | Places each number or label address in a byte. `.word`, `.dual` and `.quad` are wider.     |
+//! | `.ascii "text\n"`    | Places the bytes of the string. `\n`, `\r`, `\t`, `\0`, `\\`, `\"` and `\x41` are escapes. |
+//! | `.align 8`           | Fills with zeros up to the next multiple of the number.                                    |
+//! | `.space 16, 0xFF`    | Places the number of bytes, which are the fill byte or 0 if it is left out.                |
+//!
+//! Pseudo-instructions stand for instructions the processor does not have. Lines using them can't be assembled on
+//! their own with [assemble_line] if they need the address of the line.
+//!
+//! | Pseudo-instruction | Expansion                                                                                  |
+//! |--------------------|--------------------------------------------------------------------------------------------|
+//! | `nop`              | `trunc.q r0`, which changes nothing.                                                       |
+//! | `li r1, 300`       | `zext.w r1, 300` with the width of the smallest immediate that holds the number.           |
+//! | `li r1, -2`        | `sext.b r1, -2` with the width of the smallest immediate that holds the number.            |
+//! | `li r1, label`     | `zext.q r1, label`.                                                                        |
+//! | `call target`      | `zext.q r7, return` then `divert.q target`, where `return` is the address after the call.  |
+//! | `ret`              | `divert.q r7`, which returns from a call as long as r7 was not changed.                    |
+//!
+//! The return address of every call is a local symbol named [RETURN_SYMBOL] followed by a number. [User defined
+//! macros](macros) are expanded before anything else is assembled, once every [included](mod@include) file is in place.
+//! [assemble_listing] and [assemble_object_listing] also produce a [listing] of the bytes assembled from every line.
+//! Errors point at the token of the line which caused them and suggest fixes where they can, see [diagnostic].
+//! ```
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let program = assemble("
+//!     add.b r1, r2 ; r1 = r1 + r2
+//!     sub.w [0x10], r3
+//!     halt
+//! ").unwrap();
+//!
+//! assert_eq!(program, [
+//!     0b000000_0_0, 0b0000_00_00, 0b00_001_010,
+//!     0b000000_0_1, 0b0001_11_00, 0b01_011_000, 0x10,
+//!     0b000010_0_0, 0b0000_00_00
+//! ]);
+//! ```
+
+pub mod diagnostic;
+pub mod expression;
+pub mod include;
+pub mod listing;
+pub mod macros;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::processor::processor::instruction::builder::{BuildError, InstructionBuilder};
+use emulator::processor::processor::instruction::Instruction;
+use emulator::processor::processor::instruction::operand::{Dynamic, Indexed, Offset, OperandsPresence, Static};
+use emulator::processor::processor::instruction::operation::conversion::Conversion;
+use emulator::processor::processor::instruction::operation::executor::Executor;
+use emulator::processor::processor::instruction::operation::Extension;
+use number;
+use number::Size;
+use self::diagnostic::Diagnostic;
+use self::expression::{EvaluateError, Expression, Symbols, Value};
+use self::include::Resolver;
+use self::listing::{Entry, Listing};
+use super::object::{Object, Relocation, SourceLine, Symbol};
+use utility::{Encodable, FromRepresentation, Map};
+
+/// Start of a comment which continues until the end of the line.
+pub const COMMENT: char = ';';
+/// Directive which makes a label visible to other objects.
+pub const GLOBAL_DIRECTIVE: &str = ".global";
+/// Directive which defines a constant.
+pub const CONSTANT_DIRECTIVE: &str = ".equ";
+/// Directive which moves to an address.
+pub const ORIGIN_DIRECTIVE: &str = ".org";
+/// Directive which moves to the next multiple of a number.
+pub const ALIGN_DIRECTIVE: &str = ".align";
+/// Directive which places a number of repeated bytes.
+pub const SPACE_DIRECTIVE: &str = ".space";
+/// Directive which places the bytes of a string.
+pub const ASCII_DIRECTIVE: &str = ".ascii";
+/// Most bytes an assembled object can hold. Directives which would grow it past this fail with [LineError::Overflow]
+/// rather than allocating the bytes.
+pub const MAX_OBJECT_SIZE: usize = 1 << 24;
+/// Directives which place numbers along with the size of each number.
+pub const DATA_DIRECTIVES: [(&str, Size); 4] = [(".byte", Size::Byte), (".word", Size::Word), (".dual", Size::Dual), (".quad", Size::Quad)];
+/// Register which `call` stores the return address in and `ret` diverts to.
+pub const LINK_REGISTER: Static = 7;
+/// Start of the names of the symbols defined at the return address of every call. Labels can't start with a `.`, so
+/// they never collide.
+pub const RETURN_SYMBOL: &str = ".return";
+
+/// Reason a single line could not be assembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineError {
+    /// No operation has the mnemonic.
+    Mnemonic,
+    /// The width suffix after the mnemonic is not one of `b`, `w`, `d` or `q`.
+    Width,
+    /// An operand is not a register, number or memory dereference.
+    Operand,
+    /// More than 3 operands were given, or a pseudo-instruction was given the wrong number of operands.
+    OperandCount,
+    /// Two operands were given but neither of them is a register, so there is no static operand.
+    Static,
+    /// The operands do not make up a valid instruction.
+    Build(BuildError),
+    /// A label or symbol name has characters other than letters, digits and underscores, or starts with a digit.
+    Symbol,
+    /// A label was defined more than once.
+    Duplicate,
+    /// A symbol is referred to but not defined.
+    Undefined,
+    /// The directive does not exist.
+    Directive,
+    /// A macro definition is not ended with `.endm` before the end of the program or the start of another definition.
+    Unterminated,
+    /// A macro is used with a different number of arguments than it has parameters.
+    Arguments,
+    /// Macros are expanded inside each other more than [macros::DEPTH_LIMIT] times.
+    Recursion,
+    /// A number or the address of a label does not fit in the size it is placed in, or the object would grow past
+    /// [MAX_OBJECT_SIZE].
+    Overflow,
+    /// The address of an origin directive is before the current address.
+    Origin,
+    /// The file named by an include directive can't be found.
+    Include,
+    /// An expression can't be evaluated.
+    Expression(EvaluateError)
+}
+
+impl Display for LineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mnemonic => "no operation has the mnemonic",
+            Self::Width => "width suffix does not exist",
+            Self::Operand => "operand is not a register, number or memory dereference",
+            Self::OperandCount => "wrong number of operands",
+            Self::Static => "one of the operands must be a register",
+            Self::Build(_) => "operands do not form a valid instruction",
+            Self::Symbol => "symbol name is not valid",
+            Self::Duplicate => "label is already defined",
+            Self::Undefined => "symbol is not defined",
+            Self::Directive => "directive does not exist",
+            Self::Unterminated => "macro definition is not ended",
+            Self::Arguments => "macro is used with the wrong number of arguments",
+            Self::Recursion => "macros are nested too deeply",
+            Self::Overflow => "value does not fit in its size",
+            Self::Origin => "origin is before the current address",
+            Self::Include => "included file can't be found",
+            Self::Expression(_) => "expression can't be evaluated"
+        })
+    }
+}
+
+impl Error for LineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Build(error) => Some(error),
+            Self::Expression(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<BuildError> for LineError {
+    fn from(value: BuildError) -> Self {
+        Self::Build(value)
+    }
+}
+
+/// A line of a program which could not be assembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    /// Line number starting from 1.
+    pub line: usize,
+    pub error: LineError,
+    /// Name of the [included](mod@include) file holding the line, or [None] if the line is in the program itself.
+    pub file: Option<String>,
+    /// Part of the line which caused the error, or [None] if it is not known.
+    pub diagnostic: Option<Box<Diagnostic>>
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to assemble line {}", self.line)?;
+        match &self.file {
+            Some(file) => write!(f, " of {file}"),
+            None => Ok(())
+        }
+    }
+}
+
+impl Error for AssembleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Code of a line without its comment or surrounding whitespace. Comment characters inside strings are kept.
+pub(crate) fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            COMMENT if !quoted => return line[..index].trim(),
+            _ => ()
+        }
+    }
+
+    line.trim()
+}
+
+/// Split the label from the start of a line of code. Colons inside strings don't end a label.
+pub(crate) fn split_label(code: &str) -> (Option<&str>, &str) {
+    match code.split_once(':') {
+        Some((label, rest)) if !label.contains('"') => (Some(label.trim()), rest.trim()),
+        _ => (None, code)
+    }
+}
+
+/// Where a line of a program was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Position {
+    /// Index of the file among the files included, where 0 is the program itself.
+    file: usize,
+    line: usize,
+    /// Line of the program itself which the line is part of, which is the include directive for included lines.
+    program_line: usize
+}
+
+impl Position {
+    fn error(&self, files: &[String], error: LineError) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            error,
+            file: if self.file == 0 { None } else { files.get(self.file).cloned() },
+            diagnostic: None
+        }
+    }
+}
+
+/// Parse a number in decimal or in hexadecimal with the `0x` prefix.
+pub(crate) fn parse_number(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x") {
+        Some(hexadecimal) => u64::from_str_radix(hexadecimal, 16).ok(),
+        None => text.parse().ok()
+    }
+}
+
+/// Parse a register name like `r3`. Codes outside the register file are left for the builder to reject.
+fn parse_register(text: &str) -> Option<Static> {
+    text.strip_prefix('r')?.parse().ok()
+}
+
+/// Name of the program counter in program counter relative dereferences.
+pub const PROGRAM_COUNTER: &str = "pc";
+
+/// Parse a symbol name. Register names and the program counter are not symbols.
+fn parse_symbol(text: &str) -> Option<&str> {
+    let mut characters = text.chars();
+    let first = characters.next()?;
+
+    if !(first.is_ascii_alphabetic() || first == '_') || !characters.all(|character| character.is_ascii_alphanumeric() || character == '_') { return None }
+    if parse_register(text).is_some() || text == PROGRAM_COUNTER { return None }
+    Some(text)
+}
+
+/// Parse an immediate, which is an [expression](mod@expression). Expressions of numbers and constants are evaluated
+/// straight away and stored in the smallest size that holds them. Any other expression is stored as a zeroed quad and
+/// returned so it can be evaluated and patched in once every label is known.
+fn parse_immediate(text: &str, constants: &Map<String, u64>) -> Result<(number::Data, Option<Expression>), LineError> {
+    let expression = Expression::parse(text).ok_or(LineError::Operand)?;
+
+    match expression.evaluate(constants) {
+        Ok(Value::Absolute(value)) => Ok((number::Data::from_quad_selecting(value), None)),
+        Ok(Value::Relative { .. }) | Err(EvaluateError::Undefined(_)) => Ok((number::Data::Quad(0), Some(expression))),
+        Err(error) => Err(LineError::Expression(error))
+    }
+}
+
+/// Parse a number written with an explicit sign, such as `-4` or `+4`.
+fn parse_signed(text: &str) -> Option<i64> {
+    let (negative, magnitude) = match (text.strip_prefix('+'), text.strip_prefix('-')) {
+        (Some(magnitude), _) => (false, magnitude),
+        (_, Some(magnitude)) => (true, magnitude),
+        _ => return None
+    };
+
+    let magnitude = i64::try_from(parse_number(magnitude.trim_start())?).ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// Parse a program counter relative dereference without its brackets, such as `pc`, `pc + 8` or `pc - 8`. The
+/// displacement must be a number.
+fn parse_relative(dereference: &str) -> Option<Dynamic> {
+    let displacement = dereference.strip_prefix(PROGRAM_COUNTER)?.trim_start();
+    if displacement.is_empty() { return Some(Dynamic::Relative(number::Data::Byte(0))) }
+
+    Some(Dynamic::Relative(number::Data::from_signed_selecting(parse_signed(displacement)?)))
+}
+
+/// Parse an operand in the form written by the [Display] implementation of [Dynamic], along with the expression its
+/// immediate is patched with.
+fn parse_dynamic(text: &str, constants: &Map<String, u64>) -> Result<(Dynamic, Option<Expression>), LineError> {
+    if let Some(register) = parse_register(text) { return Ok((Dynamic::Register(register), None)) }
+    if let Some(base) = text.strip_prefix("-[").and_then(|base| base.strip_suffix(']')) { return Ok((Dynamic::PreDecrement(parse_register(base.trim()).ok_or(LineError::Operand)?), None)) }
+    if let Some(base) = text.strip_prefix('[').and_then(|base| base.strip_suffix("]+")) { return Ok((Dynamic::PostIncrement(parse_register(base.trim()).ok_or(LineError::Operand)?), None)) }
+
+    let dereference = match text.strip_prefix('[').and_then(|text| text.strip_suffix(']')) {
+        Some(dereference) => dereference.trim(),
+        None => {
+            let (constant, expression) = parse_immediate(text, constants)?;
+
+            // Constants written with a sign are sign extended, unless they can't be evaluated yet.
+            return Ok(match expression {
+                None if text.starts_with(['+', '-']) => (Dynamic::Signed(number::Data::from_signed_selecting(constant.quad() as i64)), None),
+                expression => (Dynamic::Constant(constant), expression)
+            })
+        }
+    };
+
+    if let Some(relative) = parse_relative(dereference) { return Ok((relative, None)) }
+
+    if let Some((register, offset)) = dereference.split_once('+') {
+        if let Some(register) = parse_register(register.trim()) {
+            if let Some(index) = parse_register(offset.trim()) { return Ok((Dynamic::Indexed(Indexed { base: register, index }), None)) }
+
+            let (offset, expression) = parse_immediate(offset.trim(), constants)?;
+            return Ok((Dynamic::Offset(Offset { register, offset }), expression));
+        }
+    }
+
+    parse_immediate(dereference, constants).map(|(address, expression)| (Dynamic::Memory(address), expression))
+}
+
+/// What an immediate is patched with once every label is known.
+#[derive(Debug, Clone)]
+enum Reference {
+    Expression(Expression),
+    /// Address after the code of the line, which a call returns to.
+    Return
+}
+
+/// An instruction along with what its immediate is patched with.
+type Referring = (Instruction, Option<Reference>);
+
+/// Parse a string in double quotes into its bytes.
+fn parse_string(text: &str) -> Option<Vec<u8>> {
+    let mut characters = text.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut bytes = Vec::new();
+
+    while let Some(character) = characters.next() {
+        let character = match character {
+            '"' => return None,
+            '\\' => match characters.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'x' => {
+                    let digits = characters.as_str().get(..2)?;
+                    bytes.push(u8::from_str_radix(digits, 16).ok()?);
+                    characters.nth(1);
+                    continue
+                },
+                character @ ('\\' | '"') => character,
+                _ => return None
+            },
+            character => character
+        };
+
+        bytes.extend(character.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    Some(bytes)
+}
+
+/// Whether a number fits in a size, either as an unsigned number or as a negative number in two's complement.
+fn fits(size: &Size, value: u64) -> bool {
+    let bits = size.size() as u32 * 8;
+    bits >= u64::BITS || value >> bits == 0 || (value as i64) >> (bits - 1) == -1
+}
+
+/// Evaluate an expression which only uses numbers and constants defined before it.
+fn parse_constant(text: &str, constants: &Map<String, u64>) -> Result<u64, LineError> {
+    match Expression::parse(text.trim()).ok_or(LineError::Operand)?.evaluate(constants) {
+        Ok(Value::Absolute(value)) => Ok(value),
+        Ok(Value::Relative { .. }) => Err(LineError::Expression(EvaluateError::Relative)),
+        Err(error) => Err(LineError::Expression(error))
+    }
+}
+
+/// A directive along with its arguments.
+enum Directive<'a> {
+    Global(&'a str),
+    Constant(&'a str, u64),
+    Origin(u64),
+    Align(u64),
+    /// Bytes to place, along with the offset, size and expression of every value which can't be evaluated yet.
+    Data(Vec<u8>, Vec<(usize, Size, Expression)>)
+}
+
+fn parse_directive<'a>(directive: &str, arguments: &'a str, constants: &Map<String, u64>) -> Result<Directive<'a>, LineError> {
+    let count = |text: &str| parse_constant(text, constants);
+
+    Ok(match directive {
+        GLOBAL_DIRECTIVE => Directive::Global(parse_symbol(arguments).ok_or(LineError::Symbol)?),
+        CONSTANT_DIRECTIVE => {
+            let (name, value) = arguments.split_once(',').ok_or(LineError::Operand)?;
+            Directive::Constant(parse_symbol(name.trim()).ok_or(LineError::Symbol)?, count(value)?)
+        },
+        ORIGIN_DIRECTIVE => Directive::Origin(count(arguments)?),
+        ALIGN_DIRECTIVE => Directive::Align(Some(count(arguments)?).filter(|&alignment| alignment != 0).ok_or(LineError::Operand)?),
+        ASCII_DIRECTIVE => Directive::Data(parse_string(arguments).ok_or(LineError::Operand)?, Vec::new()),
+        SPACE_DIRECTIVE => {
+            let (length, fill) = match arguments.split_once(',') {
+                Some((length, fill)) => (count(length)?, u8::try_from(count(fill)?).map_err(|_| LineError::Overflow)?),
+                None => (count(arguments)?, 0)
+            };
+
+            let length = usize::try_from(length).ok().filter(|&length| length <= MAX_OBJECT_SIZE).ok_or(LineError::Overflow)?;
+            Directive::Data(vec![fill; length], Vec::new())
+        },
+        _ => {
+            let size = match DATA_DIRECTIVES.iter().find(|(name, _)| *name == directive) {
+                Some((_, size)) => size,
+                None => return Err(LineError::Directive)
+            };
+
+            let mut bytes = Vec::new();
+            let mut references = Vec::new();
+
+            for value in arguments.split(',') {
+                let (value, expression) = parse_immediate(value.trim(), constants)?;
+                if !fits(size, value.quad()) { return Err(LineError::Overflow) }
+
+                if let Some(expression) = expression { references.push((bytes.len(), size.clone(), expression)); }
+                bytes.extend(number::Data::from_size_selecting(size, value.quad()).to_le_bytes());
+            }
+
+            Directive::Data(bytes, references)
+        }
+    })
+}
+
+/// Contents of a line of assembly.
+#[derive(Default)]
+struct Line<'a> {
+    label: Option<&'a str>,
+    directive: Option<Directive<'a>>,
+    /// There is more than one instruction for some pseudo-instructions.
+    instructions: Vec<Referring>
+}
+
+/// Instructions a pseudo-instruction stands for, or [None] if no pseudo-instruction has the mnemonic.
+fn parse_pseudo(mnemonic: &str, operands: &[&str], constants: &Map<String, u64>) -> Result<Option<Vec<Referring>>, LineError> {
+    let conversion = |conversion, width, x_static, x_dynamic| InstructionBuilder::new()
+        .extension(Extension::Conversion(conversion))
+        .width(width)
+        .static_register(x_static)
+        .dynamic(x_dynamic)
+        .build();
+    let divert = |x_dynamic| InstructionBuilder::new().extension(Extension::Executor(Executor::Divert)).width(Size::Quad).dynamic(x_dynamic).build();
+
+    Ok(Some(match (mnemonic, operands) {
+        ("nop", []) => vec![(InstructionBuilder::new().extension(Extension::Conversion(Conversion::Truncate)).width(Size::Quad).static_register(0).build()?, None)],
+        ("li", [register, value]) => {
+            let register = parse_register(register).ok_or(LineError::Operand)?;
+            let (conversion_kind, immediate, x_dynamic, expression) = match parse_dynamic(value, constants)? {
+                (Dynamic::Constant(immediate), expression) => (Conversion::ZeroExtend, immediate.clone(), Dynamic::Constant(immediate), expression),
+                (Dynamic::Signed(immediate), expression) => (Conversion::SignExtend, immediate.clone(), Dynamic::Signed(immediate), expression),
+                _ => return Err(LineError::Operand)
+            };
+
+            vec![(conversion(conversion_kind, Size::from(immediate), register, x_dynamic)?, expression.map(Reference::Expression))]
+        },
+        ("call", [target]) => {
+            let (x_dynamic, expression) = parse_dynamic(target, constants)?;
+            vec![
+                (conversion(Conversion::ZeroExtend, Size::Quad, LINK_REGISTER, Dynamic::Constant(number::Data::Quad(0)))?, Some(Reference::Return)),
+                (divert(x_dynamic)?, expression.map(Reference::Expression))
+            ]
+        },
+        ("ret", []) => vec![(divert(Dynamic::Register(LINK_REGISTER))?, None)],
+        ("nop" | "li" | "call" | "ret", _) => return Err(LineError::OperandCount),
+        _ => return Ok(None)
+    }))
+}
+
+fn parse_line<'a>(line: &'a str, constants: &Map<String, u64>) -> Result<Line<'a>, LineError> {
+    let mut parsed = Line::default();
+    let (label, line) = split_label(strip_comment(line));
+    if let Some(label) = label { parsed.label = Some(parse_symbol(label).ok_or(LineError::Symbol)?); }
+
+    if line.is_empty() { return Ok(parsed) }
+
+    if line.starts_with('.') {
+        let (directive, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        parsed.directive = Some(parse_directive(directive, arguments.trim(), constants)?);
+        return Ok(parsed);
+    }
+
+    let (synchronous, line) = match line.strip_prefix("sync ") {
+        Some(line) => (true, line.trim_start()),
+        None => (false, line)
+    };
+
+    let (mnemonic, operands) = match line.split_once(char::is_whitespace) {
+        Some((mnemonic, operands)) => (mnemonic, operands.trim()),
+        None => (line, "")
+    };
+
+    let operands = if operands.is_empty() { Vec::new() } else { operands.split(',').map(str::trim).collect::<Vec<_>>() };
+
+    if !synchronous {
+        if let Some(instructions) = parse_pseudo(mnemonic, &operands, constants)? {
+            parsed.instructions = instructions;
+            return Ok(parsed)
+        }
+    }
+
+    let (mnemonic, width) = match mnemonic.split_once('.') {
+        Some((mnemonic, width)) => (mnemonic, Some(Size::from_representation(width.into()).ok_or(LineError::Width)?)),
+        None => (mnemonic, None)
+    };
+
+    let extension = Extension::from_representation(mnemonic.into()).ok_or(LineError::Mnemonic)?;
+    let mut builder = InstructionBuilder::new().extension(extension.clone());
+    if let Some(width) = width { builder = builder.width(width); }
+    if synchronous { builder = builder.synchronous(); }
+
+    let mut expression = None;
+
+    builder = match operands.as_slice() {
+        [] => builder,
+        [operand] => match extension.presence() {
+            Some(OperandsPresence::Static) => builder.static_register(parse_register(operand).ok_or(LineError::Operand)?),
+            _ => {
+                let (x_dynamic, x_expression) = parse_dynamic(operand, constants)?;
+                expression = x_expression;
+                builder.dynamic(x_dynamic)
+            }
+        },
+        [destination, source] => {
+            let (x_static, dynamic, destination_dynamic) = match (parse_register(destination), parse_register(source)) {
+                (Some(x_static), _) => (x_static, source, false),
+                (None, Some(x_static)) => (x_static, destination, true),
+                (None, None) => return Err(if parse_dynamic(destination, constants).and(parse_dynamic(source, constants)).is_ok() { LineError::Static } else { LineError::Operand })
+            };
+
+            let (x_dynamic, x_expression) = parse_dynamic(dynamic, constants)?;
+            expression = x_expression;
+            builder = builder.static_register(x_static).dynamic(x_dynamic);
+            if destination_dynamic { builder.destination_dynamic() } else { builder }
+        },
+        [target, x_static, dynamic] => {
+            let target = parse_register(target).ok_or(LineError::Operand)?;
+            let x_static = parse_register(x_static).ok_or(LineError::Static)?;
+            let (x_dynamic, x_expression) = parse_dynamic(dynamic, constants)?;
+            expression = x_expression;
+            builder.target(target).static_register(x_static).dynamic(x_dynamic)
+        },
+        _ => return Err(LineError::OperandCount)
+    };
+
+    parsed.instructions.push((builder.build()?, expression.map(Reference::Expression)));
+    Ok(parsed)
+}
+
+/// Assemble a single line. [None] is returned for lines which are blank or only hold a comment, label or directive.
+/// Symbols can't be resolved without the rest of the program, so they are rejected along with calls, which need the
+/// address of the line.
+/// ```
+/// use atln_processor::programming::assembler::{assemble_line, LineError};
+///
+/// let instruction = assemble_line("sync add.w [r1 + 4], r2").unwrap().unwrap();
+/// assert_eq!(instruction.to_string(), "sync add.w [r1 + 4], r2");
+///
+/// assert!(matches!(assemble_line("  ; Nothing here."), Ok(None)));
+/// assert!(matches!(assemble_line("mul.b r1, r2"), Err(LineError::Mnemonic)));
+/// assert!(matches!(assemble_line("add.b 1, 2"), Err(LineError::Static)));
+/// assert!(matches!(assemble_line("add.q r1, [table]"), Err(LineError::Undefined)));
+///
+/// assert_eq!(assemble_line("li r1, 300").unwrap().unwrap().to_string(), "zext.w r1, 300");
+/// assert!(matches!(assemble_line("call 0x100"), Err(LineError::Undefined)));
+/// ```
+pub fn assemble_line(line: &str) -> Result<Option<Instruction>, LineError> {
+    match parse_line(line, &Map::new())?.instructions.as_slice() {
+        [] => Ok(None),
+        [(instruction, None)] => Ok(Some(instruction.clone())),
+        _ => Err(LineError::Undefined)
+    }
+}
+
+/// A program assembled into an object.
+struct Assembly {
+    object: Object,
+    /// Name of every included file, starting with the program itself.
+    files: Vec<String>,
+    /// Where each relocation was written, along with the text of the line.
+    relocations: Vec<(Position, String)>,
+    listing: Listing
+}
+
+/// Symbols of a file once every line has been laid out. Symbols which are not defined by the file are labels of other
+/// files.
+struct FileSymbols<'a> {
+    constants: &'a Map<String, u64>,
+    object: &'a Object
+}
+
+impl Symbols for FileSymbols<'_> {
+    fn value(&self, name: &str) -> Option<Value> {
+        Some(match self.constants.get(name) {
+            Some(&value) => Value::Absolute(value),
+            None => Value::Relative { symbol: String::from(name), addend: 0 }
+        })
+    }
+
+    fn offset(&self, name: &str) -> Option<u64> {
+        self.object.symbol(name).map(|symbol| symbol.offset)
+    }
+}
+
+/// Names of the labels and constants defined by a program, which misspelt symbols are compared against.
+fn defined<'a>(object: &'a Object, constants: &'a Map<String, u64>) -> Vec<&'a str> {
+    let labels = object.symbols.iter().map(|symbol| symbol.name.as_str()).filter(|name| !name.starts_with(RETURN_SYMBOL));
+    labels.chain(constants.keys().map(String::as_str)).collect()
+}
+
+/// Assemble a program into an object along with where each relocation was written.
+fn assemble_lines(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<Assembly, AssembleError> {
+    let mut object = Object::default();
+    let mut constants = Map::new();
+    let mut globals = Vec::new();
+    let mut returns = 0;
+    // Offset, size and value of every immediate which is patched once every label is known, along with its line.
+    let mut patches = Vec::new();
+    let mut listing = Listing::default();
+
+    let include::Included { files, lines } = include::include(source, file, resolver)?;
+    let expanded = macros::expand_lines(lines.iter().map(|(position, line)| (*position, line.as_str()))).map_err(|(position, error)| {
+        let text = lines.iter().find(|(line_position, _)| *line_position == position).map_or("", |(_, text)| text.as_str());
+        position.error(&files, error).locate(text, &[])
+    })?;
+
+    // The first pass lays out every line.
+    for (position, line) in &expanded {
+        let (position, number) = (*position, position.program_line);
+        let text = line.as_str();
+        let error = |error| position.error(&files, error).locate(text, &[]);
+        let start = object.code.len();
+        let line = parse_line(text, &constants).map_err(error)?;
+
+        if let Some(label) = line.label {
+            if object.symbol(label).is_some() || constants.contains_key(label) { return Err(error(LineError::Duplicate)) }
+            object.symbols.push(Symbol { name: String::from(label), offset: object.code.len() as u64, global: false });
+        }
+
+        match line.directive {
+            Some(Directive::Global(global)) => globals.push((position, global, text)),
+            Some(Directive::Constant(name, _)) if object.symbol(name).is_some() || constants.contains_key(name) => return Err(error(LineError::Duplicate)),
+            Some(Directive::Constant(name, value)) => { constants.insert(String::from(name), value); },
+            Some(Directive::Origin(origin)) => {
+                let origin = usize::try_from(origin).ok().filter(|&origin| origin <= MAX_OBJECT_SIZE).ok_or_else(|| error(LineError::Overflow))?;
+                if origin < object.code.len() { return Err(error(LineError::Origin)) }
+                object.code.resize(origin, 0);
+            },
+            Some(Directive::Align(alignment)) => {
+                let aligned = (object.code.len() as u64).checked_next_multiple_of(alignment).and_then(|aligned| usize::try_from(aligned).ok());
+                object.code.resize(aligned.filter(|&aligned| aligned <= MAX_OBJECT_SIZE).ok_or_else(|| error(LineError::Overflow))?, 0);
+            },
+            Some(Directive::Data(bytes, references)) => {
+                if object.code.len() + bytes.len() > MAX_OBJECT_SIZE { return Err(error(LineError::Overflow)) }
+                for (offset, size, expression) in references { patches.push((object.code.len() + offset, size, expression, position, text)); }
+                object.code.extend(bytes);
+            },
+            None => ()
+        }
+
+        let return_symbol = format!("{RETURN_SYMBOL}{returns}");
+        let mut returned = false;
+
+        for (instruction, reference) in line.instructions {
+            object.lines.push(SourceLine { offset: object.code.len() as u64, line: number as u32 });
+            object.code.extend(instruction.encode());
+
+            let expression = match reference {
+                Some(Reference::Expression(expression)) => expression,
+                Some(Reference::Return) => {
+                    returned = true;
+                    Expression::Symbol(return_symbol.clone())
+                },
+                None => continue
+            };
+
+            // Immediates are encoded last, so the quad holding the value ends the instruction.
+            patches.push((object.code.len() - Size::Quad.size() as usize, Size::Quad, expression, position, text));
+        }
+
+        if returned {
+            object.symbols.push(Symbol { name: return_symbol, offset: object.code.len() as u64, global: false });
+            returns += 1;
+        }
+
+        let file = if position.file == 0 { None } else { Some(files[position.file].clone()) };
+        listing.entries.push(Entry { address: start as u64, bytes: object.code[start..].to_vec(), file, line: position.line, source: String::from(text.trim()) });
+    }
+
+    for (position, global, text) in globals {
+        match object.symbols.iter().position(|symbol| symbol.name == global) {
+            Some(index) => object.symbols[index].global = true,
+            None => return Err(position.error(&files, LineError::Undefined).locate(text, &defined(&object, &constants)))
+        }
+    }
+
+    // The second pass patches every value which uses a label. Values relative to a label are left for the linker.
+    let mut relocations = Vec::new();
+
+    for (offset, size, expression, position, text) in patches {
+        let symbols = FileSymbols { constants: &constants, object: &object };
+        let error = |error| position.error(&files, error).locate(text, &defined(&object, &constants));
+
+        match expression.evaluate(&symbols).map_err(|evaluate| error(LineError::Expression(evaluate)))? {
+            Value::Absolute(value) => {
+                if !fits(&size, value) { return Err(error(LineError::Overflow)) }
+                object.code[offset..offset + size.size() as usize].copy_from_slice(&number::Data::from_size_selecting(&size, value).to_le_bytes());
+            },
+            Value::Relative { symbol, addend } => {
+                object.relocations.push(Relocation { offset: offset as u64, size, symbol, addend: addend as i64 });
+                relocations.push((position, String::from(text)));
+            }
+        }
+    }
+
+    listing.fill(&object.code);
+    Ok(Assembly { object, files, relocations, listing })
+}
+
+/// Assemble a program into an object whose symbols are resolved by the [linker](super::linker). The line of every
+/// instruction is recorded for debug information, the file name is left for the caller to fill in.
+/// ```
+/// use atln_processor::number::Size;
+/// use atln_processor::programming::assembler::assemble_object;
+/// use atln_processor::programming::object::{Relocation, Symbol};
+///
+/// let object = assemble_object("
+///     .global start
+///     start: add.b r1, [counter]
+///     loop:
+///     halt
+/// ").unwrap();
+///
+/// assert_eq!(object.symbols, [
+///     Symbol { name: "start".into(), offset: 0, global: true },
+///     Symbol { name: "loop".into(), offset: 11, global: false }
+/// ]);
+/// assert_eq!(object.relocations, [Relocation { offset: 3, size: Size::Quad, symbol: "counter".into(), addend: 0 }]);
+/// ```
+pub fn assemble_object(source: &str) -> Result<Object, AssembleError> {
+    assemble_object_including(source, "", &mut Map::new())
+}
+
+/// Assemble a program named `file` into an object like [assemble_object], finding the files it includes with a
+/// resolver.
+pub fn assemble_object_including(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<Object, AssembleError> {
+    assemble_object_listing(source, file, resolver).map(|(object, _)| object)
+}
+
+/// Assemble a program named `file` into an object like [assemble_object_including] along with its [listing]. Values
+/// relocated by the linker are listed as zeros until the listing is [filled](Listing::fill) with the linked code.
+/// ```
+/// use atln_processor::programming::assembler::assemble_object_listing;
+/// use atln_processor::programming::linker::link;
+/// use atln_processor::utility::Map;
+///
+/// let (object, mut listing) = assemble_object_listing("
+///     start: add.b r1, [value]
+///     halt
+///     value: .byte 42
+/// ", "main.s", &mut Map::new()).unwrap();
+///
+/// assert_eq!(listing.entries[1].source, "start: add.b r1, [value]");
+/// assert_eq!(listing.entries[1].bytes[3..], [0; 8]);
+///
+/// let image = link(&[object], 0).unwrap();
+/// listing.fill(&image.sections[0].data);
+/// assert_eq!(listing.entries[1].bytes[3..], 13u64.to_le_bytes());
+/// assert_eq!(listing.entry(13).map(|entry| entry.line), Some(4));
+///
+/// let rows: Vec<String> = listing.to_string().lines().map(str::to_string).collect();
+/// assert_eq!(rows[1..], [
+///     "00000000  00 0f 08 0d 00 00 00 00      2  start: add.b r1, [value]",
+///     "00000008  00 00 00",
+///     "0000000b  08 00                        3  halt",
+///     "0000000d  2a                           4  value: .byte 42"
+/// ]);
+/// ```
+pub fn assemble_object_listing(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<(Object, Listing), AssembleError> {
+    assemble_lines(source, file, resolver).map(|assembly| (assembly.object, assembly.listing))
+}
+
+/// Assemble a program which is loaded at address 0 and concatenate the encoded instructions. Every symbol must be
+/// defined by the program itself.
+/// ```
+/// use atln_processor::programming::assembler::{assemble, LineError};
+///
+/// let error = assemble("halt\nadd.x r1, r2").unwrap_err();
+/// assert_eq!(error.line, 2);
+/// assert_eq!(error.error, LineError::Width);
+///
+/// let program = assemble("add.b r1, end\nend: halt").unwrap();
+/// assert_eq!(program[3..11], 11u64.to_le_bytes());
+///
+/// assert_eq!(assemble("halt\nadd.b r1, missing").unwrap_err().line, 2);
+/// ```
+///
+/// Data is placed by directives.
+/// ```
+/// use atln_processor::programming::assembler::{assemble, LineError};
+///
+/// let program = assemble("
+///     .byte 1, -1, end
+///     .align 4
+///     .word 0x1234
+///     .ascii \"A;\\x42\\n\" ; Comment.
+///     .org 0x10
+///     .space 2, 0xEE
+///     end:
+/// ").unwrap();
+///
+/// assert_eq!(program, [1, 0xFF, 0x12, 0, 0x34, 0x12, b'A', b';', b'B', b'\n', 0, 0, 0, 0, 0, 0, 0xEE, 0xEE]);
+///
+/// assert_eq!(assemble(".byte 256").unwrap_err().error, LineError::Overflow);
+/// assert_eq!(assemble(".org 0x100\n.byte end\nend:").unwrap_err().error, LineError::Overflow);
+/// assert_eq!(assemble(".space 4\n.org 2").unwrap_err().error, LineError::Origin);
+///
+/// // Objects can't grow past MAX_OBJECT_SIZE, however the space is asked for.
+/// assert_eq!(assemble(".space 0x7fffffffffffffff").unwrap_err().error, LineError::Overflow);
+/// assert_eq!(assemble(".org 0x7fffffffffffffff").unwrap_err().error, LineError::Overflow);
+/// assert_eq!(assemble(".align 0x7fffffffffffffff\n.byte 1\n.align 0x7fffffffffffffff").unwrap_err().error, LineError::Overflow);
+/// assert_eq!(assemble(".space 0x800000\n.space 0x800000\n.byte 1").unwrap_err().error, LineError::Overflow);
+/// ```
+///
+/// Expressions are evaluated once every label is known.
+/// ```
+/// use atln_processor::programming::assembler::{assemble, LineError};
+/// use atln_processor::programming::assembler::expression::EvaluateError;
+///
+/// let program = assemble("
+///     .equ INDEX, 2
+///     .equ MASK, (1 << 12) - 1
+///     .word MASK & ~0xF, end - start
+///     start: .quad table + 4 * INDEX
+///     table: .space 8
+///     end:
+/// ").unwrap();
+///
+/// assert_eq!(program[..4], [0xF0, 0x0F, 16, 0]);
+/// assert_eq!(program[4..12], 20u64.to_le_bytes());
+///
+/// assert_eq!(assemble(".byte 1 / 0").unwrap_err().error, LineError::Expression(EvaluateError::DivideByZero));
+/// assert_eq!(assemble(".quad end * 2\nend:").unwrap_err().error, LineError::Expression(EvaluateError::Relative));
+/// ```
+///
+/// Pseudo-instructions and macros are expanded along the way.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble;
+///
+/// let program = assemble("
+///     li r1, 300
+///     li r2, -2
+///     call double
+///     call double
+///     nop
+///     halt
+///
+///     double: add.q r1, r1
+///     ret
+/// ").unwrap();
+///
+/// let mut core = Core::default();
+/// core.run(&mut Memory::from(program), &mut Default::default(), None);
+/// assert_eq!(core.context.registers[1..3], [1200, -2i64 as u64]);
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    assemble_including(source, "", &mut Map::new())
+}
+
+/// Assemble a program named `file` like [assemble], finding the files it includes with a resolver.
+pub fn assemble_including(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<Vec<u8>, AssembleError> {
+    assemble_listing(source, file, resolver).map(|(code, _)| code)
+}
+
+/// Assemble a program named `file` like [assemble_including] along with its [listing], which lists the bytes of the
+/// program.
+pub fn assemble_listing(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<(Vec<u8>, Listing), AssembleError> {
+    let Assembly { mut object, files, relocations, mut listing } = assemble_lines(source, file, resolver)?;
+
+    for (relocation, (position, text)) in object.relocations.iter().zip(relocations) {
+        let error = |error| position.error(&files, error).locate(&text, &defined(&object, &Map::new()));
+        let address = match object.symbol(&relocation.symbol) {
+            Some(symbol) => symbol.offset.wrapping_add_signed(relocation.addend),
+            None => return Err(error(LineError::Undefined))
+        };
+
+        let (offset, size) = (relocation.offset as usize, relocation.size.size() as usize);
+        if size < Size::Quad.size() as usize && address >> (size * 8) != 0 { return Err(error(LineError::Overflow)) }
+        object.code[offset..offset + size].copy_from_slice(&number::Data::from_size_selecting(&relocation.size, address).to_le_bytes());
+    }
+
+    listing.fill(&object.code);
+    Ok((object.code, listing))
+}
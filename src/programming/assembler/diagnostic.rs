@@ -0,0 +1,285 @@
+//! Pointing [assembler errors](AssembleError) at the part of the line which caused them and suggesting how to fix them.
+//!
+//! Every error raised while assembling a line carries a [Diagnostic] with the text of the line after macros are
+//! expanded, the [Span] of the token with the problem and, where one can be guessed, a suggestion.
+//! [AssembleError::report] formats all of it the way compilers do.
+//!
+//! | Problem                       | Token                      | Suggestion                               |
+//! |-------------------------------|----------------------------|------------------------------------------|
+//! | Unknown mnemonic or directive | The mnemonic or directive. | The closest one that exists.             |
+//! | Unknown width suffix          | The suffix.                | The suffixes that exist.                 |
+//! | Register which does not exist | The register.              | The registers that exist.                |
+//! | Operand which can't be parsed | The operand.               | The register, if only its case is wrong. |
+//! | Value which does not fit      | The value.                 | A data directive wide enough to hold it. |
+//! | Symbol which is not defined   | The symbol.                | The closest symbol that is defined.      |
+//! ```
+//! use atln_processor::programming::assembler::assemble;
+//! use atln_processor::programming::assembler::AssembleError;
+//! use atln_processor::programming::assembler::diagnostic::Span;
+//!
+//! fn suggestion(error: &AssembleError) -> Option<&str> {
+//!     error.diagnostic.as_ref()?.suggestion.as_deref()
+//! }
+//!
+//! let error = assemble("halt\n    ad.q r1, r2").unwrap_err();
+//! let diagnostic = error.diagnostic.as_ref().unwrap();
+//! assert_eq!(diagnostic.span, Span { column: 5, length: 2 });
+//! assert_eq!(diagnostic.suggestion.as_deref(), Some("did you mean `add`?"));
+//! assert_eq!(error.token(), Some("ad"));
+//!
+//! assert_eq!(error.report("main.s"), "\
+//! no operation has the mnemonic
+//!  --> main.s:2:5
+//!   |
+//! 2 |     ad.q r1, r2
+//!   |     ^^ did you mean `add`?
+//! ");
+//!
+//! let error = assemble("add.q r1, r9").unwrap_err();
+//! assert_eq!((error.token(), suggestion(&error)), (Some("r9"), Some("registers are r0 to r7")));
+//!
+//! let error = assemble(".byte 1, 300").unwrap_err();
+//! assert_eq!((error.token(), suggestion(&error)), (Some("300"), Some("300 needs 2 bytes, place it with .word")));
+//!
+//! let error = assemble("counter: halt\nadd.q r1, [countr]").unwrap_err();
+//! assert_eq!((error.token(), suggestion(&error)), (Some("countr"), Some("did you mean `counter`?")));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::Write;
+use emulator::processor::processor::instruction::builder::BuildError;
+use emulator::processor::processor::instruction::operation::metadata;
+use emulator::processor::processor::Registers;
+use number;
+use super::expression::{EvaluateError, Expression, Value};
+use super::include::INCLUDE_DIRECTIVE;
+use super::macros::{END_MACRO_DIRECTIVE, MACRO_DIRECTIVE};
+use super::{parse_dynamic, parse_register, split_label, strip_comment, AssembleError, LineError};
+use super::{ALIGN_DIRECTIVE, ASCII_DIRECTIVE, CONSTANT_DIRECTIVE, DATA_DIRECTIVES, GLOBAL_DIRECTIVE, ORIGIN_DIRECTIVE, SPACE_DIRECTIVE};
+use utility::Map;
+
+/// Mnemonics of the pseudo-instructions.
+const PSEUDO_INSTRUCTIONS: [&str; 4] = ["nop", "li", "call", "ret"];
+
+/// Columns of a line covered by a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// Column of the first character starting from 1.
+    pub column: usize,
+    /// Number of characters, which is at least 1.
+    pub length: usize
+}
+
+/// Where in its line an error is and how it might be fixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Text of the line after macros are expanded.
+    pub text: String,
+    /// Token which caused the error.
+    pub span: Span,
+    pub suggestion: Option<String>
+}
+
+/// Number of single character insertions, deletions and substitutions which turn one text into another.
+fn distance(from: &str, to: &str) -> usize {
+    let to: Vec<char> = to.chars().collect();
+    let mut previous: Vec<usize> = (0..=to.len()).collect();
+
+    for (index, from) in from.chars().enumerate() {
+        let mut current = vec![index + 1];
+        for (to_index, &to) in to.iter().enumerate() {
+            let substitution = previous[to_index] + usize::from(from != to);
+            current.push(substitution.min(previous[to_index + 1] + 1).min(current[to_index] + 1));
+        }
+        previous = current;
+    }
+
+    previous[to.len()]
+}
+
+/// Candidate closest to a misspelt name, as long as it is close enough to be a likely typo. Case is ignored, so a name
+/// written in the wrong case suggests the right one.
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    let lowercase = name.to_ascii_lowercase();
+    candidates.into_iter()
+        .map(|candidate| (distance(&lowercase, &candidate.to_ascii_lowercase()), candidate))
+        .filter(|&(distance, candidate)| candidate != name && distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Byte offset of a part of a text which was sliced from it.
+fn offset(text: &str, part: &str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// First occurrence of a name which is not part of a longer word.
+fn find_word<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let word = |character: char| character.is_ascii_alphanumeric() || character == '_';
+    text.match_indices(name).map(|(start, _)| start).find(|&start| {
+        !text[..start].ends_with(word) && !text[start + name.len()..].starts_with(word)
+    }).map(|start| &text[start..start + name.len()])
+}
+
+/// Words of a text, which are runs of letters, digits and underscores.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|character: char| !(character.is_ascii_alphanumeric() || character == '_')).filter(|word| !word.is_empty())
+}
+
+/// Parts of a line of code.
+struct Parts<'a> {
+    label: Option<&'a str>,
+    /// Mnemonic including its width suffix, or directive.
+    mnemonic: &'a str,
+    /// Everything after the mnemonic.
+    arguments: &'a str,
+    operands: Vec<&'a str>
+}
+
+impl<'a> Parts<'a> {
+    fn new(code: &'a str) -> Self {
+        let (label, code) = split_label(code);
+        let code = code.strip_prefix("sync ").map(str::trim_start).unwrap_or(code);
+        let (mnemonic, arguments) = code.split_once(char::is_whitespace).map(|(mnemonic, arguments)| (mnemonic, arguments.trim())).unwrap_or((code, &code[code.len()..]));
+        let operands = if arguments.is_empty() { Vec::new() } else { arguments.split(',').map(str::trim).collect() };
+        Self { label, mnemonic, arguments, operands }
+    }
+
+    /// First operand which can't be parsed on its own.
+    fn invalid_operand(&self) -> Option<&'a str> {
+        self.operands.iter().copied().find(|operand| parse_dynamic(operand, &Map::new()).is_err())
+    }
+
+    /// First value of a data directive which does not fit its size, along with the size it needs.
+    fn overflowing(&self) -> Option<(&'a str, u8)> {
+        let (_, size) = DATA_DIRECTIVES.iter().find(|(name, _)| *name == self.mnemonic)?;
+
+        self.operands.iter().find_map(|&operand| match Expression::parse(operand)?.evaluate(&Map::<String, u64>::new()) {
+            Ok(Value::Absolute(value)) if !super::fits(size, value) => Some((operand, number::Data::from_quad_selecting(value).size())),
+            _ => None
+        })
+    }
+}
+
+/// Token of a line which caused an error along with a suggestion. Symbols are the names defined by the program.
+fn diagnose<'a>(code: &'a str, error: &LineError, symbols: &[&str]) -> Option<(&'a str, Option<String>)> {
+    let parts = Parts::new(code);
+    let directive = parts.mnemonic.starts_with('.');
+    let name = || parts.arguments.split(|character: char| character == ',' || character.is_whitespace()).next().filter(|name| !name.is_empty());
+
+    Some(match error {
+        LineError::Mnemonic => {
+            let mnemonic = parts.mnemonic.split('.').next().unwrap_or(parts.mnemonic);
+            let operations = metadata::operations();
+            let candidates = operations.iter().map(|operation| operation.mnemonic.as_ref()).chain(PSEUDO_INSTRUCTIONS.iter().copied());
+            (mnemonic, closest(mnemonic, candidates).map(|candidate| format!("did you mean `{candidate}`?")))
+        },
+        LineError::Width => (parts.mnemonic.split_once('.')?.1, Some("widths are b, w, d and q".to_string())),
+        LineError::Directive => {
+            let directives = [GLOBAL_DIRECTIVE, CONSTANT_DIRECTIVE, ORIGIN_DIRECTIVE, ALIGN_DIRECTIVE, SPACE_DIRECTIVE, ASCII_DIRECTIVE, INCLUDE_DIRECTIVE, MACRO_DIRECTIVE, END_MACRO_DIRECTIVE];
+            let candidates = directives.iter().copied().chain(DATA_DIRECTIVES.iter().map(|(name, _)| *name));
+            (parts.mnemonic, closest(parts.mnemonic, candidates).map(|candidate| format!("did you mean `{candidate}`?")))
+        },
+        LineError::Build(BuildError::Register) => {
+            let registers = Registers::default().len();
+            let register = parts.operands.iter().flat_map(|operand| words(operand)).find(|word| parse_register(word).is_some_and(|register| register as usize >= registers))?;
+            (register, Some(format!("registers are r0 to r{}", registers - 1)))
+        },
+        LineError::Operand | LineError::Static if !directive => {
+            let uppercase = parts.operands.iter().copied().find(|operand| parse_register(operand).is_none() && parse_register(&operand.to_ascii_lowercase()).is_some());
+            match uppercase {
+                Some(operand) => (operand, Some(format!("did you mean `{}`?", operand.to_ascii_lowercase()))),
+                None if error == &LineError::Static => (parts.arguments, None),
+                None => (parts.invalid_operand().or_else(|| parts.operands.first().copied())?, None)
+            }
+        },
+        LineError::Overflow => match parts.overflowing() {
+            Some((value, size)) => {
+                let (directive, _) = DATA_DIRECTIVES.iter().find(|(_, directive_size)| directive_size.size() == size)?;
+                (value, Some(format!("{value} needs {size} bytes, place it with {directive}")))
+            },
+            None if directive => (parts.operands.last().copied()?, None),
+            None => return None
+        },
+        LineError::Symbol => match parts.label {
+            Some(label) if super::parse_symbol(label).is_none() => (label, None),
+            _ => (name()?, None)
+        },
+        LineError::Duplicate => match parts.label {
+            Some(label) if parts.mnemonic != CONSTANT_DIRECTIVE && parts.mnemonic != MACRO_DIRECTIVE => (label, None),
+            _ => (name()?, None)
+        },
+        LineError::Undefined => {
+            let undefined = if directive { name()? } else {
+                parts.operands.iter().flat_map(|operand| words(operand)).find(|word| super::parse_symbol(word).is_some() && !symbols.contains(word))?
+            };
+            (undefined, closest(undefined, symbols.iter().copied()).map(|candidate| format!("did you mean `{candidate}`?")))
+        },
+        LineError::Expression(EvaluateError::Undefined(undefined)) => {
+            let token = find_word(parts.arguments, undefined)?;
+            (token, closest(undefined, symbols.iter().copied()).map(|candidate| format!("did you mean `{candidate}`?")))
+        },
+        LineError::Arguments | LineError::Recursion | LineError::Unterminated => (parts.mnemonic, None),
+        _ if !parts.arguments.is_empty() => (parts.arguments, None),
+        _ => return None
+    })
+}
+
+impl AssembleError {
+    /// Point the error at the token of a line which caused it and suggest a fix. Symbols are the names defined by the
+    /// program, which misspelt symbols are compared against.
+    pub(crate) fn locate(mut self, text: &str, symbols: &[&str]) -> Self {
+        let code = strip_comment(text);
+        let (token, suggestion) = diagnose(code, &self.error, symbols).unwrap_or((code, None));
+        if token.is_empty() { return self }
+
+        let start = offset(text, token);
+        let span = Span { column: text[..start].chars().count() + 1, length: token.chars().count() };
+        self.diagnostic = Some(Box::new(Diagnostic { text: text.to_string(), span, suggestion }));
+        self
+    }
+
+    /// Text of the token the error points at.
+    pub fn token(&self) -> Option<&str> {
+        let diagnostic = self.diagnostic.as_ref()?;
+        let (text, span) = (diagnostic.text.as_str(), diagnostic.span);
+        let start = text.char_indices().nth(span.column - 1)?.0;
+        let end = text[start..].char_indices().nth(span.length).map_or(text.len(), |(end, _)| start + end);
+        Some(&text[start..end])
+    }
+
+    /// Describe the error along with every error that caused it, where it is and the line it is on with the token
+    /// underlined. Lines of the program itself are attributed to the name of the program, which can be left empty.
+    pub fn report(&self, program: &str) -> String {
+        let mut report = self.error.to_string();
+        let mut source = self.error.source();
+        while let Some(error) = source {
+            let _ = write!(report, ": {error}");
+            source = error.source();
+        }
+
+        let column = self.diagnostic.as_ref().map(|diagnostic| diagnostic.span.column);
+        let location = match (self.file.as_deref().unwrap_or(program), column) {
+            ("", Some(column)) => format!("line {}, column {column}", self.line),
+            ("", None) => format!("line {}", self.line),
+            (file, Some(column)) => format!("{file}:{}:{column}", self.line),
+            (file, None) => format!("{file}:{}", self.line)
+        };
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        let _ = write!(report, "\n{gutter}--> {location}\n");
+
+        if let Some(Diagnostic { text, span, suggestion }) = self.diagnostic.as_deref() {
+            let indent: String = text.chars().take(span.column - 1).map(|character| if character == '\t' { '\t' } else { ' ' }).collect();
+            let suggestion = suggestion.as_ref().map_or(String::new(), |suggestion| format!(" {suggestion}"));
+            let _ = write!(report, "{gutter} |\n{} | {}\n", self.line, text.trim_end());
+            let _ = writeln!(report, "{gutter} | {indent}{}{suggestion}", "^".repeat(span.length));
+        }
+
+        report
+    }
+}
@@ -0,0 +1,252 @@
+//! Constant expressions over numbers and symbols, such as `buffer + 4 * INDEX` or `(1 << 12) - 1`, which can be written
+//! anywhere the assembler takes a number.
+//!
+//! Operators bind as tightly as they do in C. Arithmetic is unsigned and wraps around at 64 bits.
+//!
+//! | Operators           | Meaning                                              |
+//! |---------------------|------------------------------------------------------|
+//! | `-`, `~`, `+`       | Negate, invert every bit and keep, before a value.   |
+//! | `*`, `/`, `%`       | Multiply, divide and take the remainder of dividing. |
+//! | `+`, `-`            | Add and subtract.                                    |
+//! | `<<`, `>>`          | Shift left and right, filling with zeros.            |
+//! | `&`                 | Bitwise and.                                         |
+//! | `^`                 | Bitwise exclusive or.                                |
+//! | <code>&#124;</code> | Bitwise or.                                          |
+//!
+//! A symbol is either a constant defined with `.equ NAME, value` or a label. The address of a label is not known until
+//! the program is linked, so an expression with a label can only add a number to it or subtract one from it, unless it
+//! is the difference of two labels in the same file.
+//! ```
+//! use atln_processor::programming::assembler::expression::{Expression, Value};
+//! use atln_processor::utility::Map;
+//!
+//! let mut constants = Map::new();
+//! constants.insert("INDEX".to_string(), 3);
+//!
+//! let mask = Expression::parse("(1 << 12) - 1").unwrap();
+//! assert_eq!(mask.evaluate(&constants), Ok(Value::Absolute(0xFFF)));
+//!
+//! let element = Expression::parse("buffer + 4 * INDEX").unwrap();
+//! assert!(element.evaluate(&constants).is_err());
+//!
+//! let mut symbols = Map::new();
+//! symbols.insert("buffer".to_string(), Value::Relative { symbol: "buffer".to_string(), addend: 0 });
+//! symbols.insert("INDEX".to_string(), Value::Absolute(3));
+//! assert_eq!(element.evaluate(&symbols), Ok(Value::Relative { symbol: "buffer".to_string(), addend: 12 }));
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use super::{parse_number, parse_symbol};
+use utility::Map;
+
+/// An operator written before a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unary {
+    Negate,
+    Not
+}
+
+/// An operator written between two values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binary {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Xor,
+    Or
+}
+
+/// Binary operators along with how they are written, from the loosest binding to the tightest.
+const PRECEDENCE: [&[(&str, Binary)]; 6] = [
+    &[("|", Binary::Or)],
+    &[("^", Binary::Xor)],
+    &[("&", Binary::And)],
+    &[("<<", Binary::ShiftLeft), (">>", Binary::ShiftRight)],
+    &[("+", Binary::Add), ("-", Binary::Subtract)],
+    &[("*", Binary::Multiply), ("/", Binary::Divide), ("%", Binary::Remainder)]
+];
+
+impl Binary {
+    fn apply(self, left: u64, right: u64) -> Result<u64, EvaluateError> {
+        Ok(match self {
+            Self::Multiply => left.wrapping_mul(right),
+            Self::Divide => left.checked_div(right).ok_or(EvaluateError::DivideByZero)?,
+            Self::Remainder => left.checked_rem(right).ok_or(EvaluateError::DivideByZero)?,
+            Self::Add => left.wrapping_add(right),
+            Self::Subtract => left.wrapping_sub(right),
+            Self::ShiftLeft => u32::try_from(right).ok().and_then(|right| left.checked_shl(right)).ok_or(EvaluateError::Shift)?,
+            Self::ShiftRight => u32::try_from(right).ok().and_then(|right| left.checked_shr(right)).ok_or(EvaluateError::Shift)?,
+            Self::And => left & right,
+            Self::Xor => left ^ right,
+            Self::Or => left | right
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Number(u64),
+    Symbol(String),
+    Unary(Unary, Box<Expression>),
+    Binary(Binary, Box<Expression>, Box<Expression>)
+}
+
+/// What an expression evaluates to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Absolute(u64),
+    /// A number added to the address of a label, which is known once the program is linked.
+    Relative { symbol: String, addend: u64 }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvaluateError {
+    /// A symbol is not defined.
+    Undefined(String),
+    DivideByZero,
+    /// A value is shifted by 64 bits or more.
+    Shift,
+    /// An operator other than adding or subtracting a number is applied to the address of a label.
+    Relative
+}
+
+impl Display for EvaluateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undefined(name) => write!(f, "symbol {name} is not defined"),
+            Self::DivideByZero => f.write_str("division by zero"),
+            Self::Shift => f.write_str("value is shifted by 64 bits or more"),
+            Self::Relative => f.write_str("address of a label can only have a number added or subtracted")
+        }
+    }
+}
+
+impl Error for EvaluateError {}
+
+/// Values of the symbols used by expressions.
+pub trait Symbols {
+    /// Value of a symbol, or [None] if it is not defined.
+    fn value(&self, name: &str) -> Option<Value>;
+
+    /// Offset of a label from the start of its file if it is known, which lets the difference of two labels be
+    /// evaluated.
+    fn offset(&self, name: &str) -> Option<u64> {
+        let _ = name;
+        None
+    }
+}
+
+/// Constants by name.
+impl Symbols for Map<String, u64> {
+    fn value(&self, name: &str) -> Option<Value> {
+        self.get(name).map(|&value| Value::Absolute(value))
+    }
+}
+
+impl Symbols for Map<String, Value> {
+    fn value(&self, name: &str) -> Option<Value> {
+        self.get(name).cloned()
+    }
+}
+
+struct Parser<'a> {
+    rest: &'a str
+}
+
+impl Parser<'_> {
+    /// Take a token if the rest of the text starts with it.
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.trim_start().strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Option<Expression> {
+        let operators = match PRECEDENCE.get(level) {
+            Some(operators) => operators,
+            None => return self.unary()
+        };
+
+        let mut left = self.binary(level + 1)?;
+
+        'operators: loop {
+            for &(token, operator) in operators.iter() {
+                if self.eat(token) {
+                    left = Expression::Binary(operator, Box::new(left), Box::new(self.binary(level + 1)?));
+                    continue 'operators
+                }
+            }
+
+            return Some(left)
+        }
+    }
+
+    fn unary(&mut self) -> Option<Expression> {
+        if self.eat("-") { return Some(Expression::Unary(Unary::Negate, Box::new(self.unary()?))) }
+        if self.eat("~") { return Some(Expression::Unary(Unary::Not, Box::new(self.unary()?))) }
+        if self.eat("+") { return self.unary() }
+
+        if self.eat("(") {
+            let expression = self.binary(0)?;
+            return if self.eat(")") { Some(expression) } else { None }
+        }
+
+        let rest = self.rest.trim_start();
+        let end = rest.find(|character: char| !(character.is_ascii_alphanumeric() || character == '_')).unwrap_or(rest.len());
+        let (atom, rest) = rest.split_at(end);
+        self.rest = rest;
+
+        if atom.starts_with(|character: char| character.is_ascii_digit()) { return parse_number(atom).map(Expression::Number) }
+        parse_symbol(atom).map(|symbol| Expression::Symbol(symbol.to_owned()))
+    }
+}
+
+impl Expression {
+    /// Parse an expression, or return [None] if the text is not one. Registers are not symbols, so they are not
+    /// expressions.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parser = Parser { rest: text };
+        let expression = parser.binary(0)?;
+        if parser.rest.trim().is_empty() { Some(expression) } else { None }
+    }
+
+    pub fn evaluate(&self, symbols: &dyn Symbols) -> Result<Value, EvaluateError> {
+        Ok(match self {
+            Self::Number(number) => Value::Absolute(*number),
+            Self::Symbol(name) => symbols.value(name).ok_or_else(|| EvaluateError::Undefined(name.clone()))?,
+            Self::Unary(operator, operand) => match (operator, operand.evaluate(symbols)?) {
+                (Unary::Negate, Value::Absolute(value)) => Value::Absolute(value.wrapping_neg()),
+                (Unary::Not, Value::Absolute(value)) => Value::Absolute(!value),
+                _ => return Err(EvaluateError::Relative)
+            },
+            Self::Binary(operator, left, right) => match (operator, left.evaluate(symbols)?, right.evaluate(symbols)?) {
+                (_, Value::Absolute(left), Value::Absolute(right)) => Value::Absolute(operator.apply(left, right)?),
+                (Binary::Add, Value::Relative { symbol, addend }, Value::Absolute(number))
+                | (Binary::Add, Value::Absolute(number), Value::Relative { symbol, addend }) => Value::Relative { symbol, addend: addend.wrapping_add(number) },
+                (Binary::Subtract, Value::Relative { symbol, addend }, Value::Absolute(number)) => Value::Relative { symbol, addend: addend.wrapping_sub(number) },
+                (Binary::Subtract, Value::Relative { symbol: left, addend: left_addend }, Value::Relative { symbol: right, addend: right_addend }) => {
+                    match (symbols.offset(&left), symbols.offset(&right)) {
+                        (Some(left), Some(right)) => Value::Absolute(left.wrapping_add(left_addend).wrapping_sub(right.wrapping_add(right_addend))),
+                        _ => return Err(EvaluateError::Relative)
+                    }
+                },
+                _ => return Err(EvaluateError::Relative)
+            }
+        })
+    }
+}
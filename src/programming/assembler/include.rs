@@ -0,0 +1,121 @@
+//! Splitting a program over multiple files with `.include "name"`, which assembles the named file in place of the
+//! directive.
+//!
+//! A [Resolver] finds the file that a name refers to. Every file is only included once, however many times and from
+//! however many files it is named, so a file can include everything it uses without guarding against being included
+//! twice and files which include each other don't include forever. Macros and labels of included files can be used by
+//! every other file. Included code is recorded in debug information as the line of the program which included it.
+//! ```
+//! use atln_processor::programming::assembler::{assemble_including, LineError};
+//! use atln_processor::utility::Map;
+//!
+//! let mut files = Map::new();
+//! files.insert("double.s".to_string(), ".include \"double.s\"\n.macro double register\nadd.q \\register, \\register\n.endm".to_string());
+//! files.insert("broken.s".to_string(), "halt\nadd.x r1, r2".to_string());
+//!
+//! let program = assemble_including(".include \"double.s\"\n.include \"double.s\"\ndouble r1\nhalt", "main.s", &mut files).unwrap();
+//! assert_eq!(program.len(), 5);
+//!
+//! let error = assemble_including(".include \"broken.s\"", "main.s", &mut files).unwrap_err();
+//! assert_eq!((error.file.as_deref(), error.line, error.error), (Some("broken.s"), 2, LineError::Width));
+//!
+//! let error = assemble_including(".include \"missing.s\"", "main.s", &mut files).unwrap_err();
+//! assert_eq!(error.error, LineError::Include);
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use super::{parse_string, strip_comment, AssembleError, LineError, Position};
+use utility::Map;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::iter;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Directive which assembles another file in its place.
+pub const INCLUDE_DIRECTIVE: &str = ".include";
+
+/// Finds the files which include directives name.
+pub trait Resolver {
+    /// Find the file that an include directive in the file named `from` refers to by name. The contents of the file
+    /// are returned along with a name which is the same however the file is referred to, which is used to include every
+    /// file once. [None] is returned if the file can't be found or read.
+    fn resolve(&mut self, from: &str, name: &str) -> Option<(String, String)>;
+}
+
+/// Files held in memory by name, where every file refers to other files by the same name.
+impl Resolver for Map<String, String> {
+    fn resolve(&mut self, _from: &str, name: &str) -> Option<(String, String)> {
+        self.get(name).map(|contents| (name.to_owned(), contents.clone()))
+    }
+}
+
+/// Files on the file system, which are looked for relative to the directory of the file including them first and then
+/// in each of the include directories in order. Only available with the `std` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct Directories {
+    pub directories: Vec<PathBuf>
+}
+
+#[cfg(feature = "std")]
+impl Resolver for Directories {
+    fn resolve(&mut self, from: &str, name: &str) -> Option<(String, String)> {
+        let parent = Path::new(from).parent().map(Path::to_path_buf).unwrap_or_default();
+
+        iter::once(parent).chain(self.directories.iter().cloned()).find_map(|directory| {
+            let path = fs::canonicalize(directory.join(name)).ok()?;
+            let contents = fs::read_to_string(&path).ok()?;
+            Some((path.to_string_lossy().into_owned(), contents))
+        })
+    }
+}
+
+/// A program with the lines of every file it includes in place of the include directives.
+pub(crate) struct Included {
+    /// Name of every file included, starting with the program itself.
+    pub(crate) files: Vec<String>,
+    /// Every line along with where it was written.
+    pub(crate) lines: Vec<(Position, String)>
+}
+
+struct Includer<'a> {
+    resolver: &'a mut dyn Resolver,
+    included: Included
+}
+
+impl Includer<'_> {
+    fn file(&mut self, file: usize, source: &str, program_line: Option<usize>) -> Result<(), AssembleError> {
+        for (index, line) in source.lines().enumerate() {
+            let position = Position { file, line: index + 1, program_line: program_line.unwrap_or(index + 1) };
+            let code = strip_comment(line);
+
+            let name = match code.strip_prefix(INCLUDE_DIRECTIVE).filter(|name| name.starts_with(char::is_whitespace)) {
+                Some(name) => name.trim(),
+                None => {
+                    self.included.lines.push((position, line.to_owned()));
+                    continue
+                }
+            };
+
+            let name = parse_string(name).and_then(|name| String::from_utf8(name).ok()).ok_or_else(|| position.error(&self.included.files, LineError::Operand).locate(line, &[]))?;
+            let (name, contents) = self.resolver.resolve(&self.included.files[file], &name).ok_or_else(|| position.error(&self.included.files, LineError::Include).locate(line, &[]))?;
+            if self.included.files.contains(&name) { continue }
+
+            self.included.files.push(name);
+            self.file(self.included.files.len() - 1, &contents, Some(position.program_line))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replace every include directive of a program named `file` with the lines of the file it names.
+pub(crate) fn include(source: &str, file: &str, resolver: &mut dyn Resolver) -> Result<Included, AssembleError> {
+    let mut includer = Includer { resolver, included: Included { files: vec![file.to_owned()], lines: Vec::new() } };
+    includer.file(0, source, None)?;
+    Ok(includer.included)
+}
@@ -0,0 +1,78 @@
+//! Listings of what the assembler placed at every address, which show each line of a program next to the bytes
+//! assembled from it so the output can be checked by hand and addresses in traces can be traced back to their source.
+//!
+//! Every line is listed after [macros](super::macros) are expanded and [included](mod@super::include) files are put in
+//! place, so the source of an entry is the text that was actually assembled. Lines which place nothing, such as labels
+//! on their own and comments, are listed at the address they name. Bytes are written [BYTES_PER_ROW] to a row, and an
+//! entry with more bytes continues on rows holding only the address and the bytes.
+//! ```text
+//! 00000000  00 0f 08 0d 00 00 00 00      2  start: add.b r1, [value]
+//! 00000008  00 00 00
+//! 0000000b  08 00                        3  halt
+//! 0000000d  2a                           4  value: .byte 42
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+
+/// Most bytes written on a single row of a listing.
+pub const BYTES_PER_ROW: usize = 8;
+
+/// A line of a program along with the bytes assembled from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Offset of the first byte from the start of the program.
+    pub address: u64,
+    pub bytes: Vec<u8>,
+    /// Name of the [included](mod@super::include) file holding the line, or [None] if the line is in the program itself.
+    pub file: Option<String>,
+    /// Line number starting from 1.
+    pub line: usize,
+    /// Text of the line without surrounding whitespace.
+    pub source: String
+}
+
+/// Every line of a program in the order it was assembled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listing {
+    pub entries: Vec<Entry>
+}
+
+impl Listing {
+    /// Replace the bytes of every entry with the bytes at its address in the code, such as the code of the program once
+    /// the linker has patched every relocation. Bytes past the end of the code are left alone.
+    pub fn fill(&mut self, code: &[u8]) {
+        for entry in &mut self.entries {
+            let start = entry.address as usize;
+            if let Some(bytes) = code.get(start..start + entry.bytes.len()) { entry.bytes.copy_from_slice(bytes); }
+        }
+    }
+
+    /// Entry of the line which placed the byte at an address.
+    pub fn entry(&self, address: u64) -> Option<&Entry> {
+        self.entries.iter().find(|entry| (entry.address..entry.address + entry.bytes.len() as u64).contains(&address))
+    }
+}
+
+impl Display for Listing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let mut rows = entry.bytes.chunks(BYTES_PER_ROW);
+            let hex = |row: &[u8]| row.iter().map(|byte| format!("{byte:02x}")).collect::<Vec<_>>().join(" ");
+            let location = match &entry.file {
+                Some(file) => format!("{file}:{}", entry.line),
+                None => format!("{}", entry.line)
+            };
+
+            writeln!(f, "{:08x}  {:<width$}  {location:>5}  {}", entry.address, hex(rows.next().unwrap_or_default()), entry.source, width = BYTES_PER_ROW * 3 - 1)?;
+
+            for (index, row) in rows.enumerate() {
+                writeln!(f, "{:08x}  {}", entry.address + ((index + 1) * BYTES_PER_ROW) as u64, hex(row))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! User defined macros, which are expanded into the lines they stand for before anything is assembled.
+//!
+//! A macro is defined between `.macro name first, second` and `.endm`, and used like an instruction named after it.
+//! Inside its body, `\first` is replaced by the text of the first argument and so on, and `\@` is replaced by a number
+//! which is different for every expansion so labels inside a macro can be used more than once. Macros may use other
+//! macros, including ones defined after them, and take precedence over instructions with the same mnemonic.
+//! ```
+//! use atln_processor::emulator::memory::Memory;
+//! use atln_processor::emulator::processor::processor::Core;
+//! use atln_processor::programming::assembler::assemble;
+//!
+//! let program = assemble("
+//!     .macro swap a, b
+//!         zext.q r7, \\a
+//!         zext.q \\a, \\b
+//!         zext.q \\b, r7
+//!     .endm
+//!
+//!     swap r1, r2
+//!     halt
+//! ").unwrap();
+//!
+//! let mut core = Core::default();
+//! core.context.registers[1..3].copy_from_slice(&[1, 2]);
+//! core.run(&mut Memory::from(program), &mut Default::default(), None);
+//! assert_eq!(core.context.registers[1..3], [2, 1]);
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use super::{parse_symbol, split_label, strip_comment, AssembleError, LineError};
+use utility::Map;
+
+/// Directive which starts the definition of a macro.
+pub const MACRO_DIRECTIVE: &str = ".macro";
+/// Directive which ends the definition of a macro.
+pub const END_MACRO_DIRECTIVE: &str = ".endm";
+/// Most macros which can be expanded inside each other, which stops macros which use themselves.
+pub const DEPTH_LIMIT: usize = 64;
+/// Parameters are written after this character inside the body of a macro.
+pub const PARAMETER: char = '\\';
+/// Written after [PARAMETER] to insert the number of the expansion.
+pub const UNIQUE: char = '@';
+
+struct Macro<'a> {
+    parameters: Vec<&'a str>,
+    body: Vec<&'a str>
+}
+
+/// Replace the parameters and [UNIQUE] in a line of the body of a macro.
+fn substitute(line: &str, parameters: &[&str], arguments: &[&str], expansion: usize) -> String {
+    let mut substituted = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some((before, after)) = rest.split_once(PARAMETER) {
+        substituted.push_str(before);
+
+        if let Some(after) = after.strip_prefix(UNIQUE) {
+            substituted.push_str(&expansion.to_string());
+            rest = after;
+            continue
+        }
+
+        let end = after.find(|character: char| !(character.is_ascii_alphanumeric() || character == '_')).unwrap_or(after.len());
+        match parameters.iter().position(|&parameter| parameter == &after[..end]) {
+            Some(index) => substituted.push_str(arguments[index]),
+            None => {
+                substituted.push(PARAMETER);
+                substituted.push_str(&after[..end]);
+            }
+        }
+
+        rest = &after[end..];
+    }
+
+    substituted.push_str(rest);
+    substituted
+}
+
+struct Expander<'a, P> {
+    macros: Map<&'a str, Macro<'a>>,
+    expansions: usize,
+    lines: Vec<(P, String)>
+}
+
+impl<P: Copy> Expander<'_, P> {
+    /// Add a line to the output, expanding it first if it uses a macro.
+    fn line(&mut self, number: P, line: &str, depth: usize) -> Result<(), LineError> {
+        let (label, code) = split_label(strip_comment(line));
+
+        let (name, arguments) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let arguments = if arguments.trim().is_empty() { Vec::new() } else { arguments.split(',').map(str::trim).collect::<Vec<_>>() };
+
+        let (parameters, body) = match self.macros.get(name) {
+            Some(definition) => (definition.parameters.clone(), definition.body.clone()),
+            None => {
+                self.lines.push((number, line.to_owned()));
+                return Ok(())
+            }
+        };
+
+        if depth == DEPTH_LIMIT { return Err(LineError::Recursion) }
+        if arguments.len() != parameters.len() { return Err(LineError::Arguments) }
+        if let Some(label) = label { self.lines.push((number, label.to_owned() + ":")); }
+
+        let expansion = self.expansions;
+        self.expansions += 1;
+
+        for body_line in body {
+            self.line(number, &substitute(body_line, &parameters, &arguments, expansion), depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Expand every macro used in a program and remove their definitions. Every line that remains is returned with the
+/// number of the line it came from, which is the line using the macro for lines from its body.
+/// ```
+/// use atln_processor::programming::assembler::LineError;
+/// use atln_processor::programming::assembler::macros::expand;
+///
+/// let source = "
+///     .macro spin register
+///     loop\\@: divert.q loop\\@ ; Never ends.
+///     .endm
+///     spin r1
+///     spin r2
+/// ";
+///
+/// let lines: Vec<_> = expand(source).unwrap().into_iter().map(|(number, line)| (number, line.trim().to_string())).collect();
+/// assert_eq!(lines[1..], [
+///     (5, "loop0: divert.q loop0 ; Never ends.".to_string()),
+///     (6, "loop1: divert.q loop1 ; Never ends.".to_string())
+/// ]);
+///
+/// assert_eq!(expand("spin r1\n.macro spin\n").unwrap_err().error, LineError::Unterminated);
+/// assert_eq!(expand(".macro spin\nspin\n.endm\nspin").unwrap_err().error, LineError::Recursion);
+/// assert_eq!(expand(".macro spin register\n.endm\nspin").unwrap_err().line, 3);
+/// ```
+pub fn expand(source: &str) -> Result<Vec<(usize, String)>, AssembleError> {
+    let lines = source.lines().enumerate().map(|(index, line)| (index + 1, line));
+    expand_lines(lines).map_err(|(line, error)| {
+        let error = AssembleError { line, error, file: None, diagnostic: None };
+        error.locate(source.lines().nth(line - 1).unwrap_or_default(), &[])
+    })
+}
+
+/// Expand every macro used in lines which are identified by anything that can be copied, such as their line number.
+pub(crate) fn expand_lines<'a, P: Copy>(lines: impl IntoIterator<Item = (P, &'a str)>) -> Result<Vec<(P, String)>, (P, LineError)> {
+    let mut expander = Expander { macros: Map::new(), expansions: 0, lines: Vec::new() };
+    let mut remaining = Vec::new();
+    let mut lines = lines.into_iter();
+
+    // Macros can be used before they are defined, so every definition is collected first.
+    while let Some((number, line)) = lines.next() {
+        let code = strip_comment(line);
+        let (directive, arguments) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+
+        match directive {
+            MACRO_DIRECTIVE => {
+                let (name, parameters) = arguments.trim().split_once(char::is_whitespace).unwrap_or((arguments.trim(), ""));
+                let error = |error| (number, error);
+
+                let name = parse_symbol(name).ok_or(error(LineError::Symbol))?;
+                let parameters = if parameters.trim().is_empty() { Vec::new() } else { parameters.split(',').map(str::trim).collect::<Vec<_>>() };
+                if parameters.iter().any(|parameter| parse_symbol(parameter).is_none()) { return Err(error(LineError::Symbol)) }
+
+                let mut body = Vec::new();
+                loop {
+                    match lines.next() {
+                        Some((_, line)) if strip_comment(line) == END_MACRO_DIRECTIVE => break,
+                        Some((_, line)) if !strip_comment(line).starts_with(MACRO_DIRECTIVE) => body.push(line),
+                        _ => return Err(error(LineError::Unterminated))
+                    }
+                }
+
+                if expander.macros.insert(name, Macro { parameters, body }).is_some() { return Err(error(LineError::Duplicate)) }
+            },
+            END_MACRO_DIRECTIVE => return Err((number, LineError::Directive)),
+            _ => remaining.push((number, line))
+        }
+    }
+
+    for (number, line) in remaining {
+        expander.line(number, line, 0).map_err(|error| (number, error))?;
+    }
+
+    Ok(expander.lines)
+}
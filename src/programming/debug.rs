@@ -0,0 +1,213 @@
+//! Debug information relating addresses of a linked program back to its labels and source lines.
+//!
+//! # Format
+//! Debug information is stored at the end of an [Image](crate::emulator::loader::Image). All numbers are little endian
+//! and names are UTF-8 prefixed with their length as a 16 bit number.
+//!
+//! | Field        | Size | Description                                                            |
+//! |--------------|------|------------------------------------------------------------------------|
+//! | Magic        | 4    | [MAGIC].                                                               |
+//! | Version      | 2    | [VERSION].                                                             |
+//! | File count   | 4    | Followed by every file name.                                           |
+//! | Symbol count | 4    | Followed by every symbol's name and address (8).                       |
+//! | Line count   | 4    | Followed by every line's address (8), file index (4) and line (4).     |
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter, Write};
+use super::object::write_name;
+use utility::{Encodable, SliceReader};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub const MAGIC: [u8; 4] = *b"ATLD";
+pub const VERSION: u16 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugError {
+    /// The debug information does not start with [MAGIC].
+    Magic,
+    /// The debug information was made for a version of the format that is not supported.
+    Version(u16),
+    /// The debug information ended in the middle of a table.
+    Truncated,
+    /// A name is not valid UTF-8.
+    Name,
+    /// A line refers to a file that is not in the file table.
+    File
+}
+
+impl Display for DebugError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Magic => f.write_str("debug information does not start with the magic bytes"),
+            Self::Version(version) => write!(f, "debug information version {version} is not supported"),
+            Self::Truncated => f.write_str("debug information ended unexpectedly"),
+            Self::Name => f.write_str("name is not valid UTF-8"),
+            Self::File => f.write_str("line refers to a file that does not exist")
+        }
+    }
+}
+
+impl Error for DebugError {}
+
+/// A label placed at an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DebugSymbol {
+    pub name: String,
+    pub address: u64
+}
+
+/// The source line an instruction was assembled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DebugLine {
+    pub address: u64,
+    /// Index into [DebugInfo::files].
+    pub file: u32,
+    /// Line number starting from 1.
+    pub line: u32
+}
+
+/// Symbols and source lines of a program. Symbols and lines are kept sorted by address.
+/// ```
+/// use atln_processor::programming::debug::{DebugInfo, DebugLine, DebugSymbol};
+/// use atln_processor::utility::Encodable;
+///
+/// let mut debug = DebugInfo::default();
+/// debug.files.push("main.s".into());
+/// debug.insert_symbol(DebugSymbol { name: "loop".into(), address: 8 });
+/// debug.insert_symbol(DebugSymbol { name: "start".into(), address: 0 });
+/// debug.insert_line(DebugLine { address: 0, file: 0, line: 2 });
+/// debug.insert_line(DebugLine { address: 3, file: 0, line: 3 });
+///
+/// assert_eq!(debug.symbol("loop"), Some(8));
+/// assert_eq!(debug.locate(5), Some(("start", 5)));
+/// assert_eq!(debug.line(5), Some(("main.s", 3)));
+/// assert_eq!(debug.annotate(5).unwrap(), "start+5 main.s:3");
+/// assert_eq!(debug.annotate(8).unwrap(), "loop main.s:3");
+///
+/// assert_eq!(DebugInfo::parse(&debug.encode()), Ok(debug));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DebugInfo {
+    pub files: Vec<String>,
+    pub symbols: Vec<DebugSymbol>,
+    pub lines: Vec<DebugLine>
+}
+
+fn read_name(reader: &mut SliceReader) -> Result<String, DebugError> {
+    String::from_utf8(reader.prefixed().ok_or(DebugError::Truncated)?.to_vec()).map_err(|_| DebugError::Name)
+}
+
+impl DebugInfo {
+    pub fn insert_symbol(&mut self, symbol: DebugSymbol) {
+        let index = self.symbols.partition_point(|existing| existing.address <= symbol.address);
+        self.symbols.insert(index, symbol);
+    }
+
+    pub fn insert_line(&mut self, line: DebugLine) {
+        let index = self.lines.partition_point(|existing| existing.address <= line.address);
+        self.lines.insert(index, line);
+    }
+
+    /// Address of a symbol.
+    pub fn symbol(&self, name: &str) -> Option<u64> {
+        self.symbols.iter().find(|symbol| symbol.name == name).map(|symbol| symbol.address)
+    }
+
+    /// Name of the closest symbol at or before an address, along with how far the address is past it.
+    pub fn locate(&self, address: u64) -> Option<(&str, u64)> {
+        let index = self.symbols.partition_point(|symbol| symbol.address <= address).checked_sub(1)?;
+        let symbol = &self.symbols[index];
+        Some((&symbol.name, address - symbol.address))
+    }
+
+    /// File name and line number of the instruction at or closest before an address.
+    pub fn line(&self, address: u64) -> Option<(&str, u32)> {
+        let index = self.lines.partition_point(|line| line.address <= address).checked_sub(1)?;
+        let line = &self.lines[index];
+        Some((self.files.get(line.file as usize)?, line.line))
+    }
+
+    /// Describe an address as `symbol+offset file:line`, leaving out whichever parts are unknown. [None] is returned
+    /// if neither are known.
+    pub fn annotate(&self, address: u64) -> Option<String> {
+        let mut annotation = String::new();
+
+        match self.locate(address) {
+            Some((symbol, 0)) => annotation.push_str(symbol),
+            Some((symbol, offset)) => write!(annotation, "{symbol}+{offset}").unwrap(),
+            None => {}
+        }
+
+        if let Some((file, line)) = self.line(address) {
+            if !annotation.is_empty() { annotation.push(' '); }
+            write!(annotation, "{file}:{line}").unwrap();
+        }
+
+        if annotation.is_empty() { None } else { Some(annotation) }
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, DebugError> {
+        let mut reader = SliceReader::new(bytes);
+
+        if reader.take(MAGIC.len()) != Some(&MAGIC[..]) { return Err(DebugError::Magic) }
+
+        let version = reader.u16().ok_or(DebugError::Truncated)?;
+        if version != VERSION { return Err(DebugError::Version(version)) }
+
+        let mut debug = Self::default();
+
+        for _ in 0..reader.u32().ok_or(DebugError::Truncated)? {
+            debug.files.push(read_name(&mut reader)?);
+        }
+
+        for _ in 0..reader.u32().ok_or(DebugError::Truncated)? {
+            let name = read_name(&mut reader)?;
+            let address = reader.u64().ok_or(DebugError::Truncated)?;
+            debug.insert_symbol(DebugSymbol { name, address });
+        }
+
+        for _ in 0..reader.u32().ok_or(DebugError::Truncated)? {
+            let address = reader.u64().ok_or(DebugError::Truncated)?;
+            let file = reader.u32().ok_or(DebugError::Truncated)?;
+            let line = reader.u32().ok_or(DebugError::Truncated)?;
+
+            if file as usize >= debug.files.len() { return Err(DebugError::File) }
+            debug.insert_line(DebugLine { address, file, line });
+        }
+
+        Ok(debug)
+    }
+}
+
+impl Encodable<Vec<u8>> for DebugInfo {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(MAGIC);
+        bytes.extend(VERSION.to_le_bytes());
+
+        bytes.extend((self.files.len() as u32).to_le_bytes());
+        for file in &self.files { write_name(&mut bytes, file); }
+
+        bytes.extend((self.symbols.len() as u32).to_le_bytes());
+        for symbol in &self.symbols {
+            write_name(&mut bytes, &symbol.name);
+            bytes.extend(symbol.address.to_le_bytes());
+        }
+
+        bytes.extend((self.lines.len() as u32).to_le_bytes());
+        for line in &self.lines {
+            bytes.extend(line.address.to_le_bytes());
+            bytes.extend(line.file.to_le_bytes());
+            bytes.extend(line.line.to_le_bytes());
+        }
+
+        bytes
+    }
+}
@@ -0,0 +1,121 @@
+//! Combining [Object]s into an executable [Image].
+//!
+//! The code of every object is placed one after the other starting from a base address. Relocations are resolved
+//! against the symbols of their own object first and then against the global symbols of every object. The image carries
+//! [DebugInfo] with every symbol, including local ones, and the source line of every instruction.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use emulator::loader::{Image, Permissions, Section};
+use number;
+use super::debug::{DebugInfo, DebugLine, DebugSymbol};
+use super::object::Object;
+use utility::Map;
+
+/// Execution starts at the global symbol with this name. Without it, execution starts at the base address.
+pub const ENTRY_SYMBOL: &str = "start";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    /// Multiple objects define a global symbol with the same name.
+    Duplicate(String),
+    /// A relocation refers to a symbol that no object defines.
+    Undefined(String),
+    /// The address of a symbol does not fit in the bytes of a relocation.
+    Overflow(String),
+    /// A relocation or symbol points outside the code of its object.
+    Bounds
+}
+
+impl Display for LinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate(name) => write!(f, "global symbol {name} is defined more than once"),
+            Self::Undefined(name) => write!(f, "symbol {name} is not defined"),
+            Self::Overflow(name) => write!(f, "address of symbol {name} does not fit in the relocation"),
+            Self::Bounds => f.write_str("relocation is outside of its object's code")
+        }
+    }
+}
+
+impl Error for LinkError {}
+
+/// Place objects starting from the base address and patch every relocation with the address of its symbol plus its
+/// addend. The image has a single executable section holding the code of every object.
+/// ```
+/// use atln_processor::programming::assembler::assemble_object;
+/// use atln_processor::programming::linker::{link, LinkError};
+///
+/// let main = assemble_object(".global start\nstart: add.q r1, [value]\nhalt").unwrap();
+/// let data = assemble_object(".global value\nvalue: halt").unwrap();
+///
+/// let image = link(&[main.clone(), data], 0x100).unwrap();
+/// assert_eq!(image.entry, 0x100);
+///
+/// // The address of value is patched in after the 3 bytes leading the first instruction. Value is placed after the 13
+/// // bytes of main.
+/// assert_eq!(image.sections[0].data[3..11], 0x10Du64.to_le_bytes());
+///
+/// let debug = image.debug.unwrap();
+/// assert_eq!(debug.symbol("value"), Some(0x10D));
+/// assert_eq!(debug.line(0x10B), Some(("", 3)));
+///
+/// assert_eq!(link(&[main], 0), Err(LinkError::Undefined("value".into())));
+/// ```
+pub fn link(objects: &[Object], base: u64) -> Result<Image, LinkError> {
+    let mut bases = Vec::with_capacity(objects.len());
+    let mut globals = Map::new();
+    let mut address = base;
+
+    for object in objects {
+        bases.push(address);
+
+        for symbol in object.symbols.iter().filter(|symbol| symbol.global) {
+            if symbol.offset > object.code.len() as u64 { return Err(LinkError::Bounds) }
+            if globals.insert(symbol.name.clone(), address + symbol.offset).is_some() { return Err(LinkError::Duplicate(symbol.name.clone())) }
+        }
+
+        address += object.code.len() as u64;
+    }
+
+    let mut code = Vec::with_capacity((address - base) as usize);
+    let mut debug = DebugInfo::default();
+
+    for (object, &object_base) in objects.iter().zip(&bases) {
+        let start = code.len();
+        code.extend(&object.code);
+
+        let file = debug.files.len() as u32;
+        debug.files.push(object.file.clone());
+        for symbol in &object.symbols { debug.insert_symbol(DebugSymbol { name: symbol.name.clone(), address: object_base + symbol.offset }); }
+        for line in &object.lines { debug.insert_line(DebugLine { address: object_base + line.offset, file, line: line.line }); }
+
+        for relocation in &object.relocations {
+            let target = match object.symbol(&relocation.symbol) {
+                Some(symbol) => object_base + symbol.offset,
+                None => *globals.get(&relocation.symbol).ok_or_else(|| LinkError::Undefined(relocation.symbol.clone()))?
+            }.wrapping_add_signed(relocation.addend);
+
+            let size = relocation.size.size() as u64;
+            if size < 8 && target >> (size * 8) != 0 { return Err(LinkError::Overflow(relocation.symbol.clone())) }
+
+            let offset = relocation.offset.checked_add(size).filter(|&end| end <= object.code.len() as u64).ok_or(LinkError::Bounds)? - size;
+            let patch = start + offset as usize;
+            code[patch..patch + size as usize].copy_from_slice(&number::Data::from_size_selecting(&relocation.size, target).to_le_bytes());
+        }
+    }
+
+    Ok(Image {
+        entry: globals.get(ENTRY_SYMBOL).copied().unwrap_or(base),
+        sections: vec![Section {
+            permissions: Permissions { read: true, write: true, execute: true },
+            address: base,
+            size: code.len() as u64,
+            data: code
+        }],
+        debug: Some(debug)
+    })
+}
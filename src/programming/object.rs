@@ -0,0 +1,212 @@
+//! Relocatable object files produced by the assembler and combined by the [linker](super::linker).
+//!
+//! # Format
+//! All numbers are little endian and names are UTF-8 prefixed with their length as a 16 bit number.
+//!
+//! | Field            | Size | Description                                                                               |
+//! |------------------|------|-------------------------------------------------------------------------------------------|
+//! | Magic            | 4    | [MAGIC].                                                                                  |
+//! | Version          | 2    | [VERSION].                                                                                |
+//! | Code length      | 8    | Followed by the code.                                                                     |
+//! | Symbol count     | 4    | Followed by every symbol's name, offset (8) and global flag (1).                          |
+//! | Relocation count | 4    | Followed by every relocation's offset (8), size exponent (1), addend (8) and symbol name. |
+//! | File             |      | Name of the source file.                                                                  |
+//! | Line count       | 4    | Followed by every line's offset (8) and line number (4).                                  |
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use number::Size;
+use utility::{Encodable, SliceReader};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub const MAGIC: [u8; 4] = *b"ATLO";
+pub const VERSION: u16 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectError {
+    /// The object does not start with [MAGIC].
+    Magic,
+    /// The object was made for a version of the format that is not supported.
+    Version(u16),
+    /// The object ended in the middle of a table.
+    Truncated,
+    /// A symbol name is not valid UTF-8.
+    Name,
+    /// A relocation has a size exponent that does not exist.
+    Size
+}
+
+impl Display for ObjectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Magic => f.write_str("object does not start with the magic bytes"),
+            Self::Version(version) => write!(f, "object version {version} is not supported"),
+            Self::Truncated => f.write_str("object ended unexpectedly"),
+            Self::Name => f.write_str("symbol name is not valid UTF-8"),
+            Self::Size => f.write_str("relocation size does not exist")
+        }
+    }
+}
+
+impl Error for ObjectError {}
+
+/// A named offset into the code of an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Symbol {
+    pub name: String,
+    pub offset: u64,
+    /// Whether other objects can refer to the symbol. Local symbols are only visible to their own object.
+    pub global: bool
+}
+
+/// A place in the code which must be patched with the address of a symbol once it is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Relocation {
+    /// Offset of the bytes to patch from the start of the code.
+    pub offset: u64,
+    /// Number of bytes to patch. The address must fit in them.
+    pub size: Size,
+    pub symbol: String,
+    /// Number added to the address of the symbol.
+    pub addend: i64
+}
+
+/// The source line that the code at an offset was assembled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SourceLine {
+    pub offset: u64,
+    /// Line number starting from 1.
+    pub line: u32
+}
+
+/// Code which may refer to symbols defined in other objects.
+/// ```
+/// use atln_processor::programming::object::{Object, Relocation, SourceLine, Symbol};
+/// use atln_processor::number::Size;
+/// use atln_processor::utility::Encodable;
+///
+/// let object = Object {
+///     code: vec![1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0],
+///     symbols: vec![Symbol { name: "start".into(), offset: 0, global: true }],
+///     relocations: vec![Relocation { offset: 3, size: Size::Quad, symbol: "data".into(), addend: -4 }],
+///     file: "main.s".into(),
+///     lines: vec![SourceLine { offset: 0, line: 1 }]
+/// };
+///
+/// assert_eq!(Object::parse(&object.encode()), Ok(object));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Object {
+    pub code: Vec<u8>,
+    pub symbols: Vec<Symbol>,
+    pub relocations: Vec<Relocation>,
+    /// Name of the source file, used for debug information. This is empty if the name is not known.
+    pub file: String,
+    /// Lines that each instruction was assembled from, in order of offset.
+    pub lines: Vec<SourceLine>
+}
+
+fn read_name(reader: &mut SliceReader) -> Result<String, ObjectError> {
+    String::from_utf8(reader.prefixed().ok_or(ObjectError::Truncated)?.to_vec()).map_err(|_| ObjectError::Name)
+}
+
+/// Write a name prefixed with its length. Names longer than [u16::MAX] bytes are cut off.
+pub(crate) fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    let name = &name.as_bytes()[..name.len().min(u16::MAX as usize)];
+    bytes.extend((name.len() as u16).to_le_bytes());
+    bytes.extend(name);
+}
+
+impl Object {
+    /// Find a symbol defined by this object.
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// Whether some bytes start like an object file.
+    pub fn is_object(bytes: &[u8]) -> bool {
+        bytes.starts_with(&MAGIC)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, ObjectError> {
+        let mut reader = SliceReader::new(bytes);
+
+        if reader.take(MAGIC.len()) != Some(&MAGIC[..]) { return Err(ObjectError::Magic) }
+
+        let version = reader.u16().ok_or(ObjectError::Truncated)?;
+        if version != VERSION { return Err(ObjectError::Version(version)) }
+
+        let length = reader.u64().ok_or(ObjectError::Truncated)?;
+        let code = usize::try_from(length).ok().and_then(|length| reader.take(length)).ok_or(ObjectError::Truncated)?.to_vec();
+
+        let mut symbols = Vec::new();
+        for _ in 0..reader.u32().ok_or(ObjectError::Truncated)? {
+            let name = read_name(&mut reader)?;
+            let offset = reader.u64().ok_or(ObjectError::Truncated)?;
+            let global = reader.u8().ok_or(ObjectError::Truncated)? != 0;
+            symbols.push(Symbol { name, offset, global });
+        }
+
+        let mut relocations = Vec::new();
+        for _ in 0..reader.u32().ok_or(ObjectError::Truncated)? {
+            let offset = reader.u64().ok_or(ObjectError::Truncated)?;
+            let size = Size::from_exponent(reader.u8().ok_or(ObjectError::Truncated)?).ok_or(ObjectError::Size)?;
+            let addend = reader.u64().ok_or(ObjectError::Truncated)? as i64;
+            let symbol = read_name(&mut reader)?;
+            relocations.push(Relocation { offset, size, symbol, addend });
+        }
+
+        let file = read_name(&mut reader)?;
+        let mut lines = Vec::new();
+        for _ in 0..reader.u32().ok_or(ObjectError::Truncated)? {
+            let offset = reader.u64().ok_or(ObjectError::Truncated)?;
+            let line = reader.u32().ok_or(ObjectError::Truncated)?;
+            lines.push(SourceLine { offset, line });
+        }
+
+        Ok(Self { code, symbols, relocations, file, lines })
+    }
+}
+
+impl Encodable<Vec<u8>> for Object {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(MAGIC);
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((self.code.len() as u64).to_le_bytes());
+        bytes.extend(&self.code);
+
+        bytes.extend((self.symbols.len() as u32).to_le_bytes());
+        for symbol in &self.symbols {
+            write_name(&mut bytes, &symbol.name);
+            bytes.extend(symbol.offset.to_le_bytes());
+            bytes.push(symbol.global as u8);
+        }
+
+        bytes.extend((self.relocations.len() as u32).to_le_bytes());
+        for relocation in &self.relocations {
+            bytes.extend(relocation.offset.to_le_bytes());
+            bytes.push(relocation.size.exponent());
+            bytes.extend(relocation.addend.to_le_bytes());
+            write_name(&mut bytes, &relocation.symbol);
+        }
+
+        write_name(&mut bytes, &self.file);
+        bytes.extend((self.lines.len() as u32).to_le_bytes());
+        for line in &self.lines {
+            bytes.extend(line.offset.to_le_bytes());
+            bytes.extend(line.line.to_le_bytes());
+        }
+
+        bytes
+    }
+}
@@ -0,0 +1,172 @@
+//! Peephole optimisation of straight line instruction sequences, for front ends which generate code rather than write
+//! it by hand.
+//!
+//! Each [Rule] rewrites instructions at the end of the sequence optimised so far, so a rewrite can enable another.
+//! Rules only apply where the result is the same for every register and memory value.
+//!
+//! | Rule                  | Rewrite                                                                                      |
+//! |-----------------------|----------------------------------------------------------------------------------------------|
+//! | [Rule::RedundantMove] | `movz.q r1, r1` and every other quad conditional move of a register into itself are removed. |
+//! | [Rule::Identity]      | `add.q r1, 0` and `sub.q r1, 0` are removed.                                                 |
+//! | [Rule::FoldConstants] | `add.w r1, 3` then `add.w r1, 4` becomes `add.w r1, 7`, and the same for `sub`.              |
+//!
+//! A folded addition or subtraction faults whenever the instructions it replaces would, but before either of them has
+//! written its result. Rewrites change the addresses of the instructions after them, so a sequence must not contain a
+//! destination of a divert. Optimise each block between labels before addresses are assigned.
+
+use alloc::vec::Vec;
+use emulator::processor::processor::instruction::{Data, Instruction};
+use emulator::processor::processor::instruction::operand::{AllPresent, Destination, Dynamic, Operands};
+use emulator::processor::processor::instruction::operation::arithmetic::Arithmetic;
+use emulator::processor::processor::instruction::operation::condition::Condition;
+use emulator::processor::processor::instruction::operation::Extension;
+use number;
+use number::Size;
+
+/// A rewrite the optimizer may apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Remove conditional moves which copy a register into itself.
+    RedundantMove,
+    /// Remove additions and subtractions of 0.
+    Identity,
+    /// Merge consecutive additions or subtractions of constants to the same register.
+    FoldConstants
+}
+
+impl Rule {
+    pub const ALL: [Self; 3] = [Self::RedundantMove, Self::Identity, Self::FoldConstants];
+}
+
+/// Operands of an instruction which stores its result in its static register, with a constant dynamic operand
+/// extended to the width of the instruction.
+fn constant(data: &Data) -> Option<(u8, u64)> {
+    let all = data.operands.all()?;
+    if data.destination != Destination::Static || data.synchronous { return None }
+
+    let value = match &all.x_dynamic {
+        Dynamic::Constant(immediate) => immediate.quad(),
+        Dynamic::Signed(immediate) => immediate.signed() as u64,
+        _ => return None
+    };
+
+    Some((all.x_static, number::Data::from_size_selecting(&data.width, value).quad()))
+}
+
+/// Whether an instruction does nothing according to the rules.
+fn removable(instruction: &Instruction, rules: &[Rule]) -> bool {
+    let data = match instruction.data() {
+        Some(data) if data.width == Size::Quad => data,
+        _ => return false
+    };
+
+    match instruction.extension() {
+        Extension::Arithmetic(Arithmetic::Add | Arithmetic::Subtract) => rules.contains(&Rule::Identity) && constant(data).is_some_and(|(_, value)| value == 0),
+        Extension::Condition(condition) if *condition != Condition::Compare => {
+            let all = match data.operands.all() {
+                Some(all) => all,
+                None => return false
+            };
+
+            let register = match data.destination {
+                Destination::Target(target) => target,
+                Destination::Static | Destination::Dynamic => all.x_static
+            };
+
+            rules.contains(&Rule::RedundantMove) && all.x_dynamic == Dynamic::Register(all.x_static) && register == all.x_static
+        },
+        _ => false
+    }
+}
+
+/// Smallest constant operand which extends to a value at a width.
+fn operand(width: &Size, value: u64) -> Dynamic {
+    let signed = number::Data::from_size_selecting(width, value).signed();
+    let x_signed = number::Data::from_signed_selecting(signed);
+    let x_constant = number::Data::from_quad_selecting(value);
+
+    if x_signed.size() < x_constant.size() { Dynamic::Signed(x_signed) } else { Dynamic::Constant(x_constant) }
+}
+
+/// A single instruction doing the same as two consecutive ones, if they can be folded.
+fn fold(first: &Instruction, second: &Instruction) -> Option<Instruction> {
+    if first.extension() != second.extension() || !matches!(first.extension(), Extension::Arithmetic(Arithmetic::Add | Arithmetic::Subtract)) { return None }
+
+    let (first_data, second_data) = (first.data().as_ref()?, second.data().as_ref()?);
+    if first_data.width != second_data.width { return None }
+
+    let (register, first_value) = constant(first_data)?;
+    let (second_register, second_value) = constant(second_data)?;
+    if register != second_register { return None }
+
+    // Both steps overflow together with their sum, so the sum must fit the width for the fold to keep the result.
+    let width = &first_data.width;
+    let value = first_value.checked_add(second_value).filter(|&value| number::Data::from_size_selecting(width, value).quad() == value)?;
+
+    Instruction::new(first.extension().clone(), Some(Data {
+        width: width.clone(),
+        destination: Destination::Static,
+        synchronous: false,
+        operands: Operands::AllPresent(AllPresent { x_static: register, x_dynamic: operand(width, value) })
+    }))
+}
+
+/// Optimise a straight line sequence of instructions with a set of rules.
+/// ```
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::programming::assembler::assemble_line;
+/// use atln_processor::programming::optimizer::{optimize, Rule};
+/// use atln_processor::utility::Encodable;
+///
+/// let parse = |source: &[&str]| -> Vec<_> { source.iter().map(|line| assemble_line(line).unwrap().unwrap()).collect() };
+/// let instructions = parse(&["add.q r1, 3", "movz.q r2, r2", "add.q r1, 4", "sub.w r3, 2", "sub.w r3, 0x300", "add.q r4, 0", "halt"]);
+///
+/// let optimized = optimize(&instructions, &Rule::ALL);
+/// let listing: Vec<String> = optimized.iter().map(ToString::to_string).collect();
+/// assert_eq!(listing, ["add.q r1, 7", "sub.w r3, 770", "halt"]);
+///
+/// // Folding is left out when its rule is not selected, or when the sum does not fit the width.
+/// assert_eq!(optimize(&instructions, &[Rule::Identity]).len(), 6);
+/// assert_eq!(optimize(&parse(&["add.b r5, 200", "add.b r5, 100"]), &Rule::ALL).len(), 2);
+///
+/// // Both sequences leave the same registers behind.
+/// let run = |instructions: &[_]| {
+///     let program: Vec<u8> = instructions.iter().flat_map(Encodable::encode).collect();
+///     let mut core = Core::default();
+///     core.context.registers[3] = 0x1000;
+///     core.run(&mut Memory::from(program), &mut Default::default(), None);
+///     core.context.registers
+/// };
+///
+/// assert_eq!(run(&instructions), run(&optimized));
+/// ```
+pub fn optimize(instructions: &[Instruction], rules: &[Rule]) -> Vec<Instruction> {
+    let mut optimized: Vec<Instruction> = Vec::with_capacity(instructions.len());
+
+    for instruction in instructions {
+        optimized.push(instruction.clone());
+
+        loop {
+            if optimized.last().is_some_and(|last| removable(last, rules)) {
+                optimized.pop();
+                continue
+            }
+
+            let folded = match optimized.as_slice() {
+                [.., first, second] if rules.contains(&Rule::FoldConstants) => fold(first, second),
+                _ => None
+            };
+
+            match folded {
+                Some(folded) => {
+                    optimized.truncate(optimized.len() - 2);
+                    optimized.push(folded);
+                },
+                None => break
+            }
+        }
+    }
+
+    optimized
+}
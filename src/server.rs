@@ -0,0 +1,348 @@
+//! Remote control of an emulated machine over JSON-RPC on TCP, for tooling and CI which are not written in Rust.
+//!
+//! A [Server] owns a single [Machine]. Every line a client sends is a JSON-RPC 2.0 request, which is answered with a
+//! line holding its response. Requests without an id are notifications and are executed without a response, and
+//! batches are not supported. Each connection is served on a thread of its own, and requests from every connection are
+//! executed one at a time on the shared machine. Only available with the `server` feature.
+//!
+//! | Method            | Parameters                            | Result                                             |
+//! | ----------------- | ------------------------------------- | -------------------------------------------------- |
+//! | `load`            | `data`, `address`                     | `entry` the program counter was set to.            |
+//! | `run`             | `instructions`                        | Status of the core once it stopped.                |
+//! | `step`            | `count`                               | Status of the core after the last instruction.     |
+//! | `read_memory`     | `address`, `length`                   | `data` which was read.                             |
+//! | `write_memory`    | `address`, `data`                     | Number of bytes `written`.                         |
+//! | `registers`       |                                       | `registers`, `program_counter`, `flags`, `cycles`. |
+//! | `write_registers` | `registers`, `program_counter`        | `null`.                                            |
+//! | `set_framebuffer` | `address`, `width`, `height`, `depth` | `null`.                                            |
+//! | `screenshot`      |                                       | `width`, `height`, `depth` and pixels as `data`.   |
+//!
+//! Bytes are written as hexadecimal strings and addresses are physical. `load` accepts an executable
+//! [image](crate::emulator::loader), which starts at its entry point, or any other bytes, which are loaded at `address`
+//! and start there. Without `instructions`, `run` continues until the core halts or faults, which blocks every other
+//! request until it does. A status is an object with the `status` of the core, which is `running`, `halted`, `faulted`
+//! or `budget_exhausted`, its `program_counter` and the exception as text in `fault` if it faulted. The framebuffer is
+//! a region of memory with `depth` bytes for each pixel, in rows from the top.
+//!
+//! ```text
+//! --> {"jsonrpc":"2.0","id":1,"method":"step"}
+//! <-- {"id":1,"jsonrpc":"2.0","result":{"program_counter":4,"status":"running"}}
+//! ```
+
+pub mod command;
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use serde_json::{json, Value};
+use emulator::loader::{Image, LoadError};
+use emulator::memory::Memory;
+use emulator::processor::processor::{Budget, Core, Ports, Status};
+use self::command::{to_hex, Command, Framebuffer};
+
+// region: Error codes
+pub const PARSE_ERROR_CODE     : i64 = -32700;
+pub const INVALID_REQUEST_CODE : i64 = -32600;
+pub const METHOD_NOT_FOUND_CODE: i64 = -32601;
+pub const INVALID_PARAMS_CODE  : i64 = -32602;
+/// The image could not be loaded.
+pub const LOAD_ERROR_CODE      : i64 = -32000;
+/// A screenshot was requested without a framebuffer.
+pub const FRAMEBUFFER_CODE     : i64 = -32001;
+// endregion
+
+/// Reason for a request failing, which is sent back as a JSON-RPC error with the [code](RequestError::code).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// The line is not JSON.
+    Parse,
+    /// The JSON is not a request.
+    Request,
+    /// There is no method with the name.
+    Method(String),
+    /// The parameter with the name is missing or has the wrong type.
+    Params(String),
+    Load(LoadError),
+    /// There is no framebuffer to take a screenshot of.
+    Framebuffer
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse => f.write_str("request is not valid JSON"),
+            Self::Request => f.write_str("request is not a JSON-RPC request"),
+            Self::Method(method) => write!(f, "no method named {method}"),
+            Self::Params(name) => write!(f, "parameter {name} is missing or invalid"),
+            Self::Load(_) => f.write_str("failed to load the image"),
+            Self::Framebuffer => f.write_str("no framebuffer is set")
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Load(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<LoadError> for RequestError {
+    fn from(value: LoadError) -> Self {
+        Self::Load(value)
+    }
+}
+
+impl RequestError {
+    /// JSON-RPC error code.
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::Parse => PARSE_ERROR_CODE,
+            Self::Request => INVALID_REQUEST_CODE,
+            Self::Method(_) => METHOD_NOT_FOUND_CODE,
+            Self::Params(_) => INVALID_PARAMS_CODE,
+            Self::Load(_) => LOAD_ERROR_CODE,
+            Self::Framebuffer => FRAMEBUFFER_CODE
+        }
+    }
+}
+
+/// Core with its memory and ports, driven by requests.
+#[derive(Debug)]
+pub struct Machine {
+    pub core: Core,
+    pub memory: Memory,
+    pub ports: Ports,
+    /// Region of memory which `screenshot` returns.
+    pub framebuffer: Option<Framebuffer>
+}
+
+impl Machine {
+    pub fn new(core: Core, memory: Memory) -> Self {
+        Self { core, memory, ports: Ports::default(), framebuffer: None }
+    }
+
+    /// Execute a command and return its result.
+    pub fn execute(&mut self, command: Command) -> Result<Value, RequestError> {
+        Ok(match command {
+            Command::Load { address, data } => {
+                if Image::is_image(&data) {
+                    let image = Image::parse(&data)?;
+                    image.load_into(&mut self.memory)?;
+                    self.core.context.program_counter = image.entry;
+                } else {
+                    if self.memory.write_bytes(address, false, &data) != data.len() { return Err(LoadError::Memory.into()) }
+                    self.core.context.program_counter = address;
+                }
+
+                json!({ "entry": self.core.context.program_counter })
+            },
+            Command::Run { instructions } => {
+                let status = self.core.run(&mut self.memory, &mut self.ports, instructions.map(Budget::Instructions));
+                self.status(status)
+            },
+            Command::Step { count } => {
+                let status = self.core.run(&mut self.memory, &mut self.ports, Some(Budget::Instructions(count)));
+                self.status(match status {
+                    Status::BudgetExhausted => Status::Running,
+                    status => status
+                })
+            },
+            Command::ReadMemory { address, length } => json!({ "data": to_hex(&self.read(address, length)) }),
+            Command::WriteMemory { address, data } => json!({ "written": self.memory.write_bytes(address, false, &data) }),
+            Command::Registers => {
+                let context = &self.core.context;
+                json!({
+                    "registers": context.registers,
+                    "program_counter": context.program_counter,
+                    "flags": {
+                        "zero": context.flags.zero,
+                        "carry": context.flags.carry,
+                        "sign": context.flags.sign,
+                        "overflow": context.flags.overflow
+                    },
+                    "cycles": self.core.cycles
+                })
+            },
+            Command::WriteRegisters { registers, program_counter } => {
+                if registers.len() > self.core.context.registers.len() { return Err(RequestError::Params("registers".into())) }
+
+                self.core.context.registers[..registers.len()].copy_from_slice(&registers);
+                if let Some(program_counter) = program_counter { self.core.context.program_counter = program_counter; }
+                Value::Null
+            },
+            Command::SetFramebuffer(framebuffer) => {
+                self.framebuffer = Some(framebuffer);
+                Value::Null
+            },
+            Command::Screenshot => {
+                let framebuffer = self.framebuffer.ok_or(RequestError::Framebuffer)?;
+                json!({
+                    "width": framebuffer.width,
+                    "height": framebuffer.height,
+                    "depth": framebuffer.depth,
+                    "data": to_hex(&self.read(framebuffer.address, framebuffer.length()))
+                })
+            }
+        })
+    }
+
+    /// Read physical memory, stopping where the memory ends.
+    fn read(&self, address: u64, length: u64) -> Vec<u8> {
+        let mut bytes = vec![0; length.min(self.memory.bytes.len() as u64) as usize];
+        let read = self.memory.read_bytes(address, false, &mut bytes);
+        bytes.truncate(read);
+        bytes
+    }
+
+    fn status(&self, status: Status) -> Value {
+        let mut result = json!({ "program_counter": self.core.context.program_counter });
+        result["status"] = match status {
+            Status::Running => "running",
+            Status::Halted => "halted",
+            Status::BudgetExhausted => "budget_exhausted",
+            Status::Faulted(exception) => {
+                result["fault"] = describe(&exception).into();
+                "faulted"
+            }
+        }.into();
+
+        result
+    }
+}
+
+/// Machine shared by every connection. Clones of the server share the machine.
+/// ```
+/// use std::io::{BufRead, BufReader, Write};
+/// use std::net::{TcpListener, TcpStream};
+/// use std::thread;
+/// use atln_processor::emulator::memory::Memory;
+/// use atln_processor::emulator::processor::processor::Core;
+/// use atln_processor::server::{Machine, Server};
+///
+/// let server = Server::new(Machine::new(Core::default(), Memory::from(vec![0; 64])));
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let address = listener.local_addr().unwrap();
+/// let listening = server.clone();
+/// thread::spawn(move || listening.listen(listener));
+///
+/// let mut stream = TcpStream::connect(address).unwrap();
+/// let mut responses = BufReader::new(stream.try_clone().unwrap()).lines();
+///
+/// // add.b r1, 5 followed by halt.
+/// writeln!(stream, r#"{{"jsonrpc":"2.0","id":1,"method":"load","params":{{"address":8,"data":"000808050800"}}}}"#).unwrap();
+/// writeln!(stream, r#"{{"jsonrpc":"2.0","id":2,"method":"run"}}"#).unwrap();
+/// writeln!(stream, r#"{{"jsonrpc":"2.0","id":3,"method":"read_memory","params":{{"address":8,"length":2}}}}"#).unwrap();
+///
+/// assert_eq!(responses.next().unwrap().unwrap(), r#"{"id":1,"jsonrpc":"2.0","result":{"entry":8}}"#);
+/// assert_eq!(responses.next().unwrap().unwrap(), r#"{"id":2,"jsonrpc":"2.0","result":{"program_counter":14,"status":"halted"}}"#);
+/// assert_eq!(responses.next().unwrap().unwrap(), r#"{"id":3,"jsonrpc":"2.0","result":{"data":"0008"}}"#);
+/// assert_eq!(server.machine.lock().unwrap().core.context.registers[1], 5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Server {
+    pub machine: Arc<Mutex<Machine>>
+}
+
+impl Server {
+    pub fn new(machine: Machine) -> Self {
+        Self { machine: Arc::new(Mutex::new(machine)) }
+    }
+
+    /// Answer a line holding a request, or return [None] if it was a notification.
+    /// ```
+    /// use atln_processor::emulator::memory::Memory;
+    /// use atln_processor::emulator::processor::processor::Core;
+    /// use atln_processor::server::{Machine, Server};
+    ///
+    /// let server = Server::new(Machine::new(Core::default(), Memory::from(vec![0; 8])));
+    ///
+    /// assert_eq!(server.respond(r#"{"jsonrpc":"2.0","id":"a","method":"screenshot"}"#).unwrap(),
+    ///     r#"{"error":{"code":-32001,"message":"no framebuffer is set"},"id":"a","jsonrpc":"2.0"}"#);
+    /// assert_eq!(server.respond("{").unwrap(),
+    ///     r#"{"error":{"code":-32700,"message":"request is not valid JSON"},"id":null,"jsonrpc":"2.0"}"#);
+    /// assert_eq!(server.respond(r#"{"jsonrpc":"2.0","method":"write_registers","params":{"registers":[1,2]}}"#), None);
+    /// assert_eq!(server.machine.lock().unwrap().core.context.registers[..3], [1, 2, 0]);
+    /// ```
+    pub fn respond(&self, line: &str) -> Option<String> {
+        let request = match serde_json::from_str::<Value>(line) {
+            Ok(request) => request,
+            Err(_) => return Some(response(Value::Null, Err(RequestError::Parse)))
+        };
+
+        let id = request.get("id").cloned();
+        let method = match (request.get("jsonrpc").and_then(Value::as_str), request.get("method").and_then(Value::as_str)) {
+            (Some("2.0"), Some(method)) => method,
+            _ => return Some(response(id.unwrap_or(Value::Null), Err(RequestError::Request)))
+        };
+
+        let result = Command::parse(method, request.get("params").unwrap_or(&Value::Null))
+            .and_then(|command| self.machine.lock().unwrap_or_else(PoisonError::into_inner).execute(command));
+
+        id.map(|id| response(id, result))
+    }
+
+    /// Answer every request read from a connection until it closes. Empty lines are ignored.
+    pub fn serve(&self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() { continue }
+
+            if let Some(response) = self.respond(&line) {
+                writeln!(output, "{response}")?;
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Accept connections until the listener fails, serving each on its own thread.
+    pub fn listen(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+
+            thread::spawn(move || {
+                let input = BufReader::new(stream.try_clone()?);
+                server.serve(input, stream)
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Encode a JSON-RPC response.
+fn response(id: Value, result: Result<Value, RequestError>) -> String {
+    let mut response = json!({ "jsonrpc": "2.0", "id": id });
+    match result {
+        Ok(result) => response["result"] = result,
+        Err(error) => response["error"] = json!({ "code": error.code(), "message": describe(&error) })
+    }
+
+    response.to_string()
+}
+
+/// An error followed by every error that caused it, so the reason is visible without the Rust types.
+fn describe(error: &dyn Error) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        description.push_str(": ");
+        description.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    description
+}
@@ -0,0 +1,108 @@
+//! Methods of the server and the parameters they take.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde_json::{Map, Value};
+use super::RequestError;
+
+/// A region of memory holding pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framebuffer {
+    pub address: u64,
+    pub width: u64,
+    pub height: u64,
+    /// Number of bytes per pixel.
+    pub depth: u64
+}
+
+impl Framebuffer {
+    /// Number of bytes of all pixels.
+    pub fn length(&self) -> u64 {
+        self.width.saturating_mul(self.height).saturating_mul(self.depth)
+    }
+}
+
+/// A request for the machine, parsed from the method and parameters of a JSON-RPC request.
+/// ```
+/// # extern crate atln_processor;
+/// # extern crate serde_json;
+/// use serde_json::json;
+/// use atln_processor::server::RequestError;
+/// use atln_processor::server::command::Command;
+///
+/// assert_eq!(Command::parse("load", &json!({ "address": 16, "data": "0aff" })), Ok(Command::Load { address: 16, data: vec![0x0a, 0xff] }));
+/// assert_eq!(Command::parse("step", &json!(null)), Ok(Command::Step { count: 1 }));
+/// assert_eq!(Command::parse("step", &json!({ "count": "many" })), Err(RequestError::Params("count".into())));
+/// assert_eq!(Command::parse("jump", &json!({})), Err(RequestError::Method("jump".into())));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Load an executable image, or raw bytes at an address.
+    Load { address: u64, data: Vec<u8> },
+    /// Run until the core stops, or until a number of instructions were executed.
+    Run { instructions: Option<u64> },
+    Step { count: u64 },
+    ReadMemory { address: u64, length: u64 },
+    WriteMemory { address: u64, data: Vec<u8> },
+    Registers,
+    /// Replace the first registers and the program counter.
+    WriteRegisters { registers: Vec<u64>, program_counter: Option<u64> },
+    SetFramebuffer(Framebuffer),
+    Screenshot
+}
+
+impl Command {
+    pub fn parse(method: &str, params: &Value) -> Result<Self, RequestError> {
+        let empty = Map::new();
+        let params = match params {
+            Value::Object(params) => params,
+            Value::Null => &empty,
+            _ => return Err(RequestError::Params("params".into()))
+        };
+
+        let number = |name: &str| match params.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value.as_u64().map(Some).ok_or_else(|| RequestError::Params(name.into()))
+        };
+        let required = |name: &str| number(name)?.ok_or_else(|| RequestError::Params(name.into()));
+        let bytes = |name: &str| params.get(name).and_then(Value::as_str).and_then(parse_hex).ok_or_else(|| RequestError::Params(name.into()));
+
+        Ok(match method {
+            "load" => Self::Load { address: number("address")?.unwrap_or(0), data: bytes("data")? },
+            "run" => Self::Run { instructions: number("instructions")? },
+            "step" => Self::Step { count: number("count")?.unwrap_or(1) },
+            "read_memory" => Self::ReadMemory { address: required("address")?, length: required("length")? },
+            "write_memory" => Self::WriteMemory { address: required("address")?, data: bytes("data")? },
+            "registers" => Self::Registers,
+            "write_registers" => Self::WriteRegisters {
+                registers: match params.get("registers") {
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(registers) => registers.as_array()
+                        .and_then(|registers| registers.iter().map(Value::as_u64).collect::<Option<Vec<_>>>())
+                        .ok_or_else(|| RequestError::Params("registers".into()))?
+                },
+                program_counter: number("program_counter")?
+            },
+            "set_framebuffer" => Self::SetFramebuffer(Framebuffer {
+                address: required("address")?,
+                width: required("width")?,
+                height: required("height")?,
+                depth: required("depth")?
+            }),
+            "screenshot" => Self::Screenshot,
+            _ => return Err(RequestError::Method(method.to_string()))
+        })
+    }
+}
+
+/// Encode bytes as lowercase hexadecimal.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decode hexadecimal text into bytes, or [None] if it is not an even number of hexadecimal digits.
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|digit| digit.is_ascii_hexdigit()) { return None }
+
+    (0..text.len()).step_by(2).map(|index| u8::from_str_radix(&text[index..index + 2], 16).ok()).collect()
+}
@@ -1,15 +1,48 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+/// Map for sparse lookups keyed by addresses and codes. Keys are hashed with `std`, without it they are ordered because
+/// `alloc` has no hash map.
+#[cfg(feature = "std")]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// Advance a splitmix64 generator and return its next number. Seeded randomness in the emulator comes from this, so
+/// the same seed always gives the same numbers.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut mixed = *state;
+    mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    mixed ^ (mixed >> 31)
+}
 
 /// Read a vector like a stream. Read buffer.len() amount of bytes from the vector and into the buffer. This will return
 /// the number of bytes read.
 /// ```
-/// assert!(false); // TODO: Test
+/// use atln_processor::utility::read_vec_into_buffer;
+///
+/// let source = vec![1, 2, 3, 4];
+/// let mut buffer = [0u8; 3];
+///
+/// assert_eq!(read_vec_into_buffer(&source, 1, &mut buffer), 3);
+/// assert_eq!(buffer, [2, 3, 4]);
+///
+/// // Reading past the end of the vector stops early.
+/// assert_eq!(read_vec_into_buffer(&source, 3, &mut buffer), 1);
+/// assert_eq!(buffer[0], 4);
 /// ```
-pub fn read_vec_into_buffer(vec: &Vec<u8>, start: usize, buffer: &mut [u8]) -> usize {
+pub fn read_vec_into_buffer(vec: &[u8], start: usize, buffer: &mut [u8]) -> usize {
     let mut bytes_read = 0;
-    for index in 0..buffer.len() {
+    for (index, byte) in buffer.iter_mut().enumerate() {
         match vec.get(start + index) {
-            Some(value) => buffer[index] = *value,
+            Some(value) => *byte = *value,
             None => return bytes_read
         }
 
@@ -22,7 +55,13 @@ pub fn read_vec_into_buffer(vec: &Vec<u8>, start: usize, buffer: &mut [u8]) -> u
 /// Write buffer.len() amount of bytes into the vector starting from the start index. This will return
 /// the number of bytes written.
 /// ```
-/// assert!(false); // TODO: Test
+/// use atln_processor::utility::write_buffer_into_vec;
+///
+/// let mut target = vec![0u8; 2];
+///
+/// // The vector grows to fit the buffer.
+/// assert_eq!(write_buffer_into_vec(&mut target, 1, &[5, 6, 7]), 3);
+/// assert_eq!(target, [0, 5, 6, 7]);
 /// ```
 pub fn write_buffer_into_vec(vec: &mut Vec<u8>, start: usize, buffer: &[u8]) -> usize {
     let mut bytes_written = 0;
@@ -44,6 +83,22 @@ pub trait Coded<Type> {
 
 pub trait Encodable<Type> {
     fn encode(&self) -> Type;
+
+    /// Write the encoded form into a writer instead of returning it.
+    /// ```
+    /// use atln_processor::emulator::processor::processor::instruction::Driver;
+    /// use atln_processor::utility::Encodable;
+    ///
+    /// let driver = Driver::new([0b001010_0_1, 0b1111_10_01]);
+    /// let mut encoded = Vec::new();
+    /// driver.encode_into(&mut encoded).unwrap();
+    ///
+    /// assert_eq!(encoded, driver.encode());
+    /// ```
+    #[cfg(feature = "std")]
+    fn encode_into(&self, writer: &mut impl Write) -> io::Result<()> where Type: AsRef<[u8]> {
+        writer.write_all(self.encode().as_ref())
+    }
 }
 
 /// Allows an object to be represented as some text.
@@ -96,4 +151,68 @@ where
 pub trait LastError<E> {
     /// Get the last emitted error from a member of the parent object.
     fn last_error(&self) -> &Option<E>;
-}
\ No newline at end of file
+}
+
+/// Little endian values consumed from the front of a byte slice. Every read returns [None] if the slice is too short, in
+/// which case nothing is consumed.
+/// ```
+/// use atln_processor::utility::SliceReader;
+///
+/// let mut reader = SliceReader::new(&[1, 2, 0, 3]);
+/// assert_eq!(reader.u8(), Some(1));
+/// assert_eq!(reader.u16(), Some(2));
+/// assert_eq!(reader.u64(), None);
+/// assert_eq!(reader.remaining(), [3]);
+///
+/// let mut reader = SliceReader::new(&[2, 0, b'h', b'i', 5, 0]);
+/// assert_eq!(reader.prefixed(), Some(&b"hi"[..]));
+/// assert_eq!(reader.prefixed(), None);
+/// assert_eq!(reader.remaining(), [5, 0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < length { return None }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Bytes prefixed with their length as a 16 bit number.
+    pub fn prefixed(&mut self) -> Option<&'a [u8]> {
+        let mut reader = self.clone();
+        let length = reader.u16()?;
+        let bytes = reader.take(length as usize)?;
+        *self = reader;
+        Some(bytes)
+    }
+}
@@ -0,0 +1,170 @@
+//! JavaScript bindings through wasm-bindgen for running the emulator in a browser.
+//!
+//! A [Machine] owns a core along with its memory and ports. Programs are loaded as raw bytes, and a region of memory can
+//! be marked as a framebuffer so a page can draw it without copying the rest of memory across.
+//!
+//! The library is only built as an rlib so it stays usable without `std`. The module wasm-bindgen loads is built as a
+//! dynamic library on request with `cargo rustc --lib --release --features wasm --target wasm32-unknown-unknown
+//! --crate-type cdylib`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use wasm_bindgen::prelude::*;
+use emulator::memory::Memory;
+use emulator::processor::processor::{Budget, Core, Ports, Status};
+
+/// Simplified [Status] that can cross into JavaScript.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Running,
+    Halted,
+    Faulted,
+    BudgetExhausted
+}
+
+/// A region of memory holding pixels.
+#[derive(Debug, Clone, Copy)]
+struct Framebuffer {
+    address: u64,
+    width: u32,
+    height: u32,
+    /// Number of bytes per pixel.
+    depth: u32
+}
+
+impl Framebuffer {
+    /// Number of bytes of the pixels, saturating rather than wrapping when it doesn't fit in a `usize`.
+    fn length(&self) -> usize {
+        (self.width as usize).saturating_mul(self.height as usize).saturating_mul(self.depth as usize)
+    }
+}
+
+#[wasm_bindgen]
+pub struct Machine {
+    core: Core,
+    memory: Memory,
+    ports: Ports,
+    framebuffer: Option<Framebuffer>,
+    /// Description of the exception that faulted the core most recently.
+    fault: Option<String>
+}
+
+#[wasm_bindgen]
+impl Machine {
+    /// Create a machine with zeroed memory of the given size.
+    #[wasm_bindgen(constructor)]
+    pub fn new(memory_size: usize) -> Self {
+        Self {
+            core: Core::default(),
+            memory: Memory::from(vec![0u8; memory_size]),
+            ports: Ports::default(),
+            framebuffer: None,
+            fault: None
+        }
+    }
+
+    /// Copy a raw image into physical memory and start executing from its first byte. Returns the number of bytes that
+    /// fit in memory.
+    pub fn load(&mut self, address: u64, image: &[u8]) -> usize {
+        let written = self.memory.write_bytes(address, false, image);
+        self.core.context.program_counter = address;
+        self.fault = None;
+        written
+    }
+
+    pub fn step(&mut self) -> RunStatus {
+        let status = self.core.step(&mut self.memory, &mut self.ports);
+        self.status(status)
+    }
+
+    /// Run until the core stops or `instructions` have been executed.
+    pub fn run(&mut self, instructions: u64) -> RunStatus {
+        let status = self.core.run(&mut self.memory, &mut self.ports, Some(Budget::Instructions(instructions)));
+        self.status(status)
+    }
+
+    /// Description of the exception that most recently faulted the core.
+    pub fn fault(&self) -> Option<String> {
+        self.fault.clone()
+    }
+
+    /// Value of a register, or [None] if the index does not exist.
+    pub fn register(&self, index: usize) -> Option<u64> {
+        self.core.context.registers.get(index).copied()
+    }
+
+    pub fn set_register(&mut self, index: usize, value: u64) -> bool {
+        match self.core.context.registers.get_mut(index) {
+            Some(register) => { *register = value; true },
+            None => false
+        }
+    }
+
+    /// All registers in order.
+    pub fn registers(&self) -> Vec<u64> {
+        self.core.context.registers.to_vec()
+    }
+
+    pub fn program_counter(&self) -> u64 {
+        self.core.context.program_counter
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.core.cycles
+    }
+
+    pub fn ports(&self) -> Vec<u8> {
+        self.ports.to_vec()
+    }
+
+    /// Read physical memory. The result is shorter than `length` if the range leaves memory.
+    pub fn read_memory(&self, address: u64, length: usize) -> Vec<u8> {
+        let mut buffer = vec![0u8; length.min(self.memory.bytes.len())];
+        let read = self.memory.read_bytes(address, false, &mut buffer);
+        buffer.truncate(read);
+        buffer
+    }
+
+    /// Write physical memory, returning the number of bytes written.
+    pub fn write_memory(&mut self, address: u64, bytes: &[u8]) -> usize {
+        self.memory.write_bytes(address, false, bytes)
+    }
+
+    /// Treat a region of memory as a framebuffer of `width` by `height` pixels with `depth` bytes each.
+    pub fn set_framebuffer(&mut self, address: u64, width: u32, height: u32, depth: u32) {
+        self.framebuffer = Some(Framebuffer { address, width, height, depth });
+    }
+
+    /// Pixels of the framebuffer, or an empty array if there is none.
+    pub fn framebuffer(&self) -> Vec<u8> {
+        match self.framebuffer {
+            Some(framebuffer) => self.read_memory(framebuffer.address, framebuffer.length()),
+            None => Vec::new()
+        }
+    }
+}
+
+impl Machine {
+    fn status(&mut self, status: Status) -> RunStatus {
+        match status {
+            Status::Running => RunStatus::Running,
+            Status::Halted => RunStatus::Halted,
+            Status::BudgetExhausted => RunStatus::BudgetExhausted,
+            Status::Faulted(exception) => {
+                // Include every source so the reason is visible without the Rust types.
+                let mut fault = exception.to_string();
+                let mut source = exception.source();
+                while let Some(error) = source {
+                    fault.push_str(": ");
+                    fault.push_str(&error.to_string());
+                    source = error.source();
+                }
+
+                self.fault = Some(fault);
+                RunStatus::Faulted
+            }
+        }
+    }
+}